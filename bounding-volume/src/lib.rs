@@ -20,6 +20,34 @@ impl Aabb {
         (self.max - self.min) / 2.0
     }
 
+    /// Edge lengths of the box. Inverted boxes have a size of zero along the inverted axes.
+    pub fn size(&self) -> Vec3 {
+        (self.max - self.min).max(Vec3::ZERO)
+    }
+
+    pub fn volume(&self) -> f32 {
+        let size = self.size();
+        size.x * size.y * size.z
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        [
+            Vec3::new(self.min.x, self.min.y, self.min.z),
+            Vec3::new(self.max.x, self.min.y, self.min.z),
+            Vec3::new(self.min.x, self.max.y, self.min.z),
+            Vec3::new(self.max.x, self.max.y, self.min.z),
+            Vec3::new(self.min.x, self.min.y, self.max.z),
+            Vec3::new(self.max.x, self.min.y, self.max.z),
+            Vec3::new(self.min.x, self.max.y, self.max.z),
+            Vec3::new(self.max.x, self.max.y, self.max.z),
+        ]
+    }
+
     pub fn extend(&mut self, point: Vec3) {
         self.min = self.min.min(point);
         self.max = self.max.max(point);
@@ -50,4 +78,148 @@ impl Aabb {
             None
         }
     }
+
+    /// Points on the faces of the box are inside.
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Boxes which only touch each other intersect.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    /// Distances along the ray at which it enters and leaves the box.
+    /// The entry is 0 if the ray starts inside of the box, boxes behind the ray are missed.
+    pub fn intersect_ray(&self, origin: Vec3, direction: Vec3) -> Option<(f32, f32)> {
+        let inverse_direction = direction.recip();
+        let t0 = (self.min - origin) * inverse_direction;
+        let t1 = (self.max - origin) * inverse_direction;
+
+        let entry = t0.min(t1).max_element().max(0.0);
+        let exit = t0.max(t1).min_element();
+
+        (entry <= exit).then_some((entry, exit))
+    }
+
+    /// Smallest box which contains all given boxes.
+    pub fn union_all<'a, T: IntoIterator<Item = &'a Aabb>>(boxes: T) -> Option<Self> {
+        let mut box_iter = boxes.into_iter();
+        let mut aabb = *box_iter.next()?;
+
+        for other in box_iter {
+            aabb.extend_aabb(other);
+        }
+
+        Some(aabb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_box_volume_and_surface_area() {
+        let aabb = Aabb::new(Vec3::ZERO, Vec3::ONE);
+
+        assert_eq!(aabb.volume(), 1.0);
+        assert_eq!(aabb.surface_area(), 6.0);
+    }
+
+    #[test]
+    fn box_volume_and_surface_area() {
+        let aabb = Aabb::new(Vec3::new(-1.0, 0.0, 2.0), Vec3::new(1.0, 3.0, 6.0));
+
+        assert_eq!(aabb.volume(), 24.0);
+        assert_eq!(aabb.surface_area(), 52.0);
+    }
+
+    #[test]
+    fn flat_box_has_no_volume() {
+        let aabb = Aabb::new(Vec3::ZERO, Vec3::new(2.0, 3.0, 0.0));
+
+        assert_eq!(aabb.volume(), 0.0);
+        assert_eq!(aabb.surface_area(), 12.0);
+    }
+
+    #[test]
+    fn inverted_box_is_empty() {
+        let aabb = Aabb::new(Vec3::ONE, Vec3::new(2.0, 0.0, 2.0));
+
+        assert_eq!(aabb.size(), Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(aabb.volume(), 0.0);
+        assert_eq!(aabb.surface_area(), 2.0);
+    }
+
+    #[test]
+    fn union_all_of_nothing() {
+        assert!(Aabb::union_all([]).is_none());
+    }
+
+    #[test]
+    fn union_all_contains_all_boxes() {
+        let boxes = [
+            Aabb::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)),
+            Aabb::new(Vec3::new(2.0, -3.0, 0.5), Vec3::new(4.0, 0.0, 0.5)),
+            Aabb::new(Vec3::new(0.0, 0.0, -2.0), Vec3::new(0.0, 0.0, 5.0)),
+        ];
+
+        let union = Aabb::union_all(&boxes).unwrap();
+
+        assert_eq!(union.min, Vec3::new(-1.0, -3.0, -2.0));
+        assert_eq!(union.max, Vec3::new(4.0, 1.0, 5.0));
+    }
+
+    #[test]
+    fn union_all_of_single_box() {
+        let aabb = Aabb::new(Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0));
+
+        let union = Aabb::union_all([&aabb]).unwrap();
+
+        assert_eq!(union.min, aabb.min);
+        assert_eq!(union.max, aabb.max);
+    }
+
+    #[test]
+    fn contains_points_on_faces() {
+        let aabb = Aabb::new(Vec3::ZERO, Vec3::ONE);
+
+        assert!(aabb.contains(Vec3::splat(0.5)));
+        assert!(aabb.contains(Vec3::new(1.0, 0.0, 0.5)));
+        assert!(!aabb.contains(Vec3::new(1.1, 0.5, 0.5)));
+        assert!(!aabb.contains(Vec3::new(0.5, -0.1, 0.5)));
+    }
+
+    #[test]
+    fn ray_enters_and_leaves_the_box() {
+        let aabb = Aabb::new(Vec3::ZERO, Vec3::ONE);
+
+        assert_eq!(
+            aabb.intersect_ray(Vec3::new(-1.0, 0.5, 0.5), Vec3::X),
+            Some((1.0, 2.0))
+        );
+        assert_eq!(
+            aabb.intersect_ray(Vec3::splat(0.5), Vec3::NEG_Z),
+            Some((0.0, 0.5))
+        );
+        assert_eq!(
+            aabb.intersect_ray(Vec3::new(-1.0, 0.5, 0.5), Vec3::NEG_X),
+            None
+        );
+        assert_eq!(aabb.intersect_ray(Vec3::new(-1.0, 2.0, 0.5), Vec3::X), None);
+    }
+
+    #[test]
+    fn intersecting_boxes() {
+        let aabb = Aabb::new(Vec3::ZERO, Vec3::ONE);
+
+        assert!(aabb.intersects(&Aabb::new(Vec3::splat(0.5), Vec3::splat(2.0))));
+        assert!(aabb.intersects(&Aabb::new(Vec3::ONE, Vec3::splat(2.0))));
+        assert!(aabb.intersects(&Aabb::new(Vec3::splat(-1.0), Vec3::splat(2.0))));
+        assert!(!aabb.intersects(&Aabb::new(
+            Vec3::new(1.5, 0.0, 0.0),
+            Vec3::new(2.0, 1.0, 1.0)
+        )));
+    }
 }
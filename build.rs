@@ -0,0 +1,24 @@
+use std::process::Command;
+
+/// Provides the short hash of the current git commit as `GIT_HASH` for `BuildInfo` in `src/app.rs`.
+/// Builds outside of a git repository use `unknown`.
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+
+    if let Ok(head) = std::fs::read_to_string(".git/HEAD") {
+        if let Some(reference) = head.strip_prefix("ref: ") {
+            println!("cargo:rerun-if-changed=.git/{}", reference.trim());
+        }
+    }
+
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=GIT_HASH={}", git_hash);
+}
@@ -0,0 +1,210 @@
+//! A single file container for a converted point cloud.
+//!
+//! Layout, all numbers are little endian:
+//! ```text
+//! magic "PCAR" | version u32
+//! metadata length u64 | metadata json
+//! number of cells u32 | (hierarchy u32, index 3 * i32, offset u64, length u64) per cell
+//! cell data
+//! ```
+//! Offsets are relative to the start of the archive.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use glam::IVec3;
+use rustc_hash::FxHashMap;
+
+use crate::cell::{Cell, CellId, Header};
+use crate::metadata::Metadata;
+use crate::Endianess;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ArchiveEntry {
+    pub offset: u64,
+    pub length: u64,
+}
+
+#[derive(Debug)]
+pub struct Archive {
+    metadata: Metadata,
+    metadata_entry: ArchiveEntry,
+    cells: FxHashMap<CellId, ArchiveEntry>,
+    paths: FxHashMap<String, ArchiveEntry>,
+}
+
+impl Archive {
+    pub const EXTENSION: &'static str = "pcar";
+    const MAGIC: [u8; 4] = *b"PCAR";
+    const VERSION: u32 = 1;
+    const INDEX_ENTRY_SIZE: u64 = 4 + 3 * 4 + 8 + 8;
+
+    /// Reads the metadata and the cell index. Cells are only read on demand.
+    pub fn read_from<R: Read + Seek>(reader: &mut R) -> Result<Self, Error> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        if magic != Self::MAGIC {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Not a point cloud archive",
+            ));
+        }
+
+        let version = reader.read_u32::<Endianess>()?;
+
+        if version != Self::VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unsupported archive version {}", version),
+            ));
+        }
+
+        let metadata_length = reader.read_u64::<Endianess>()?;
+        let metadata_entry = ArchiveEntry {
+            offset: reader.stream_position()?,
+            length: metadata_length,
+        };
+
+        let metadata = Metadata::read_from(&mut reader.by_ref().take(metadata_length))
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+
+        let number_of_cells = reader.read_u32::<Endianess>()?;
+        let mut cells = FxHashMap::default();
+
+        for _ in 0..number_of_cells {
+            let hierarchy = reader.read_u32::<Endianess>()?;
+            let x = reader.read_i32::<Endianess>()?;
+            let y = reader.read_i32::<Endianess>()?;
+            let z = reader.read_i32::<Endianess>()?;
+
+            let offset = reader.read_u64::<Endianess>()?;
+            let length = reader.read_u64::<Endianess>()?;
+
+            let id = CellId {
+                hierarchy,
+                index: IVec3::new(x, y, z),
+            };
+
+            cells.insert(id, ArchiveEntry { offset, length });
+        }
+
+        let mut paths = cells
+            .iter()
+            .map(|(id, entry)| (id.logical_path(&metadata.cell_extension), *entry))
+            .collect::<FxHashMap<_, _>>();
+        paths.insert(Metadata::logical_path(), metadata_entry);
+
+        Ok(Self {
+            metadata,
+            metadata_entry,
+            cells,
+            paths,
+        })
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let mut buf_reader = BufReader::new(file);
+        Self::read_from(&mut buf_reader)
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn metadata_entry(&self) -> ArchiveEntry {
+        self.metadata_entry
+    }
+
+    pub fn cell_ids(&self) -> impl Iterator<Item = &CellId> {
+        self.cells.keys()
+    }
+
+    pub fn cell_entry(&self, id: &CellId) -> Option<ArchiveEntry> {
+        self.cells.get(id).copied()
+    }
+
+    /// Looks up an entry by the path it would have inside a converted directory.
+    /// Both `/` and `\` are accepted as separators, see [crate::logical_path].
+    pub fn entry_for_path(&self, path: &str) -> Option<ArchiveEntry> {
+        self.paths
+            .get(&crate::logical_path::normalize(path))
+            .copied()
+    }
+
+    pub fn read_cell<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        id: &CellId,
+    ) -> Result<Option<Cell>, Error> {
+        let Some(entry) = self.cell_entry(id) else {
+            return Ok(None);
+        };
+
+        reader.seek(SeekFrom::Start(entry.offset))?;
+        Ok(Some(Cell::read_from(
+            &mut reader.by_ref().take(entry.length),
+        )?))
+    }
+}
+
+/// Packs the metadata and all cells of a converted directory into a single archive file.
+pub fn write_archive(directory: &Path, output: &Path) -> Result<(), Error> {
+    let metadata_bytes =
+        std::fs::read(directory.join(crate::logical_path::to_path(&Metadata::logical_path())))?;
+    let metadata = Metadata::read_from(&mut metadata_bytes.as_slice())
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+
+    let mut cells = Vec::new();
+
+    for hierarchy in 0..metadata.hierarchies {
+        let hierarchy_dir = directory.join(Metadata::hierarchy_string(hierarchy));
+
+        for dir_entry in hierarchy_dir.read_dir()? {
+            let path = dir_entry?.path();
+
+            if !Cell::has_extension(&path) {
+                continue;
+            }
+
+            let mut file = BufReader::new(File::open(&path)?);
+            let header = Header::read_from(&mut file)?;
+            let length = file.get_ref().metadata()?.len();
+
+            cells.push((header.id, path, length));
+        }
+    }
+
+    let index_size = 4 + cells.len() as u64 * Archive::INDEX_ENTRY_SIZE;
+    let mut offset = 4 + 4 + 8 + metadata_bytes.len() as u64 + index_size;
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    writer.write_all(&Archive::MAGIC)?;
+    writer.write_u32::<Endianess>(Archive::VERSION)?;
+    writer.write_u64::<Endianess>(metadata_bytes.len() as u64)?;
+    writer.write_all(&metadata_bytes)?;
+
+    writer.write_u32::<Endianess>(cells.len() as u32)?;
+
+    for (id, _, length) in &cells {
+        writer.write_u32::<Endianess>(id.hierarchy)?;
+        writer.write_i32::<Endianess>(id.index.x)?;
+        writer.write_i32::<Endianess>(id.index.y)?;
+        writer.write_i32::<Endianess>(id.index.z)?;
+
+        writer.write_u64::<Endianess>(offset)?;
+        writer.write_u64::<Endianess>(*length)?;
+
+        offset += length;
+    }
+
+    for (_, path, _) in &cells {
+        let mut file = File::open(path)?;
+        std::io::copy(&mut file, &mut writer)?;
+    }
+
+    writer.flush()
+}
@@ -2,27 +2,38 @@ use std::collections::hash_map::Entry;
 use std::io::{Read, Write};
 use std::path::Path;
 
+use bounding_volume::Aabb;
 use byteorder::{ReadBytesExt, WriteBytesExt};
 use glam::{IVec3, Vec3};
 use rustc_hash::{FxBuildHasher, FxHashMap};
+use serde::{Deserialize, Serialize};
 
+use crate::error::ConverterError;
 use crate::hex::{HexWorldIndex, OffsetIndex};
-use crate::metadata::{Metadata, MetadataConfig};
-use crate::point::Point;
+use crate::metadata::{Metadata, MetadataConfig, PointReduction};
+use crate::point::{Color, Point, PointAttributes};
 use crate::Endianess;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CellId {
     pub hierarchy: u32,
     pub index: IVec3,
 }
 
 impl CellId {
-    pub fn path(&self) -> std::path::PathBuf {
-        let mut path = std::path::PathBuf::from(Metadata::hierarchy_string(self.hierarchy));
-        path.push(self.index_string());
-        path.set_extension(Cell::EXTENSION);
-        path
+    /// Path of the cell file inside the converted directory, see [crate::logical_path].
+    /// The extension is the [Metadata::cell_extension] of the point cloud.
+    pub fn logical_path(&self, extension: &str) -> String {
+        format!(
+            "{}/{}.{}",
+            Metadata::hierarchy_string(self.hierarchy),
+            self.index_string(),
+            extension
+        )
+    }
+
+    pub fn path(&self, extension: &str) -> std::path::PathBuf {
+        crate::logical_path::to_path(&self.logical_path(extension))
     }
 
     pub fn index_string(&self) -> String {
@@ -30,21 +41,67 @@ impl CellId {
     }
 }
 
+/// Colors of all points which fell into a sub grid cell, for [PointReduction::AverageColor].
+/// They aren't written with the cell, see [Cell::write_color_sums].
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ColorSum {
+    sum: [u32; 4],
+    count: u32,
+    /// Color of the kept point before it was replaced by the average.
+    kept_color: Color,
+}
+
+impl ColorSum {
+    fn new(color: Color) -> Self {
+        Self {
+            sum: color.to_array().map(u32::from),
+            count: 1,
+            kept_color: color,
+        }
+    }
+
+    fn add(&mut self, color: Color) {
+        for (sum, channel) in self.sum.iter_mut().zip(color.to_array()) {
+            *sum += channel as u32;
+        }
+
+        self.count += 1;
+    }
+
+    fn average(&self) -> Color {
+        Color::from_array(self.sum.map(|sum| (sum / self.count) as u8))
+    }
+}
+
+pub(crate) type ColorSums = FxHashMap<OffsetIndex, ColorSum>;
+
 #[derive(Debug)]
 pub struct Cell {
     header: Header,
     points_grid: FxHashMap<OffsetIndex, Point>,
     pub(crate) overflow: FxHashMap<IVec3, Option<Vec<Point>>>,
+    color_sums: ColorSums,
 }
 
 impl Cell {
-    pub const EXTENSION: &'static str = "bin";
+    /// Extension of newly converted cell files.
+    pub const EXTENSION: &'static str = "pcc";
+    /// Extension of cell files which were converted before [Cell::EXTENSION].
+    pub const LEGACY_EXTENSION: &'static str = "bin";
+
+    /// Whether the file has the extension of a cell file, including [Cell::LEGACY_EXTENSION].
+    pub fn has_extension(path: &Path) -> bool {
+        path.extension()
+            .and_then(|it| it.to_str())
+            .is_some_and(|it| it == Self::EXTENSION || it == Self::LEGACY_EXTENSION)
+    }
 
     pub fn new(id: CellId, sub_cell_size: f32, size: f32, pos: Vec3, capacity: usize) -> Self {
         Self {
             header: Header::new(id, sub_cell_size, size, pos),
             points_grid: FxHashMap::with_capacity_and_hasher(capacity, FxBuildHasher),
             overflow: FxHashMap::default(),
+            color_sums: FxHashMap::default(),
         }
     }
 
@@ -63,21 +120,162 @@ impl Cell {
             .flatten()
     }
 
+    /// Counts the points of the sub grid and of the overflow, independent of the header.
+    /// Both have to match [Header::number_of_points] and [Header::number_of_overflow_points].
+    pub fn recount(&self) -> (u32, u32) {
+        let number_of_points = self.points_grid.len() as u32;
+        let number_of_overflow_points = self.overflow_points().count() as u32;
+
+        (number_of_points, number_of_overflow_points)
+    }
+
+    /// Number of points in each overflow sub cell.
+    /// [None] if the sub cell was full and its points were passed on to the next hierarchy.
+    pub fn overflow_fill(&self) -> impl Iterator<Item = (&IVec3, Option<usize>)> {
+        self.overflow
+            .iter()
+            .map(|(index, points)| (index, points.as_ref().map(Vec::len)))
+    }
+
     pub fn all_points(&self) -> impl Iterator<Item = &Point> {
         self.points().chain(self.overflow_points())
     }
 
-    pub fn add_point(&mut self, point: Point) -> Option<Point> {
+    /// Marches the ray through the sub grid and returns the point of the first occupied sub grid cell,
+    /// so that picking doesn't have to test every point of dense cells.
+    /// The ray is sampled in steps of an eighth of a sub grid cell,
+    /// which can miss sub grid cells whose corners it only grazes.
+    /// Overflow points aren't part of the sub grid and can't be picked.
+    pub fn pick(&self, origin: Vec3, direction: Vec3) -> Option<&Point> {
+        let direction = direction.normalize();
+        let sub_cell_size = self.header.sub_cell_size;
+
+        // sub grid cells of points near the faces reach out of the cell
+        let half_size = Vec3::splat(self.header.size / 2.0 + sub_cell_size);
+        let bounds = Aabb::new(self.header.pos - half_size, self.header.pos + half_size);
+        let (entry, exit) = bounds.intersect_ray(origin, direction)?;
+
+        let step = sub_cell_size / 8.0;
+        let mut previous_index = None;
+
+        for i in 0..=((exit - entry) / step).ceil() as u32 {
+            let pos = origin + direction * (entry + i as f32 * step);
+            let index = OffsetIndex::from_world(pos, sub_cell_size / 2.0);
+
+            if previous_index.replace(index) == Some(index) {
+                continue;
+            }
+
+            if let Some(point) = self.points_grid.get(&index) {
+                return Some(point);
+            }
+        }
+
+        None
+    }
+
+    /// Extension of the files with the color sums of evicted cells, see [Cell::write_color_sums].
+    pub(crate) const COLOR_SUMS_EXTENSION: &'static str = "sums";
+
+    pub(crate) fn has_color_sums(&self) -> bool {
+        !self.color_sums.is_empty()
+    }
+
+    /// Writes the color sums of [PointReduction::AverageColor], which aren't written with the cell.
+    /// The [crate::converter::Converter] writes them next to the cells it evicts from its cache,
+    /// so that the average colors don't depend on the cache size.
+    pub(crate) fn write_color_sums(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        writer.write_u32::<Endianess>(self.color_sums.len() as u32)?;
+
+        for (index, color_sum) in &self.color_sums {
+            writer.write_i32::<Endianess>(index.x)?;
+            writer.write_i32::<Endianess>(index.y)?;
+            writer.write_i32::<Endianess>(index.z)?;
+
+            for sum in color_sum.sum {
+                writer.write_u32::<Endianess>(sum)?;
+            }
+
+            writer.write_u32::<Endianess>(color_sum.count)?;
+            writer.write_all(&color_sum.kept_color.to_array())?;
+        }
+
+        Ok(())
+    }
+
+    /// Continues averaging with the color sums of [Cell::write_color_sums].
+    pub(crate) fn read_color_sums(&mut self, reader: &mut dyn Read) -> Result<(), std::io::Error> {
+        let len = reader.read_u32::<Endianess>()? as usize;
+        self.color_sums = FxHashMap::with_capacity_and_hasher(len, FxBuildHasher);
+
+        for _ in 0..len {
+            let index = OffsetIndex {
+                x: reader.read_i32::<Endianess>()?,
+                y: reader.read_i32::<Endianess>()?,
+                z: reader.read_i32::<Endianess>()?,
+            };
+
+            let mut sum = [0; 4];
+            for sum in &mut sum {
+                *sum = reader.read_u32::<Endianess>()?;
+            }
+
+            let count = reader.read_u32::<Endianess>()?;
+
+            let mut kept_color = [0; 4];
+            reader.read_exact(&mut kept_color)?;
+
+            self.color_sums.insert(
+                index,
+                ColorSum {
+                    sum,
+                    count,
+                    kept_color: Color::from_array(kept_color),
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Adds the point to its sub grid cell.
+    /// Returns the point which wasn't kept if the sub grid cell was already occupied.
+    pub fn add_point(&mut self, point: Point, reduction: PointReduction) -> Option<Point> {
+        self.header.attributes |= PointAttributes::of(&point);
+
         let index = self.header.sub_grid_index_for_point(point);
 
         match self.points_grid.entry(index) {
             Entry::Occupied(mut entry) => {
+                if reduction == PointReduction::First {
+                    return Some(point);
+                }
+
                 let pos = index.to_world(self.header.sub_cell_size / 2.0);
 
                 let old_distance = pos.distance_squared(entry.get().pos);
                 let new_distance = pos.distance_squared(point.pos);
+                let replace = new_distance < old_distance;
+
+                if reduction == PointReduction::AverageColor {
+                    let color_sum = self
+                        .color_sums
+                        .entry(index)
+                        .or_insert_with(|| ColorSum::new(entry.get().color));
+                    color_sum.add(point.color);
+
+                    if replace {
+                        let mut old_point = entry.insert(point);
+                        old_point.color = std::mem::replace(&mut color_sum.kept_color, point.color);
+                        entry.get_mut().color = color_sum.average();
+                        return Some(old_point);
+                    }
 
-                if new_distance < old_distance {
+                    entry.get_mut().color = color_sum.average();
+                    return Some(point);
+                }
+
+                if replace {
                     let old_point = entry.insert(point);
                     Some(old_point)
                 } else {
@@ -93,11 +291,11 @@ impl Cell {
         }
     }
 
-    pub fn add_points(&mut self, points: Vec<Point>) -> Vec<Point> {
+    pub fn add_points(&mut self, points: Vec<Point>, reduction: PointReduction) -> Vec<Point> {
         let mut overflow_points = Vec::with_capacity(points.capacity());
 
         for point in points {
-            if let Some(point) = self.add_point(point) {
+            if let Some(point) = self.add_point(point, reduction) {
                 overflow_points.push(point);
             }
         }
@@ -113,6 +311,10 @@ impl Cell {
         let mut remaining_overflow_points = FxHashMap::default();
 
         for (cell_index, mut points) in overflow_points {
+            for point in &points {
+                self.header.attributes |= PointAttributes::of(point);
+            }
+
             match self.overflow.entry(cell_index) {
                 Entry::Vacant(entry) => {
                     if points.len() <= config.cell_point_overflow_limit as usize {
@@ -134,7 +336,7 @@ impl Cell {
 
                         cell_points.append(&mut points);
 
-                        if cell_points.len() < config.cell_point_overflow_limit as usize {
+                        if cell_points.len() <= config.cell_point_overflow_limit as usize {
                             self.header.total_number_of_points += points_len;
                             self.header.number_of_overflow_points += points_len;
                         } else {
@@ -156,7 +358,7 @@ impl Cell {
         self.header.write_to(writer)?;
 
         for point in self.points_grid.values() {
-            point.write_to(writer)?;
+            point.write_to(writer, self.header.attributes)?;
         }
 
         writer.write_u8(self.overflow.len() as u8)?;
@@ -170,7 +372,7 @@ impl Cell {
                 writer.write_u32::<Endianess>(points.len() as u32)?;
 
                 for point in points {
-                    point.write_to(writer)?;
+                    point.write_to(writer, self.header.attributes)?;
                 }
             } else {
                 writer.write_u32::<Endianess>(0)?;
@@ -180,22 +382,72 @@ impl Cell {
         Ok(())
     }
 
-    pub fn read_from(reader: &mut dyn Read) -> Result<Self, std::io::Error> {
+    /// Fails with [ConverterError::CorruptData] if the file has fewer points than its header claims,
+    /// e.g. because writing it was interrupted.
+    pub fn read_from(reader: &mut dyn Read) -> Result<Self, ConverterError> {
+        let (cell, truncated) = Self::read(reader, false)?;
+        debug_assert!(!truncated);
+        Ok(cell)
+    }
+
+    /// Like [Cell::read_from], but a cell whose points are truncated is returned
+    /// with the points which could be read, and its header only counts those.
+    /// Returns whether the cell was truncated.
+    /// The header itself still has to be complete.
+    pub fn read_partial_from(reader: &mut dyn Read) -> Result<(Self, bool), ConverterError> {
+        Self::read(reader, true)
+    }
+
+    fn read(reader: &mut dyn Read, allow_truncation: bool) -> Result<(Self, bool), ConverterError> {
         let header = Header::read_from(reader)?;
 
-        let mut points_grid =
-            FxHashMap::with_capacity_and_hasher(header.number_of_points as usize, FxBuildHasher);
+        let mut cell = Self {
+            points_grid: FxHashMap::with_capacity_and_hasher(
+                header.number_of_points as usize,
+                FxBuildHasher,
+            ),
+            header,
+            overflow: FxHashMap::default(),
+            color_sums: FxHashMap::default(),
+        };
 
-        for _ in 0..header.number_of_points {
-            let point = Point::read_from(reader)?;
+        match cell.read_points(reader) {
+            Ok(()) => Ok((cell, false)),
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                let (number_of_points, number_of_overflow_points) = cell.recount();
+                let read_points = number_of_points + number_of_overflow_points;
+
+                if !allow_truncation {
+                    return Err(ConverterError::CorruptData {
+                        detail: format!(
+                            "cell {:?} is truncated after {} of {} points",
+                            cell.header.id, read_points, cell.header.total_number_of_points
+                        ),
+                    });
+                }
 
-            let sub_grid_index = header.sub_grid_index_for_point(point);
+                cell.header.number_of_points = number_of_points;
+                cell.header.number_of_overflow_points = number_of_overflow_points;
+                cell.header.total_number_of_points = read_points;
 
-            points_grid.insert(sub_grid_index, point);
+                Ok((cell, true))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Reads the points after the header, keeping everything read so far if it fails.
+    fn read_points(&mut self, reader: &mut dyn Read) -> Result<(), std::io::Error> {
+        for _ in 0..self.header.number_of_points {
+            let point = Point::read_from(reader, self.header.attributes)?;
+
+            let sub_grid_index = self.header.sub_grid_index_for_point(point);
+
+            self.points_grid.insert(sub_grid_index, point);
         }
 
         let overflow_len = reader.read_u8()? as usize;
-        let mut overflow = FxHashMap::with_capacity_and_hasher(overflow_len, FxBuildHasher);
+        self.overflow.reserve(overflow_len);
 
         for _ in 0..overflow_len {
             let key = {
@@ -208,34 +460,33 @@ impl Cell {
             let number_of_overflow_points = reader.read_u32::<Endianess>()? as usize;
 
             if number_of_overflow_points == 0 {
-                overflow.insert(key, None);
+                self.overflow.insert(key, None);
             } else {
-                let mut overflow_points = Vec::with_capacity(number_of_overflow_points);
+                let overflow_points = self
+                    .overflow
+                    .entry(key)
+                    .or_insert(Some(Vec::with_capacity(number_of_overflow_points)))
+                    .get_or_insert_with(Vec::new);
 
                 for _ in 0..number_of_overflow_points {
-                    let point = Point::read_from(reader)?;
+                    let point = Point::read_from(reader, self.header.attributes)?;
                     overflow_points.push(point);
                 }
-
-                overflow.insert(key, Some(overflow_points));
             }
         }
 
-        Ok(Self {
-            header,
-            points_grid,
-            overflow,
-        })
+        Ok(())
     }
 
-    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, std::io::Error> {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, ConverterError> {
         let file = std::fs::File::open(path)?;
         let mut buf_reader = std::io::BufReader::new(file);
         Self::read_from(&mut buf_reader)
     }
 }
 
-#[derive(Debug, Clone)]
+/// The serde representation is meant for tests and tools, cell files use [Header::write_to].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Header {
     /// A unique id over all cells
     pub id: CellId,
@@ -258,9 +509,17 @@ pub struct Header {
     /// The position of the cell in the world.
     /// This is the center of the cell.
     pub pos: Vec3,
+
+    /// Attributes which the points of the cell file store besides their position and color.
+    /// Stored in the highest byte of the hierarchy with the gps time in the highest bit,
+    /// so cells without attributes keep the plain layout.
+    pub attributes: PointAttributes,
 }
 
 impl Header {
+    const ATTRIBUTES_SHIFT: u32 = 24;
+    const HIERARCHY_MASK: u32 = (1 << Self::ATTRIBUTES_SHIFT) - 1;
+
     pub fn new(id: CellId, sub_cell_size: f32, size: f32, pos: Vec3) -> Self {
         Self {
             id,
@@ -270,6 +529,7 @@ impl Header {
             sub_cell_size,
             size,
             pos,
+            attributes: PointAttributes::NONE,
         }
     }
 
@@ -278,7 +538,8 @@ impl Header {
     }
 
     pub fn write_to(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
-        writer.write_u32::<Endianess>(self.id.hierarchy)?;
+        let flags = (self.attributes.bits().reverse_bits() as u32) << Self::ATTRIBUTES_SHIFT;
+        writer.write_u32::<Endianess>(self.id.hierarchy | flags)?;
         writer.write_i32::<Endianess>(self.id.index.x)?;
         writer.write_i32::<Endianess>(self.id.index.y)?;
         writer.write_i32::<Endianess>(self.id.index.z)?;
@@ -297,9 +558,21 @@ impl Header {
         Ok(())
     }
 
-    pub fn read_from(reader: &mut dyn Read) -> Result<Self, std::io::Error> {
+    pub fn read_from(reader: &mut dyn Read) -> Result<Self, ConverterError> {
+        let hierarchy = reader.read_u32::<Endianess>()?;
+        let attributes = PointAttributes::from_bits(
+            ((hierarchy >> Self::ATTRIBUTES_SHIFT) as u8).reverse_bits(),
+        );
+
+        if attributes | PointAttributes::ALL != PointAttributes::ALL {
+            return Err(ConverterError::Unsupported(format!(
+                "unknown point attributes {:#010b}",
+                attributes.bits()
+            )));
+        }
+
         let id = {
-            let hierarchy = reader.read_u32::<Endianess>()?;
+            let hierarchy = hierarchy & Self::HIERARCHY_MASK;
             let x = reader.read_i32::<Endianess>()?;
             let y = reader.read_i32::<Endianess>()?;
             let z = reader.read_i32::<Endianess>()?;
@@ -331,6 +604,400 @@ impl Header {
             sub_cell_size,
             size,
             pos,
+            attributes,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::PointAttribute;
+
+    fn cell() -> Cell {
+        let id = CellId {
+            hierarchy: 0,
+            index: IVec3::ZERO,
+        };
+
+        Cell::new(id, 1.0, 10.0, Vec3::splat(5.0), 10)
+    }
+
+    #[test]
+    fn pick_returns_the_first_point_along_the_ray() {
+        let mut cell = cell();
+
+        for x in [2.0, 8.0] {
+            let point = Point {
+                pos: Vec3::new(x, 5.0, 5.0),
+                ..Default::default()
+            };
+            cell.add_point(point, PointReduction::First);
+        }
+
+        let picked = |origin: Vec3, direction: Vec3| cell.pick(origin, direction).map(|it| it.pos);
+
+        assert_eq!(
+            picked(Vec3::new(-1.0, 5.0, 5.0), Vec3::X),
+            Some(Vec3::new(2.0, 5.0, 5.0))
+        );
+        assert_eq!(
+            picked(Vec3::new(11.0, 5.0, 5.0), Vec3::NEG_X),
+            Some(Vec3::new(8.0, 5.0, 5.0))
+        );
+        assert_eq!(
+            picked(Vec3::new(8.0, 5.0, 20.0), Vec3::new(0.0, 0.0, -3.0)),
+            Some(Vec3::new(8.0, 5.0, 5.0))
+        );
+        assert_eq!(picked(Vec3::new(-1.0, 1.0, 9.0), Vec3::X), None);
+        assert_eq!(picked(Vec3::new(-1.0, 5.0, 5.0), Vec3::NEG_X), None);
+    }
+
+    #[test]
+    fn header_json_round_trip() {
+        let mut header = cell().header().clone();
+        header.attributes = PointAttribute::Intensity.into();
+
+        let json = serde_json::to_string(&header).unwrap();
+
+        assert_eq!(serde_json::from_str::<Header>(&json).unwrap(), header);
+    }
+
+    fn point(pos: Vec3, gray: u8) -> Point {
+        Point {
+            pos,
+            color: Color::from_rgb(gray, gray, gray),
+            ..Default::default()
+        }
+    }
+
+    fn add_points(reduction: PointReduction) -> (Cell, Vec<Point>) {
+        let mut cell = cell();
+        let center = cell
+            .header()
+            .sub_grid_index_for_point(point(Vec3::splat(0.5), 0));
+        let center = center.to_world(cell.header().sub_cell_size / 2.0);
+
+        let points = vec![
+            point(center + Vec3::splat(0.2), 0),
+            point(center + Vec3::splat(0.01), 90),
+            point(center + Vec3::splat(0.1), 30),
+        ];
+
+        let overflow = cell.add_points(points, reduction);
+
+        (cell, overflow)
+    }
+
+    fn grays(points: &[Point]) -> Vec<u8> {
+        points.iter().map(|point| point.color.r).collect()
+    }
+
+    #[test]
+    fn closest_to_center_keeps_closest_point() {
+        let (cell, overflow) = add_points(PointReduction::ClosestToCenter);
+
+        assert_eq!(grays(&cell.points().copied().collect::<Vec<_>>()), vec![90]);
+        assert_eq!(grays(&overflow), vec![0, 30]);
+    }
+
+    #[test]
+    fn first_keeps_first_point() {
+        let (cell, overflow) = add_points(PointReduction::First);
+
+        assert_eq!(grays(&cell.points().copied().collect::<Vec<_>>()), vec![0]);
+        assert_eq!(grays(&overflow), vec![90, 30]);
+    }
+
+    #[test]
+    fn average_color_keeps_closest_position_with_average_color() {
+        let (cell, overflow) = add_points(PointReduction::AverageColor);
+        let (closest, _) = add_points(PointReduction::ClosestToCenter);
+
+        let kept = cell.points().next().unwrap();
+
+        assert_eq!(kept.pos, closest.points().next().unwrap().pos);
+        assert_eq!(kept.color, Color::from_rgb(40, 40, 40));
+        assert_eq!(grays(&overflow), vec![0, 30]);
+    }
+
+    fn overflow_config(limit: u32) -> MetadataConfig {
+        MetadataConfig {
+            cell_point_overflow_limit: limit,
+            ..MetadataConfig::default()
+        }
+    }
+
+    fn overflow(cell_index: IVec3, len: usize) -> FxHashMap<IVec3, Vec<Point>> {
+        let mut overflow = FxHashMap::default();
+        overflow.insert(cell_index, vec![Point::default(); len]);
+        overflow
+    }
+
+    fn assert_counts(cell: &Cell) {
+        let (number_of_points, number_of_overflow_points) = cell.recount();
+        let header = cell.header();
+
+        assert_eq!(header.number_of_points, number_of_points);
+        assert_eq!(header.number_of_overflow_points, number_of_overflow_points);
+        assert_eq!(
+            header.total_number_of_points,
+            number_of_points + number_of_overflow_points
+        );
+    }
+
+    #[test]
+    fn overflow_under_limit_is_kept() {
+        let mut cell = cell();
+        let config = overflow_config(3);
+
+        let remaining = cell.add_points_in_overflow(overflow(IVec3::ZERO, 2), &config);
+
+        assert!(remaining.is_empty());
+        assert_eq!(cell.header().number_of_overflow_points, 2);
+        assert_counts(&cell);
+    }
+
+    #[test]
+    fn overflow_up_to_limit_is_kept_in_both_branches() {
+        let config = overflow_config(3);
+
+        let mut vacant = cell();
+        let remaining = vacant.add_points_in_overflow(overflow(IVec3::ZERO, 3), &config);
+        assert!(remaining.is_empty());
+        assert_counts(&vacant);
+
+        let mut occupied = cell();
+        occupied.add_points_in_overflow(overflow(IVec3::ZERO, 1), &config);
+        let remaining = occupied.add_points_in_overflow(overflow(IVec3::ZERO, 2), &config);
+        assert!(remaining.is_empty());
+        assert_eq!(occupied.header().number_of_overflow_points, 3);
+        assert_counts(&occupied);
+    }
+
+    #[test]
+    fn crossing_the_overflow_limit_passes_all_points_on() {
+        let mut cell = cell();
+        let config = overflow_config(3);
+
+        cell.add_points_in_overflow(overflow(IVec3::ZERO, 2), &config);
+        let remaining = cell.add_points_in_overflow(overflow(IVec3::ZERO, 2), &config);
+
+        assert_eq!(remaining[&IVec3::ZERO].len(), 4);
+        assert_eq!(
+            cell.overflow_fill().collect::<Vec<_>>(),
+            vec![(&IVec3::ZERO, None)]
+        );
+        assert_eq!(cell.header().number_of_overflow_points, 0);
+        assert_counts(&cell);
+    }
+
+    #[test]
+    fn vacant_overflow_over_limit_passes_all_points_on() {
+        let mut cell = cell();
+        let config = overflow_config(3);
+
+        let remaining = cell.add_points_in_overflow(overflow(IVec3::ZERO, 4), &config);
+
+        assert_eq!(remaining[&IVec3::ZERO].len(), 4);
+        assert_counts(&cell);
+    }
+
+    #[test]
+    fn full_overflow_passes_new_points_on() {
+        let mut cell = cell();
+        let config = overflow_config(3);
+
+        cell.add_points_in_overflow(overflow(IVec3::ZERO, 4), &config);
+        let remaining = cell.add_points_in_overflow(overflow(IVec3::ZERO, 1), &config);
+
+        assert_eq!(remaining[&IVec3::ZERO].len(), 1);
+        assert_eq!(cell.header().number_of_overflow_points, 0);
+        assert_counts(&cell);
+    }
+
+    #[test]
+    fn overflow_counts_match_recount_with_grid_points() {
+        let (mut cell, _) = add_points(PointReduction::ClosestToCenter);
+        let config = overflow_config(3);
+
+        for (index, len) in [(IVec3::X, 1), (IVec3::Y, 3), (IVec3::X, 2), (IVec3::Y, 1)] {
+            cell.add_points_in_overflow(overflow(index, len), &config);
+            assert_counts(&cell);
+        }
+
+        assert_eq!(cell.header().number_of_points, 1);
+        assert_eq!(cell.header().number_of_overflow_points, 3);
+    }
+
+    fn write_and_read(cell: &Cell) -> (Cell, usize) {
+        let mut bytes = Vec::new();
+        cell.write_to(&mut bytes).unwrap();
+        let read = Cell::read_from(&mut bytes.as_slice()).unwrap();
+        (read, bytes.len())
+    }
+
+    #[test]
+    fn gps_time_round_trips() {
+        let mut cell = cell();
+        let mut points = (0..4)
+            .map(|i| Point {
+                gps_time: Some(1_000_000.125 + i as f64),
+                ..point(Vec3::splat(0.5 + i as f32 * 2.0), i as u8)
+            })
+            .collect::<Vec<_>>();
+        points[3].gps_time = None;
+        cell.add_points(points.clone(), PointReduction::ClosestToCenter);
+
+        let (read, _) = write_and_read(&cell);
+
+        assert!(read.header().attributes.contains(PointAttribute::GpsTime));
+        assert_eq!(read.header().id, cell.header().id);
+
+        let mut gps_times = read
+            .points()
+            .map(|point| (point.color.r, point.gps_time))
+            .collect::<Vec<_>>();
+        gps_times.sort_by_key(|(gray, _)| *gray);
+
+        let expected = points
+            .iter()
+            .map(|point| (point.color.r, point.gps_time))
+            .collect::<Vec<_>>();
+        assert_eq!(gps_times, expected);
+    }
+
+    #[test]
+    fn cells_without_gps_time_keep_their_layout() {
+        let mut cell = cell();
+        cell.add_points(
+            vec![point(Vec3::splat(0.5), 0), point(Vec3::splat(2.5), 1)],
+            PointReduction::ClosestToCenter,
+        );
+
+        let (read, len) = write_and_read(&cell);
+
+        assert!(read.header().attributes.is_empty());
+        assert!(read.points().all(|point| point.gps_time.is_none()));
+        assert_eq!(len, 48 + 2 * 16 + 1);
+    }
+
+    #[test]
+    fn truncated_cells_are_corrupt_or_partial() {
+        let mut cell = cell();
+        cell.add_points(
+            (0..5)
+                .map(|i| point(Vec3::splat(0.5 + i as f32 * 2.0), i as u8))
+                .collect(),
+            PointReduction::ClosestToCenter,
+        );
+        cell.add_points_in_overflow(overflow(IVec3::ONE, 3), &overflow_config(10));
+
+        let mut bytes = Vec::new();
+        cell.write_to(&mut bytes).unwrap();
+
+        let header_size = 48;
+
+        for len in 0..bytes.len() {
+            let truncated = &bytes[..len];
+
+            let err = Cell::read_from(&mut &truncated[..]).unwrap_err();
+            assert!(matches!(err, ConverterError::CorruptData { .. }), "{}", len);
+
+            let partial = Cell::read_partial_from(&mut &truncated[..]);
+
+            if len < header_size {
+                assert!(partial.is_err());
+                continue;
+            }
+
+            let (partial, is_truncated) = partial.unwrap();
+            assert!(is_truncated);
+            assert!(partial.header().total_number_of_points < 8);
+            assert_counts(&partial);
+        }
+
+        let (complete, is_truncated) = Cell::read_partial_from(&mut &bytes[..]).unwrap();
+        assert!(!is_truncated);
+        assert_eq!(complete.header().total_number_of_points, 8);
+        assert_counts(&complete);
+    }
+
+    #[test]
+    fn missing_attributes_survive_a_round_trip() {
+        let points = vec![
+            Point {
+                intensity: Some(0),
+                classification: Some(2),
+                ..point(Vec3::splat(0.5), 0)
+            },
+            Point {
+                gps_time: Some(1.5),
+                classification: Some(0),
+                ..point(Vec3::splat(2.5), 1)
+            },
+            point(Vec3::splat(4.5), 2),
+            // falls into the overflow
+            Point {
+                intensity: Some(7),
+                ..point(Vec3::splat(0.6), 3)
+            },
+        ];
+
+        let mut cell = cell();
+        let overflow = cell.add_points(points.clone(), PointReduction::First);
+        cell.add_points_in_overflow(
+            FxHashMap::from_iter([(IVec3::ZERO, overflow)]),
+            &MetadataConfig::default(),
+        );
+
+        let mut bytes = Vec::new();
+        cell.write_to(&mut bytes).unwrap();
+        let read = Cell::read_from(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(read.header().attributes, PointAttributes::ALL);
+
+        let mut read_points = read.all_points().copied().collect::<Vec<_>>();
+        read_points.sort_by_key(|point| point.color.r);
+        assert_eq!(read_points, points);
+    }
+
+    #[test]
+    fn cells_store_the_attributes_of_their_points() {
+        let mut cell = cell();
+        cell.add_points(
+            vec![
+                Point {
+                    intensity: Some(500),
+                    ..point(Vec3::splat(0.5), 0)
+                },
+                point(Vec3::splat(2.5), 1),
+            ],
+            PointReduction::ClosestToCenter,
+        );
+
+        let mut bytes = Vec::new();
+        cell.write_to(&mut bytes).unwrap();
+
+        let read = Cell::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(
+            read.header().attributes,
+            PointAttributes::from(PointAttribute::Intensity)
+        );
+        assert_eq!(read.header().id, cell.header().id);
+
+        let mut intensities = read
+            .points()
+            .map(|point| point.intensity)
+            .collect::<Vec<_>>();
+        intensities.sort();
+        assert_eq!(intensities, vec![None, Some(500)]);
+
+        // a cell of a newer version with an unknown attribute
+        bytes[3] |= 0x01;
+        assert!(matches!(
+            Cell::read_from(&mut bytes.as_slice()),
+            Err(ConverterError::Unsupported(_))
+        ));
+    }
+}
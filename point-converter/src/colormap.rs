@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::point::Color;
+
+/// Maps a value between 0 and 1 to a color.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Colormap {
+    Grayscale,
+    Viridis,
+}
+
+impl Colormap {
+    /// Samples of viridis at equal distances.
+    const VIRIDIS: [[u8; 3]; 9] = [
+        [68, 1, 84],
+        [71, 44, 122],
+        [59, 81, 139],
+        [44, 113, 142],
+        [33, 144, 141],
+        [39, 173, 129],
+        [92, 200, 99],
+        [170, 220, 50],
+        [253, 231, 37],
+    ];
+
+    /// Values outside of 0 to 1 are clamped.
+    pub fn map(self, value: f32) -> Color {
+        let value = value.clamp(0.0, 1.0);
+
+        match self {
+            Colormap::Grayscale => {
+                let gray = (value * 255.0).round() as u8;
+                Color::from_rgb(gray, gray, gray)
+            }
+            Colormap::Viridis => {
+                let position = value * (Self::VIRIDIS.len() - 1) as f32;
+                let index = (position.floor() as usize).min(Self::VIRIDIS.len() - 2);
+                let t = position - index as f32;
+
+                let [r, g, b] = [0, 1, 2].map(|channel| {
+                    let from = Self::VIRIDIS[index][channel] as f32;
+                    let to = Self::VIRIDIS[index + 1][channel] as f32;
+                    (from + (to - from) * t).round() as u8
+                });
+
+                Color::from_rgb(r, g, b)
+            }
+        }
+    }
+}
+
+/// Colors points by their intensity.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct IntensityColors {
+    pub colormap: Colormap,
+    /// Intensity which is mapped to the end of the colormap.
+    pub max_intensity: u16,
+}
+
+impl IntensityColors {
+    /// Not every file uses the full 16 bit range for intensities,
+    /// so the range is guessed from the largest intensity, rounded up to a full bit depth of at least 8 bits.
+    pub fn new<T: IntoIterator<Item = u16>>(colormap: Colormap, intensities: T) -> Self {
+        let largest = intensities.into_iter().max().unwrap_or(0);
+        let max_intensity = ((largest as u32 + 1).next_power_of_two() - 1).max(u8::MAX as u32);
+
+        Self {
+            colormap,
+            max_intensity: max_intensity as u16,
+        }
+    }
+
+    pub fn color(&self, intensity: u16) -> Color {
+        self.colormap
+            .map(intensity as f32 / self.max_intensity as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colormap_ends() {
+        assert_eq!(Colormap::Grayscale.map(0.0), Color::from_rgb(0, 0, 0));
+        assert_eq!(Colormap::Grayscale.map(1.0), Color::from_rgb(255, 255, 255));
+        assert_eq!(Colormap::Viridis.map(0.0), Color::from_rgb(68, 1, 84));
+        assert_eq!(Colormap::Viridis.map(1.0), Color::from_rgb(253, 231, 37));
+        assert_eq!(Colormap::Viridis.map(2.0), Colormap::Viridis.map(1.0));
+    }
+
+    #[test]
+    fn intensity_range_is_rounded_up_to_bit_depth() {
+        let range = |intensities: &[u16]| {
+            IntensityColors::new(Colormap::Grayscale, intensities.iter().copied()).max_intensity
+        };
+
+        assert_eq!(range(&[]), 255);
+        assert_eq!(range(&[3, 200]), 255);
+        assert_eq!(range(&[256]), 511);
+        assert_eq!(range(&[1000, 4000]), 4095);
+        assert_eq!(range(&[u16::MAX]), u16::MAX);
+    }
+
+    #[test]
+    fn intensity_color() {
+        let intensity_colors = IntensityColors::new(Colormap::Grayscale, [4000]);
+
+        assert_eq!(intensity_colors.color(0), Color::from_rgb(0, 0, 0));
+        assert_eq!(intensity_colors.color(4095), Color::from_rgb(255, 255, 255));
+        assert_eq!(intensity_colors.color(2048), Color::from_rgb(128, 128, 128));
+    }
+}
@@ -1,32 +1,120 @@
 use std::collections::hash_map::Entry;
+use std::fmt::{Display, Formatter};
 use std::fs::{create_dir, create_dir_all, File};
 use std::hash::BuildHasherDefault;
-use std::io::{BufWriter, Cursor, ErrorKind, Write};
+use std::io::{BufWriter, Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use bounding_volume::Aabb;
 use caches::{Cache, LRUCache, PutResult};
-use glam::IVec3;
-use rustc_hash::{FxHashMap, FxHasher};
+use glam::{IVec3, Vec2};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 
+pub use archive::BatchedArchivePointReader;
 pub use las::BatchedLasPointReader;
 pub use own::BatchedPointCloudPointReader;
 pub use ply::BatchedPlyPointReader;
 
 use crate::cell::{Cell, CellId};
+use crate::colormap::Colormap;
+use crate::error::ConverterError;
+use crate::index::CellIndex;
 use crate::metadata::{Metadata, MetadataConfig};
-use crate::point::Point;
+use crate::palette::Palette;
+use crate::point::{ChannelOrder, Point, PointAttributes};
 
+mod archive;
 mod las;
 mod own;
 mod ply;
 
 pub trait BatchedPointReader {
-    fn get_batch(&mut self, size: usize) -> Result<Vec<Point>, std::io::Error>;
+    fn get_batch(&mut self, size: usize) -> Result<Vec<Point>, ConverterError>;
 
     fn total_points(&self) -> u64;
 
     fn remaining_points(&self) -> u64;
+
+    /// Points of sources without colors get a default color instead.
+    fn has_colors(&self) -> bool {
+        true
+    }
+
+    /// Colors the points by their intensity instead of their colors.
+    /// Returns false if the source has no intensities.
+    fn color_by_intensity(&mut self, _colormap: Colormap) -> bool {
+        false
+    }
+
+    /// Bytes of the source which were read so far.
+    /// Compressed sources don't take the same time for every point,
+    /// so their progress is better measured in bytes.
+    fn bytes_processed(&self) -> Option<u64> {
+        None
+    }
+
+    /// Size of the source, see [BatchedPointReader::bytes_processed].
+    fn bytes_total(&self) -> Option<u64> {
+        None
+    }
+
+    /// Fraction of the source which was read, by bytes if the reader knows them
+    /// and by points otherwise.
+    fn progress(&self) -> f64 {
+        match (self.bytes_processed(), self.bytes_total()) {
+            (Some(processed), Some(total)) if total > 0 => {
+                (processed as f64 / total as f64).min(1.0)
+            }
+            _ => {
+                let total = self.total_points();
+
+                if total == 0 {
+                    1.0
+                } else {
+                    (total - self.remaining_points()) as f64 / total as f64
+                }
+            }
+        }
+    }
+}
+
+/// Shares the position of the wrapped reader,
+/// for readers which move it into a parser, like [las::Reader].
+#[derive(Debug)]
+pub(crate) struct PositionTrackingReader<R> {
+    inner: R,
+    position: Arc<AtomicU64>,
+}
+
+impl<R> PositionTrackingReader<R> {
+    pub(crate) fn new(inner: R) -> (Self, Arc<AtomicU64>) {
+        let position = Arc::new(AtomicU64::new(0));
+
+        let reader = Self {
+            inner,
+            position: Arc::clone(&position),
+        };
+
+        (reader, position)
+    }
+}
+
+impl<R: Read> Read for PositionTrackingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for PositionTrackingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.position.store(position, Ordering::Relaxed);
+        Ok(position)
+    }
 }
 
 pub fn group_points(
@@ -64,19 +152,164 @@ pub fn add_points_to_cell(
     points: Vec<Point>,
     cell: &mut Cell,
 ) -> FxHashMap<IVec3, Vec<Point>> {
-    let overflow_points = cell.add_points(points);
+    let overflow_points = cell.add_points(points, config.point_reduction);
     let overflow_points = group_points(overflow_points, cell.header().id.hierarchy + 1, config);
     cell.add_points_in_overflow(overflow_points, config)
 }
 
+/// Points which still overflow the cells of the last hierarchy are practically coincident,
+/// e.g. from duplicated scans, and are dropped instead of subdividing their cells forever.
+/// Returns the number of dropped points.
+pub fn drop_coincident_points(points: FxHashMap<IVec3, Vec<Point>>) -> usize {
+    let mut dropped = 0;
+
+    for points in points.values() {
+        if let Some(point) = points.first() {
+            log::warn!(
+                "Dropping {} coincident points at {}, which don't fit into hierarchy {}",
+                points.len(),
+                point.pos,
+                Metadata::MAX_HIERARCHIES - 1
+            );
+        }
+
+        dropped += points.len();
+    }
+
+    dropped
+}
+
+/// Counts how often cells were found in the cell cache of the [Converter].
+#[derive(Debug, Default, Copy, Clone)]
+pub struct CellCacheStats {
+    /// Cell was already in the cache.
+    pub hits: u64,
+    /// Cell had to be loaded from disk or newly created.
+    pub misses: u64,
+    /// Cell was written to disk to make room for another cell.
+    pub evictions: u64,
+}
+
+impl CellCacheStats {
+    /// Fraction of cell accesses which were served from the cache.
+    pub fn hit_rate(&self) -> f64 {
+        let accesses = self.hits + self.misses;
+
+        if accesses == 0 {
+            0.0
+        } else {
+            self.hits as f64 / accesses as f64
+        }
+    }
+}
+
+impl Display for CellCacheStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses, {} evictions, {:.1}% hit rate",
+            self.hits,
+            self.misses,
+            self.evictions,
+            self.hit_rate() * 100.0
+        )
+    }
+}
+
+/// Removes duplicated points where tiles of a tiled dataset overlap.
+/// Tiles are often exported with a buffer around them, which repeats points of their neighbours,
+/// so points of a tile are dropped if an earlier tile had a point in the same cube of [SeamDeduplication::KEY_SIZE].
+///
+/// Only the keys of points within [SeamDeduplication::BORDER_WIDTH] of the x and y bounds of a tile
+/// are kept for the following tiles, as the tiles are expected to be split horizontally.
+/// An outlier which widens the bounds of a tile can only cause duplicates to be kept, never unique points to be dropped.
+#[derive(Debug, Default)]
+pub struct SeamDeduplication {
+    /// Keys of the points of earlier tiles close to their bounds.
+    border_keys: FxHashSet<SeamKey>,
+    /// Keys of the points of the current tile with their horizontal position.
+    current_keys: FxHashMap<SeamKey, Vec2>,
+    current_tile: Option<Aabb>,
+}
+
+/// Like [Point::quantized_key], but with 64 bit integers,
+/// which don't overflow for projected coordinates like UTM.
+type SeamKey = (i64, i64, i64);
+
+impl SeamDeduplication {
+    /// Edge length of the cubes of the keys, points closer than this can be taken as duplicates.
+    pub const KEY_SIZE: f64 = 0.001;
+    /// Overlaps up to this wide are deduplicated.
+    pub const BORDER_WIDTH: f32 = 10.0;
+
+    fn key(point: &Point) -> SeamKey {
+        let key = (point.pos.as_dvec3() / Self::KEY_SIZE).floor();
+        (key.x as i64, key.y as i64, key.z as i64)
+    }
+
+    /// Drops the points which earlier tiles already had.
+    /// Returns the number of dropped points.
+    pub fn filter(&mut self, points: &mut Vec<Point>) -> usize {
+        if let Some(aabb) = Aabb::from(points.iter().map(|point| point.pos)) {
+            match &mut self.current_tile {
+                Some(current_tile) => current_tile.extend_aabb(&aabb),
+                None => self.current_tile = Some(aabb),
+            }
+        }
+
+        let len = points.len();
+        points.retain(|point| !self.border_keys.contains(&Self::key(point)));
+
+        self.current_keys.extend(
+            points
+                .iter()
+                .map(|point| (Self::key(point), point.pos.truncate())),
+        );
+
+        len - points.len()
+    }
+
+    /// Following points belong to the next tile.
+    pub fn finish_tile(&mut self) {
+        let current_keys = std::mem::take(&mut self.current_keys);
+
+        let Some(tile) = self.current_tile.take() else {
+            return;
+        };
+
+        let inner_min = tile.min.truncate() + Self::BORDER_WIDTH;
+        let inner_max = tile.max.truncate() - Self::BORDER_WIDTH;
+
+        self.border_keys.extend(
+            current_keys
+                .into_iter()
+                .filter(|(_, pos)| !(pos.cmpgt(inner_min).all() && pos.cmplt(inner_max).all()))
+                .map(|(key, _)| key),
+        );
+    }
+}
+
 pub struct Converter {
     metadata: Metadata,
     working_directory: PathBuf,
     cell_cache: LRUCache<CellId, Cell, BuildHasherDefault<FxHasher>>,
+    cell_cache_stats: CellCacheStats,
 }
 
 impl Converter {
+    pub const DEFAULT_CELL_CACHE_SIZE: usize = 100;
+
     pub fn new(metadata: Metadata, working_directory: &Path) -> Self {
+        Self::with_cache_size(metadata, working_directory, Self::DEFAULT_CELL_CACHE_SIZE)
+    }
+
+    /// Creates a converter which holds up to `cache_size` cells in memory before writing
+    /// the least recently used ones to disk. At least one cell is always held.
+    pub fn with_cache_size(
+        metadata: Metadata,
+        working_directory: &Path,
+        cache_size: usize,
+    ) -> Self {
         if let Err(err) = create_dir_all(working_directory) {
             match err.kind() {
                 ErrorKind::AlreadyExists => {}
@@ -89,10 +322,16 @@ impl Converter {
         Self {
             metadata,
             working_directory: working_directory.to_path_buf(),
-            cell_cache: LRUCache::with_hasher(100, BuildHasherDefault::default()).unwrap(),
+            cell_cache: LRUCache::with_hasher(cache_size.max(1), BuildHasherDefault::default())
+                .unwrap(),
+            cell_cache_stats: CellCacheStats::default(),
         }
     }
 
+    pub fn cell_cache_stats(&self) -> CellCacheStats {
+        self.cell_cache_stats
+    }
+
     fn update_bounding_box(&mut self, points: &[Point]) {
         if let Some(aabb) = Aabb::from(points.iter().map(|point| point.pos)) {
             if self.metadata.number_of_points == 0 {
@@ -103,10 +342,41 @@ impl Converter {
         }
     }
 
-    pub fn add_points_batch(&mut self, points: Vec<Point>) {
+    pub fn palette(&self) -> Option<&Palette> {
+        self.metadata.palette.as_ref()
+    }
+
+    /// Colors of all following points are snapped to the palette.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.metadata.palette = Some(palette);
+    }
+
+    pub fn set_intensity_colormap(&mut self, colormap: Colormap) {
+        self.metadata.intensity_colormap = Some(colormap);
+    }
+
+    pub fn set_default_color(&mut self, color: [u8; 4]) {
+        self.metadata.default_color = Some(color);
+    }
+
+    pub fn set_channel_order(&mut self, channel_order: ChannelOrder) {
+        self.metadata.channel_order = Some(channel_order);
+    }
+
+    pub fn add_points_batch(&mut self, mut points: Vec<Point>) {
+        if let Some(palette) = &self.metadata.palette {
+            for point in &mut points {
+                point.color = palette.quantize(point.color);
+            }
+        }
+
         self.update_bounding_box(&points);
         self.metadata.number_of_points += points.len() as u64;
 
+        for point in &points {
+            self.metadata.attributes |= PointAttributes::of(point);
+        }
+
         let grouped_points = group_points(points, 0, &self.metadata.config);
         self.add_points_in_hierarchy(0, &self.metadata.config.clone(), grouped_points);
     }
@@ -133,8 +403,14 @@ impl Converter {
             merge_point_maps(&mut next_hierarchy_points, remaining_points);
         }
 
-        if !next_hierarchy_points.is_empty() {
+        if next_hierarchy_points.is_empty() {
+            return;
+        }
+
+        if hierarchy + 1 < Metadata::MAX_HIERARCHIES {
             self.add_points_in_hierarchy(hierarchy + 1, config, next_hierarchy_points);
+        } else {
+            self.metadata.number_of_points -= drop_coincident_points(next_hierarchy_points) as u64;
         }
     }
 
@@ -158,17 +434,48 @@ impl Converter {
     }
 
     fn get_cell_mut(&mut self, cell_id: CellId) -> &mut Cell {
-        if !self.cell_cache.contains(&cell_id) {
-            let cell =
-                self.load_or_create_cell(&self.working_directory.join(cell_id.path()), cell_id);
+        if self.cell_cache.contains(&cell_id) {
+            self.cell_cache_stats.hits += 1;
+        } else {
+            self.cell_cache_stats.misses += 1;
+
+            let mut cell = self.load_or_create_cell(
+                &self
+                    .working_directory
+                    .join(cell_id.path(&self.metadata.cell_extension)),
+                cell_id,
+            );
+
+            Self::load_color_sums(
+                &self
+                    .working_directory
+                    .join(cell_id.path(Cell::COLOR_SUMS_EXTENSION)),
+                &mut cell,
+            )
+            .unwrap();
 
             if let PutResult::Evicted {
                 key: old_cell_id,
                 value: old_cell,
             } = self.cell_cache.put(cell_id, cell)
             {
-                Self::save_cell(&self.working_directory.join(old_cell_id.path()), &old_cell)
-                    .unwrap();
+                self.cell_cache_stats.evictions += 1;
+
+                Self::save_cell(
+                    &self
+                        .working_directory
+                        .join(old_cell_id.path(&self.metadata.cell_extension)),
+                    &old_cell,
+                )
+                .unwrap();
+
+                Self::save_color_sums(
+                    &self
+                        .working_directory
+                        .join(old_cell_id.path(Cell::COLOR_SUMS_EXTENSION)),
+                    &old_cell,
+                )
+                .unwrap();
             }
         }
 
@@ -178,10 +485,9 @@ impl Converter {
     }
 
     fn load_cell(&self, cell_path: &Path) -> Result<Cell, std::io::Error> {
-        std::fs::read(cell_path).and_then(|bytes| {
-            let mut cursor = Cursor::new(bytes);
-            Cell::read_from(&mut cursor)
-        })
+        let bytes = std::fs::read(cell_path)?;
+        let mut cursor = Cursor::new(bytes);
+        Ok(Cell::read_from(&mut cursor)?)
     }
 
     fn load_or_create_cell(&self, cell_path: &Path, id: CellId) -> Cell {
@@ -196,7 +502,7 @@ impl Converter {
                         self.metadata.config.sub_cell_size(cell_size),
                         cell_size,
                         cell_pos,
-                        50_000,
+                        self.metadata.config.cell_capacity(),
                     )
                 }
                 _ => {
@@ -206,7 +512,12 @@ impl Converter {
         }
     }
 
+    /// Cells without points are not written, so that readers never have to deal with them.
     fn save_cell(cell_path: &Path, cell: &Cell) -> Result<(), std::io::Error> {
+        if cell.header().total_number_of_points == 0 {
+            return Ok(());
+        }
+
         let file = File::create(cell_path)?;
         let mut buf_writer = BufWriter::new(file);
         cell.write_to(&mut buf_writer)?;
@@ -215,14 +526,71 @@ impl Converter {
         Ok(())
     }
 
+    /// Color sums of evicted cells are written next to them, see [Cell::write_color_sums].
+    fn save_color_sums(path: &Path, cell: &Cell) -> Result<(), std::io::Error> {
+        if !cell.has_color_sums() {
+            return Ok(());
+        }
+
+        let file = File::create(path)?;
+        let mut buf_writer = BufWriter::new(file);
+        cell.write_color_sums(&mut buf_writer)?;
+        buf_writer.flush()
+    }
+
+    /// The file is removed after reading, so that it never outlives a newer version of the cell.
+    fn load_color_sums(path: &Path, cell: &mut Cell) -> Result<(), std::io::Error> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        cell.read_color_sums(&mut Cursor::new(bytes))?;
+        std::fs::remove_file(path)
+    }
+
+    /// Color sums are only needed while converting and aren't part of the converted point cloud.
+    fn remove_color_sums(&self) -> Result<(), std::io::Error> {
+        for hierarchy in 0..self.metadata.hierarchies {
+            let hierarchy_dir = self
+                .working_directory
+                .join(Metadata::hierarchy_string(hierarchy));
+
+            for dir_entry in hierarchy_dir.read_dir()? {
+                let path = dir_entry?.path();
+
+                if path
+                    .extension()
+                    .is_some_and(|it| it == Cell::COLOR_SUMS_EXTENSION)
+                {
+                    std::fs::remove_file(path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn save_cache(&self) -> Result<(), std::io::Error> {
         for (cell_id, cell) in &self.cell_cache {
-            Self::save_cell(&self.working_directory.join(cell_id.path()), cell)?;
+            Self::save_cell(
+                &self
+                    .working_directory
+                    .join(cell_id.path(&self.metadata.cell_extension)),
+                cell,
+            )?;
         }
 
         Ok(())
     }
 
+    /// Writes the index of all existing cells, including the ones from previous conversions.
+    pub fn save_cell_index(&self) -> Result<(), std::io::Error> {
+        let index = CellIndex::from_directory(&self.working_directory, self.metadata.hierarchies)?;
+        index.save(self.working_directory.join(CellIndex::path()))
+    }
+
     pub fn save_metadata(&self) -> Result<(), std::io::Error> {
         let path = self
             .working_directory
@@ -241,6 +609,314 @@ impl Converter {
 impl Drop for Converter {
     fn drop(&mut self) {
         self.save_cache().unwrap();
+        self.remove_color_sums().unwrap();
         self.save_metadata().unwrap();
+        self.save_cell_index().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::metadata::PointReduction;
+    use crate::point::Color;
+
+    use super::*;
+
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("point-converter-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn conversion_never_writes_empty_cells() {
+        let directory = temp_directory("empty-cells");
+
+        // many points at the same positions are pushed into deeper hierarchies
+        let points = (0..20_000)
+            .map(|i| Point {
+                pos: Vec3::new(
+                    (i % 7) as f32,
+                    (i % 5) as f32 * 10.0,
+                    (i % 3) as f32 * 100.0,
+                ),
+                color: Color::from_rgb(255, 0, 0),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        {
+            let mut converter = Converter::with_cache_size(Metadata::default(), &directory, 2);
+            converter.add_points_batch(points);
+        }
+
+        let mut number_of_cells = 0;
+
+        for dir_entry in std::fs::read_dir(&directory).unwrap() {
+            let path = dir_entry.unwrap().path();
+
+            if !path.is_dir() {
+                continue;
+            }
+
+            for cell_entry in std::fs::read_dir(&path).unwrap() {
+                let cell_path = cell_entry.unwrap().path();
+
+                if !Cell::has_extension(&cell_path) {
+                    continue;
+                }
+                let cell = Cell::from_path(&cell_path).unwrap();
+
+                assert!(
+                    cell.header().total_number_of_points > 0,
+                    "{:?} has no points",
+                    cell_path
+                );
+
+                number_of_cells += 1;
+            }
+        }
+
+        assert!(number_of_cells > 0, "no cells were written");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn coincident_points_stop_at_last_hierarchy() {
+        let directory = temp_directory("coincident-points");
+
+        let points = vec![
+            Point {
+                pos: Vec3::new(1.0, 2.0, 3.0),
+                color: Color::from_rgb(0, 255, 0),
+                ..Default::default()
+            };
+            100_000
+        ];
+
+        let metadata = {
+            let mut converter = Converter::new(Metadata::default(), &directory);
+            converter.add_points_batch(points);
+            converter.metadata.clone()
+        };
+
+        assert_eq!(metadata.hierarchies, Metadata::MAX_HIERARCHIES);
+        // every hierarchy keeps the single point in its sub grid cell
+        assert_eq!(metadata.number_of_points, Metadata::MAX_HIERARCHIES as u64);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn cache_size_of_zero_holds_a_single_cell() {
+        let directory = temp_directory("zero-cache-size");
+
+        let points = (0..1_000)
+            .map(|i| Point {
+                pos: Vec3::new(i as f32 * 10.0, 0.0, 0.0),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let metadata = {
+            let mut converter = Converter::with_cache_size(Metadata::default(), &directory, 0);
+            converter.add_points_batch(points);
+            converter.metadata.clone()
+        };
+
+        assert_eq!(metadata.number_of_points, 1_000);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn average_colors_do_not_depend_on_cache_size() {
+        let convert = |name: &str, cache_size: usize| {
+            let directory = temp_directory(name);
+            let mut metadata = Metadata::default();
+            metadata.config.point_reduction = PointReduction::AverageColor;
+
+            {
+                let mut converter = Converter::with_cache_size(metadata, &directory, cache_size);
+
+                // every batch adds to the same sub grid cells of two far apart cells,
+                // so that a small cache evicts and reloads them in between
+                for batch in 0..10u8 {
+                    let points = [0.0, 10_000.0]
+                        .into_iter()
+                        .map(|x| Point {
+                            pos: Vec3::new(x + batch as f32 * 0.001, 0.0, 0.0),
+                            color: Color::from_rgb(batch * 20, 0, 0),
+                            ..Default::default()
+                        })
+                        .collect();
+
+                    converter.add_points_batch(points);
+                }
+            }
+
+            let mut colors = Vec::new();
+
+            for dir_entry in std::fs::read_dir(&directory).unwrap() {
+                let path = dir_entry.unwrap().path();
+
+                if !path.is_dir() {
+                    continue;
+                }
+
+                for cell_entry in std::fs::read_dir(&path).unwrap() {
+                    let cell_path = cell_entry.unwrap().path();
+                    assert_ne!(
+                        cell_path.extension().unwrap(),
+                        Cell::COLOR_SUMS_EXTENSION,
+                        "color sums are removed after the conversion"
+                    );
+
+                    if Cell::has_extension(&cell_path) {
+                        let cell = Cell::from_path(&cell_path).unwrap();
+                        colors.extend(cell.points().map(|point| (cell.header().id, point.color)));
+                    }
+                }
+            }
+
+            std::fs::remove_dir_all(&directory).unwrap();
+
+            colors.sort_by_key(|(id, color)| (id.hierarchy, id.index.to_array(), color.r));
+            colors
+        };
+
+        let cached = convert("average-color-cached", Converter::DEFAULT_CELL_CACHE_SIZE);
+        let evicted = convert("average-color-evicted", 1);
+
+        assert!(!cached.is_empty());
+        assert_eq!(cached, evicted);
+    }
+
+    #[test]
+    fn seam_deduplication_keeps_distinct_points_of_the_overlap() {
+        let points = |xs: &mut dyn Iterator<Item = f32>| {
+            xs.map(|x| Point {
+                pos: Vec3::new(x, 0.0, 1.0),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>()
+        };
+
+        let mut seam_deduplication = SeamDeduplication::default();
+
+        // the outlier widens the bounds of the first tile over the whole second tile
+        let mut first_tile = points(&mut (0..=10).map(|x| x as f32).chain([1_000.0]));
+        assert_eq!(seam_deduplication.filter(&mut first_tile), 0);
+        seam_deduplication.finish_tile();
+
+        let mut second_tile = points(&mut (5..=20).map(|x| x as f32 + 0.5));
+        assert_eq!(seam_deduplication.filter(&mut second_tile), 0);
+        assert_eq!(second_tile.len(), 16);
+        seam_deduplication.finish_tile();
+
+        let mut third_tile = points(&mut (15..=25).map(|x| x as f32 + 0.5));
+        assert_eq!(seam_deduplication.filter(&mut third_tile), 6);
+        assert_eq!(third_tile.first().unwrap().pos.x, 21.5);
+    }
+
+    #[test]
+    fn seam_deduplication_keeps_points_with_projected_coordinates() {
+        let point = |x: f32, y: f32| Point {
+            pos: Vec3::new(x, y, 100.0),
+            ..Default::default()
+        };
+
+        let mut seam_deduplication = SeamDeduplication::default();
+
+        // utm coordinates, which overflow an i32 key of millimeters
+        let mut first_tile = vec![point(500_000.0, 5_399_950.0), point(500_000.0, 5_400_000.0)];
+        assert_eq!(seam_deduplication.filter(&mut first_tile), 0);
+        seam_deduplication.finish_tile();
+
+        let mut second_tile = vec![point(500_000.0, 5_400_000.0), point(500_000.0, 5_400_050.0)];
+        assert_eq!(seam_deduplication.filter(&mut second_tile), 1);
+        assert_eq!(second_tile, vec![point(500_000.0, 5_400_050.0)]);
+    }
+
+    #[test]
+    fn empty_cell_is_not_saved() {
+        let directory = temp_directory("save-empty-cell");
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let id = CellId {
+            hierarchy: 0,
+            index: IVec3::ZERO,
+        };
+        let cell = Cell::new(id, 1.0, 10.0, Vec3::ZERO, 10);
+        let cell_path = directory.join("empty").with_extension(Cell::EXTENSION);
+
+        Converter::save_cell(&cell_path, &cell).unwrap();
+
+        assert!(!cell_path.exists());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn cells_are_found_with_either_separator() {
+        let directory = temp_directory("separators");
+        let archive_path = directory.with_extension(crate::archive::Archive::EXTENSION);
+
+        let points = (0..1_000)
+            .map(|i| Point {
+                pos: Vec3::new(i as f32, (i % 10) as f32, 0.0),
+                color: Color::from_rgb(0, 0, 255),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        {
+            let mut converter = Converter::new(Metadata::default(), &directory);
+            converter.add_points_batch(points);
+        }
+
+        crate::archive::write_archive(&directory, &archive_path).unwrap();
+        let archive = crate::archive::Archive::from_path(&archive_path).unwrap();
+
+        let cell_ids = archive.cell_ids().copied().collect::<Vec<_>>();
+        assert!(!cell_ids.is_empty());
+
+        for cell_id in cell_ids {
+            // as if the path came from a directory which was converted on windows
+            let windows_path = cell_id.logical_path(Cell::EXTENSION).replace('/', "\\");
+
+            let cell = Cell::from_path(directory.join(crate::logical_path::to_path(&windows_path)))
+                .unwrap();
+            assert_eq!(cell.header().id, cell_id);
+
+            assert_eq!(
+                archive.entry_for_path(&windows_path),
+                archive.cell_entry(&cell_id)
+            );
+            assert!(archive
+                .entry_for_path(&cell_id.logical_path(Cell::EXTENSION))
+                .is_some());
+        }
+
+        std::fs::remove_dir_all(&directory).unwrap();
+        std::fs::remove_file(&archive_path).unwrap();
+    }
+
+    #[test]
+    fn position_tracking_reader_follows_reads_and_seeks() {
+        let (mut reader, position) = PositionTrackingReader::new(Cursor::new(vec![0u8; 100]));
+
+        reader.read_exact(&mut [0; 10]).unwrap();
+        assert_eq!(position.load(Ordering::Relaxed), 10);
+
+        reader.seek(SeekFrom::End(-20)).unwrap();
+        assert_eq!(position.load(Ordering::Relaxed), 80);
+
+        reader.read_to_end(&mut Vec::new()).unwrap();
+        assert_eq!(position.load(Ordering::Relaxed), 100);
     }
 }
@@ -0,0 +1,91 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use itertools::Itertools;
+
+use crate::archive::Archive;
+use crate::cell::CellId;
+use crate::converter::BatchedPointReader;
+use crate::error::ConverterError;
+use crate::point::Point;
+
+pub struct BatchedArchivePointReader {
+    archive: Archive,
+    reader: BufReader<File>,
+    remaining_cells: Vec<CellId>,
+    buffered_points: Vec<Point>,
+    read_points: u64,
+    read_bytes: u64,
+}
+
+impl BatchedArchivePointReader {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, ConverterError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let archive = Archive::read_from(&mut reader)?;
+        let remaining_cells = archive.cell_ids().copied().collect_vec();
+
+        Ok(Self {
+            archive,
+            reader,
+            remaining_cells,
+            buffered_points: Vec::new(),
+            read_points: 0,
+            read_bytes: 0,
+        })
+    }
+}
+
+impl BatchedPointReader for BatchedArchivePointReader {
+    fn get_batch(&mut self, size: usize) -> Result<Vec<Point>, ConverterError> {
+        let batch_size = self.remaining_points().min(size as u64) as usize;
+
+        while self.buffered_points.len() < batch_size {
+            let Some(cell_id) = self.remaining_cells.pop() else {
+                break;
+            };
+
+            if let Some(cell) = self.archive.read_cell(&mut self.reader, &cell_id)? {
+                self.buffered_points.extend(cell.all_points().copied());
+            }
+
+            self.read_bytes += self
+                .archive
+                .cell_entry(&cell_id)
+                .map_or(0, |entry| entry.length);
+        }
+
+        let batch_size = batch_size.min(self.buffered_points.len());
+        let batch = self
+            .buffered_points
+            .drain(self.buffered_points.len() - batch_size..)
+            .collect_vec();
+
+        self.read_points += batch.len() as u64;
+
+        Ok(batch)
+    }
+
+    fn total_points(&self) -> u64 {
+        self.archive.metadata().number_of_points
+    }
+
+    fn remaining_points(&self) -> u64 {
+        self.total_points() - self.read_points
+    }
+
+    /// Counts the cells which were read, including the points which are still buffered.
+    fn bytes_processed(&self) -> Option<u64> {
+        Some(self.read_bytes)
+    }
+
+    fn bytes_total(&self) -> Option<u64> {
+        Some(
+            self.archive
+                .cell_ids()
+                .filter_map(|id| self.archive.cell_entry(id))
+                .map(|entry| entry.length)
+                .sum(),
+        )
+    }
+}
@@ -1,35 +1,80 @@
+use std::fs::File;
+use std::io::BufReader;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use las::{Read, Reader};
 
-use crate::converter::BatchedPointReader;
-use crate::point::Point;
+use crate::colormap::{Colormap, IntensityColors};
+use crate::converter::{BatchedPointReader, PositionTrackingReader};
+use crate::error::ConverterError;
+use crate::point::{Color, Point};
 
 pub struct BatchedLasPointReader {
     reader: Reader<'static>,
     read_points: u64,
+    intensity_colormap: Option<Colormap>,
+    /// Determined from the first batch.
+    intensity_colors: Option<IntensityColors>,
+    /// Position in the file, which for laz grows with the compressed chunks instead of the points.
+    position: Arc<AtomicU64>,
+    file_size: u64,
 }
 
 impl BatchedLasPointReader {
-    pub fn new<P: AsRef<Path>>(path: P) -> Self {
-        Self {
-            reader: Reader::from_path(path).unwrap(),
+    pub const EXTENSIONS: [&'static str; 2] = ["las", "laz"];
+
+    /// Fails if the header can't be read or its point format isn't supported.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, ConverterError> {
+        let file = File::open(path)?;
+        let file_size = file.metadata()?.len();
+        let (tracking_reader, position) = PositionTrackingReader::new(BufReader::new(file));
+
+        let reader = Reader::new(tracking_reader).map_err(|err| match err {
+            las::Error::Io(err) => ConverterError::from(err),
+            err => ConverterError::Unsupported(err.to_string()),
+        })?;
+
+        Ok(Self {
+            reader,
             read_points: 0,
-        }
+            intensity_colormap: None,
+            intensity_colors: None,
+            position,
+            file_size,
+        })
     }
 }
 
 impl BatchedPointReader for BatchedLasPointReader {
-    fn get_batch(&mut self, size: usize) -> Result<Vec<Point>, std::io::Error> {
+    fn get_batch(&mut self, size: usize) -> Result<Vec<Point>, ConverterError> {
         self.reader
             .read_n(size as u64)
             .map(|points| {
                 self.read_points += points.len() as u64;
 
+                let intensity_colors = self.intensity_colormap.map(|colormap| {
+                    *self.intensity_colors.get_or_insert_with(|| {
+                        IntensityColors::new(colormap, points.iter().map(|it| it.intensity))
+                    })
+                });
+
                 points
                     .into_iter()
                     .map(|las_point| {
-                        let color = las_point.color.unwrap_or_default();
+                        let color = match &intensity_colors {
+                            Some(intensity_colors) => intensity_colors.color(las_point.intensity),
+                            None => {
+                                let color = las_point.color.unwrap_or_default();
+
+                                Color::from_rgb(
+                                    color.red as u8,
+                                    color.green as u8,
+                                    color.blue as u8,
+                                )
+                            }
+                        };
 
                         Point {
                             pos: glam::Vec3::new(
@@ -37,12 +82,20 @@ impl BatchedPointReader for BatchedLasPointReader {
                                 las_point.y as f32,
                                 las_point.z as f32,
                             ),
-                            color: [color.red as u8, color.green as u8, color.blue as u8, 255],
+                            color,
+                            gps_time: las_point.gps_time,
+                            intensity: Some(las_point.intensity),
+                            classification: Some(u8::from(las_point.classification)),
                         }
                     })
                     .collect()
             })
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))
+            .map_err(|err| match err {
+                las::Error::Io(err) => ConverterError::from(err),
+                err => ConverterError::CorruptData {
+                    detail: err.to_string(),
+                },
+            })
     }
 
     fn total_points(&self) -> u64 {
@@ -52,4 +105,21 @@ impl BatchedPointReader for BatchedLasPointReader {
     fn remaining_points(&self) -> u64 {
         self.total_points() - self.read_points
     }
+
+    fn has_colors(&self) -> bool {
+        self.intensity_colormap.is_some() || self.reader.header().point_format().has_color
+    }
+
+    fn color_by_intensity(&mut self, colormap: Colormap) -> bool {
+        self.intensity_colormap = Some(colormap);
+        true
+    }
+
+    fn bytes_processed(&self) -> Option<u64> {
+        Some(self.position.load(Ordering::Relaxed))
+    }
+
+    fn bytes_total(&self) -> Option<u64> {
+        Some(self.file_size)
+    }
 }
@@ -1,8 +1,10 @@
-use std::io::{Error, ErrorKind};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use crate::cell::Cell;
 use crate::converter::BatchedPointReader;
+use crate::error::ConverterError;
 use crate::metadata::Metadata;
 use crate::point::Point;
 
@@ -10,15 +12,18 @@ pub struct BatchedPointCloudPointReader {
     metadata: Metadata,
     point_iterator: Box<dyn Iterator<Item = Point> + Send>,
     read_points: u64,
+    /// Size of the cell files which were read, shared with the point iterator.
+    read_bytes: Arc<AtomicU64>,
+    total_bytes: u64,
 }
 
 impl BatchedPointCloudPointReader {
-    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, ConverterError> {
         match Metadata::from_path(path.as_ref()) {
             Ok(metadata) => {
                 let working_directory = path.as_ref().parent().unwrap().to_path_buf();
 
-                let point_iterator = (0..metadata.hierarchies)
+                let cell_paths = (0..metadata.hierarchies)
                     .map(move |hierarchy| {
                         working_directory.join(Metadata::hierarchy_string(hierarchy))
                     })
@@ -32,38 +37,54 @@ impl BatchedPointCloudPointReader {
                     })
                     .flatten()
                     .filter_map(|dir_entry_result| match dir_entry_result {
-                        Ok(dir_entry) => Some(dir_entry.path()),
-                        Err(err) => {
-                            log::error!("Failed to read file: {}", err);
-                            None
+                        Ok(dir_entry) => {
+                            let size = dir_entry.metadata().map_or(0, |it| it.len());
+                            Some((dir_entry.path(), size))
                         }
-                    })
-                    .map(Cell::from_path)
-                    .filter_map(|cell_result| match cell_result {
-                        Ok(cell) => Some(cell),
                         Err(err) => {
-                            log::error!("Failed to read cell {}", err);
+                            log::error!("Failed to read file: {}", err);
                             None
                         }
                     })
-                    .flat_map(|cell| cell.all_points().copied().collect::<Vec<_>>());
+                    .collect::<Vec<_>>();
+
+                let total_bytes = cell_paths.iter().map(|(_, size)| size).sum();
+                let read_bytes = Arc::new(AtomicU64::new(0));
+
+                let point_iterator = {
+                    let read_bytes = Arc::clone(&read_bytes);
+
+                    cell_paths
+                        .into_iter()
+                        .filter_map(move |(path, size)| {
+                            read_bytes.fetch_add(size, Ordering::Relaxed);
+
+                            match Cell::from_path(path) {
+                                Ok(cell) => Some(cell),
+                                Err(err) => {
+                                    log::error!("Failed to read cell {}", err);
+                                    None
+                                }
+                            }
+                        })
+                        .flat_map(|cell| cell.all_points().copied().collect::<Vec<_>>())
+                };
 
                 Ok(Self {
                     metadata,
                     point_iterator: Box::new(point_iterator),
                     read_points: 0,
+                    read_bytes,
+                    total_bytes,
                 })
             }
-            Err(err) => Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!("Couldn't parse metadata at {:?}: {}", path.as_ref(), err),
-            )),
+            Err(err) => Err(err),
         }
     }
 }
 
 impl BatchedPointReader for BatchedPointCloudPointReader {
-    fn get_batch(&mut self, size: usize) -> Result<Vec<Point>, Error> {
+    fn get_batch(&mut self, size: usize) -> Result<Vec<Point>, ConverterError> {
         let batch_size = self.remaining_points().min(size as u64);
         let mut batch = Vec::with_capacity(batch_size as usize);
 
@@ -84,4 +105,12 @@ impl BatchedPointReader for BatchedPointCloudPointReader {
     fn remaining_points(&self) -> u64 {
         self.total_points() - self.read_points
     }
+
+    fn bytes_processed(&self) -> Option<u64> {
+        Some(self.read_bytes.load(Ordering::Relaxed))
+    }
+
+    fn bytes_total(&self) -> Option<u64> {
+        Some(self.total_bytes)
+    }
 }
@@ -3,9 +3,10 @@ use std::io::{BufRead, BufReader, Error};
 use std::path::Path;
 
 use ply_rs::parser::Parser;
-use ply_rs::ply::{Encoding, Header};
+use ply_rs::ply::{DefaultElement, ElementDef, Encoding, Header, PropertyType};
 
 use crate::converter::BatchedPointReader;
+use crate::error::ConverterError;
 use crate::point::Point;
 
 pub struct BatchedPlyPointReader {
@@ -16,24 +17,62 @@ pub struct BatchedPlyPointReader {
 }
 
 impl BatchedPlyPointReader {
-    pub fn new<P: AsRef<Path>>(path: P) -> Self {
-        let file = File::open(path).unwrap();
+    pub const EXTENSION: &'static str = "ply";
+
+    /// Fails if the file has no vertices with scalar positions.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, ConverterError> {
+        let file = File::open(path)?;
         let mut buf_reader = BufReader::new(file);
 
         let parser = Parser::new();
-        let header = parser.read_header(&mut buf_reader).unwrap();
+        let header = parser.read_header(&mut buf_reader)?;
+        check_vertex_element(&header)?;
+        skip_elements_before_vertices(&mut buf_reader, &header)?;
 
-        Self {
+        Ok(Self {
             buf_reader,
             parser,
             header,
             read_points: 0,
+        })
+    }
+}
+
+/// Positions have to be scalars, everything else is optional.
+fn check_vertex_element(header: &Header) -> Result<(), ConverterError> {
+    let Some(element) = header.elements.get("vertex") else {
+        return Err(ConverterError::Unsupported(
+            "ply has no vertex element".to_string(),
+        ));
+    };
+
+    for name in ["x", "y", "z"] {
+        match element
+            .properties
+            .get(name)
+            .map(|property| &property.data_type)
+        {
+            Some(PropertyType::Scalar(_)) => {}
+            Some(PropertyType::List(..)) => {
+                return Err(ConverterError::Unsupported(format!(
+                    "ply vertex property '{}' is a list",
+                    name
+                )));
+            }
+            None => {
+                return Err(ConverterError::Unsupported(format!(
+                    "ply vertex property '{}' is missing",
+                    name
+                )));
+            }
         }
     }
+
+    Ok(())
 }
 
 impl BatchedPointReader for BatchedPlyPointReader {
-    fn get_batch(&mut self, size: usize) -> Result<Vec<Point>, Error> {
+    fn get_batch(&mut self, size: usize) -> Result<Vec<Point>, ConverterError> {
         let element = self.header.elements.get("vertex").unwrap();
         let point_count = self.remaining_points().min(size as u64);
 
@@ -45,7 +84,8 @@ impl BatchedPointReader for BatchedPlyPointReader {
                 for _ in 0..point_count {
                     line_str.clear();
                     self.buf_reader.read_line(&mut line_str)?;
-                    self.parser.read_ascii_element(&line_str, element)?;
+                    let point = self.parser.read_ascii_element(&line_str, element)?;
+                    batch.push(point);
                     self.read_points += 1;
                 }
             }
@@ -79,4 +119,222 @@ impl BatchedPointReader for BatchedPlyPointReader {
     fn remaining_points(&self) -> u64 {
         self.total_points() - self.read_points
     }
+
+    fn has_colors(&self) -> bool {
+        let element = self.header.elements.get("vertex").unwrap();
+
+        ["red", "r", "diffuse_red"]
+            .iter()
+            .any(|name| element.properties.contains_key(*name))
+    }
+}
+
+/// Elements are stored in the order of the header, so everything in front of the vertices has to be read first.
+fn skip_elements_before_vertices(
+    buf_reader: &mut BufReader<File>,
+    header: &Header,
+) -> Result<(), Error> {
+    let parser = Parser::<DefaultElement>::new();
+
+    for element in header
+        .elements
+        .values()
+        .take_while(|element| element.name != "vertex")
+    {
+        for _ in 0..element.count {
+            skip_element(buf_reader, &parser, header.encoding, element)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn skip_element(
+    buf_reader: &mut BufReader<File>,
+    parser: &Parser<DefaultElement>,
+    encoding: Encoding,
+    element: &ElementDef,
+) -> Result<(), Error> {
+    match encoding {
+        Encoding::Ascii => {
+            let mut line_str = String::new();
+            buf_reader.read_line(&mut line_str)?;
+        }
+        Encoding::BinaryBigEndian => {
+            parser.read_big_endian_element(buf_reader, element)?;
+        }
+        Encoding::BinaryLittleEndian => {
+            parser.read_little_endian_element(buf_reader, element)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use glam::Vec3;
+
+    use crate::point::Color;
+
+    use super::*;
+
+    fn fixture(name: &str, header: &str, body: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "point-converter-{}-{}.ply",
+            name,
+            std::process::id()
+        ));
+
+        let mut content = header.as_bytes().to_vec();
+        content.extend_from_slice(body);
+        std::fs::write(&path, content).unwrap();
+
+        path
+    }
+
+    fn read_all(path: &Path) -> Vec<Point> {
+        let mut reader = BatchedPlyPointReader::new(path).unwrap();
+        let points = reader.get_batch(usize::MAX).unwrap();
+        assert_eq!(reader.remaining_points(), 0);
+        std::fs::remove_file(path).unwrap();
+        points
+    }
+
+    #[test]
+    fn ascii_with_positions_after_confidence() {
+        let header = "ply\n\
+            format ascii 1.0\n\
+            comment photogrammetry export\n\
+            element vertex 2\n\
+            property float confidence\n\
+            property uchar red\n\
+            property uchar green\n\
+            property uchar blue\n\
+            property float x\n\
+            property float y\n\
+            property float z\n\
+            end_header\n";
+        let body = b"0.5 255 128 0 1 2 3\n0.25 1 2 3 -1.5 0 4.25\n";
+
+        let path = fixture("ascii-order", header, body);
+        let reader = BatchedPlyPointReader::new(&path).unwrap();
+        assert!(reader.has_colors());
+        assert_eq!(reader.total_points(), 2);
+        drop(reader);
+
+        let points = read_all(&path);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].pos, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(points[0].color, Color::from_rgb(255, 128, 0));
+        assert_eq!(points[1].pos, Vec3::new(-1.5, 0.0, 4.25));
+        assert_eq!(points[1].color, Color::from_rgb(1, 2, 3));
+    }
+
+    #[test]
+    fn binary_little_endian_with_double_positions_and_float_colors() {
+        let header = "ply\n\
+            format binary_little_endian 1.0\n\
+            element vertex 1\n\
+            property float red\n\
+            property float green\n\
+            property float blue\n\
+            property double x\n\
+            property double y\n\
+            property double z\n\
+            property float nx\n\
+            property float ny\n\
+            property float nz\n\
+            end_header\n";
+
+        let mut body = Vec::new();
+        for v in [1.0f32, 0.5, 0.0] {
+            body.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [10.0f64, 20.0, 30.0] {
+            body.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [0.0f32, 0.0, 1.0] {
+            body.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let points = read_all(&fixture("binary-le", header, &body));
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].pos, Vec3::new(10.0, 20.0, 30.0));
+        assert_eq!(points[0].color, Color::from_rgb(255, 128, 0));
+    }
+
+    #[test]
+    fn binary_big_endian_with_leading_element_and_ushort_colors() {
+        let header = "ply\n\
+            format binary_big_endian 1.0\n\
+            element camera 1\n\
+            property float focal\n\
+            element vertex 1\n\
+            property int x\n\
+            property int y\n\
+            property int z\n\
+            property ushort red\n\
+            property ushort green\n\
+            property ushort blue\n\
+            end_header\n";
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&35.0f32.to_be_bytes());
+        for v in [1i32, -2, 3] {
+            body.extend_from_slice(&v.to_be_bytes());
+        }
+        for v in [u16::MAX, 0x8000, 0] {
+            body.extend_from_slice(&v.to_be_bytes());
+        }
+
+        let points = read_all(&fixture("binary-be", header, &body));
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].pos, Vec3::new(1.0, -2.0, 3.0));
+        assert_eq!(points[0].color, Color::from_rgb(255, 128, 0));
+    }
+
+    #[test]
+    fn ascii_without_colors() {
+        let header = "ply\n\
+            format ascii 1.0\n\
+            element vertex 1\n\
+            property float x\n\
+            property float y\n\
+            property float z\n\
+            element face 0\n\
+            property list uchar int vertex_indices\n\
+            end_header\n";
+
+        let path = fixture("ascii-no-colors", header, b"1 2 3\n");
+        assert!(!BatchedPlyPointReader::new(&path).unwrap().has_colors());
+
+        let points = read_all(&path);
+        assert_eq!(points[0].pos, Vec3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn unsupported_headers_fail_on_creation() {
+        let missing_z = "ply\n\
+            format ascii 1.0\n\
+            element vertex 1\n\
+            property float x\n\
+            property float y\n\
+            end_header\n";
+        let no_vertices = "ply\n\
+            format ascii 1.0\n\
+            element face 0\n\
+            property list uchar int vertex_indices\n\
+            end_header\n";
+
+        for (name, header) in [("missing-z", missing_z), ("no-vertices", no_vertices)] {
+            let path = fixture(name, header, b"");
+            let err = BatchedPlyPointReader::new(&path).err().unwrap();
+            std::fs::remove_file(path).unwrap();
+
+            assert!(matches!(err, ConverterError::Unsupported(_)), "{}", err);
+        }
+    }
 }
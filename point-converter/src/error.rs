@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::ErrorKind;
+
+use crate::metadata::MetadataError;
+
+/// Why points, cells or metadata couldn't be read.
+#[derive(Debug)]
+pub enum ConverterError {
+    /// The file couldn't be read, e.g. because it doesn't exist.
+    Io(std::io::Error),
+    /// The file is none of the supported formats.
+    UnsupportedFormat(String),
+    /// The file has a supported format, but its content is invalid or truncated.
+    CorruptData { detail: String },
+    /// The file is valid, but uses a feature which isn't supported,
+    /// like a las point format or a ply without vertices.
+    Unsupported(String),
+}
+
+impl ConverterError {
+    /// Whether reading the same file again can't succeed.
+    /// Only some io errors, like timeouts, may go away by themselves.
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            ConverterError::Io(err) => !matches!(
+                err.kind(),
+                ErrorKind::Interrupted
+                    | ErrorKind::TimedOut
+                    | ErrorKind::WouldBlock
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+            ),
+            ConverterError::UnsupportedFormat(_)
+            | ConverterError::CorruptData { .. }
+            | ConverterError::Unsupported(_) => true,
+        }
+    }
+}
+
+/// Errors of parsers which read from [std::io::Read] are sorted by their kind.
+impl From<std::io::Error> for ConverterError {
+    fn from(value: std::io::Error) -> Self {
+        match value.kind() {
+            ErrorKind::UnexpectedEof | ErrorKind::InvalidData | ErrorKind::InvalidInput => {
+                ConverterError::CorruptData {
+                    detail: value.to_string(),
+                }
+            }
+            ErrorKind::Unsupported => ConverterError::Unsupported(value.to_string()),
+            _ => ConverterError::Io(value),
+        }
+    }
+}
+
+impl From<MetadataError> for ConverterError {
+    fn from(value: MetadataError) -> Self {
+        ConverterError::CorruptData {
+            detail: value.to_string(),
+        }
+    }
+}
+
+/// For functions which only deal with io, like writing cells and archives.
+impl From<ConverterError> for std::io::Error {
+    fn from(value: ConverterError) -> Self {
+        match value {
+            ConverterError::Io(err) => err,
+            ConverterError::UnsupportedFormat(_) | ConverterError::Unsupported(_) => {
+                std::io::Error::new(ErrorKind::Unsupported, value)
+            }
+            ConverterError::CorruptData { .. } => {
+                std::io::Error::new(ErrorKind::InvalidData, value)
+            }
+        }
+    }
+}
+
+impl Display for ConverterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConverterError::Io(err) => write!(f, "{}", err),
+            ConverterError::UnsupportedFormat(format) => {
+                write!(f, "Unsupported file format '{}'", format)
+            }
+            ConverterError::CorruptData { detail } => write!(f, "Corrupt data: {}", detail),
+            ConverterError::Unsupported(detail) => write!(f, "Not supported: {}", detail),
+        }
+    }
+}
+
+impl Error for ConverterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConverterError::Io(err) => Some(err),
+            ConverterError::UnsupportedFormat(_)
+            | ConverterError::CorruptData { .. }
+            | ConverterError::Unsupported(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_are_sorted_by_kind() {
+        let truncated = ConverterError::from(std::io::Error::from(ErrorKind::UnexpectedEof));
+        assert!(matches!(truncated, ConverterError::CorruptData { .. }));
+        assert!(truncated.is_permanent());
+
+        let not_found = ConverterError::from(std::io::Error::from(ErrorKind::NotFound));
+        assert!(matches!(not_found, ConverterError::Io(_)));
+        assert!(not_found.is_permanent());
+
+        let timed_out = ConverterError::from(std::io::Error::from(ErrorKind::TimedOut));
+        assert!(!timed_out.is_permanent());
+    }
+
+    #[test]
+    fn round_trips_through_io_error() {
+        let io_error = std::io::Error::from(ConverterError::Unsupported("list".to_string()));
+        assert_eq!(io_error.kind(), ErrorKind::Unsupported);
+
+        let not_found = std::io::Error::from(ConverterError::Io(ErrorKind::NotFound.into()));
+        assert_eq!(not_found.kind(), ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use crate::metadata::MetadataConfig;
+
+/// Rough size of a converted point cloud, see [estimate_output_size].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct EstimatedSize {
+    pub number_of_points: u64,
+    pub number_of_cells: u64,
+    /// Bytes of all cell files.
+    pub bytes: u64,
+}
+
+/// Bytes of a point without attributes in a cell file, see [crate::point::Point::write_to].
+const POINT_SIZE: u64 = crate::point::Point::BASE_SIZE as u64;
+
+/// Bytes of a cell without its points, see [crate::cell::Cell::write_to].
+/// The header, the number of overflow entries and the index and point count of each entry.
+/// A cell overflows into the 8 cells of the next hierarchy it contains.
+const CELL_OVERHEAD: u64 = 48 + 1 + 8 * 16;
+
+/// Scans are mostly surfaces, which pass through about 1.5 * [MetadataConfig::sub_grid_dimension]^2
+/// sub grid cells of a cell instead of filling the whole sub grid.
+const EXPECTED_SURFACE_FILL: f64 = 1.5;
+
+/// Overflow entries are on average about half full before they are passed on to the next hierarchy.
+const EXPECTED_OVERFLOW_FILL: f64 = 0.5;
+
+impl EstimatedSize {
+    pub fn from_number_of_points(number_of_points: u64, config: &MetadataConfig) -> Self {
+        let sub_grid_points = EXPECTED_SURFACE_FILL * (config.sub_grid_dimension as f64).powi(2);
+        let overflow_points =
+            EXPECTED_OVERFLOW_FILL * 8.0 * config.cell_point_overflow_limit as f64;
+        let points_per_cell = (sub_grid_points + overflow_points).max(1.0);
+
+        let number_of_cells = (number_of_points as f64 / points_per_cell).ceil() as u64;
+
+        Self {
+            number_of_points,
+            number_of_cells,
+            bytes: number_of_points * POINT_SIZE + number_of_cells * CELL_OVERHEAD,
+        }
+    }
+}
+
+/// Estimates the size of the converted point cloud from the number of points in the files,
+/// without reading the points themselves.
+/// Files which can't be read are skipped.
+/// Duplicated points are counted, as every point of the input is stored in one cell.
+pub fn estimate_output_size<P: AsRef<Path>>(paths: &[P], config: &MetadataConfig) -> EstimatedSize {
+    let number_of_points = paths
+        .iter()
+        .filter_map(crate::get_batched_point_reader)
+        .map(|reader| reader.total_points())
+        .sum();
+
+    EstimatedSize::from_number_of_points(number_of_points, config)
+}
+
+/// Formats bytes with binary units, e.g. `1.5 GiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_points_no_cells() {
+        let estimate = EstimatedSize::from_number_of_points(0, &MetadataConfig::default());
+
+        assert_eq!(estimate, EstimatedSize::default());
+    }
+
+    #[test]
+    fn grows_with_points() {
+        let config = MetadataConfig::default();
+        let small = EstimatedSize::from_number_of_points(1_000, &config);
+        let large = EstimatedSize::from_number_of_points(1_000_000_000, &config);
+
+        assert_eq!(small.number_of_cells, 1);
+        assert_eq!(small.bytes, 1_000 * POINT_SIZE + CELL_OVERHEAD);
+        assert!(large.number_of_cells > 10_000);
+        assert!(large.bytes > 1_000_000_000 * POINT_SIZE);
+    }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(50 * 1024 * 1024 * 1024), "50.0 GiB");
+    }
+}
@@ -0,0 +1,154 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, Read, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use glam::IVec3;
+use rustc_hash::FxHashSet;
+
+use crate::cell::{Cell, CellId};
+use crate::metadata::Metadata;
+use crate::Endianess;
+
+/// Set of all cells which exist on disk, so that missing cells don't have to be requested.
+#[derive(Debug, Default, Clone)]
+pub struct CellIndex {
+    hierarchies: Vec<FxHashSet<IVec3>>,
+}
+
+impl CellIndex {
+    pub const FILE_NAME: &'static str = "cells";
+    pub const EXTENSION: &'static str = "idx";
+
+    /// See [crate::logical_path].
+    pub fn logical_path() -> String {
+        format!("{}.{}", Self::FILE_NAME, Self::EXTENSION)
+    }
+
+    pub fn path() -> PathBuf {
+        crate::logical_path::to_path(&Self::logical_path())
+    }
+
+    /// Collects all cell files of the given hierarchies inside a converted directory.
+    pub fn from_directory(directory: &Path, hierarchies: u32) -> Result<Self, Error> {
+        let mut index = Self::default();
+
+        for hierarchy in 0..hierarchies {
+            let hierarchy_dir = directory.join(Metadata::hierarchy_string(hierarchy));
+
+            for dir_entry in hierarchy_dir.read_dir()? {
+                let path = dir_entry?.path();
+
+                if !Cell::has_extension(&path) {
+                    continue;
+                }
+
+                let cell_index = path
+                    .file_stem()
+                    .and_then(|it| it.to_str())
+                    .and_then(parse_index_string);
+
+                match cell_index {
+                    Some(cell_index) => index.insert(CellId {
+                        hierarchy,
+                        index: cell_index,
+                    }),
+                    None => log::warn!("Unexpected file in cell directory {:?}", path),
+                }
+            }
+        }
+
+        Ok(index)
+    }
+
+    pub fn insert(&mut self, cell_id: CellId) {
+        let hierarchy = cell_id.hierarchy as usize;
+
+        if self.hierarchies.len() <= hierarchy {
+            self.hierarchies
+                .resize_with(hierarchy + 1, FxHashSet::default);
+        }
+
+        self.hierarchies[hierarchy].insert(cell_id.index);
+    }
+
+    pub fn contains(&self, cell_id: &CellId) -> bool {
+        self.hierarchies
+            .get(cell_id.hierarchy as usize)
+            .is_some_and(|cells| cells.contains(&cell_id.index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.hierarchies.iter().map(FxHashSet::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn write_to(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        writer.write_u32::<Endianess>(self.hierarchies.len() as u32)?;
+
+        for cells in &self.hierarchies {
+            writer.write_u32::<Endianess>(cells.len() as u32)?;
+
+            for cell_index in cells {
+                writer.write_i32::<Endianess>(cell_index.x)?;
+                writer.write_i32::<Endianess>(cell_index.y)?;
+                writer.write_i32::<Endianess>(cell_index.z)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut dyn Read) -> Result<Self, Error> {
+        let number_of_hierarchies = reader.read_u32::<Endianess>()?;
+        let mut hierarchies = Vec::with_capacity(number_of_hierarchies as usize);
+
+        for _ in 0..number_of_hierarchies {
+            let number_of_cells = reader.read_u32::<Endianess>()? as usize;
+            let mut cells = FxHashSet::default();
+            cells.reserve(number_of_cells);
+
+            for _ in 0..number_of_cells {
+                let x = reader.read_i32::<Endianess>()?;
+                let y = reader.read_i32::<Endianess>()?;
+                let z = reader.read_i32::<Endianess>()?;
+                cells.insert(IVec3::new(x, y, z));
+            }
+
+            hierarchies.push(cells);
+        }
+
+        Ok(Self { hierarchies })
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let mut buf_reader = BufReader::new(file);
+        Self::read_from(&mut buf_reader)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let file = File::create(path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.write_to(&mut buf_writer)?;
+        buf_writer.flush()
+    }
+}
+
+/// Inverse of [CellId::index_string].
+fn parse_index_string(index_string: &str) -> Option<IVec3> {
+    let mut parts = index_string.strip_prefix("c_")?.split('_');
+
+    let x = parts.next()?.parse().ok()?;
+    let y = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(IVec3::new(x, y, z))
+}
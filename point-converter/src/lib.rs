@@ -2,19 +2,208 @@ pub(crate) use byteorder::LittleEndian as Endianess;
 
 use crate::converter::BatchedPointReader;
 
+pub mod archive;
 pub mod cell;
+pub mod colormap;
 pub mod converter;
+pub mod error;
+pub mod estimate;
 pub mod hex;
+pub mod index;
+pub mod logical_path;
 pub mod metadata;
+pub mod palette;
 pub mod point;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod stats;
 
-pub fn convert_from_paths<O: AsRef<std::path::Path>>(paths: &[std::path::PathBuf], output: O) {
-    let metadata = load_metadata(output.as_ref());
-    let mut converter = converter::Converter::new(metadata, output.as_ref());
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    /// Number of cells which are kept in memory during conversion.
+    pub cell_cache_size: usize,
+    /// Reduces the colors of the points to a palette.
+    /// Ignored if the existing metadata already has a palette.
+    pub color_quantization: Option<palette::ColorQuantization>,
+    /// RGBA color of points from files without colors.
+    pub default_color: [u8; 4],
+    /// Colors the points by their intensity instead of their colors.
+    pub intensity_colormap: Option<colormap::Colormap>,
+    /// Config of a new point cloud.
+    /// If the output already contains a point cloud, its config has to match the preset.
+    /// [None] uses the config of an existing point cloud or the default one.
+    pub preset: Option<metadata::MetadataConfigPreset>,
+    /// Config of a new point cloud which replaces the one of the preset, see [retile].
+    pub config: Option<metadata::MetadataConfig>,
+    /// Overrides [metadata::MetadataConfig::point_reduction] of the preset,
+    /// e.g. to average the colors of the points which fall into a sub grid cell.
+    pub point_reduction: Option<metadata::PointReduction>,
+    /// Converts into an existing point cloud even if its config doesn't match the preset.
+    /// The config of the existing point cloud is kept.
+    pub force: bool,
+    /// Soft limit of the converted points per second, to keep the machine responsive.
+    /// [None] converts as fast as possible.
+    pub max_points_per_second: Option<u64>,
+    /// Treats the files as overlapping tiles of one dataset and drops the points of a file
+    /// which an earlier file already had near its bounds, see [converter::SeamDeduplication].
+    pub deduplicate_seams: bool,
+    /// Up axis of the input files. Points are rotated into the Z-up convention of the renderer.
+    pub up_axis: point::UpAxis,
+    /// Channel order of the colors in the input files, which are swapped into RGB.
+    /// Ignored for files without colors or if the points are colored by their intensity.
+    pub channel_order: point::ChannelOrder,
+    /// Optional attributes of the points which are kept if the files have them.
+    /// Points without attributes are stored more compactly.
+    pub attributes: point::PointAttributes,
+    /// Unit of the coordinates of a new point cloud, see [metadata::Metadata::units].
+    pub units: Option<String>,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            cell_cache_size: converter::Converter::DEFAULT_CELL_CACHE_SIZE,
+            color_quantization: None,
+            default_color: point::Color::default().to_array(),
+            intensity_colormap: None,
+            preset: None,
+            config: None,
+            point_reduction: None,
+            force: false,
+            max_points_per_second: None,
+            deduplicate_seams: false,
+            up_axis: point::UpAxis::default(),
+            channel_order: point::ChannelOrder::default(),
+            attributes: point::PointAttributes::DEFAULT_CONVERTED,
+            units: None,
+        }
+    }
+}
+
+/// Stops a running conversion from another thread.
+/// The conversion checks it between batches of points and writes everything converted so far
+/// before it returns.
+#[derive(Debug, Default, Clone)]
+pub struct CancelFlag(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl CancelFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// Sleeps between batches, so that the average throughput stays below a number of points per second.
+struct Throttle {
+    max_points_per_second: u64,
+    start: std::time::Instant,
+    points: u64,
+}
+
+impl Throttle {
+    fn new(max_points_per_second: u64) -> Self {
+        Self {
+            max_points_per_second: max_points_per_second.max(1),
+            start: std::time::Instant::now(),
+            points: 0,
+        }
+    }
+
+    /// How long to wait until the converted points are within the limit again.
+    fn delay(&self, elapsed: std::time::Duration) -> Option<std::time::Duration> {
+        let expected = std::time::Duration::from_secs_f64(
+            self.points as f64 / self.max_points_per_second as f64,
+        );
+
+        expected
+            .checked_sub(elapsed)
+            .filter(|delay| !delay.is_zero())
+    }
+
+    fn wait(&mut self, points: usize) {
+        self.points += points as u64;
+
+        if let Some(delay) = self.delay(self.start.elapsed()) {
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Progress of [convert_from_paths_with_progress], reported after each batch of points.
+#[derive(Debug, Copy, Clone)]
+pub struct ConvertProgress {
+    /// Index of the file that is currently converted.
+    pub file_index: usize,
+    pub number_of_files: usize,
+    /// Number of points in the current file.
+    pub total_points: u64,
+    /// Number of points in the current file which aren't converted yet.
+    pub remaining_points: u64,
+    /// Fraction of the current file which was read, see [converter::BatchedPointReader::progress].
+    pub file_progress: f64,
+    /// Time since the conversion of all files started.
+    pub elapsed: std::time::Duration,
+}
+
+impl ConvertProgress {
+    /// Rough time until all files are converted,
+    /// assuming that the remaining files take as long as the converted ones.
+    pub fn remaining_time(&self) -> Option<std::time::Duration> {
+        let done = (self.file_index as f64 + self.file_progress) / self.number_of_files as f64;
+
+        (done > 0.0).then(|| self.elapsed.mul_f64((1.0 - done) / done))
+    }
+}
+
+pub fn convert_from_paths<O: AsRef<std::path::Path>>(
+    paths: &[std::path::PathBuf],
+    output: O,
+    options: &ConvertOptions,
+    cancel: &CancelFlag,
+) -> Result<(), metadata::MetadataError> {
+    let mut log_instant = std::time::Instant::now();
+
+    convert_from_paths_with_progress(paths, output, options, cancel, &mut |progress| {
+        if log_instant.elapsed() > std::time::Duration::from_millis(5000) {
+            match progress.remaining_time() {
+                Some(remaining_time) => log::info!(
+                    "Remaining points: {}, about {} s left",
+                    progress.remaining_points,
+                    remaining_time.as_secs()
+                ),
+                None => log::info!("Remaining points: {}", progress.remaining_points),
+            }
+            log_instant = std::time::Instant::now();
+        }
+    })
+}
+
+pub fn convert_from_paths_with_progress<O: AsRef<std::path::Path>>(
+    paths: &[std::path::PathBuf],
+    output: O,
+    options: &ConvertOptions,
+    cancel: &CancelFlag,
+    progress: &mut dyn FnMut(ConvertProgress),
+) -> Result<(), metadata::MetadataError> {
+    let metadata = load_metadata(output.as_ref(), options)?;
+    let mut converter =
+        converter::Converter::with_cache_size(metadata, output.as_ref(), options.cell_cache_size);
 
     let total_instant = std::time::Instant::now();
+    let mut throttle = options.max_points_per_second.map(Throttle::new);
+    let mut seam_deduplication = options
+        .deduplicate_seams
+        .then(converter::SeamDeduplication::default);
+    let mut deduplicated_points = 0;
 
-    for (path_index, path) in paths.iter().enumerate() {
+    'files: for (path_index, path) in paths.iter().enumerate() {
         log::info!(
             "Converting file {}/{}, {:?}",
             path_index + 1,
@@ -26,12 +215,83 @@ pub fn convert_from_paths<O: AsRef<std::path::Path>>(paths: &[std::path::PathBuf
             let total_points = batched_reader.total_points();
             log::info!("Converting {} points", total_points);
 
-            let mut file_instant = std::time::Instant::now();
+            let mut colored_by_intensity = false;
+
+            if let Some(colormap) = options.intensity_colormap {
+                if batched_reader.color_by_intensity(colormap) {
+                    converter.set_intensity_colormap(colormap);
+                    colored_by_intensity = true;
+                } else {
+                    log::warn!("File has no intensities, its colors are used instead");
+                }
+            }
+
+            let has_colors = batched_reader.has_colors();
+
+            if !has_colors {
+                log::info!("File has no colors, using {:?}", options.default_color);
+                converter.set_default_color(options.default_color);
+            }
+
+            let swap_channels = has_colors
+                && !colored_by_intensity
+                && options.channel_order != point::ChannelOrder::Rgb;
+
+            if swap_channels {
+                converter.set_channel_order(options.channel_order);
+            }
+
+            let mut cache_stats_instant = std::time::Instant::now();
 
             loop {
+                if cancel.is_canceled() {
+                    log::info!("Conversion was canceled");
+                    break 'files;
+                }
+
                 match batched_reader.get_batch(10_000) {
-                    Ok(batch) => {
+                    Ok(mut batch) => {
+                        if options.up_axis != point::UpAxis::Z {
+                            batch
+                                .iter_mut()
+                                .for_each(|point| point.pos = options.up_axis.to_z_up(point.pos));
+                        }
+
+                        if swap_channels {
+                            batch.iter_mut().for_each(|point| {
+                                point.color = options.channel_order.to_rgb(point.color)
+                            });
+                        }
+
+                        if options.attributes != point::PointAttributes::ALL {
+                            batch
+                                .iter_mut()
+                                .for_each(|point| point.retain_attributes(options.attributes));
+                        }
+
+                        if let Some(seam_deduplication) = &mut seam_deduplication {
+                            deduplicated_points += seam_deduplication.filter(&mut batch);
+                        }
+
+                        if !has_colors {
+                            let color = point::Color::from_array(options.default_color);
+                            batch.iter_mut().for_each(|point| point.color = color);
+                        }
+
+                        if converter.palette().is_none() {
+                            if let Some(quantization) = options.color_quantization {
+                                let palette = create_palette(quantization, &batch);
+                                log::info!("Quantizing colors to {} colors", palette.len());
+                                converter.set_palette(palette);
+                            }
+                        }
+
+                        let batch_len = batch.len();
                         converter.add_points_batch(batch);
+
+                        if let Some(throttle) = &mut throttle {
+                            throttle.wait(batch_len);
+                        }
                     }
                     Err(err) => {
                         log::error!("{:?}", err);
@@ -41,49 +301,203 @@ pub fn convert_from_paths<O: AsRef<std::path::Path>>(paths: &[std::path::PathBuf
 
                 let remaining_points = batched_reader.remaining_points();
 
-                if file_instant.elapsed() > std::time::Duration::from_millis(5000) {
-                    log::info!("Remaining points: {}", remaining_points);
-                    file_instant = std::time::Instant::now();
+                progress(ConvertProgress {
+                    file_index: path_index,
+                    number_of_files: paths.len(),
+                    total_points,
+                    remaining_points,
+                    file_progress: batched_reader.progress(),
+                    elapsed: total_instant.elapsed(),
+                });
+
+                if cache_stats_instant.elapsed() > std::time::Duration::from_millis(5000) {
+                    log::info!("Cell cache: {}", converter.cell_cache_stats());
+                    cache_stats_instant = std::time::Instant::now();
                 }
 
                 if remaining_points == 0 {
                     break;
                 }
             }
+
+            if let Some(seam_deduplication) = &mut seam_deduplication {
+                seam_deduplication.finish_tile();
+            }
         }
     }
 
+    if seam_deduplication.is_some() {
+        log::info!("Dropped {} points on tile seams", deduplicated_points);
+    }
+
+    // the cells, metadata and index are written when the converter is dropped,
+    // which also keeps the points converted before a cancellation
     log::info!(
         "Finished converting after {} ms",
         total_instant.elapsed().as_millis()
     );
+
+    log::info!(
+        "Cell cache with size {}: {}",
+        options.cell_cache_size,
+        converter.cell_cache_stats()
+    );
+
+    Ok(())
 }
 
+/// Converts an already converted point cloud again into `output` with another config,
+/// e.g. a different cell size, for when the original files aren't available anymore.
+/// All points are streamed from the cells of `source`, so this takes about as long as a conversion.
+pub fn retile<S: AsRef<std::path::Path>, O: AsRef<std::path::Path>>(
+    source: S,
+    output: O,
+    config: metadata::MetadataConfig,
+    options: &ConvertOptions,
+    cancel: &CancelFlag,
+) -> Result<(), metadata::MetadataError> {
+    config.validate()?;
+
+    let is_same_directory = std::fs::canonicalize(source.as_ref())
+        .ok()
+        .zip(std::fs::canonicalize(output.as_ref()).ok())
+        .is_some_and(|(source, output)| source == output);
+
+    if is_same_directory {
+        return Err(metadata::MetadataError::Invalid {
+            field: "output",
+            reason: "must be another directory than the source".to_string(),
+        });
+    }
+
+    let metadata_path = source
+        .as_ref()
+        .join(metadata::Metadata::FILE_NAME)
+        .with_extension(metadata::Metadata::EXTENSION);
+
+    let units = options.units.clone().or_else(|| {
+        metadata::Metadata::from_path(&metadata_path)
+            .ok()
+            .and_then(|metadata| metadata.units)
+    });
+
+    let options = ConvertOptions {
+        config: Some(config),
+        units,
+        // the converted points are already z-up, rgb and only have the attributes which were kept
+        up_axis: point::UpAxis::Z,
+        channel_order: point::ChannelOrder::Rgb,
+        attributes: point::PointAttributes::ALL,
+        ..options.clone()
+    };
+
+    convert_from_paths(&[metadata_path], output, &options, cancel)
+}
+
+/// A median cut palette is built from the first batch of points.
+fn create_palette(
+    quantization: palette::ColorQuantization,
+    points: &[point::Point],
+) -> palette::Palette {
+    match quantization {
+        palette::ColorQuantization::MedianCut(size) => {
+            let colors = points.iter().map(|point| point.color).collect::<Vec<_>>();
+            palette::Palette::median_cut(&colors, size)
+        }
+        palette::ColorQuantization::Fixed => palette::Palette::fixed(),
+    }
+}
+
+/// A file which can't be read is skipped instead of aborting the conversion of the other files.
 pub fn get_batched_point_reader<P: AsRef<std::path::Path>>(
     path: P,
 ) -> Option<Box<dyn BatchedPointReader + Send>> {
+    let display_path = path.as_ref().display().to_string();
+
+    match open_batched_point_reader(path) {
+        Ok(reader) => Some(reader),
+        Err(err @ error::ConverterError::UnsupportedFormat(_)) => {
+            log::warn!("Skipping '{}': {}", display_path, err);
+            None
+        }
+        Err(err) => {
+            log::error!("Skipping '{}': {}", display_path, err);
+            None
+        }
+    }
+}
+
+/// Extensions of the files which [open_batched_point_reader] can read.
+pub const SUPPORTED_EXTENSIONS: [&str; 5] = [
+    converter::BatchedLasPointReader::EXTENSIONS[0],
+    converter::BatchedLasPointReader::EXTENSIONS[1],
+    converter::BatchedPlyPointReader::EXTENSION,
+    metadata::Metadata::EXTENSION,
+    archive::Archive::EXTENSION,
+];
+
+/// Chooses the reader by the extension of the file.
+pub fn open_batched_point_reader<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<Box<dyn BatchedPointReader + Send>, error::ConverterError> {
     let extension = path
         .as_ref()
         .extension()
         .and_then(|it| it.to_str())
-        .map(String::from);
-
-    extension.and_then::<Box<dyn BatchedPointReader + Send>, _>(|extension| {
-        match extension.as_str() {
-            "las" | "laz" => Some(Box::new(converter::BatchedLasPointReader::new(path))),
-            "ply" => Some(Box::new(converter::BatchedPlyPointReader::new(path))),
-            metadata::Metadata::EXTENSION => Some(Box::new(
-                converter::BatchedPointCloudPointReader::new(path).unwrap(),
-            )),
-            _ => {
-                log::warn!("Unsupported file format '{}'", extension);
-                None
-            }
+        .unwrap_or_default()
+        .to_string();
+
+    match extension.as_str() {
+        extension if converter::BatchedLasPointReader::EXTENSIONS.contains(&extension) => {
+            boxed(converter::BatchedLasPointReader::new(path))
         }
-    })
+        converter::BatchedPlyPointReader::EXTENSION => {
+            boxed(converter::BatchedPlyPointReader::new(path))
+        }
+        metadata::Metadata::EXTENSION => boxed(converter::BatchedPointCloudPointReader::new(path)),
+        archive::Archive::EXTENSION => boxed(converter::BatchedArchivePointReader::new(path)),
+        _ => Err(error::ConverterError::UnsupportedFormat(extension)),
+    }
+}
+
+fn boxed<R>(
+    reader: Result<R, error::ConverterError>,
+) -> Result<Box<dyn BatchedPointReader + Send>, error::ConverterError>
+where
+    R: BatchedPointReader + Send + 'static,
+{
+    reader.map(|reader| Box::new(reader) as Box<dyn BatchedPointReader + Send>)
+}
+
+impl ConvertOptions {
+    /// Config of the preset with the overridden point reduction.
+    fn config(&self) -> metadata::MetadataConfig {
+        let mut config = self
+            .config
+            .clone()
+            .unwrap_or_else(|| self.preset.unwrap_or_default().config());
+
+        if let Some(point_reduction) = self.point_reduction {
+            config.point_reduction = point_reduction;
+        }
+
+        config
+    }
+
+    fn config_name(&self) -> &'static str {
+        match self.config {
+            Some(_) => "custom",
+            None => self.preset.unwrap_or_default().name(),
+        }
+    }
 }
 
-fn load_metadata(output: &std::path::Path) -> metadata::Metadata {
+/// Fails if the existing metadata doesn't match the preset, unless the conversion is forced,
+/// because cells which were converted with different configs don't fit together.
+fn load_metadata(
+    output: &std::path::Path,
+    options: &ConvertOptions,
+) -> Result<metadata::Metadata, metadata::MetadataError> {
     match std::fs::read(
         output
             .join(metadata::Metadata::FILE_NAME)
@@ -91,11 +505,294 @@ fn load_metadata(output: &std::path::Path) -> metadata::Metadata {
     ) {
         Ok(bytes) => {
             log::info!("Found an existing metadata file.");
-            metadata::Metadata::read_from(&mut std::io::Cursor::new(bytes)).unwrap()
+            let metadata = metadata::Metadata::read_from(&mut std::io::Cursor::new(bytes))?;
+
+            if options.preset.is_some() || options.config.is_some() {
+                let differences = metadata.config.differences(&options.config());
+
+                if !differences.is_empty() {
+                    if !options.force {
+                        return Err(metadata::MetadataError::ConfigMismatch { differences });
+                    }
+
+                    log::warn!(
+                        "Keeping the existing config instead of the {} config: {}",
+                        options.config_name(),
+                        differences.join(", ")
+                    );
+                }
+            }
+
+            Ok(metadata)
         }
         Err(_) => {
-            log::info!("Found no metadata file. A new one will be created.");
-            metadata::Metadata::default()
+            log::info!(
+                "Found no metadata file. A new one will be created with the {} config.",
+                options.config_name()
+            );
+
+            Ok(metadata::Metadata {
+                config: options.config(),
+                units: options.units.clone(),
+                ..metadata::Metadata::default()
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn throttle_delays_until_below_limit() {
+        let mut throttle = Throttle::new(1000);
+        throttle.points = 500;
+
+        assert_eq!(
+            throttle.delay(Duration::from_millis(200)),
+            Some(Duration::from_millis(300))
+        );
+        assert_eq!(throttle.delay(Duration::from_millis(500)), None);
+        assert_eq!(throttle.delay(Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn point_reduction_overrides_preset() {
+        let options = ConvertOptions {
+            preset: Some(metadata::MetadataConfigPreset::Aerial),
+            point_reduction: Some(metadata::PointReduction::AverageColor),
+            ..ConvertOptions::default()
+        };
+
+        let config = options.config();
+        let preset_config = metadata::MetadataConfigPreset::Aerial.config();
+
+        assert_eq!(
+            config.point_reduction,
+            metadata::PointReduction::AverageColor
+        );
+        assert_eq!(config.sub_grid_dimension, preset_config.sub_grid_dimension);
+        assert_eq!(
+            ConvertOptions::default().config().point_reduction,
+            metadata::PointReduction::default()
+        );
+    }
+
+    fn ply_tile(name: &str, xs: std::ops::RangeInclusive<i32>) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "point-converter-{}-{}.ply",
+            name,
+            std::process::id()
+        ));
+
+        let mut content = format!(
+            "ply\nformat ascii 1.0\nelement vertex {}\n\
+            property float x\nproperty float y\nproperty float z\nend_header\n",
+            xs.clone().count()
+        );
+
+        for x in xs {
+            content.push_str(&format!("{} {} 1\n", x, x % 3));
+        }
+
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn convert_overlapping_tiles(name: &str, deduplicate_seams: bool) -> metadata::Metadata {
+        let output =
+            std::env::temp_dir().join(format!("point-converter-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&output);
+
+        let tiles = vec![
+            ply_tile(&format!("{}-a", name), 0..=10),
+            ply_tile(&format!("{}-b", name), 8..=20),
+        ];
+
+        let options = ConvertOptions {
+            deduplicate_seams,
+            ..ConvertOptions::default()
+        };
+        convert_from_paths(&tiles, &output, &options, &CancelFlag::new()).unwrap();
+
+        let metadata = metadata::Metadata::from_path(
+            output
+                .join(metadata::Metadata::FILE_NAME)
+                .with_extension(metadata::Metadata::EXTENSION),
+        )
+        .unwrap();
+
+        for tile in tiles {
+            std::fs::remove_file(tile).unwrap();
         }
+        std::fs::remove_dir_all(&output).unwrap();
+
+        metadata
+    }
+
+    #[test]
+    fn bgr_colors_are_swapped_and_recorded() {
+        let name = format!("point-converter-bgr-{}", std::process::id());
+        let tile = std::env::temp_dir().join(&name).with_extension("ply");
+        let output = std::env::temp_dir().join(&name);
+        let _ = std::fs::remove_dir_all(&output);
+
+        std::fs::write(
+            &tile,
+            "ply\nformat ascii 1.0\nelement vertex 1\n\
+            property float x\nproperty float y\nproperty float z\n\
+            property uchar red\nproperty uchar green\nproperty uchar blue\nend_header\n\
+            1 2 3 10 20 30\n",
+        )
+        .unwrap();
+
+        let options = ConvertOptions {
+            channel_order: point::ChannelOrder::Bgr,
+            ..ConvertOptions::default()
+        };
+        convert_from_paths(
+            std::slice::from_ref(&tile),
+            &output,
+            &options,
+            &CancelFlag::new(),
+        )
+        .unwrap();
+
+        let metadata = metadata::Metadata::from_path(
+            output
+                .join(metadata::Metadata::FILE_NAME)
+                .with_extension(metadata::Metadata::EXTENSION),
+        )
+        .unwrap();
+        assert_eq!(metadata.channel_order, Some(point::ChannelOrder::Bgr));
+
+        let cell_id = cell::CellId {
+            hierarchy: 0,
+            index: glam::IVec3::ZERO,
+        };
+        let cell =
+            cell::Cell::from_path(output.join(cell_id.path(&metadata.cell_extension))).unwrap();
+        let colors = cell.points().map(|point| point.color).collect::<Vec<_>>();
+        assert_eq!(colors, vec![point::Color::from_rgb(30, 20, 10)]);
+
+        std::fs::remove_file(tile).unwrap();
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[test]
+    fn overlapping_tiles_union_their_bounds() {
+        for deduplicate_seams in [false, true] {
+            let metadata = convert_overlapping_tiles(
+                &format!("tiles-bounds-{}", deduplicate_seams),
+                deduplicate_seams,
+            );
+
+            assert_eq!(metadata.bounding_box.min, glam::Vec3::new(0.0, 0.0, 1.0));
+            assert_eq!(metadata.bounding_box.max, glam::Vec3::new(20.0, 2.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn seam_deduplication_drops_points_of_the_overlap() {
+        let duplicated = convert_overlapping_tiles("tiles-duplicated", false);
+        assert_eq!(duplicated.number_of_points, 11 + 13);
+
+        let deduplicated = convert_overlapping_tiles("tiles-deduplicated", true);
+        assert_eq!(deduplicated.number_of_points, 11 + 10);
+    }
+
+    #[test]
+    fn retile_keeps_all_points_with_the_new_config() {
+        let name = "retile";
+        let source =
+            std::env::temp_dir().join(format!("point-converter-{}-{}", name, std::process::id()));
+        let output = source.with_extension("retiled");
+        let _ = std::fs::remove_dir_all(&source);
+        let _ = std::fs::remove_dir_all(&output);
+
+        let tile = ply_tile(name, 0..=50);
+        convert_from_paths(
+            std::slice::from_ref(&tile),
+            &source,
+            &ConvertOptions::default(),
+            &CancelFlag::new(),
+        )
+        .unwrap();
+
+        let config = metadata::MetadataConfig {
+            max_cell_size: 64.0,
+            ..metadata::MetadataConfig::default()
+        };
+
+        assert!(retile(
+            &source,
+            &source,
+            config.clone(),
+            &ConvertOptions::default(),
+            &CancelFlag::new()
+        )
+        .is_err());
+
+        retile(
+            &source,
+            &output,
+            config.clone(),
+            &ConvertOptions::default(),
+            &CancelFlag::new(),
+        )
+        .unwrap();
+
+        let read_metadata = |directory: &std::path::Path| {
+            metadata::Metadata::from_path(
+                directory
+                    .join(metadata::Metadata::FILE_NAME)
+                    .with_extension(metadata::Metadata::EXTENSION),
+            )
+            .unwrap()
+        };
+        let source_metadata = read_metadata(&source);
+        let retiled_metadata = read_metadata(&output);
+
+        assert_eq!(
+            retiled_metadata.number_of_points,
+            source_metadata.number_of_points
+        );
+        assert_eq!(retiled_metadata.config.max_cell_size, config.max_cell_size);
+        assert_eq!(
+            retiled_metadata.bounding_box.min,
+            source_metadata.bounding_box.min
+        );
+        assert_eq!(
+            retiled_metadata.bounding_box.max,
+            source_metadata.bounding_box.max
+        );
+
+        std::fs::remove_file(tile).unwrap();
+        std::fs::remove_dir_all(&source).unwrap();
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[test]
+    fn remaining_time_is_extrapolated_from_all_files() {
+        let progress = ConvertProgress {
+            file_index: 1,
+            number_of_files: 4,
+            total_points: 100,
+            remaining_points: 50,
+            file_progress: 0.0,
+            elapsed: Duration::from_secs(10),
+        };
+
+        assert_eq!(progress.remaining_time(), Some(Duration::from_secs(30)));
+
+        let started = ConvertProgress {
+            file_index: 0,
+            ..progress
+        };
+
+        assert_eq!(started.remaining_time(), None);
     }
 }
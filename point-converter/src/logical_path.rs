@@ -0,0 +1,45 @@
+//! Paths inside a converted directory, like `h_0/c_1_2_3.pcc`.
+//! They always use `/` as separator, no matter on which platform the directory was converted
+//! or how it is read, and are only turned into a [PathBuf] at the file system.
+
+use std::path::{Path, PathBuf};
+
+/// Splits at `/` and `\`, skipping empty and `.` segments.
+pub fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\'])
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+}
+
+/// Turns a path with any separators into a logical path.
+pub fn normalize(path: &str) -> String {
+    segments(path).collect::<Vec<_>>().join("/")
+}
+
+pub fn from_path(path: &Path) -> String {
+    normalize(&path.to_string_lossy())
+}
+
+/// The path relative to the converted directory on this platform.
+pub fn to_path(path: &str) -> PathBuf {
+    segments(path).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_both_separators() {
+        assert_eq!(normalize("h_0\\c_1_-2_3.bin"), "h_0/c_1_-2_3.bin");
+        assert_eq!(normalize("./h_0//c_1_-2_3.bin"), "h_0/c_1_-2_3.bin");
+        assert_eq!(normalize("h_0/c_1_-2_3.bin"), "h_0/c_1_-2_3.bin");
+    }
+
+    #[test]
+    fn round_trips_through_platform_paths() {
+        let path = to_path("h_0\\c_1_-2_3.bin");
+
+        assert_eq!(path.components().count(), 2);
+        assert_eq!(from_path(&path), "h_0/c_1_-2_3.bin");
+    }
+}
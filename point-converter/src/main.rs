@@ -1,13 +1,21 @@
 use std::fs::read_dir;
 use std::path::PathBuf;
 
-use clap::{arg, Parser};
+use clap::{Parser, Subcommand, ValueEnum};
 use itertools::Itertools;
 
-use point_converter::convert_from_paths;
+use point_converter::archive::write_archive;
+use point_converter::cell::Cell;
+use point_converter::colormap::Colormap;
+use point_converter::converter::Converter;
+use point_converter::metadata::{MetadataConfigPreset, PointReduction};
+use point_converter::palette::ColorQuantization;
+use point_converter::point::{ChannelOrder, PointAttribute, UpAxis};
+use point_converter::stats::compute_stats;
+use point_converter::{convert_from_paths, retile, CancelFlag, ConvertOptions};
 
 /// Point converter will convert your points to a format that the point cloud renderer can use.
-/// Currently supported file formats are las/laz and ply and the generated metadata.json.
+/// Currently supported file formats are las/laz and ply and the generated metadata.json or pcar archive.
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None, verbatim_doc_comment)]
 struct Args {
@@ -23,12 +31,202 @@ struct Args {
     /// Input files with the points to convert.
     #[arg(short, long, value_name = "FILES")]
     files: Vec<PathBuf>,
+
+    /// Number of cells kept in memory while converting.
+    /// Larger values reduce how often cells are written and read again but need more memory.
+    #[arg(
+        long,
+        value_name = "CELLS",
+        default_value_t = Converter::DEFAULT_CELL_CACHE_SIZE,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    cache_size: usize,
+
+    /// Packs the output directory into a single archive file after converting.
+    /// Can be used without any input files to only pack an already converted directory.
+    #[arg(long, value_name = "FILE")]
+    archive: Option<PathBuf>,
+
+    /// Reduces the colors of the points to a palette with up to this many colors,
+    /// built from the colors of the first points.
+    #[arg(long, value_name = "COLORS", value_parser = clap::value_parser!(u16).range(1..=256))]
+    palette: Option<u16>,
+
+    /// Reduces the colors of the points to a fixed palette of 216 colors.
+    #[arg(long, conflicts_with = "palette")]
+    fixed_palette: bool,
+
+    /// Color of points from files without colors.
+    /// The alpha is optional.
+    #[arg(long, value_name = "R,G,B[,A]", value_parser = parse_color, default_value = "0,0,0,255")]
+    default_color: [u8; 4],
+
+    /// Where the colors of the points come from.
+    #[arg(long, value_enum, default_value_t = ColorFrom::Rgb)]
+    color_from: ColorFrom,
+
+    /// Colormap for colors which are computed from another attribute, like the intensity.
+    #[arg(long, value_enum, default_value_t = Colormap::Grayscale)]
+    colormap: Colormap,
+
+    /// Config for the kind of scan, used when the output doesn't contain a point cloud yet.
+    /// If it does, the config of the existing point cloud has to match the preset.
+    /// Without a preset, the general one is used for new point clouds.
+    #[arg(long, value_enum)]
+    preset: Option<MetadataConfigPreset>,
+
+    /// Which point a sub grid cell keeps if several points fall into it, overriding the preset.
+    /// average-color keeps the position of the closest point with the average color of all of them,
+    /// which reduces speckles in textured point clouds.
+    #[arg(long, value_enum)]
+    point_reduction: Option<PointReduction>,
+
+    /// Converts into an existing point cloud even if its config doesn't match the preset.
+    /// The existing config is kept.
+    #[arg(long, requires = "preset")]
+    force: bool,
+
+    /// Converts at most about this many points per second, to leave cpu time for other programs.
+    #[arg(long, value_name = "POINTS", value_parser = clap::value_parser!(u64).range(1..))]
+    max_points_per_sec: Option<u64>,
+
+    /// Treats the files as overlapping tiles of one dataset,
+    /// dropping points of a file which an earlier file already had near its bounds.
+    #[arg(long)]
+    deduplicate_seams: bool,
+
+    /// Axis which points up in the input files.
+    /// Y-up points are rotated so that they are Z-up like the renderer expects.
+    #[arg(long, value_enum, default_value_t = UpAxis::Z)]
+    up: UpAxis,
+
+    /// Order of the color channels in the input files.
+    /// bgr colors are swapped to rgb, which fixes blue tinted point clouds.
+    #[arg(long, value_enum, default_value_t = ChannelOrder::Rgb)]
+    channel_order: ChannelOrder,
+
+    /// Optional point attributes which are kept if the input files have them.
+    /// Every attribute makes the converted points larger.
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "gps-time")]
+    attributes: Vec<PointAttribute>,
+
+    /// Unit of the coordinates, like m or ft, which the viewer shows next to lengths.
+    #[arg(long, value_name = "UNIT")]
+    units: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone)]
+enum ColorFrom {
+    /// The colors of the points.
+    Rgb,
+    /// The normalized intensity of the points, mapped through the colormap.
+    /// Only supported for las/laz.
+    Intensity,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Prints the header and the overflow fill of a converted cell without converting anything.
+    Inspect {
+        /// Cell file inside a converted directory.
+        #[arg(value_name = "CELL")]
+        cell: PathBuf,
+    },
+    /// Prints the number of points, centroid, extent and standard deviation of a converted point cloud.
+    Stats {
+        /// Directory of a converted point cloud.
+        #[arg(value_name = "DIR")]
+        directory: PathBuf,
+    },
+    /// Converts an already converted point cloud again into the output directory with another config,
+    /// without the original files. Uses the config of the preset unless it is overridden.
+    Retile {
+        /// Directory of a converted point cloud.
+        #[arg(value_name = "DIR")]
+        source: PathBuf,
+
+        /// Size of the largest cell, overriding the preset.
+        #[arg(long, value_name = "SIZE")]
+        max_cell_size: Option<f32>,
+
+        /// Number of sub grid cells along each axis of a cell, overriding the preset.
+        #[arg(long, value_name = "CELLS")]
+        sub_grid_dimension: Option<u32>,
+    },
+    /// Serves a converted point cloud over http, so that the viewer can stream it from a URL.
+    /// Only meant for local development.
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Directory or archive of a converted point cloud.
+        #[arg(value_name = "SOURCE")]
+        source: PathBuf,
+
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        address: std::net::SocketAddr,
+    },
 }
 
 fn main() {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let args = Args::parse();
 
+    match &args.command {
+        Some(Command::Inspect { cell }) => {
+            if let Err(err) = inspect_cell(cell) {
+                log::error!("Failed to read cell {:?}: {}", cell, err);
+            }
+
+            return;
+        }
+        Some(Command::Stats { directory }) => {
+            match compute_stats(directory) {
+                Ok(stats) => print!("{}", stats),
+                Err(err) => log::error!("Failed to compute stats of {:?}: {}", directory, err),
+            }
+
+            return;
+        }
+        Some(Command::Retile {
+            source,
+            max_cell_size,
+            sub_grid_dimension,
+        }) => {
+            let mut config = args.preset.unwrap_or_default().config();
+
+            if let Some(max_cell_size) = max_cell_size {
+                config.max_cell_size = *max_cell_size;
+            }
+
+            if let Some(sub_grid_dimension) = sub_grid_dimension {
+                config.sub_grid_dimension = *sub_grid_dimension;
+            }
+
+            if let Err(err) = retile(
+                source,
+                output_directory(&args),
+                config,
+                &convert_options(&args),
+                &CancelFlag::new(),
+            ) {
+                log::error!("Failed to retile {:?}: {}", source, err);
+            }
+
+            return;
+        }
+        #[cfg(feature = "serve")]
+        Some(Command::Serve { source, address }) => {
+            if let Err(err) = serve(source, *address) {
+                log::error!("Failed to serve {:?}: {}", source, err);
+            }
+
+            return;
+        }
+        None => {}
+    }
+
     let dirs = args
         .directories
         .iter()
@@ -37,14 +235,156 @@ fn main() {
 
     let files = args.files.iter().cloned().chain(dirs).collect_vec();
 
-    if files.is_empty() {
+    let output = output_directory(&args);
+
+    if files.is_empty() && args.archive.is_none() {
         log::warn!("Please provide some files or directories");
         return;
     }
 
-    convert_from_paths(
-        &files,
-        args.output
-            .unwrap_or_else(|| std::env::current_dir().unwrap()),
+    if !files.is_empty() {
+        let options = convert_options(&args);
+
+        if let Err(err) = convert_from_paths(&files, &output, &options, &CancelFlag::new()) {
+            log::error!("{}", err);
+            return;
+        }
+    }
+
+    if let Some(archive) = args.archive {
+        log::info!("Packing {:?} into {:?}", output, archive);
+
+        if let Err(err) = write_archive(&output, &archive) {
+            log::error!("Failed to write archive: {}", err);
+        }
+    }
+}
+
+#[cfg(feature = "serve")]
+fn serve(source: &std::path::Path, address: std::net::SocketAddr) -> Result<(), std::io::Error> {
+    use point_converter::serve::ServedSource;
+
+    let source = ServedSource::open(source)?;
+    let listener = std::net::TcpListener::bind(address)?;
+
+    log::info!(
+        "Serving at http://{}/{}",
+        listener.local_addr()?,
+        point_converter::metadata::Metadata::logical_path()
+    );
+
+    point_converter::serve::serve(listener, source)
+}
+
+fn output_directory(args: &Args) -> PathBuf {
+    args.output
+        .clone()
+        .unwrap_or_else(|| std::env::current_dir().unwrap())
+}
+
+fn convert_options(args: &Args) -> ConvertOptions {
+    let color_quantization = if args.fixed_palette {
+        Some(ColorQuantization::Fixed)
+    } else {
+        args.palette
+            .map(|colors| ColorQuantization::MedianCut(colors as usize))
+    };
+
+    ConvertOptions {
+        cell_cache_size: args.cache_size,
+        color_quantization,
+        default_color: args.default_color,
+        intensity_colormap: match args.color_from {
+            ColorFrom::Rgb => None,
+            ColorFrom::Intensity => Some(args.colormap),
+        },
+        preset: args.preset,
+        config: None,
+        point_reduction: args.point_reduction,
+        force: args.force,
+        max_points_per_second: args.max_points_per_sec,
+        deduplicate_seams: args.deduplicate_seams,
+        up_axis: args.up,
+        channel_order: args.channel_order,
+        attributes: args.attributes.clone().into(),
+        units: args.units.clone(),
+    }
+}
+
+fn parse_color(color: &str) -> Result<[u8; 4], String> {
+    let channels = color
+        .split(',')
+        .map(|channel| channel.trim().parse::<u8>().map_err(|err| err.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match channels[..] {
+        [r, g, b] => Ok([r, g, b, 255]),
+        [r, g, b, a] => Ok([r, g, b, a]),
+        _ => Err("expected 3 or 4 comma separated channels".to_string()),
+    }
+}
+
+fn inspect_cell(path: &PathBuf) -> Result<(), std::io::Error> {
+    let cell = Cell::from_path(path)?;
+    let header = cell.header();
+
+    println!("Cell {:?}", path);
+    println!("  hierarchy:       {}", header.id.hierarchy);
+    println!(
+        "  index:           {} {} {}",
+        header.id.index.x, header.id.index.y, header.id.index.z
+    );
+    println!("  total points:    {}", header.total_number_of_points);
+    println!("  points:          {}", header.number_of_points);
+    println!("  overflow points: {}", header.number_of_overflow_points);
+    println!("  size:            {}", header.size);
+    println!("  sub cell size:   {}", header.sub_cell_size);
+    println!(
+        "  pos:             {} {} {}",
+        header.pos.x, header.pos.y, header.pos.z
+    );
+    println!(
+        "  attributes:      {:?}",
+        header.attributes.iter().collect_vec()
+    );
+
+    let mut full_sub_cells = 0;
+    // bucket i counts sub cells with 2^i up to 2^(i+1) - 1 points
+    let mut buckets = Vec::<usize>::new();
+
+    for (_, fill) in cell.overflow_fill() {
+        match fill {
+            Some(points) => {
+                let bucket = points.max(1).ilog2() as usize;
+
+                if buckets.len() <= bucket {
+                    buckets.resize(bucket + 1, 0);
+                }
+
+                buckets[bucket] += 1;
+            }
+            None => {
+                full_sub_cells += 1;
+            }
+        }
+    }
+
+    let max_count = buckets.iter().copied().max().unwrap_or(0).max(1);
+
+    println!(
+        "Overflow sub cells: {}",
+        buckets.iter().sum::<usize>() + full_sub_cells
     );
+
+    for (bucket, count) in buckets.iter().enumerate() {
+        let min = 1usize << bucket;
+        let max = (1usize << (bucket + 1)) - 1;
+        let bar = "#".repeat((count * 40).div_ceil(max_count));
+
+        println!("  {:>6}..={:<6} {:>6} {}", min, max, count, bar);
+    }
+
+    println!("  {:>15} {:>6}", "passed on", full_sub_cells);
+
+    Ok(())
 }
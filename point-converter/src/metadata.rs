@@ -1,4 +1,6 @@
-use std::io::{ErrorKind, Read, Write};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
 use std::path::Path;
 
 use glam::{IVec3, Vec3};
@@ -6,6 +8,12 @@ use serde::{Deserialize, Serialize};
 
 use bounding_volume::Aabb;
 
+use crate::cell::Cell;
+use crate::colormap::Colormap;
+use crate::error::ConverterError;
+use crate::palette::Palette;
+use crate::point::{ChannelOrder, PointAttributes};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Metadata {
     /// The current version of this metadata file.
@@ -25,6 +33,37 @@ pub struct Metadata {
 
     /// Configuration
     pub config: MetadataConfig,
+
+    /// Colors of all points if they were quantized during conversion.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub palette: Option<Palette>,
+
+    /// Colormap through which the intensities of points were mapped to their colors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intensity_colormap: Option<Colormap>,
+
+    /// RGBA color given to the points of files without colors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_color: Option<[u8; 4]>,
+
+    /// Channel order of input files whose colors were swapped into RGB while converting.
+    /// [None] if all colors were already RGB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_order: Option<ChannelOrder>,
+
+    /// Attributes which points of this point cloud have, besides their position and color.
+    /// Every cell declares which of them it stores, see [crate::cell::Header::attributes].
+    #[serde(default, skip_serializing_if = "PointAttributes::is_empty")]
+    pub attributes: PointAttributes,
+
+    /// Unit of the coordinates, like `m` or `ft`, which is only shown to the user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+
+    /// Extension of the cell files, see [crate::cell::CellId::logical_path].
+    /// Point clouds which were converted before it was recorded use [Cell::LEGACY_EXTENSION].
+    #[serde(default = "Metadata::legacy_cell_extension")]
+    pub cell_extension: String,
 }
 
 impl Default for Metadata {
@@ -36,6 +75,13 @@ impl Default for Metadata {
             hierarchies: 0,
             bounding_box: Aabb::default(),
             config: MetadataConfig::default(),
+            palette: None,
+            intensity_colormap: None,
+            default_color: None,
+            channel_order: None,
+            attributes: PointAttributes::NONE,
+            units: None,
+            cell_extension: Cell::EXTENSION.to_string(),
         }
     }
 }
@@ -43,24 +89,126 @@ impl Default for Metadata {
 impl Metadata {
     pub const FILE_NAME: &'static str = "metadata";
     pub const EXTENSION: &'static str = "json";
+    /// The renderer packs the hierarchy of a point into 5 bits.
+    /// Points which don't fit into the cells of the last hierarchy are dropped, see
+    /// [crate::converter::drop_coincident_points].
+    pub const MAX_HIERARCHIES: u32 = 32;
 
     pub fn hierarchy_string(hierarchy: u32) -> String {
         format!("h_{}", hierarchy)
     }
 
+    fn legacy_cell_extension() -> String {
+        Cell::LEGACY_EXTENSION.to_string()
+    }
+
+    /// Path of the metadata file inside the converted directory, see [crate::logical_path].
+    pub fn logical_path() -> String {
+        format!("{}.{}", Self::FILE_NAME, Self::EXTENSION)
+    }
+
+    /// Serializes straight into the writer without building the whole json in memory.
     pub fn write_to(&self, writer: &mut dyn Write) -> serde_json::Result<()> {
         serde_json::to_writer_pretty(writer, self)
     }
 
-    pub fn read_from(reader: &mut dyn Read) -> serde_json::Result<Self> {
-        serde_json::from_reader(reader)
+    /// Parses and validates the metadata.
+    pub fn read_from(reader: &mut dyn Read) -> Result<Self, MetadataError> {
+        let metadata: Self = serde_json::from_reader(reader)?;
+        metadata.validate()?;
+        Ok(metadata)
     }
 
-    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, std::io::Error> {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, ConverterError> {
         let file = std::fs::File::open(path)?;
-        let buf_reader = std::io::BufReader::new(file);
-        serde_json::from_reader(buf_reader)
-            .map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))
+        let mut buf_reader = std::io::BufReader::new(file);
+        Ok(Self::read_from(&mut buf_reader)?)
+    }
+
+    /// Checks invariants which can't be expressed by deserialization alone.
+    pub fn validate(&self) -> Result<(), MetadataError> {
+        if self.number_of_points > 0 && self.hierarchies == 0 {
+            return Err(MetadataError::Invalid {
+                field: "hierarchies",
+                reason: format!(
+                    "must be at least 1 for a cloud with {} points",
+                    self.number_of_points
+                ),
+            });
+        }
+
+        if self.cell_extension.is_empty() || self.cell_extension.contains(['.', '/', '\\']) {
+            return Err(MetadataError::Invalid {
+                field: "cell_extension",
+                reason: format!("{:?} is not a file extension", self.cell_extension),
+            });
+        }
+
+        if self.bounding_box.min.cmpgt(self.bounding_box.max).any() {
+            return Err(MetadataError::Invalid {
+                field: "bounding_box",
+                reason: format!(
+                    "min {} is larger than max {}",
+                    self.bounding_box.min, self.bounding_box.max
+                ),
+            });
+        }
+
+        self.config.validate()
+    }
+}
+
+#[derive(Debug)]
+pub enum MetadataError {
+    /// The json couldn't be parsed or doesn't match the expected structure.
+    Parse(serde_json::Error),
+    /// The json could be parsed but contains a value which is not allowed.
+    Invalid { field: &'static str, reason: String },
+    /// The config of existing metadata differs from the config that was asked for.
+    /// Each difference names the field and both values.
+    ConfigMismatch { differences: Vec<String> },
+}
+
+impl From<serde_json::Error> for MetadataError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Parse(value)
+    }
+}
+
+impl Display for MetadataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetadataError::Parse(err) => {
+                let kind = match err.classify() {
+                    serde_json::error::Category::Io => "Failed to read metadata",
+                    serde_json::error::Category::Syntax => "Metadata is not valid json",
+                    serde_json::error::Category::Data => "Metadata has an unexpected structure",
+                    serde_json::error::Category::Eof => "Metadata is truncated",
+                };
+
+                // the serde error already contains the line and column
+                write!(f, "{}: {}", kind, err)
+            }
+            MetadataError::Invalid { field, reason } => {
+                write!(f, "Invalid metadata field `{}`: {}", field, reason)
+            }
+            MetadataError::ConfigMismatch { differences } => {
+                write!(
+                    f,
+                    "Existing metadata has a different config: {}",
+                    differences.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl Error for MetadataError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MetadataError::Parse(err) => Some(err),
+            MetadataError::Invalid { .. } | MetadataError::ConfigMismatch { .. } => None,
+        }
     }
 }
 
@@ -75,6 +223,36 @@ pub struct MetadataConfig {
 
     /// Size of the largest cell of the largest hierarchy level.
     pub max_cell_size: f32,
+
+    /// Which point a sub grid cell keeps if several points fall into it.
+    #[serde(default)]
+    pub point_reduction: PointReduction,
+}
+
+/// Decides which point is kept when several points fall into the same sub grid cell.
+/// The points which are not kept are passed on to the next hierarchy.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum PointReduction {
+    /// Keeps the point closest to the center of the sub grid cell.
+    /// Gives the most uniform distribution of points, but the kept color is that of a random
+    /// point, so small colored structures can disappear in coarse hierarchies.
+    #[default]
+    ClosestToCenter,
+    /// Keeps the first point which falls into the sub grid cell.
+    /// The cheapest policy, but the result depends on the order of the input points
+    /// and points can clump at the borders of sub grid cells.
+    First,
+    /// Keeps the position of the point closest to the center,
+    /// but gives it the average color of all points which fell into the sub grid cell.
+    /// Coarse hierarchies look like a smoothed version of the data, which also blurs sharp edges.
+    /// The average is only tracked during a conversion, so converting more points into an existing
+    /// point cloud starts averaging from the stored colors. Averaged colors are not snapped to a palette.
+    AverageColor,
+}
+
+impl PointReduction {
+    pub const ALL: [Self; 3] = [Self::ClosestToCenter, Self::First, Self::AverageColor];
 }
 
 impl Default for MetadataConfig {
@@ -83,19 +261,94 @@ impl Default for MetadataConfig {
             sub_grid_dimension: 96,
             cell_point_overflow_limit: 5_000, // smaller values give better quality but more small files
             max_cell_size: 1000.0,
+            point_reduction: PointReduction::default(),
+        }
+    }
+}
+
+/// Named configurations for common kinds of scans, for users who don't want to tune the config.
+/// The spacings are the [MetadataConfig::min_point_spacing] at the given hierarchy.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum MetadataConfigPreset {
+    /// The default config, a compromise for scans of a few hundred meters to a few kilometers.
+    /// Spacing of 5.2 m at hierarchy 0 and 4 cm at hierarchy 7.
+    #[default]
+    General,
+    /// Airborne lidar of cities or landscapes, several kilometers wide
+    /// with 1 to 100 points per square meter.
+    /// Large cells keep the number of coarse hierarchies small.
+    /// Spacing of 15.6 m at hierarchy 0 and 12 cm at hierarchy 7.
+    Aerial,
+    /// Terrestrial laser scans of buildings or sites up to a few hundred meters
+    /// with a point spacing in the millimeter range.
+    /// The larger overflow limit avoids many tiny cells in the densely scanned areas.
+    /// Spacing of 1.6 m at hierarchy 0 and 6 mm at hierarchy 8.
+    Terrestrial,
+    /// Photogrammetry of objects or single buildings up to 200 m with a spacing of about a centimeter.
+    /// Spacing of 1 m at hierarchy 0 and 1.6 cm at hierarchy 6.
+    Photogrammetry,
+}
+
+impl MetadataConfigPreset {
+    pub const ALL: [Self; 4] = [
+        Self::General,
+        Self::Aerial,
+        Self::Terrestrial,
+        Self::Photogrammetry,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::General => "General",
+            Self::Aerial => "Aerial LiDAR",
+            Self::Terrestrial => "Terrestrial scan",
+            Self::Photogrammetry => "Photogrammetry",
+        }
+    }
+
+    pub fn config(self) -> MetadataConfig {
+        match self {
+            Self::General => MetadataConfig::default(),
+            Self::Aerial => MetadataConfig {
+                sub_grid_dimension: 128,
+                cell_point_overflow_limit: 10_000,
+                max_cell_size: 4000.0,
+                ..MetadataConfig::default()
+            },
+            Self::Terrestrial => MetadataConfig {
+                sub_grid_dimension: 128,
+                cell_point_overflow_limit: 20_000,
+                max_cell_size: 400.0,
+                ..MetadataConfig::default()
+            },
+            Self::Photogrammetry => MetadataConfig {
+                sub_grid_dimension: 96,
+                cell_point_overflow_limit: 5_000,
+                max_cell_size: 200.0,
+                ..MetadataConfig::default()
+            },
         }
     }
 }
 
 impl MetadataConfig {
     pub fn cell_size(&self, hierarchy: u32) -> f32 {
-        self.max_cell_size / 2u32.pow(hierarchy) as f32
+        // a float power, because the overflow of the last hierarchy is still grouped by the cells
+        // of hierarchy 32 before it is dropped, and 2^32 doesn't fit into a u32
+        self.max_cell_size / 2f32.powi(hierarchy as i32)
     }
 
     pub fn sub_cell_size(&self, cell_size: f32) -> f32 {
         cell_size / self.sub_grid_dimension as f32
     }
 
+    /// Number of points for which a new cell reserves space.
+    /// Scans are mostly surfaces, which fill about [Self::sub_grid_dimension]^2 of the ^3 sub grid cells,
+    /// so reserving the theoretical maximum would waste most of it.
+    pub fn cell_capacity(&self) -> usize {
+        (self.sub_grid_dimension as usize).pow(2)
+    }
+
     // when this function is changed, also adjust the function inside the compute shader
     pub fn cell_index(&self, pos: Vec3, cell_size: f32) -> IVec3 {
         (pos / cell_size).floor().as_ivec3()
@@ -105,9 +358,135 @@ impl MetadataConfig {
         cell_index.as_vec3() * cell_size + cell_size / 2.0
     }
 
+    pub fn validate(&self) -> Result<(), MetadataError> {
+        if self.sub_grid_dimension == 0 {
+            return Err(MetadataError::Invalid {
+                field: "config.sub_grid_dimension",
+                reason: "must be larger than 0".to_string(),
+            });
+        }
+
+        if !(self.max_cell_size.is_finite() && self.max_cell_size > 0.0) {
+            return Err(MetadataError::Invalid {
+                field: "config.max_cell_size",
+                reason: format!("must be a positive number but is {}", self.max_cell_size),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Fields which differ from the other config, formatted as `field: self != other`.
+    pub fn differences(&self, other: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        if self.cell_point_overflow_limit != other.cell_point_overflow_limit {
+            differences.push(format!(
+                "cell_point_overflow_limit: {} != {}",
+                self.cell_point_overflow_limit, other.cell_point_overflow_limit
+            ));
+        }
+
+        if self.sub_grid_dimension != other.sub_grid_dimension {
+            differences.push(format!(
+                "sub_grid_dimension: {} != {}",
+                self.sub_grid_dimension, other.sub_grid_dimension
+            ));
+        }
+
+        if self.max_cell_size != other.max_cell_size {
+            differences.push(format!(
+                "max_cell_size: {} != {}",
+                self.max_cell_size, other.max_cell_size
+            ));
+        }
+
+        if self.point_reduction != other.point_reduction {
+            differences.push(format!(
+                "point_reduction: {:?} != {:?}",
+                self.point_reduction, other.point_reduction
+            ));
+        }
+
+        differences
+    }
+
+    /// Smallest distance between the centers of two neighbouring sub grid cells
+    /// at the given hierarchy. As every sub grid cell holds at most one point,
+    /// this is the resolution of the hierarchy.
+    pub fn min_point_spacing(&self, hierarchy: u32) -> f32 {
+        // the sub grid is a hexagonal prism grid with a cell radius of half the sub cell size
+        // which stacks its layers at the distance of one cell radius
+        self.sub_cell_size(self.cell_size(hierarchy)) / 2.0
+    }
+
     pub fn cell_spacing(&self, cell_size: f32) -> f32 {
         let cell_size = cell_size / self.sub_grid_dimension as f32;
         let cell_radius = cell_size * 0.5;
         cell_radius.hypot(cell_radius * 0.5) * 1.05
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_valid() {
+        for preset in MetadataConfigPreset::ALL {
+            assert!(preset.config().validate().is_ok(), "{:?}", preset);
+        }
+    }
+
+    #[test]
+    fn metadata_without_cell_extension_uses_the_legacy_one() {
+        let mut json = serde_json::to_value(Metadata::default()).unwrap();
+        assert_eq!(json["cell_extension"], Cell::EXTENSION);
+
+        json.as_object_mut().unwrap().remove("cell_extension");
+
+        let metadata = Metadata::read_from(&mut json.to_string().as_bytes()).unwrap();
+        assert_eq!(metadata.cell_extension, Cell::LEGACY_EXTENSION);
+    }
+
+    #[test]
+    fn config_differences() {
+        let config = MetadataConfig::default();
+
+        assert!(config.differences(&MetadataConfig::default()).is_empty());
+
+        let other = MetadataConfig {
+            sub_grid_dimension: 128,
+            max_cell_size: 500.0,
+            ..MetadataConfig::default()
+        };
+
+        assert_eq!(
+            config.differences(&other),
+            vec![
+                "sub_grid_dimension: 96 != 128".to_string(),
+                "max_cell_size: 1000 != 500".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn cell_capacity_covers_a_surface_through_the_sub_grid() {
+        assert_eq!(MetadataConfig::default().cell_capacity(), 96 * 96);
+        assert_eq!(
+            MetadataConfigPreset::Aerial.config().cell_capacity(),
+            128 * 128
+        );
+    }
+
+    #[test]
+    fn documented_preset_spacings() {
+        let spacing =
+            |preset: MetadataConfigPreset, hierarchy| preset.config().min_point_spacing(hierarchy);
+
+        assert!((spacing(MetadataConfigPreset::General, 7) - 0.041).abs() < 0.001);
+        assert!((spacing(MetadataConfigPreset::Aerial, 7) - 0.122).abs() < 0.001);
+        assert!((spacing(MetadataConfigPreset::Terrestrial, 8) - 0.006).abs() < 0.001);
+        assert!((spacing(MetadataConfigPreset::Photogrammetry, 6) - 0.016).abs() < 0.001);
+    }
+}
@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+
+use crate::point::Color;
+
+/// How the colors of the points are reduced during conversion.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorQuantization {
+    /// A palette with up to the given number of colors is built from the colors of the first points.
+    MedianCut(usize),
+    /// The colors are snapped to [Palette::fixed].
+    Fixed,
+}
+
+/// A small set of colors which the colors of the points are snapped to.
+/// Stored in the metadata, so that colors can be replaced by an index into it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    colors: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    /// Largest palette whose indices still fit into a byte.
+    pub const MAX_SIZE: usize = 256;
+
+    /// 6 levels per channel, which gives the 216 "web safe" colors.
+    pub fn fixed() -> Self {
+        const LEVELS: [u8; 6] = [0, 51, 102, 153, 204, 255];
+
+        let colors = LEVELS
+            .iter()
+            .flat_map(|r| {
+                LEVELS
+                    .iter()
+                    .flat_map(move |g| LEVELS.iter().map(move |b| [*r, *g, *b]))
+            })
+            .collect();
+
+        Self { colors }
+    }
+
+    /// Repeatedly splits the box of colors with the largest range at the median of that range,
+    /// until there are `size` boxes. Each box contributes its average color.
+    pub fn median_cut(colors: &[Color], size: usize) -> Self {
+        let size = size.clamp(1, Self::MAX_SIZE);
+
+        let mut boxes: Vec<Vec<[u8; 3]>> = vec![colors
+            .iter()
+            .map(|color| [color.r, color.g, color.b])
+            .collect()];
+
+        while boxes.len() < size {
+            let widest = boxes
+                .iter()
+                .enumerate()
+                .filter(|(_, colors)| colors.len() > 1)
+                .map(|(index, colors)| (index, widest_channel(colors)))
+                .max_by_key(|(_, (_, range))| *range);
+
+            let Some((index, (channel, range))) = widest else {
+                break;
+            };
+
+            if range == 0 {
+                break;
+            }
+
+            let mut colors = boxes.swap_remove(index);
+            colors.sort_unstable_by_key(|color| color[channel]);
+            let upper = colors.split_off(colors.len() / 2);
+
+            boxes.push(colors);
+            boxes.push(upper);
+        }
+
+        let colors = boxes
+            .iter()
+            .filter(|colors| !colors.is_empty())
+            .map(|colors| {
+                let mut sum = [0u64; 3];
+
+                for color in colors {
+                    for channel in 0..3 {
+                        sum[channel] += color[channel] as u64;
+                    }
+                }
+
+                sum.map(|channel| (channel / colors.len() as u64) as u8)
+            })
+            .collect();
+
+        Self { colors }
+    }
+
+    pub fn colors(&self) -> &[[u8; 3]] {
+        &self.colors
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Index of the palette color which is closest to the given color.
+    pub fn index_of(&self, color: Color) -> u8 {
+        self.colors
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, palette_color)| {
+                palette_color
+                    .iter()
+                    .zip([color.r, color.g, color.b])
+                    .map(|(a, b)| (*a as i32 - b as i32).pow(2))
+                    .sum::<i32>()
+            })
+            .map_or(0, |(index, _)| index as u8)
+    }
+
+    /// Replaces the color by the closest palette color. The alpha is kept.
+    pub fn quantize(&self, color: Color) -> Color {
+        match self.colors.get(self.index_of(color) as usize) {
+            Some([r, g, b]) => Color::from_rgba(*r, *g, *b, color.a),
+            None => color,
+        }
+    }
+}
+
+/// The channel with the largest difference between its smallest and largest value.
+fn widest_channel(colors: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let min = colors.iter().map(|color| color[channel]).min().unwrap_or(0);
+            let max = colors.iter().map(|color| color[channel]).max().unwrap_or(0);
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn max_channel_error(palette: &Palette, colors: &[Color]) -> u8 {
+        colors
+            .iter()
+            .map(|color| {
+                let quantized = palette.quantize(*color);
+
+                [
+                    color.r.abs_diff(quantized.r),
+                    color.g.abs_diff(quantized.g),
+                    color.b.abs_diff(quantized.b),
+                ]
+                .into_iter()
+                .max()
+                .unwrap()
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn fixed_palette_round_trip_error() {
+        let palette = Palette::fixed();
+        assert_eq!(palette.len(), 216);
+
+        let colors = (0..=255u8)
+            .step_by(5)
+            .map(|value| Color::from_rgb(value, 255 - value, value / 2))
+            .collect::<Vec<_>>();
+
+        assert!(max_channel_error(&palette, &colors) <= 26);
+    }
+
+    #[test]
+    fn median_cut_finds_clusters() {
+        let centers = [[200, 30, 30], [20, 180, 40], [40, 40, 220], [250, 250, 250]];
+
+        let colors = centers
+            .iter()
+            .flat_map(|[r, g, b]| {
+                (0..4u8).map(move |offset| Color::from_rgb(r + offset, g + offset, b + offset))
+            })
+            .collect::<Vec<_>>();
+
+        let palette = Palette::median_cut(&colors, 4);
+
+        assert_eq!(palette.len(), 4);
+        assert!(max_channel_error(&palette, &colors) <= 3);
+    }
+
+    #[test]
+    fn median_cut_with_fewer_colors_than_size() {
+        let colors = [Color::from_rgb(1, 2, 3), Color::from_rgb(1, 2, 3)];
+
+        let palette = Palette::median_cut(&colors, 16);
+
+        assert_eq!(palette.colors(), &[[1, 2, 3]]);
+    }
+
+    #[test]
+    fn quantize_keeps_alpha() {
+        let palette = Palette::fixed();
+
+        let quantized = palette.quantize(Color::from_rgba(10, 250, 100, 128));
+
+        assert_eq!(quantized, Color::from_rgba(0, 255, 102, 128));
+    }
+}
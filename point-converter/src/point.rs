@@ -2,41 +2,334 @@ use std::io::{Read, Write};
 
 use byteorder::{ReadBytesExt, WriteBytesExt};
 use glam::Vec3;
+use serde::{Deserialize, Serialize};
 
 use crate::Endianess;
 
-#[derive(Debug, Copy, Clone)]
-pub struct Point {
-    /// Position of the point in 3D Space.
-    pub pos: Vec3,
-    /// RGBA color value 0..=255.
-    pub color: [u8; 4],
+/// RGBA color with 8 bits per channel.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
 }
 
-impl Default for Point {
+impl Default for Color {
     fn default() -> Self {
-        Self {
-            pos: Vec3::ZERO,
-            color: [0, 0, 0, 255],
+        Self::from_rgb(0, 0, 0)
+    }
+}
+
+impl Color {
+    /// Creates an opaque color.
+    pub const fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::from_rgba(r, g, b, 255)
+    }
+
+    pub const fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Channels in the order red, green, blue, alpha.
+    pub const fn from_array([r, g, b, a]: [u8; 4]) -> Self {
+        Self::from_rgba(r, g, b, a)
+    }
+
+    /// Channels in the order red, green, blue, alpha.
+    pub const fn to_array(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+/// Order of the color channels in the input files.
+/// Converted point clouds are always RGB, so colors of other orders are swapped while converting.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelOrder {
+    #[default]
+    Rgb,
+    /// Written by some PLY exporters, which makes the point cloud look blue tinted.
+    Bgr,
+}
+
+impl ChannelOrder {
+    pub const ALL: [Self; 2] = [Self::Rgb, Self::Bgr];
+
+    /// Reorders a color of this order into RGB.
+    pub fn to_rgb(self, color: Color) -> Color {
+        match self {
+            ChannelOrder::Rgb => color,
+            ChannelOrder::Bgr => Color::from_rgba(color.b, color.g, color.r, color.a),
+        }
+    }
+}
+
+/// Which axis of the input files points up.
+/// Converted point clouds are always Z-up, so points of other conventions are rotated while converting.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum UpAxis {
+    #[default]
+    Z,
+    /// Used by many photogrammetry and 3D modelling tools.
+    Y,
+}
+
+impl UpAxis {
+    /// Rotates a position of this convention into the Z-up convention.
+    /// Y-up is rotated by 90° around the X axis, which keeps the coordinate system right-handed.
+    pub fn to_z_up(self, pos: Vec3) -> Vec3 {
+        match self {
+            UpAxis::Z => pos,
+            UpAxis::Y => Vec3::new(pos.x, -pos.z, pos.y),
         }
     }
 }
 
+/// Optional attribute of points besides their position and color.
+/// Cells only store the attributes which at least one of their points has,
+/// see [crate::cell::Header::attributes].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum PointAttribute {
+    /// Time at which the point was captured, stored as f64.
+    GpsTime,
+    /// Strength of the laser return, stored as u16.
+    Intensity,
+    /// Las classification, like ground or building, stored as u8.
+    Classification,
+}
+
+impl PointAttribute {
+    /// In the order in which they are stored after the color of a point.
+    pub const ALL: [Self; 3] = [Self::GpsTime, Self::Intensity, Self::Classification];
+
+    /// Bytes per point in a cell file.
+    pub fn size(self) -> usize {
+        match self {
+            PointAttribute::GpsTime => 8,
+            PointAttribute::Intensity => 2,
+            PointAttribute::Classification => 1,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Set of [PointAttribute]s, serialized as a list.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "Vec<PointAttribute>", into = "Vec<PointAttribute>")]
+pub struct PointAttributes(u8);
+
+impl PointAttributes {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self((1 << PointAttribute::ALL.len()) - 1);
+
+    /// Kept by default while converting.
+    /// Intensities and classifications have to be requested, as most point clouds only need colors.
+    pub const DEFAULT_CONVERTED: Self = Self(1 << PointAttribute::GpsTime as u8);
+
+    /// Attributes which the point has.
+    pub fn of(point: &Point) -> Self {
+        let mut attributes = Self::NONE;
+
+        for (attribute, is_set) in [
+            (PointAttribute::GpsTime, point.gps_time.is_some()),
+            (PointAttribute::Intensity, point.intensity.is_some()),
+            (
+                PointAttribute::Classification,
+                point.classification.is_some(),
+            ),
+        ] {
+            if is_set {
+                attributes.insert(attribute);
+            }
+        }
+
+        attributes
+    }
+
+    pub fn contains(self, attribute: PointAttribute) -> bool {
+        self.0 & attribute.bit() != 0
+    }
+
+    pub fn insert(&mut self, attribute: PointAttribute) {
+        self.0 |= attribute.bit();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = PointAttribute> {
+        PointAttribute::ALL
+            .into_iter()
+            .filter(move |attribute| self.contains(*attribute))
+    }
+
+    /// Whether every point stores which of these attributes it has, see [Point::write_to].
+    /// Only gps times can mark a missing value, so sets of only gps times keep the layout of older cells.
+    pub fn has_presence_byte(self) -> bool {
+        self.contains(PointAttribute::Intensity) || self.contains(PointAttribute::Classification)
+    }
+
+    /// Bytes per point in a cell file with these attributes.
+    pub fn point_size(self) -> usize {
+        let presence_byte_size = usize::from(self.has_presence_byte());
+        Point::BASE_SIZE + presence_byte_size + self.iter().map(PointAttribute::size).sum::<usize>()
+    }
+
+    /// Bits of the set, see [PointAttributes::from_bits].
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Unknown bits are kept, so that they can be detected.
+    pub fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+}
+
+impl std::ops::BitOr for PointAttributes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for PointAttributes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl From<PointAttribute> for PointAttributes {
+    fn from(value: PointAttribute) -> Self {
+        Self(value.bit())
+    }
+}
+
+impl From<Vec<PointAttribute>> for PointAttributes {
+    fn from(value: Vec<PointAttribute>) -> Self {
+        value
+            .into_iter()
+            .map(Self::from)
+            .fold(Self::NONE, |a, b| a | b)
+    }
+}
+
+impl From<PointAttributes> for Vec<PointAttribute> {
+    fn from(value: PointAttributes) -> Self {
+        value.iter().collect()
+    }
+}
+
+/// Equality is exact, use [Point::pos_eq] to compare positions within an epsilon.
+///
+/// The serde representation is meant for tests and tools, cell files use [Point::write_to].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    /// Position of the point in 3D Space.
+    pub pos: Vec3,
+    pub color: Color,
+    /// See [PointAttribute::GpsTime].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gps_time: Option<f64>,
+    /// See [PointAttribute::Intensity].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intensity: Option<u16>,
+    /// See [PointAttribute::Classification].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification: Option<u8>,
+}
+
 impl Point {
-    pub fn write_to(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+    /// Bytes of the position and color, which every point has.
+    pub const BASE_SIZE: usize = 16;
+
+    /// Index of the cube with an edge length of `cell_size` which contains the point.
+    /// Hashable, so that points can be deduplicated with a set of keys.
+    ///
+    /// Points which are closer than `cell_size` to each other can still get different keys
+    /// if they lie on both sides of a boundary between two cubes.
+    pub fn quantized_key(&self, cell_size: f32) -> (i32, i32, i32) {
+        let key = (self.pos / cell_size).floor().as_ivec3();
+        (key.x, key.y, key.z)
+    }
+
+    /// Whether the positions differ by at most `epsilon` on every axis.
+    /// Colors and gps times are ignored.
+    pub fn pos_eq(&self, other: &Point, epsilon: f32) -> bool {
+        self.pos.abs_diff_eq(other.pos, epsilon)
+    }
+
+    /// Drops the values of all attributes which aren't in the set.
+    pub fn retain_attributes(&mut self, attributes: PointAttributes) {
+        if !attributes.contains(PointAttribute::GpsTime) {
+            self.gps_time = None;
+        }
+
+        if !attributes.contains(PointAttribute::Intensity) {
+            self.intensity = None;
+        }
+
+        if !attributes.contains(PointAttribute::Classification) {
+            self.classification = None;
+        }
+    }
+
+    /// Writes exactly the given attributes, see [PointAttributes::point_size].
+    /// Attributes the point doesn't have are written as placeholders, a missing gps time as NaN
+    /// and a missing intensity or classification as 0.
+    /// As 0 is a valid intensity and classification, the attributes which the point has are written
+    /// in front of them, see [PointAttributes::has_presence_byte], so that they are read back as [None].
+    pub fn write_to(
+        &self,
+        writer: &mut dyn Write,
+        attributes: PointAttributes,
+    ) -> Result<(), std::io::Error> {
         writer.write_f32::<Endianess>(self.pos.x)?;
         writer.write_f32::<Endianess>(self.pos.y)?;
         writer.write_f32::<Endianess>(self.pos.z)?;
 
-        writer.write_u8(self.color[0])?;
-        writer.write_u8(self.color[1])?;
-        writer.write_u8(self.color[2])?;
-        writer.write_u8(self.color[3])?;
+        writer.write_u8(self.color.r)?;
+        writer.write_u8(self.color.g)?;
+        writer.write_u8(self.color.b)?;
+        writer.write_u8(self.color.a)?;
+
+        // fast path for the common case of only positions and colors
+        if attributes.is_empty() {
+            return Ok(());
+        }
+
+        if attributes.has_presence_byte() {
+            writer.write_u8(PointAttributes::of(self).bits() & attributes.bits())?;
+        }
+
+        for attribute in attributes.iter() {
+            match attribute {
+                PointAttribute::GpsTime => {
+                    writer.write_f64::<Endianess>(self.gps_time.unwrap_or(f64::NAN))?
+                }
+                PointAttribute::Intensity => {
+                    writer.write_u16::<Endianess>(self.intensity.unwrap_or(0))?
+                }
+                PointAttribute::Classification => {
+                    writer.write_u8(self.classification.unwrap_or(0))?
+                }
+            }
+        }
 
         Ok(())
     }
 
-    pub fn read_from(reader: &mut dyn Read) -> Result<Self, std::io::Error> {
+    pub fn read_from(
+        reader: &mut dyn Read,
+        attributes: PointAttributes,
+    ) -> Result<Self, std::io::Error> {
         let x = reader.read_f32::<Endianess>()?;
         let y = reader.read_f32::<Endianess>()?;
         let z = reader.read_f32::<Endianess>()?;
@@ -46,10 +339,40 @@ impl Point {
         let b = reader.read_u8()?;
         let a = reader.read_u8()?;
 
-        Ok(Self {
+        let mut point = Self {
             pos: Vec3::new(x, y, z),
-            color: [r, g, b, a],
-        })
+            color: Color::from_rgba(r, g, b, a),
+            ..Default::default()
+        };
+
+        if attributes.is_empty() {
+            return Ok(point);
+        }
+
+        let present = if attributes.has_presence_byte() {
+            PointAttributes::from_bits(reader.read_u8()?)
+        } else {
+            attributes
+        };
+
+        for attribute in attributes.iter() {
+            let is_present = present.contains(attribute);
+
+            match attribute {
+                PointAttribute::GpsTime => {
+                    point.gps_time = Some(reader.read_f64::<Endianess>()?)
+                        .filter(|gps_time| is_present && !gps_time.is_nan());
+                }
+                PointAttribute::Intensity => {
+                    point.intensity = Some(reader.read_u16::<Endianess>()?).filter(|_| is_present);
+                }
+                PointAttribute::Classification => {
+                    point.classification = Some(reader.read_u8()?).filter(|_| is_present);
+                }
+            }
+        }
+
+        Ok(point)
     }
 }
 
@@ -58,74 +381,196 @@ impl ply_rs::ply::PropertyAccess for Point {
         Self::default()
     }
 
+    /// Properties are matched by name, so their order and type in the header doesn't matter.
     fn set_property(&mut self, property_name: String, property: ply_rs::ply::Property) {
-        use ply_rs::ply::Property;
-
         match property_name.as_ref() {
-            "x" => match property {
-                Property::Float(v) => {
-                    self.pos.x = v;
-                }
-                Property::Double(v) => {
-                    self.pos.x = v as f32;
-                }
-                _ => {}
-            },
-            "y" => match property {
-                Property::Float(v) => {
-                    self.pos.y = v;
-                }
-                Property::Double(v) => {
-                    self.pos.y = v as f32;
-                }
-                _ => {}
-            },
-            "z" => match property {
-                Property::Float(v) => {
-                    self.pos.z = v;
-                }
-                Property::Double(v) => {
-                    self.pos.z = v as f32;
-                }
-                _ => {}
-            },
-            "red" | "r" => match property {
-                Property::UChar(v) => {
-                    self.color[0] = v;
-                }
-                Property::Float(v) => {
-                    self.color[0] = (v / 255.0) as u8;
-                }
-                _ => {}
-            },
-            "green" | "g" => match property {
-                Property::UChar(v) => {
-                    self.color[1] = v;
-                }
-                Property::Float(v) => {
-                    self.color[1] = (v / 255.0) as u8;
-                }
-                _ => {}
-            },
-            "blue" | "b" => match property {
-                Property::UChar(v) => {
-                    self.color[2] = v;
-                }
-                Property::Float(v) => {
-                    self.color[2] = (v / 255.0) as u8;
-                }
-                _ => {}
-            },
-            "alpha" | "a" => match property {
-                Property::UChar(v) => {
-                    self.color[3] = v;
-                }
-                Property::Float(v) => {
-                    self.color[3] = (v / 255.0) as u8;
-                }
-                _ => {}
-            },
+            "x" => self.pos.x = ply_scalar(&property).unwrap_or(self.pos.x as f64) as f32,
+            "y" => self.pos.y = ply_scalar(&property).unwrap_or(self.pos.y as f64) as f32,
+            "z" => self.pos.z = ply_scalar(&property).unwrap_or(self.pos.z as f64) as f32,
+            "red" | "r" | "diffuse_red" => {
+                self.color.r = ply_color_channel(&property).unwrap_or(self.color.r)
+            }
+            "green" | "g" | "diffuse_green" => {
+                self.color.g = ply_color_channel(&property).unwrap_or(self.color.g)
+            }
+            "blue" | "b" | "diffuse_blue" => {
+                self.color.b = ply_color_channel(&property).unwrap_or(self.color.b)
+            }
+            "alpha" | "a" | "diffuse_alpha" => {
+                self.color.a = ply_color_channel(&property).unwrap_or(self.color.a)
+            }
             _ => {}
         }
     }
 }
+
+/// Any scalar ply property as a float. Lists are ignored.
+fn ply_scalar(property: &ply_rs::ply::Property) -> Option<f64> {
+    use ply_rs::ply::Property;
+
+    match *property {
+        Property::Char(v) => Some(v as f64),
+        Property::UChar(v) => Some(v as f64),
+        Property::Short(v) => Some(v as f64),
+        Property::UShort(v) => Some(v as f64),
+        Property::Int(v) => Some(v as f64),
+        Property::UInt(v) => Some(v as f64),
+        Property::Float(v) => Some(v as f64),
+        Property::Double(v) => Some(v),
+        _ => None,
+    }
+}
+
+/// Scales a ply color channel to 8 bits.
+/// Integer channels use their full range and float channels are expected to be in 0..=1.
+fn ply_color_channel(property: &ply_rs::ply::Property) -> Option<u8> {
+    use ply_rs::ply::Property;
+
+    let normalized = match *property {
+        Property::UChar(v) => return Some(v),
+        Property::Char(v) => return Some(v.max(0) as u8),
+        Property::UShort(v) => return Some((v >> 8) as u8),
+        Property::Short(v) => return Some((v.max(0) >> 7) as u8),
+        Property::UInt(v) => return Some((v >> 24) as u8),
+        Property::Int(v) => return Some((v.max(0) >> 23) as u8),
+        Property::Float(v) => v as f64,
+        Property::Double(v) => v,
+        _ => return None,
+    };
+
+    Some((normalized.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32, z: f32) -> Point {
+        Point {
+            pos: Vec3::new(x, y, z),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn json_skips_missing_attributes() {
+        let mut point = point(1.0, 2.0, 3.0);
+        point.intensity = Some(7);
+
+        let json = serde_json::to_string(&point).unwrap();
+
+        assert!(json.contains("intensity"));
+        assert!(!json.contains("gps_time"));
+        assert_eq!(serde_json::from_str::<Point>(&json).unwrap(), point);
+    }
+
+    #[test]
+    fn y_up_is_rotated_to_z_up() {
+        assert_eq!(
+            UpAxis::Z.to_z_up(Vec3::new(1.0, 2.0, 3.0)),
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(UpAxis::Y.to_z_up(Vec3::Y), Vec3::Z);
+        assert_eq!(UpAxis::Y.to_z_up(Vec3::X), Vec3::X);
+        // forward in Y-up is towards the viewer, which becomes -Y
+        assert_eq!(UpAxis::Y.to_z_up(Vec3::Z), Vec3::NEG_Y);
+    }
+
+    #[test]
+    fn bgr_is_swapped_to_rgb() {
+        let color = Color::from_rgba(1, 2, 3, 4);
+
+        assert_eq!(ChannelOrder::Rgb.to_rgb(color), color);
+        assert_eq!(
+            ChannelOrder::Bgr.to_rgb(color),
+            Color::from_rgba(3, 2, 1, 4)
+        );
+    }
+
+    #[test]
+    fn quantized_key_floors_towards_negative_infinity() {
+        assert_eq!(point(0.0, 0.4, 0.99).quantized_key(1.0), (0, 0, 0));
+        assert_eq!(point(-0.1, -1.0, -1.1).quantized_key(1.0), (-1, -1, -2));
+        assert_eq!(point(2.5, 5.0, -2.5).quantized_key(2.5), (1, 2, -1));
+    }
+
+    #[test]
+    fn points_straddling_a_boundary_get_different_keys() {
+        let epsilon = 0.001;
+        let below = point(0.9995, 0.5, 0.5);
+        let above = point(1.0005, 0.5, 0.5);
+
+        assert!(below.pos_eq(&above, epsilon));
+        assert_ne!(below.quantized_key(0.01), above.quantized_key(0.01));
+
+        let same = point(0.9991, 0.5, 0.5);
+        assert_eq!(below.quantized_key(0.01), same.quantized_key(0.01));
+    }
+
+    #[test]
+    fn deduplicates_by_key() {
+        let points = [
+            point(0.1, 0.1, 0.1),
+            point(0.2, 0.2, 0.2),
+            point(1.1, 0.1, 0.1),
+        ];
+
+        let keys = points
+            .iter()
+            .map(|point| point.quantized_key(1.0))
+            .collect::<rustc_hash::FxHashSet<_>>();
+
+        assert_eq!(keys.len(), 2);
+        assert!(!points[0].pos_eq(&points[1], 0.01));
+        assert_eq!(points[0], points[0]);
+    }
+
+    #[test]
+    fn writes_exactly_the_given_attributes() {
+        let point = Point {
+            pos: Vec3::new(1.0, 2.0, 3.0),
+            color: Color::from_rgb(10, 20, 30),
+            gps_time: Some(42.5),
+            intensity: Some(1234),
+            classification: None,
+        };
+
+        for bits in 0..8 {
+            let attributes = PointAttributes::from_bits(bits);
+
+            let mut bytes = Vec::new();
+            point.write_to(&mut bytes, attributes).unwrap();
+            assert_eq!(bytes.len(), attributes.point_size());
+
+            let read = Point::read_from(&mut bytes.as_slice(), attributes).unwrap();
+            assert_eq!(read.pos, point.pos);
+            assert_eq!(read.color, point.color);
+            assert_eq!(
+                read.gps_time,
+                point
+                    .gps_time
+                    .filter(|_| attributes.contains(PointAttribute::GpsTime))
+            );
+            assert_eq!(
+                read.intensity,
+                point
+                    .intensity
+                    .filter(|_| attributes.contains(PointAttribute::Intensity))
+            );
+            assert_eq!(read.classification, None);
+        }
+    }
+
+    #[test]
+    fn attributes_are_serialized_as_list() {
+        let attributes =
+            PointAttributes::from(PointAttribute::Classification) | PointAttribute::GpsTime.into();
+
+        let json = serde_json::to_string(&attributes).unwrap();
+        assert_eq!(json, r#"["gps_time","classification"]"#);
+        assert_eq!(
+            serde_json::from_str::<PointAttributes>(&json).unwrap(),
+            attributes
+        );
+    }
+}
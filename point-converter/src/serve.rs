@@ -0,0 +1,343 @@
+//! Minimal HTTP server for a converted point cloud, so that the viewer can stream it from a URL
+//! without setting up a web server. Only meant for local development, every request gets its own thread.
+//!
+//! Files are addressed by their [crate::logical_path], e.g. `/metadata.json` or `/h_0/c_0_0_0.pcc`.
+//! GET and HEAD support a single byte range and every response allows any origin,
+//! so that the web build can be served from another port.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::archive::{Archive, ArchiveEntry};
+use crate::metadata::Metadata;
+
+/// Converted directory or archive whose files are served.
+#[derive(Debug)]
+pub enum ServedSource {
+    Directory(PathBuf),
+    Archive {
+        path: PathBuf,
+        archive: Box<Archive>,
+    },
+}
+
+impl ServedSource {
+    /// Archives are recognized by their extension, everything else has to be a converted directory.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        if path.extension().and_then(|it| it.to_str()) == Some(Archive::EXTENSION) {
+            Ok(Self::Archive {
+                path: path.to_path_buf(),
+                archive: Box::new(Archive::from_path(path)?),
+            })
+        } else if path
+            .join(crate::logical_path::to_path(&Metadata::logical_path()))
+            .is_file()
+        {
+            Ok(Self::Directory(path.to_path_buf()))
+        } else {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{:?} contains no converted point cloud", path),
+            ))
+        }
+    }
+
+    /// Opens the file at a logical path with the offset and length of its bytes inside the opened file.
+    fn open_file(&self, logical_path: &str) -> Result<(File, ArchiveEntry), Error> {
+        match self {
+            ServedSource::Directory(directory) => {
+                let file = File::open(directory.join(crate::logical_path::to_path(logical_path)))?;
+                let metadata = file.metadata()?;
+
+                if !metadata.is_file() {
+                    return Err(Error::from(ErrorKind::NotFound));
+                }
+
+                let entry = ArchiveEntry {
+                    offset: 0,
+                    length: metadata.len(),
+                };
+
+                Ok((file, entry))
+            }
+            ServedSource::Archive { path, archive } => {
+                let entry = archive
+                    .entry_for_path(logical_path)
+                    .ok_or_else(|| Error::from(ErrorKind::NotFound))?;
+
+                Ok((File::open(path)?, entry))
+            }
+        }
+    }
+}
+
+/// Serves the source until accepting a connection fails.
+pub fn serve(listener: TcpListener, source: ServedSource) -> Result<(), Error> {
+    let source = Arc::new(source);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let source = Arc::clone(&source);
+
+        std::thread::spawn(move || {
+            if let Err(err) = handle_connection(stream, &source) {
+                log::warn!("Failed to answer request: {}", err);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    range: Option<String>,
+}
+
+fn read_request(reader: &mut dyn BufRead) -> Result<Request, Error> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Error::new(ErrorKind::InvalidData, "Invalid request line"));
+    };
+
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+
+    let mut range = None;
+
+    loop {
+        let mut line = String::new();
+
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("range") {
+                range = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        range,
+    })
+}
+
+/// Logical path of the request path, or [None] if it leaves the served directory.
+fn logical_path(request_path: &str) -> Option<String> {
+    let logical_path = crate::logical_path::normalize(request_path);
+    let is_escaping = logical_path.split('/').any(|segment| segment == "..");
+
+    (!is_escaping && !logical_path.is_empty()).then_some(logical_path)
+}
+
+/// Parses a single range like `bytes=0-99`, `bytes=100-` or `bytes=-100` within a file of the given length.
+/// Returns [None] if the range can't be satisfied.
+fn parse_range(range: &str, length: u64) -> Option<Range<u64>> {
+    let (start, end) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+
+    let range = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix = suffix.parse::<u64>().ok()?;
+            length.saturating_sub(suffix)..length
+        }
+        (start, "") => start.parse().ok()?..length,
+        (start, end) => {
+            let end = end.parse::<u64>().ok()?.saturating_add(1);
+            start.parse().ok()?..end.min(length)
+        }
+    };
+
+    (range.start < range.end).then_some(range)
+}
+
+fn handle_connection(stream: TcpStream, source: &ServedSource) -> Result<(), Error> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let request = read_request(&mut reader)?;
+    let mut writer = stream;
+
+    log::debug!("{} {}", request.method, request.path);
+
+    let has_body = match request.method.as_str() {
+        "GET" => true,
+        "HEAD" => false,
+        "OPTIONS" => {
+            return write_head(
+                &mut writer,
+                "204 No Content",
+                &[
+                    ("Access-Control-Allow-Methods", "GET, HEAD, OPTIONS"),
+                    ("Access-Control-Allow-Headers", "Range"),
+                ],
+                0,
+            );
+        }
+        _ => return write_head(&mut writer, "405 Method Not Allowed", &[], 0),
+    };
+
+    let Some(logical_path) = logical_path(&request.path) else {
+        return write_head(&mut writer, "404 Not Found", &[], 0);
+    };
+
+    let (mut file, entry) = match source.open_file(&logical_path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return write_head(&mut writer, "404 Not Found", &[], 0);
+        }
+        Err(err) => return Err(err),
+    };
+
+    let content_type = if logical_path.ends_with(".json") {
+        "application/json"
+    } else {
+        "application/octet-stream"
+    };
+
+    let (status, range, content_range) = match &request.range {
+        Some(range) => match parse_range(range, entry.length) {
+            Some(range) => {
+                let content_range =
+                    format!("bytes {}-{}/{}", range.start, range.end - 1, entry.length);
+                ("206 Partial Content", range, Some(content_range))
+            }
+            None => {
+                let content_range = format!("bytes */{}", entry.length);
+                return write_head(
+                    &mut writer,
+                    "416 Range Not Satisfiable",
+                    &[("Content-Range", &content_range)],
+                    0,
+                );
+            }
+        },
+        None => ("200 OK", 0..entry.length, None),
+    };
+
+    let mut headers = vec![("Content-Type", content_type), ("Accept-Ranges", "bytes")];
+    if let Some(content_range) = &content_range {
+        headers.push(("Content-Range", content_range));
+    }
+
+    write_head(&mut writer, status, &headers, range.end - range.start)?;
+
+    if has_body {
+        file.seek(SeekFrom::Start(entry.offset + range.start))?;
+        std::io::copy(&mut file.take(range.end - range.start), &mut writer)?;
+    }
+
+    writer.flush()
+}
+
+fn write_head(
+    writer: &mut dyn Write,
+    status: &str,
+    headers: &[(&str, &str)],
+    content_length: u64,
+) -> Result<(), Error> {
+    write!(writer, "HTTP/1.1 {}\r\n", status)?;
+    write!(writer, "Access-Control-Allow-Origin: *\r\n")?;
+    write!(writer, "Connection: close\r\n")?;
+    write!(writer, "Content-Length: {}\r\n", content_length)?;
+
+    for (name, value) in headers {
+        write!(writer, "{}: {}\r\n", name, value)?;
+    }
+
+    write!(writer, "\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_byte_ranges() {
+        assert_eq!(parse_range("bytes=0-9", 100), Some(0..10));
+        assert_eq!(parse_range("bytes=90-", 100), Some(90..100));
+        assert_eq!(parse_range("bytes=-10", 100), Some(90..100));
+        assert_eq!(parse_range("bytes=90-200", 100), Some(90..100));
+        assert_eq!(parse_range("bytes=100-", 100), None);
+        assert_eq!(parse_range("bytes=5-2", 100), None);
+        assert_eq!(parse_range("items=0-9", 100), None);
+    }
+
+    #[test]
+    fn paths_stay_inside_the_directory() {
+        assert_eq!(
+            logical_path("/h_0/c_1_-2_3.pcc").as_deref(),
+            Some("h_0/c_1_-2_3.pcc")
+        );
+        assert_eq!(logical_path("/../secret"), None);
+        assert_eq!(logical_path("/h_0/../../secret"), None);
+        assert_eq!(logical_path("/"), None);
+    }
+
+    #[test]
+    fn serves_ranges_of_files() {
+        let directory =
+            std::env::temp_dir().join(format!("point-converter-serve-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let metadata_path = directory.join(crate::logical_path::to_path(&Metadata::logical_path()));
+        let mut metadata_file = File::create(&metadata_path).unwrap();
+        Metadata::default().write_to(&mut metadata_file).unwrap();
+        let metadata_bytes = std::fs::read(&metadata_path).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let source = ServedSource::open(&directory).unwrap();
+        std::thread::spawn(move || serve(listener, source));
+
+        let get = |path: &str, range: Option<&str>| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let range = range
+                .map(|range| format!("Range: {}\r\n", range))
+                .unwrap_or_default();
+            write!(
+                stream,
+                "GET {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n",
+                path, range
+            )
+            .unwrap();
+
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).unwrap();
+
+            let split = response
+                .windows(4)
+                .position(|it| it == b"\r\n\r\n")
+                .unwrap();
+            let head = String::from_utf8(response[..split].to_vec()).unwrap();
+            (head, response[split + 4..].to_vec())
+        };
+
+        let (head, body) = get("/metadata.json", None);
+        assert!(head.starts_with("HTTP/1.1 200"));
+        assert_eq!(body, metadata_bytes);
+
+        let (head, body) = get("/metadata.json", Some("bytes=2-5"));
+        assert!(head.starts_with("HTTP/1.1 206"));
+        assert!(head.contains(&format!(
+            "Content-Range: bytes 2-5/{}",
+            metadata_bytes.len()
+        )));
+        assert_eq!(body, metadata_bytes[2..6]);
+
+        let (head, _) = get("/h_0/c_0_0_0.pcc", None);
+        assert!(head.starts_with("HTTP/1.1 404"));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
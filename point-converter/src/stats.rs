@@ -0,0 +1,197 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use glam::{DVec3, Vec3};
+
+use crate::converter::{BatchedPointCloudPointReader, BatchedPointReader};
+use crate::error::ConverterError;
+use crate::metadata::Metadata;
+
+/// Summary of the positions of all points of a point cloud.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PointStats {
+    pub number_of_points: u64,
+    pub centroid: DVec3,
+    pub min: DVec3,
+    pub max: DVec3,
+    /// Population standard deviation per axis.
+    pub std_dev: DVec3,
+}
+
+impl PointStats {
+    pub fn extent(&self) -> DVec3 {
+        self.max - self.min
+    }
+}
+
+impl Display for PointStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Points:   {}", self.number_of_points)?;
+
+        if self.number_of_points == 0 {
+            return Ok(());
+        }
+
+        writeln!(f, "          {:>16} {:>16} {:>16}", "x", "y", "z")?;
+
+        for (name, value) in [
+            ("centroid", self.centroid),
+            ("min", self.min),
+            ("max", self.max),
+            ("extent", self.extent()),
+            ("std dev", self.std_dev),
+        ] {
+            writeln!(
+                f,
+                "{:<9} {:>16.4} {:>16.4} {:>16.4}",
+                name, value.x, value.y, value.z
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes [PointStats] in a single pass with Welford's algorithm,
+/// which stays accurate for many points far away from the origin.
+#[derive(Debug, Copy, Clone)]
+pub struct PointStatsAccumulator {
+    number_of_points: u64,
+    mean: DVec3,
+    /// Sum of the squared differences to the mean.
+    m2: DVec3,
+    min: DVec3,
+    max: DVec3,
+}
+
+impl Default for PointStatsAccumulator {
+    fn default() -> Self {
+        Self {
+            number_of_points: 0,
+            mean: DVec3::ZERO,
+            m2: DVec3::ZERO,
+            min: DVec3::INFINITY,
+            max: DVec3::NEG_INFINITY,
+        }
+    }
+}
+
+impl PointStatsAccumulator {
+    pub fn add(&mut self, pos: Vec3) {
+        let pos = pos.as_dvec3();
+
+        self.number_of_points += 1;
+
+        let delta = pos - self.mean;
+        self.mean += delta / self.number_of_points as f64;
+        self.m2 += delta * (pos - self.mean);
+
+        self.min = self.min.min(pos);
+        self.max = self.max.max(pos);
+    }
+
+    /// Every value is zero if no point was added.
+    pub fn finish(&self) -> PointStats {
+        if self.number_of_points == 0 {
+            return PointStats {
+                number_of_points: 0,
+                centroid: DVec3::ZERO,
+                min: DVec3::ZERO,
+                max: DVec3::ZERO,
+                std_dev: DVec3::ZERO,
+            };
+        }
+
+        PointStats {
+            number_of_points: self.number_of_points,
+            centroid: self.mean,
+            min: self.min,
+            max: self.max,
+            std_dev: (self.m2 / self.number_of_points as f64).powf(0.5),
+        }
+    }
+}
+
+/// Streams every cell of the converted point cloud in the directory.
+/// Each point is stored in exactly one cell, so all hierarchies are read to cover every point.
+pub fn compute_stats(directory: &Path) -> Result<PointStats, ConverterError> {
+    let metadata_path = directory
+        .join(Metadata::FILE_NAME)
+        .with_extension(Metadata::EXTENSION);
+
+    let mut reader = BatchedPointCloudPointReader::new(metadata_path)?;
+    let mut accumulator = PointStatsAccumulator::default();
+
+    while reader.remaining_points() > 0 {
+        let batch = reader.get_batch(10_000)?;
+
+        if batch.is_empty() {
+            break;
+        }
+
+        for point in batch {
+            accumulator.add(point.pos);
+        }
+    }
+
+    Ok(accumulator.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(positions: &[Vec3]) -> PointStats {
+        let mut accumulator = PointStatsAccumulator::default();
+        positions.iter().for_each(|pos| accumulator.add(*pos));
+        accumulator.finish()
+    }
+
+    #[test]
+    fn no_points() {
+        let stats = stats(&[]);
+
+        assert_eq!(stats.number_of_points, 0);
+        assert_eq!(stats.centroid, DVec3::ZERO);
+        assert_eq!(stats.extent(), DVec3::ZERO);
+    }
+
+    #[test]
+    fn matches_two_pass_computation() {
+        let positions = [
+            Vec3::new(1.0, -2.0, 3.0),
+            Vec3::new(4.0, 0.0, -1.0),
+            Vec3::new(-2.0, 5.0, 2.0),
+            Vec3::new(0.5, 1.5, 0.0),
+        ];
+
+        let stats = stats(&positions);
+
+        let n = positions.len() as f64;
+        let mean = positions.iter().map(|pos| pos.as_dvec3()).sum::<DVec3>() / n;
+        let variance = positions
+            .iter()
+            .map(|pos| (pos.as_dvec3() - mean).powf(2.0))
+            .sum::<DVec3>()
+            / n;
+
+        assert_eq!(stats.number_of_points, 4);
+        assert!(stats.centroid.abs_diff_eq(mean, 1e-12));
+        assert!(stats.std_dev.abs_diff_eq(variance.powf(0.5), 1e-12));
+        assert_eq!(stats.min, DVec3::new(-2.0, -2.0, -1.0));
+        assert_eq!(stats.max, DVec3::new(4.0, 5.0, 3.0));
+    }
+
+    #[test]
+    fn stays_accurate_far_from_the_origin() {
+        let offset = Vec3::new(100_000.0, -200_000.0, 50_000.0);
+        let positions = (0..10_000)
+            .map(|i| offset + Vec3::splat(if i % 2 == 0 { -1.0 } else { 1.0 }))
+            .collect::<Vec<_>>();
+
+        let stats = stats(&positions);
+
+        assert!(stats.centroid.abs_diff_eq(offset.as_dvec3(), 1e-9));
+        assert!(stats.std_dev.abs_diff_eq(DVec3::ONE, 1e-9));
+    }
+}
@@ -1,32 +1,37 @@
 use bevy_core::FrameCountPlugin;
 use bevy_diagnostic::FrameTimeDiagnosticsPlugin;
+use bevy_ecs::prelude::*;
 use bevy_state::app::StatesPlugin;
 use bevy_time::TimePlugin;
 use cfg_if::cfg_if;
 use std::sync::Arc;
 use url::Url;
 
-use crate::plugins::camera::CameraPlugin;
-use crate::plugins::cell::CellPlugin;
 use crate::plugins::debug::DebugPlugin;
-use crate::plugins::input::InputPlugin;
-use crate::plugins::metadata::MetadataPlugin;
-use crate::plugins::render::RenderPlugin;
-use crate::plugins::thread_pool::ThreadPoolPlugin;
+use crate::plugins::render::ui::UiPlugin;
 use crate::plugins::wgpu::WGPUPlugin;
 use crate::plugins::winit::{Window, WinitPlugin};
+use crate::{CloudViewerPlugin, ThreadPoolPlugin};
 
 pub struct App {
     pub canvas_id: Option<String>,
     pub url: Option<Url>,
+    /// See [CloudViewerPlugin::camera_pose].
+    pub camera_pose: Option<String>,
+    /// Reloads changed shaders from the source tree, see
+    /// [ShaderHotReloadPlugin](crate::plugins::render::shader::hot_reload::ShaderHotReloadPlugin).
+    /// Ignored on the web.
+    pub dev: bool,
 }
 
 impl App {
     pub async fn run(self) {
         setup_logger();
+        log::info!("{}", BuildInfo::CURRENT);
 
         let mut app = bevy_app::App::new();
-        app.add_plugins(WinitPlugin::new(self.canvas_id));
+        app.insert_resource(BuildInfo::CURRENT)
+            .add_plugins(WinitPlugin::new(self.canvas_id));
 
         WGPUPlugin::build(
             Arc::clone(app.world().get_resource::<Window>().unwrap()),
@@ -40,27 +45,92 @@ impl App {
             FrameCountPlugin,
             FrameTimeDiagnosticsPlugin,
         ))
-        .add_plugins((InputPlugin, CameraPlugin))
+        .add_plugins(CloudViewerPlugin {
+            url: self.url,
+            camera_pose: self.camera_pose,
+            thread_pool: ThreadPoolPlugin::default(),
+        })
         .add_plugins((
-            ThreadPoolPlugin,
-            MetadataPlugin { url: self.url },
-            CellPlugin,
             #[cfg(not(target_arch = "wasm32"))]
             crate::plugins::converter::ConverterPlugin,
             DebugPlugin,
-            RenderPlugin,
-        ))
-        .run();
+            UiPlugin,
+        ));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.dev {
+            app.add_plugins(crate::plugins::render::shader::hot_reload::ShaderHotReloadPlugin);
+        }
+
+        app.run();
     }
 }
 
+/// Which build of the viewer is running, for bug reports.
+#[derive(Debug, Copy, Clone, Resource)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// Short hash of the git commit, or `unknown` if it was built outside of a git repository.
+    pub git_hash: &'static str,
+}
+
+impl BuildInfo {
+    pub const CURRENT: Self = Self {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: env!("GIT_HASH"),
+    };
+}
+
+impl std::fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "point-cloud {} ({})", self.version, self.git_hash)
+    }
+}
+
+/// Records of the own crates pass the logger up to trace, so that [log::set_max_level]
+/// alone decides what is logged and can be changed at runtime, see [draw_log_level_ui].
+/// Other crates stay at warn unless `RUST_LOG` says otherwise.
 fn setup_logger() {
     cfg_if! {
         if #[cfg(target_arch = "wasm32")] {
             std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-            console_log::init_with_level(log::Level::Debug).expect("Couldn't initialize logger");
+            console_log::init_with_level(log::Level::Trace).expect("Couldn't initialize logger");
+            log::set_max_level(log::LevelFilter::Debug);
         } else {
-            env_logger::init();
+            if std::env::var_os("RUST_LOG").is_some() {
+                env_logger::init();
+            } else {
+                env_logger::Builder::new()
+                    .filter_level(log::LevelFilter::Warn)
+                    .filter_module("point_cloud_lib", log::LevelFilter::Trace)
+                    .filter_module("point_cloud", log::LevelFilter::Trace)
+                    .filter_module("point_converter", log::LevelFilter::Trace)
+                    .init();
+                log::set_max_level(log::LevelFilter::Info);
+            }
         }
     }
 }
+
+pub fn draw_log_level_ui(ui: &mut egui::Ui) {
+    let mut level = log::max_level();
+
+    egui::ComboBox::from_label("Log level")
+        .selected_text(level.as_str())
+        .show_ui(ui, |ui| {
+            for option in log::LevelFilter::iter() {
+                ui.selectable_value(&mut level, option, option.as_str());
+            }
+        });
+
+    if level != log::max_level() {
+        log::set_max_level(level);
+        log::info!("Log level set to {}", level);
+    }
+}
+
+pub fn draw_build_info_ui(ui: &mut egui::Ui, world: &mut World) {
+    if let Some(build_info) = world.get_resource::<BuildInfo>() {
+        ui.label(build_info.to_string());
+    }
+}
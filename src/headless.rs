@@ -0,0 +1,150 @@
+//! Drives the viewer without a window and without the winit event loop,
+//! so that the streaming can be tested deterministically, e.g. on a CI machine.
+
+use std::path::PathBuf;
+
+use bevy_core::FrameCountPlugin;
+use bevy_ecs::prelude::*;
+use bevy_state::app::StatesPlugin;
+use bevy_state::prelude::*;
+use bevy_time::TimePlugin;
+
+use crate::plugins::cell::{CellHeader, StreamingSettled};
+use crate::plugins::metadata::MetadataState;
+use crate::plugins::wgpu::WGPUPlugin;
+use crate::plugins::winit::{WindowEvent, WindowResized};
+use crate::{CloudViewerPlugin, ThreadPoolPlugin};
+
+/// The [CloudViewerPlugin] without a window, which only advances when [HeadlessApp::step] is called.
+/// Nothing is rendered, but cells are still streamed and uploaded to the gpu.
+pub struct HeadlessApp {
+    app: bevy_app::App,
+}
+
+impl HeadlessApp {
+    const SIZE: (u32, u32) = (1280, 720);
+
+    /// Returns [None] if there is no adapter, see [WGPUPlugin::build_headless].
+    pub async fn new() -> Option<Self> {
+        let mut app = bevy_app::App::new();
+        app.add_event::<WindowEvent>().add_event::<WindowResized>();
+
+        if !WGPUPlugin::build_headless(&mut app, Self::SIZE.0, Self::SIZE.1).await {
+            return None;
+        }
+
+        app.add_plugins((StatesPlugin, TimePlugin, FrameCountPlugin))
+            .add_plugins(CloudViewerPlugin {
+                url: None,
+                camera_pose: None,
+                thread_pool: ThreadPoolPlugin::default(),
+            });
+
+        app.finish();
+        app.cleanup();
+        // Runs the startup schedules, which e.g. create the active point cloud
+        app.update();
+
+        Some(Self { app })
+    }
+
+    /// Loads a metadata file or an archive, which is only picked up by the following steps.
+    pub fn open(&mut self, path: PathBuf) -> Result<(), String> {
+        crate::plugins::metadata::open_metadata_path(self.app.world_mut(), path)
+    }
+
+    /// Runs all schedules once, like a single frame.
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+
+    /// Steps until the predicate holds after a step.
+    /// Returns false if it still didn't hold after `max_steps`.
+    pub fn step_until(&mut self, max_steps: usize, predicate: impl Fn(&World) -> bool) -> bool {
+        for _ in 0..max_steps {
+            self.step();
+
+            if predicate(self.world()) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Steps until the metadata is loaded and all visible cells are streamed, see [StreamingSettled].
+    pub fn step_until_settled(&mut self, max_steps: usize) -> bool {
+        self.step_until(max_steps, |world| {
+            let loaded = world
+                .get_resource::<State<MetadataState>>()
+                .is_some_and(|state| *state.get() == MetadataState::Loaded);
+
+            loaded && world.resource::<StreamingSettled>().is_settled()
+        })
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    /// Number of points in all cells which are loaded and uploaded.
+    pub fn loaded_points(&mut self) -> u64 {
+        self.world_mut()
+            .query::<&CellHeader>()
+            .iter(self.app.world())
+            .map(|header| header.0.number_of_points as u64)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use point_converter::metadata::Metadata;
+    use point_converter::{convert_from_paths, CancelFlag, ConvertOptions};
+
+    #[test]
+    #[ignore = "needs a gpu adapter which isn't GL, run with --ignored"]
+    fn streams_a_converted_point_cloud() {
+        let mut app = pollster::block_on(HeadlessApp::new()).expect("There is no adapter");
+
+        let name = format!("point-cloud-headless-{}", std::process::id());
+        let tile = std::env::temp_dir().join(&name).with_extension("ply");
+        let output = std::env::temp_dir().join(&name);
+        let _ = std::fs::remove_dir_all(&output);
+
+        let number_of_points = 100;
+        let mut content = format!(
+            "ply\nformat ascii 1.0\nelement vertex {}\n\
+            property float x\nproperty float y\nproperty float z\nend_header\n",
+            number_of_points
+        );
+
+        for x in 0..number_of_points {
+            content.push_str(&format!("{} {} 1\n", x, x % 3));
+        }
+
+        std::fs::write(&tile, content).unwrap();
+        convert_from_paths(
+            &[tile],
+            &output,
+            &ConvertOptions::default(),
+            &CancelFlag::new(),
+        )
+        .unwrap();
+
+        app.open(
+            output
+                .join(Metadata::FILE_NAME)
+                .with_extension(Metadata::EXTENSION),
+        )
+        .unwrap();
+
+        assert!(app.step_until_settled(100_000));
+        assert_eq!(app.loaded_points(), number_of_points);
+    }
+}
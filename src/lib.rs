@@ -1,9 +1,27 @@
+use bevy_app::{App as BevyApp, Plugin};
+use url::Url;
+
 pub use app::App;
+#[cfg(not(target_arch = "wasm32"))]
+pub use headless::HeadlessApp;
+pub use plugins::debug::DebugPlugin;
+pub use plugins::render::ui::UiPlugin;
+pub use plugins::thread_pool::ThreadPoolPlugin;
+pub use plugins::wgpu::WGPUPlugin;
+pub use plugins::winit::{Window, WinitPlugin};
+
+use plugins::camera::CameraPlugin;
+use plugins::cell::CellPlugin;
+use plugins::input::InputPlugin;
+use plugins::metadata::MetadataPlugin;
+use plugins::render::RenderPlugin;
 
 mod app;
 mod event_set;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
 mod plugins;
-mod sorted_hash;
+pub mod sorted_hash;
 mod texture;
 mod transform;
 
@@ -12,14 +30,51 @@ mod web;
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen::prelude::wasm_bindgen]
-pub async fn run(canvas_id: String, url: Option<String>) {
+pub async fn run(canvas_id: String, url: Option<String>, camera_pose: Option<String>) {
     use std::str::FromStr;
     use url::Url;
 
     App {
         canvas_id: Some(canvas_id),
         url: url.map(|url| Url::from_str(&url).unwrap()),
+        camera_pose,
+        dev: false,
     }
     .run()
     .await;
 }
+
+/// Loads the metadata, streams the visible cells and renders them.
+/// The egui side panel isn't part of it and can be added with [UiPlugin].
+///
+/// Requires the [WinitPlugin], [WGPUPlugin] and the bevy state and time plugins.
+pub struct CloudViewerPlugin {
+    pub url: Option<Url>,
+    /// Initial camera pose as `x,y,z,qx,qy,qz,qw`, which is ignored if it can't be parsed.
+    pub camera_pose: Option<String>,
+    pub thread_pool: ThreadPoolPlugin,
+}
+
+impl Plugin for CloudViewerPlugin {
+    fn build(&self, app: &mut BevyApp) {
+        let initial_pose = self.camera_pose.as_deref().and_then(|pose| {
+            let initial_pose = plugins::camera::parse_camera_pose(pose);
+
+            if initial_pose.is_none() {
+                log::warn!("Ignoring invalid camera pose '{}'", pose);
+            }
+
+            initial_pose
+        });
+
+        app.add_plugins((InputPlugin, CameraPlugin { initial_pose }))
+            .add_plugins((
+                self.thread_pool.clone(),
+                MetadataPlugin {
+                    url: self.url.clone(),
+                },
+                CellPlugin,
+                RenderPlugin,
+            ));
+    }
+}
@@ -4,6 +4,8 @@ fn main() {
     let future = App {
         canvas_id: None,
         url: None,
+        camera_pose: None,
+        dev: std::env::args().any(|arg| arg == "--dev"),
     }
     .run();
 
@@ -6,11 +6,13 @@ use std::ops::{Deref, DerefMut};
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemState;
 use flume::{Receiver, Sender, TryRecvError};
 use rustc_hash::{FxHashMap, FxHashSet};
+use thousands::Separable;
 
 use crate::plugins::asset::source::{Source, SourceError};
-use crate::plugins::thread_pool::{ThreadPool, ThreadPoolRes};
+use crate::plugins::thread_pool::{IoThreadPoolRes, ThreadPool};
 
 pub mod source;
 
@@ -34,7 +36,11 @@ where
         }
 
         app.add_event::<AssetEvent<T>>()
-            .add_systems(PreUpdate, handle_loaded_events::<T>)
+            .add_event::<AssetSavedEvent<T>>()
+            .add_systems(
+                PreUpdate,
+                (handle_loaded_events::<T>, handle_saved_events::<T>),
+            )
             .add_systems(
                 PostUpdate,
                 (
@@ -159,6 +165,26 @@ where
     asset: Result<T, SourceError>,
 }
 
+#[derive(Debug)]
+struct SavedAssetMsg<T>
+where
+    T: Asset,
+{
+    id: T::Id,
+    result: Result<(), SourceError>,
+}
+
+/// Sent when an asset which was saved in the background is written,
+/// see [AssetManager::save_all_in_background].
+#[derive(Debug, Event)]
+pub struct AssetSavedEvent<T>
+where
+    T: Asset,
+{
+    pub id: T::Id,
+    pub result: Result<(), SourceError>,
+}
+
 #[derive(Debug)]
 pub enum AssetLoadedEvent<T>
 where
@@ -321,6 +347,9 @@ where
     just_changed: FxHashSet<AssetHandle<T>>,
     load_channels: Channels<LoadAssetMsg<T>>,
     loaded_channels: Channels<LoadedAssetMsg<T>>,
+    saved_channels: Channels<SavedAssetMsg<T>>,
+    /// Number of background saves which aren't finished yet.
+    pending_saves: usize,
     ref_count_channels: Channels<ChangeRefCount<T::Id>>,
     ref_counts: FxHashMap<T::Id, u32>,
     waiting_for_reply: FxHashMap<T::Id, Vec<Sender<AssetLoadedEvent<T>>>>,
@@ -350,6 +379,8 @@ where
             just_changed: FxHashSet::default(),
             load_channels: Channels::default(),
             loaded_channels: Channels::default(),
+            saved_channels: Channels::default(),
+            pending_saves: 0,
             ref_count_channels: Channels::default(),
             ref_counts: FxHashMap::default(),
             waiting_for_reply: FxHashMap::default(),
@@ -361,6 +392,20 @@ impl<T> AssetManager<T>
 where
     T: Asset,
 {
+    /// Number of assets which are loaded or loading.
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    /// Number of assets which are loading.
+    /// Load messages which weren't received yet are not counted.
+    pub fn pending_loads(&self) -> usize {
+        self.store
+            .values()
+            .filter(|entry| entry.load_status == AssetLoadStatus::Loading)
+            .count()
+    }
+
     pub fn load_sender(&self) -> &Sender<LoadAssetMsg<T>> {
         &self.load_channels.sender
     }
@@ -390,6 +435,48 @@ where
         }
     }
 
+    /// Like [AssetManager::save_all], but saves copies of the assets on the thread pool,
+    /// so that a large asset doesn't stall the frame. Sends an [AssetSavedEvent] for each of them.
+    /// The assets count as saved right away and as unsaved again if saving fails.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_all_in_background(&mut self, thread_pool: &ThreadPool)
+    where
+        T: Clone,
+    {
+        for (id, entry) in self.store.iter_mut() {
+            if entry.is_saved {
+                continue;
+            }
+
+            let Some(asset) = &entry.asset else {
+                continue;
+            };
+
+            entry.is_saved = true;
+            self.pending_saves += 1;
+
+            let asset = asset.clone();
+            let source = entry.source.clone();
+            let id = id.clone();
+            let saved_sender = self.saved_channels.sender.clone();
+
+            thread_pool.execute(move || {
+                let result = asset.save(source);
+                saved_sender.send(SavedAssetMsg { id, result }).unwrap();
+            });
+        }
+    }
+
+    /// Number of assets which are being saved in the background.
+    pub fn pending_saves(&self) -> usize {
+        self.pending_saves
+    }
+
+    /// Saves the asset to another source, without changing where it is saved to afterwards.
+    pub fn save_copy(&self, handle: &AssetHandle<T>, source: Source) -> Result<(), SourceError> {
+        self.get_asset(handle).save(source)
+    }
+
     pub fn set_source(&mut self, handle: &AssetHandle<T>, source: Source) {
         let entry = self.store.get_mut(handle.id()).unwrap();
         entry.source = source;
@@ -575,6 +662,35 @@ where
         }
     }
 
+    fn handle_saved_events(&mut self, event_writer: &mut EventWriter<AssetSavedEvent<T>>) {
+        loop {
+            match self.saved_channels.receiver.try_recv() {
+                Ok(msg) => {
+                    self.pending_saves -= 1;
+
+                    if let Err(err) = &msg.result {
+                        log::error!("Failed to save asset {:?}: {}", msg.id, err);
+
+                        if let Some(entry) = self.store.get_mut(&msg.id) {
+                            entry.is_saved = false;
+                        }
+                    }
+
+                    event_writer.send(AssetSavedEvent {
+                        id: msg.id,
+                        result: msg.result,
+                    });
+                }
+                Err(TryRecvError::Empty) => {
+                    break;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    unreachable!("self always holds a sender")
+                }
+            }
+        }
+    }
+
     fn send_created_events(&mut self, event_writer: &mut EventWriter<AssetEvent<T>>) {
         event_writer.send_batch(
             self.just_created
@@ -640,9 +756,9 @@ where
 fn handle_load_events<T: Asset>(
     mut asset_manager: AssetManagerResMut<T>,
     mut asset_events: EventWriter<AssetEvent<T>>,
-    thread_pool: ThreadPoolRes,
+    io_thread_pool: IoThreadPoolRes,
 ) {
-    asset_manager.handle_load_events(&mut asset_events, &thread_pool);
+    asset_manager.handle_load_events(&mut asset_events, &io_thread_pool);
 }
 
 fn handle_loaded_events<T: Asset>(
@@ -652,6 +768,13 @@ fn handle_loaded_events<T: Asset>(
     asset_manager.handle_loaded_events(&mut asset_events);
 }
 
+fn handle_saved_events<T: Asset>(
+    mut asset_manager: AssetManagerResMut<T>,
+    mut asset_events: EventWriter<AssetSavedEvent<T>>,
+) {
+    asset_manager.handle_saved_events(&mut asset_events);
+}
+
 fn send_created_and_changed_events<T: Asset>(
     mut asset_manager: AssetManagerResMut<T>,
     mut asset_events: EventWriter<AssetEvent<T>>,
@@ -674,3 +797,15 @@ where
         }
     }
 }
+
+pub fn draw_ui<T: Asset>(ui: &mut egui::Ui, world: &mut World, name: &str) {
+    let mut params = SystemState::<AssetManagerRes<T>>::new(world);
+    let manager = params.get(world);
+
+    ui.label(format!(
+        "{}: {} ({} loading)",
+        name,
+        manager.len().separate_with_commas(),
+        manager.pending_loads().separate_with_commas()
+    ));
+}
@@ -1,4 +1,5 @@
 use crate::plugins::asset::Asset;
+use point_converter::error::ConverterError;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use url::Url;
@@ -33,6 +34,17 @@ impl From<std::io::Error> for SourceError {
     }
 }
 
+impl From<ConverterError> for SourceError {
+    fn from(value: ConverterError) -> Self {
+        match value {
+            ConverterError::Io(err) => err.into(),
+            ConverterError::UnsupportedFormat(_)
+            | ConverterError::CorruptData { .. }
+            | ConverterError::Unsupported(_) => std::io::Error::from(value).into(),
+        }
+    }
+}
+
 #[cfg(target_arch = "wasm32")]
 impl From<wasm_bindgen::JsValue> for SourceError {
     fn from(value: wasm_bindgen::JsValue) -> Self {
@@ -70,28 +82,65 @@ impl Display for SourceError {
 }
 impl Error for SourceError {}
 
+/// An opened archive whose entries can be loaded individually.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct ArchiveFile {
+    pub path: std::path::PathBuf,
+    pub archive: point_converter::archive::Archive,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ArchiveFile {
+    pub fn open(path: std::path::PathBuf) -> Result<Self, SourceError> {
+        let archive = point_converter::archive::Archive::from_path(&path)?;
+        Ok(Self { path, archive })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Directory {
     #[cfg(not(target_arch = "wasm32"))]
     Path(std::path::PathBuf),
+    #[cfg(not(target_arch = "wasm32"))]
+    Archive(std::sync::Arc<ArchiveFile>),
     #[cfg(target_arch = "wasm32")]
     WebDir(crate::web::WebDir),
+    #[cfg(target_arch = "wasm32")]
+    Zip(crate::web::WebZip),
     URL(Url),
 }
 
 impl Directory {
-    pub fn join(&self, path: &std::path::Path) -> Source {
+    /// Joins a path inside the converted directory, see [point_converter::logical_path].
+    /// It only becomes a platform path for [Directory::Path].
+    pub fn join(&self, path: &str) -> Source {
+        let path = point_converter::logical_path::normalize(path);
+
         match self {
             #[cfg(not(target_arch = "wasm32"))]
-            Directory::Path(dir) => Source::Path(dir.join(path)),
+            Directory::Path(dir) => {
+                Source::Path(dir.join(point_converter::logical_path::to_path(&path)))
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Directory::Archive(archive) => Source::ArchiveEntry {
+                archive: archive.clone(),
+                path,
+            },
             #[cfg(target_arch = "wasm32")]
             Directory::WebDir(dir) => Source::PathInDirectory {
                 directory: dir.clone(),
-                path: path.to_path_buf(),
+                path,
+            },
+            #[cfg(target_arch = "wasm32")]
+            Directory::Zip(zip) => Source::ZipEntry {
+                zip: zip.clone(),
+                path,
             },
             Directory::URL(url) => {
                 let mut url = url.clone();
-                url.set_path(path.to_str().unwrap());
+                let joined_path = format!("{}/{}", url.path().trim_end_matches('/'), path);
+                url.set_path(&joined_path);
                 Source::URL(url)
             }
         }
@@ -103,10 +152,25 @@ pub enum Source {
     #[cfg(not(target_arch = "wasm32"))]
     Path(std::path::PathBuf),
 
+    /// A file inside an archive, addressed by its path inside the converted directory.
+    /// The paths of this and the following variants are [point_converter::logical_path]s.
+    #[cfg(not(target_arch = "wasm32"))]
+    ArchiveEntry {
+        archive: std::sync::Arc<ArchiveFile>,
+        path: String,
+    },
+
     #[cfg(target_arch = "wasm32")]
     PathInDirectory {
         directory: crate::web::WebDir,
-        path: std::path::PathBuf,
+        path: String,
+    },
+
+    /// A file inside an uploaded zip, addressed by its path inside the converted directory.
+    #[cfg(target_arch = "wasm32")]
+    ZipEntry {
+        zip: crate::web::WebZip,
+        path: String,
     },
 
     URL(Url),
@@ -123,6 +187,19 @@ impl Source {
                 let mut buf_reader = std::io::BufReader::new(file);
                 T::read_from(&mut buf_reader)
             }
+            Source::ArchiveEntry { archive, path } => {
+                use std::io::{Read, Seek, SeekFrom};
+
+                let entry = archive
+                    .archive
+                    .entry_for_path(path)
+                    .ok_or_else(|| SourceError::NotFound(path.clone()))?;
+
+                let mut file = std::fs::File::open(&archive.path)?;
+                file.seek(SeekFrom::Start(entry.offset))?;
+                let mut buf_reader = std::io::BufReader::new(file.take(entry.length));
+                T::read_from(&mut buf_reader)
+            }
             Source::URL(url) => {
                 let request = ehttp::Request::get(url);
                 let response = ehttp::fetch_blocking(&request);
@@ -136,39 +213,30 @@ impl Source {
     pub async fn load<T: Asset>(&self) -> Result<T, SourceError> {
         match self {
             Source::PathInDirectory { directory, path } => {
-                use std::path::Component;
+                let segments = point_converter::logical_path::segments(path).collect::<Vec<_>>();
+
+                let Some((file_name, dir_names)) = segments.split_last() else {
+                    return Err(SourceError::InvalidPath(path.clone()));
+                };
+
+                if segments.contains(&"..") {
+                    return Err(SourceError::InvalidPath(path.clone()));
+                }
 
                 let mut dir = directory.clone();
 
-                let components = path.components().collect::<Vec<_>>();
-
-                for (i, component) in components.iter().enumerate() {
-                    match component {
-                        Component::Prefix(_) | Component::ParentDir => {
-                            return Err(SourceError::InvalidPath(
-                                path.to_str().unwrap().to_string(),
-                            ));
-                        }
-                        Component::RootDir | Component::CurDir => {
-                            continue;
-                        }
-                        Component::Normal(segment) => {
-                            let segment = segment.to_str().unwrap();
-
-                            if i == components.len() - 1 {
-                                let bytes =
-                                    dir.get_file_handle(segment).await?.read_bytes().await?;
-
-                                let mut cursor = std::io::Cursor::new(bytes);
-                                return T::read_from(&mut cursor);
-                            } else {
-                                dir = dir.get_dir_handle(segment).await?;
-                            }
-                        }
-                    }
+                for dir_name in dir_names {
+                    dir = dir.get_dir_handle(dir_name).await?;
                 }
 
-                Err(SourceError::InvalidPath(path.to_str().unwrap().to_string()))
+                let bytes = dir.get_file_handle(file_name).await?.read_bytes().await?;
+                let mut cursor = std::io::Cursor::new(bytes);
+                T::read_from(&mut cursor)
+            }
+            Source::ZipEntry { zip, path } => {
+                let bytes = zip.read_bytes(path)?;
+                let mut cursor = std::io::Cursor::new(bytes);
+                T::read_from(&mut cursor)
             }
             Source::URL(url) => {
                 let request = ehttp::Request::get(url);
@@ -180,15 +248,35 @@ impl Source {
     }
 }
 
+/// Sends `bytes` with a POST request to `url`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn upload_to_url(url: &Url, bytes: Vec<u8>) -> Result<(), SourceError> {
+    let mut request = ehttp::Request::post(url, bytes);
+    request
+        .headers
+        .insert("Content-Type", "application/octet-stream");
+
+    let response = ehttp::fetch_blocking(&request);
+    check_response(url, response).map(|_| ())
+}
+
 fn handle_response_from_url<T: Asset>(
     url: &Url,
     response: ehttp::Result<ehttp::Response>,
 ) -> Result<T, SourceError> {
+    let response = check_response(url, response)?;
+    let mut cursor = std::io::Cursor::new(response.bytes);
+    T::read_from(&mut cursor)
+}
+
+fn check_response(
+    url: &Url,
+    response: ehttp::Result<ehttp::Response>,
+) -> Result<ehttp::Response, SourceError> {
     match response {
         Ok(response) => {
             if (200..300).contains(&response.status) {
-                let mut cursor = std::io::Cursor::new(response.bytes);
-                T::read_from(&mut cursor)
+                Ok(response)
             } else if response.status == 404 {
                 Err(SourceError::NotFound(url.to_string()))
             } else {
@@ -1,11 +1,15 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
-use glam::{Mat4, UVec2, Vec3};
+use bevy_state::prelude::*;
+use bounding_volume::Aabb;
+use glam::{EulerRot, Mat4, Quat, UVec2, Vec3, Vec4};
 use wgpu::util::DeviceExt;
 
 use crate::plugins::camera::fly_cam::{FlyCamController, FlyCamPlugin};
 use crate::plugins::camera::frustum::Frustum;
 use crate::plugins::camera::projection::PerspectiveProjection;
+use crate::plugins::camera::turntable::TurntablePlugin;
+use crate::plugins::metadata::{look_at_bounding_box, ActiveMetadata, MetadataState};
 use crate::plugins::render::BufferSet;
 use crate::plugins::wgpu::{Device, Queue, SurfaceConfig};
 use crate::plugins::winit::WindowResized;
@@ -14,13 +18,23 @@ use crate::transform::Transform;
 pub mod fly_cam;
 pub mod frustum;
 pub mod projection;
+pub mod turntable;
 
-pub struct CameraPlugin;
+pub struct CameraPlugin {
+    /// Replaces the initial view of the first loaded point cloud, see [parse_camera_pose].
+    pub initial_pose: Option<Transform>,
+}
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(FlyCamPlugin)
+        app.add_plugins((FlyCamPlugin, TurntablePlugin))
+            .insert_resource(InitialCameraPose(self.initial_pose))
             .add_systems(Startup, setup.in_set(BufferSet))
+            .add_systems(
+                OnEnter(MetadataState::Loaded),
+                apply_initial_camera_pose.after(look_at_bounding_box),
+            )
+            .add_systems(OnEnter(MetadataState::NotLoaded), reset_camera)
             .add_systems(
                 PreUpdate,
                 update_aspect_ratio.run_if(on_event::<WindowResized>()),
@@ -93,6 +107,53 @@ impl ViewportBuffer {
 #[derive(Component)]
 pub struct Camera;
 
+#[derive(Resource)]
+struct InitialCameraPose(Option<Transform>);
+
+/// Only the first loaded point cloud uses the initial pose.
+fn apply_initial_camera_pose(
+    mut initial_camera_pose: ResMut<InitialCameraPose>,
+    mut camera_query: Query<&mut Transform, With<Camera>>,
+) {
+    let Some(pose) = initial_camera_pose.0.take() else {
+        return;
+    };
+
+    for mut transform in camera_query.iter_mut() {
+        *transform = pose;
+    }
+}
+
+/// Translation and rotation as `x,y,z,qx,qy,qz,qw`, short enough for a link to a view.
+pub fn camera_pose_to_string(transform: &Transform) -> String {
+    let t = transform.translation;
+    let r = transform.rotation;
+
+    [t.x, t.y, t.z, r.x, r.y, r.z, r.w]
+        .map(|value| value.to_string())
+        .join(",")
+}
+
+/// Inverse of [camera_pose_to_string]. The rotation is normalized.
+pub fn parse_camera_pose(pose: &str) -> Option<Transform> {
+    let values = pose
+        .split(',')
+        .map(|value| value.trim().parse::<f32>().ok().filter(|it| it.is_finite()))
+        .collect::<Option<Vec<_>>>()?;
+
+    let [x, y, z, qx, qy, qz, qw] = values[..] else {
+        return None;
+    };
+
+    let rotation = Vec4::new(qx, qy, qz, qw).try_normalize()?;
+
+    Some(Transform {
+        translation: Vec3::new(x, y, z),
+        rotation: Quat::from_vec4(rotation),
+        scale: Vec3::ONE,
+    })
+}
+
 #[derive(Debug, Copy, Clone, Component)]
 pub struct Visibility {
     pub visible: bool,
@@ -104,9 +165,12 @@ impl Visibility {
     }
 }
 
+fn initial_transform() -> Transform {
+    Transform::from_translation(Vec3::new(0.0, -1.0, 0.0)).looking_at(Vec3::ZERO, Vec3::Z)
+}
+
 fn setup(mut commands: Commands, device: Res<Device>, config: Res<SurfaceConfig>) {
-    let transform =
-        Transform::from_translation(Vec3::new(0.0, -1.0, 0.0)).looking_at(Vec3::ZERO, Vec3::Z);
+    let transform = initial_transform();
 
     let projection = PerspectiveProjection::default();
 
@@ -121,6 +185,12 @@ fn setup(mut commands: Commands, device: Res<Device>, config: Res<SurfaceConfig>
     ));
 }
 
+fn reset_camera(mut query: Query<&mut Transform, With<Camera>>) {
+    for mut transform in query.iter_mut() {
+        *transform = initial_transform();
+    }
+}
+
 fn update_aspect_ratio(
     mut window_resized: EventReader<WindowResized>,
     mut query: Query<&mut PerspectiveProjection>,
@@ -190,15 +260,340 @@ fn write_viewport_uniform(
     }
 }
 
+/// Views along the principal axes onto the center of a bounding box.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViewPreset {
+    /// Looks down along -Z.
+    Top,
+    /// Looks along +Y.
+    Front,
+    /// Looks along -X.
+    Side,
+    /// Looks diagonally down from the front right.
+    Iso,
+}
+
+impl ViewPreset {
+    pub const ALL: [Self; 4] = [Self::Top, Self::Front, Self::Side, Self::Iso];
+
+    fn direction_and_up(self) -> (Vec3, Vec3) {
+        match self {
+            Self::Top => (Vec3::NEG_Z, Vec3::Y),
+            Self::Front => (Vec3::Y, Vec3::Z),
+            Self::Side => (Vec3::NEG_X, Vec3::Z),
+            Self::Iso => (Vec3::new(-1.0, 1.0, -1.0).normalize(), Vec3::Z),
+        }
+    }
+
+    /// Places the camera far enough away that the bounding sphere of the box fits into the vertical field of view.
+    pub fn transform(self, aabb: &Aabb, fov_y: f32) -> Transform {
+        let (direction, up) = self.direction_and_up();
+
+        let center = (aabb.min + aabb.max) / 2.0;
+        let radius = ((aabb.max - aabb.min).length() / 2.0).max(1.0);
+        let distance = radius / (fov_y / 2.0).sin();
+
+        let mut transform = Transform::from_translation(center - direction * distance);
+        transform.look_to(direction, up);
+        transform
+    }
+}
+
+/// Smallest distance from the center of the box at which the camera,
+/// looking along `direction`, sees all corners of the box.
+/// Unlike a bounding sphere this frames flat and tall boxes tightly for any aspect ratio.
+pub fn framing_distance(
+    aabb: &Aabb,
+    direction: Vec3,
+    up: Vec3,
+    fov_y: f32,
+    aspect_ratio: f32,
+) -> f32 {
+    let direction = direction.normalize();
+    let right = direction.cross(up).normalize();
+    let up = right.cross(direction);
+
+    let slope_y = (fov_y / 2.0).tan();
+    let slope_x = slope_y * aspect_ratio;
+
+    let half_size = (aabb.max - aabb.min) / 2.0;
+
+    // Relative to the center of the box
+    let corners = (0..8).map(|i| {
+        half_size
+            * Vec3::new(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { -1.0 } else { 1.0 },
+            )
+    });
+
+    corners
+        .map(|corner| {
+            // A corner further away along the view direction needs less distance to fit
+            let depth = corner.dot(direction);
+            let x = corner.dot(right).abs() / slope_x;
+            let y = corner.dot(up).abs() / slope_y;
+
+            x.max(y) - depth
+        })
+        .fold(1.0, f32::max)
+}
+
 pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
-    let mut query = world.query_filtered::<&Transform, With<Camera>>();
-    for transform in query.iter_mut(world) {
-        ui.collapsing("Position", |ui| {
-            ui.label(format!("x: {}", transform.translation.x));
-            ui.label(format!("y: {}", transform.translation.y));
-            ui.label(format!("z: {}", transform.translation.z));
+    let mut query = world.query_filtered::<&mut Transform, With<Camera>>();
+    for mut transform in query.iter_mut(world) {
+        ui.collapsing("Transform", |ui| {
+            draw_transform_ui(ui, &mut transform);
         });
     }
 
+    draw_view_preset_ui(ui, world);
+
     fly_cam::draw_ui(ui, world);
+
+    turntable::draw_ui(ui, world);
+
+    #[cfg(target_arch = "wasm32")]
+    draw_view_link_ui(ui, world);
+}
+
+fn draw_view_preset_ui(ui: &mut egui::Ui, world: &mut World) {
+    let is_loaded = *world.resource::<State<MetadataState>>().get() == MetadataState::Loaded;
+
+    let clicked = ui
+        .horizontal(|ui| {
+            ui.label("View");
+
+            ViewPreset::ALL.into_iter().find(|preset| {
+                ui.add_enabled(is_loaded, egui::Button::new(format!("{:?}", preset)))
+                    .clicked()
+            })
+        })
+        .inner;
+
+    let Some(preset) = clicked else {
+        return;
+    };
+
+    let mut params = bevy_ecs::system::SystemState::<(
+        ActiveMetadata,
+        Query<(&mut Transform, &PerspectiveProjection), With<Camera>>,
+    )>::new(world);
+    let (active_metadata, mut camera_query) = params.get_mut(world);
+
+    let aabb = active_metadata
+        .point_cloud()
+        .transform
+        .transform_aabb(&active_metadata.get().bounding_box);
+
+    for (mut transform, projection) in camera_query.iter_mut() {
+        *transform = preset.transform(&aabb, projection.fov_y);
+    }
+}
+
+/// Writes the camera pose and the url of the point cloud into the address bar and copies the link.
+#[cfg(target_arch = "wasm32")]
+fn draw_view_link_ui(ui: &mut egui::Ui, world: &mut World) {
+    use crate::plugins::asset::source::Source;
+
+    if !ui.button("Copy view link").clicked() {
+        return;
+    }
+
+    let mut camera_query = world.query_filtered::<&Transform, With<Camera>>();
+    let Ok(transform) = camera_query.get_single(world) else {
+        return;
+    };
+
+    let mut params = vec![("camera", camera_pose_to_string(transform))];
+
+    if *world.resource::<State<MetadataState>>().get() == MetadataState::Loaded {
+        let mut system_state = bevy_ecs::system::SystemState::<ActiveMetadata>::new(world);
+        let active_metadata = system_state.get(world);
+
+        if let Source::URL(url) = active_metadata.get_source() {
+            params.push(("url", url.to_string()));
+        }
+    }
+
+    match crate::web::replace_query_params(&params) {
+        Ok(link) => ui.output_mut(|output| output.copied_text = link),
+        Err(err) => log::error!("Failed to create a view link: {:?}", err),
+    }
+}
+
+fn draw_transform_ui(ui: &mut egui::Ui, transform: &mut Mut<Transform>) {
+    if let Some(new_transform) = draw_transform_grid(ui, "camera_transform_grid", transform, true) {
+        **transform = new_transform;
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("Copy as RON").clicked() {
+            let ron = transform_to_ron(transform);
+            ui.output_mut(|output| output.copied_text = ron);
+        }
+
+        if ui
+            .button("Copy pose")
+            .on_hover_text("As used by the camera query parameter of the web viewer")
+            .clicked()
+        {
+            let pose = camera_pose_to_string(transform);
+            ui.output_mut(|output| output.copied_text = pose);
+        }
+    });
+}
+
+/// Editable translation, rotation and optionally scale.
+/// Returns the new transform only when a value changed,
+/// so that callers don't trigger change detection every frame.
+pub(crate) fn draw_transform_grid(
+    ui: &mut egui::Ui,
+    id_source: impl std::hash::Hash,
+    transform: &Transform,
+    with_scale: bool,
+) -> Option<Transform> {
+    let mut translation = transform.translation;
+    let (yaw, pitch, roll) = transform.rotation.to_euler(EulerRot::ZXY);
+    let mut rotation = Vec3::new(pitch.to_degrees(), roll.to_degrees(), yaw.to_degrees());
+    let mut scale = transform.scale;
+
+    let mut changed = false;
+
+    egui::Grid::new(id_source).num_columns(4).show(ui, |ui| {
+        let mut vec3_row = |ui: &mut egui::Ui, label: &str, value: &mut Vec3, speed: f32| {
+            ui.label(label);
+            for component in [&mut value.x, &mut value.y, &mut value.z] {
+                changed |= ui
+                    .add(egui::DragValue::new(component).speed(speed).max_decimals(3))
+                    .changed();
+            }
+            ui.end_row();
+        };
+
+        vec3_row(ui, "Translation", &mut translation, 0.1);
+        vec3_row(ui, "Rotation (°)", &mut rotation, 1.0);
+
+        if with_scale {
+            vec3_row(ui, "Scale", &mut scale, 0.01);
+        }
+    });
+
+    changed.then(|| Transform {
+        translation,
+        rotation: Quat::from_euler(
+            EulerRot::ZXY,
+            rotation.z.to_radians(),
+            rotation.x.to_radians(),
+            rotation.y.to_radians(),
+        ),
+        scale,
+    })
+}
+
+fn transform_to_ron(transform: &Transform) -> String {
+    let Transform {
+        translation: t,
+        rotation: r,
+        scale: s,
+    } = transform;
+
+    format!(
+        "(translation: ({}, {}, {}), rotation: ({}, {}, {}, {}), scale: ({}, {}, {}))",
+        t.x, t.y, t.z, r.x, r.y, r.z, r.w, s.x, s.y, s.z
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_pose_round_trip() {
+        let transform = Transform::from_xyz(1.5, -2.25, 300.125).looking_at(Vec3::ZERO, Vec3::Z);
+        let parsed = parse_camera_pose(&camera_pose_to_string(&transform)).unwrap();
+
+        assert_eq!(parsed.translation, transform.translation);
+        assert!(parsed.rotation.abs_diff_eq(transform.rotation, 1e-6));
+    }
+
+    #[test]
+    fn view_presets_look_at_the_center() {
+        let aabb = Aabb::new(Vec3::new(-10.0, 0.0, 0.0), Vec3::new(10.0, 40.0, 5.0));
+        let center = Vec3::new(0.0, 20.0, 2.5);
+
+        for preset in ViewPreset::ALL {
+            let transform = preset.transform(&aabb, std::f32::consts::FRAC_PI_4);
+            let (direction, _) = preset.direction_and_up();
+            let to_center = (center - transform.translation).normalize();
+
+            assert!(
+                transform.forward().abs_diff_eq(direction, 1e-5),
+                "{:?}",
+                preset
+            );
+            assert!(to_center.abs_diff_eq(direction, 1e-5), "{:?}", preset);
+        }
+
+        let top = ViewPreset::Top.transform(&aabb, std::f32::consts::FRAC_PI_4);
+        assert!(top.translation.z > aabb.max.z);
+        assert!(top.up().abs_diff_eq(Vec3::Y, 1e-5));
+    }
+
+    #[test]
+    fn framing_distance_fits_all_corners() {
+        let fov_y = std::f32::consts::FRAC_PI_4;
+
+        for aabb in [
+            Aabb::new(Vec3::new(-500.0, -300.0, 0.0), Vec3::new(500.0, 300.0, 2.0)),
+            Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(4.0, 4.0, 200.0)),
+        ] {
+            for aspect_ratio in [0.5, 1.0, 2.0] {
+                let direction = Vec3::new(-1.0, 1.0, -1.0).normalize();
+                let distance = framing_distance(&aabb, direction, Vec3::Z, fov_y, aspect_ratio);
+
+                let center = (aabb.min + aabb.max) / 2.0;
+                let transform = Transform::from_translation(center - direction * distance)
+                    .looking_at(center, Vec3::Z);
+                let view_projection = PerspectiveProjection {
+                    fov_y,
+                    aspect_ratio,
+                    ..Default::default()
+                }
+                .compute_matrix()
+                    * transform.compute_matrix().inverse();
+
+                let mut max_extent = 0.0_f32;
+
+                for i in 0..8 {
+                    let corner = Vec3::new(
+                        if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                        if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                        if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+                    );
+                    let ndc = view_projection.project_point3(corner);
+                    max_extent = max_extent.max(ndc.x.abs()).max(ndc.y.abs());
+                }
+
+                // Inside the viewport and touching its border
+                assert!(max_extent <= 1.0 + 1e-4, "{:?} {}", aabb, aspect_ratio);
+                assert!(max_extent >= 1.0 - 1e-4, "{:?} {}", aabb, aspect_ratio);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_camera_poses() {
+        for pose in [
+            "",
+            "1,2,3",
+            "1,2,3,0,0,0,0",
+            "1,2,3,0,0,0,1,5",
+            "a,2,3,0,0,0,1",
+            "NaN,2,3,0,0,0,1",
+        ] {
+            assert!(parse_camera_pose(pose).is_none(), "{}", pose);
+        }
+    }
 }
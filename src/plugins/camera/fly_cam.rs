@@ -1,20 +1,31 @@
 use crate::plugins::camera::CameraControlSet;
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
+use bevy_state::prelude::*;
 use bevy_time::Time;
 use glam::{EulerRot, Quat, Vec3};
 use winit::event::{MouseButton, MouseScrollDelta};
 use winit::keyboard::KeyCode;
 
 use crate::plugins::input::{CursorEvent, MouseWheelEvent, PressedKeys, PressedMouseButtons};
+use crate::plugins::metadata::{ActiveMetadata, MetadataState};
 use crate::transform::Transform;
 
 pub struct FlyCamPlugin;
 
 impl Plugin for FlyCamPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, update_movement_speed.in_set(CameraControlSet))
-            .add_systems(FixedUpdate, update.in_set(CameraControlSet));
+        app.insert_resource(MovementSpeed::default())
+            .add_systems(
+                Update,
+                (
+                    update_movement_speed,
+                    update_bounding_box_scale.run_if(in_state(MetadataState::Loaded)),
+                )
+                    .in_set(CameraControlSet),
+            )
+            .add_systems(FixedUpdate, update.in_set(CameraControlSet))
+            .add_systems(OnEnter(MetadataState::NotLoaded), reset_bounding_box_scale);
     }
 }
 
@@ -22,25 +33,65 @@ impl Plugin for FlyCamPlugin {
 pub struct FlyCamController {
     keybindings: FlyCamKeybindings,
     mouse_sensitivity: f32,
-    movement_speed: f32,
     look_around: bool,
 }
 
 impl FlyCamController {
-    const MIN_MOVEMENT_SPEED: f32 = 5.0;
-    const MAX_MOVEMENT_SPEED: f32 = 1000.0;
-    const MOVEMENT_SPEED_STEP: f32 = 5.0;
-
     pub fn new() -> Self {
         Self {
             keybindings: FlyCamKeybindings::default(),
             mouse_sensitivity: 0.15,
-            movement_speed: 50.0,
             look_around: false,
         }
     }
 }
 
+/// Movement speed of the fly cam in units per second.
+#[derive(Debug, Resource)]
+pub struct MovementSpeed {
+    pub speed: f32,
+    /// Multiplies the speed while the boost key is held.
+    pub boost_factor: f32,
+    /// Scales the speed by the size of the active point cloud,
+    /// so that the same speed feels similar for small and large point clouds.
+    pub scale_with_bounding_box: bool,
+    bounding_box_scale: f32,
+}
+
+impl MovementSpeed {
+    const MIN: f32 = 5.0;
+    const MAX: f32 = 1000.0;
+    const STEP: f32 = 5.0;
+
+    /// Diagonal of a bounding box for which the speed isn't scaled.
+    const REFERENCE_SIZE: f32 = 500.0;
+
+    fn current(&self, boost: bool) -> f32 {
+        let mut speed = self.speed;
+
+        if self.scale_with_bounding_box {
+            speed *= self.bounding_box_scale;
+        }
+
+        if boost {
+            speed *= self.boost_factor;
+        }
+
+        speed
+    }
+}
+
+impl Default for MovementSpeed {
+    fn default() -> Self {
+        Self {
+            speed: 50.0,
+            boost_factor: 4.0,
+            scale_with_bounding_box: false,
+            bounding_box_scale: 1.0,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 struct FlyCamKeybindings {
     forward: KeyCode,
@@ -49,6 +100,7 @@ struct FlyCamKeybindings {
     right: KeyCode,
     ascend: KeyCode,
     descend: KeyCode,
+    boost: KeyCode,
     look_around: MouseButton,
 }
 
@@ -60,7 +112,8 @@ impl Default for FlyCamKeybindings {
             left: KeyCode::KeyA,
             right: KeyCode::KeyD,
             ascend: KeyCode::Space,
-            descend: KeyCode::ShiftLeft,
+            descend: KeyCode::ControlLeft,
+            boost: KeyCode::ShiftLeft,
             look_around: MouseButton::Right,
         }
     }
@@ -71,6 +124,7 @@ fn update(
     pressed_keys: Res<PressedKeys>,
     pressed_mouse_buttons: Res<PressedMouseButtons>,
     mut cursor_events: EventReader<CursorEvent>,
+    movement_speed: Res<MovementSpeed>,
     time: Res<Time>,
 ) {
     for (mut fly_cam, mut transform) in query.iter_mut() {
@@ -104,7 +158,9 @@ fn update(
             velocity -= up;
         }
 
-        velocity = velocity.normalize_or_zero() * fly_cam.movement_speed * time.delta_seconds();
+        let boost = pressed_keys.is_pressed(&fly_cam.keybindings.boost);
+        velocity =
+            velocity.normalize_or_zero() * movement_speed.current(boost) * time.delta_seconds();
 
         fly_cam.look_around = pressed_mouse_buttons.is_pressed(&fly_cam.keybindings.look_around);
 
@@ -137,10 +193,11 @@ fn update(
 }
 
 fn update_movement_speed(
-    mut query: Query<&mut FlyCamController>,
+    query: Query<&FlyCamController>,
+    mut movement_speed: ResMut<MovementSpeed>,
     mut mouse_wheel_event: EventReader<MouseWheelEvent>,
 ) {
-    let mut fly_cam = query.get_single_mut().unwrap();
+    let fly_cam = query.get_single().unwrap();
 
     if !fly_cam.look_around {
         return;
@@ -155,29 +212,69 @@ fn update_movement_speed(
         let y_delta = if y_delta == 0.0 {
             0.0
         } else {
-            y_delta.signum() * FlyCamController::MOVEMENT_SPEED_STEP
+            y_delta.signum() * MovementSpeed::STEP
         };
 
-        fly_cam.movement_speed = (fly_cam.movement_speed + y_delta).clamp(
-            FlyCamController::MIN_MOVEMENT_SPEED,
-            FlyCamController::MAX_MOVEMENT_SPEED,
-        );
+        movement_speed.speed =
+            (movement_speed.speed + y_delta).clamp(MovementSpeed::MIN, MovementSpeed::MAX);
+    }
+}
+
+fn reset_bounding_box_scale(mut movement_speed: ResMut<MovementSpeed>) {
+    movement_speed.bounding_box_scale = 1.0;
+}
+
+fn update_bounding_box_scale(
+    active_metadata: ActiveMetadata,
+    mut movement_speed: ResMut<MovementSpeed>,
+) {
+    let aabb = active_metadata
+        .point_cloud()
+        .transform
+        .transform_aabb(&active_metadata.get().bounding_box);
+
+    let size = aabb.size().length();
+    let scale = if size > 0.0 {
+        size / MovementSpeed::REFERENCE_SIZE
+    } else {
+        1.0
+    };
+
+    if movement_speed.bounding_box_scale != scale {
+        movement_speed.bounding_box_scale = scale;
     }
 }
 
 pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
-    let mut fly_cam = world
-        .query::<&mut FlyCamController>()
-        .get_single_mut(world)
-        .unwrap();
+    let mut movement_speed = world.get_resource_mut::<MovementSpeed>().unwrap();
 
     ui.label("Speed:");
 
-    let movement_speed = egui::Slider::new(
-        &mut fly_cam.movement_speed,
-        FlyCamController::MIN_MOVEMENT_SPEED..=FlyCamController::MAX_MOVEMENT_SPEED,
-    )
-    .step_by(FlyCamController::MOVEMENT_SPEED_STEP as f64);
+    let mut speed = movement_speed.speed;
+    let speed_slider = egui::Slider::new(&mut speed, MovementSpeed::MIN..=MovementSpeed::MAX)
+        .step_by(MovementSpeed::STEP as f64);
+
+    if ui.add(speed_slider).changed() {
+        movement_speed.speed = speed;
+    }
+
+    let mut boost_factor = movement_speed.boost_factor;
+    let boost_slider = egui::Slider::new(&mut boost_factor, 1.0..=10.0).text("Boost (Shift)");
 
-    ui.add(movement_speed);
+    if ui.add(boost_slider).changed() {
+        movement_speed.boost_factor = boost_factor;
+    }
+
+    let mut scale_with_bounding_box = movement_speed.scale_with_bounding_box;
+
+    if ui
+        .checkbox(&mut scale_with_bounding_box, "Scale with bounding box")
+        .on_hover_text(format!(
+            "Current factor: {:.2}",
+            movement_speed.bounding_box_scale
+        ))
+        .changed()
+    {
+        movement_speed.scale_with_bounding_box = scale_with_bounding_box;
+    }
 }
@@ -0,0 +1,157 @@
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_state::prelude::*;
+use bevy_time::Time;
+use glam::{Quat, Vec3};
+
+use crate::plugins::camera::{Camera, CameraControlSet};
+use crate::plugins::input::{MouseWheelEvent, PressedKeys, PressedMouseButtons};
+use crate::plugins::metadata::{ActiveMetadata, MetadataState};
+use crate::transform::Transform;
+
+/// Slowly orbits the camera around the center of the active point cloud, e.g. for demos.
+/// The camera keeps its distance and height, so it orbits from wherever it was placed.
+/// Cells are streamed as usual while the camera moves.
+pub struct TurntablePlugin;
+
+impl Plugin for TurntablePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Turntable>().add_systems(
+            Update,
+            (pause_on_input, rotate)
+                .chain()
+                .run_if(|turntable: Res<Turntable>| turntable.enabled)
+                .run_if(in_state(MetadataState::Loaded))
+                .in_set(CameraControlSet),
+        );
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct Turntable {
+    pub enabled: bool,
+    /// Positive values rotate counterclockwise when looking down.
+    pub degrees_per_second: f32,
+    /// Seconds without input until the rotation continues after it was paused by the user.
+    pub resume_after: f32,
+    paused_for: f32,
+}
+
+impl Default for Turntable {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            degrees_per_second: 10.0,
+            resume_after: 5.0,
+            paused_for: 0.0,
+        }
+    }
+}
+
+impl Turntable {
+    pub fn is_paused(&self) -> bool {
+        self.paused_for > 0.0
+    }
+}
+
+/// Rotates the transform around the vertical axis through the center.
+fn orbit(transform: &mut Transform, center: Vec3, angle: f32) {
+    let rotation = Quat::from_rotation_z(angle);
+
+    transform.translation = center + rotation * (transform.translation - center);
+    transform.rotation = rotation * transform.rotation;
+}
+
+fn pause_on_input(
+    mut turntable: ResMut<Turntable>,
+    pressed_keys: Res<PressedKeys>,
+    pressed_mouse_buttons: Res<PressedMouseButtons>,
+    mut mouse_wheel_events: EventReader<MouseWheelEvent>,
+    time: Res<Time>,
+) {
+    let has_input = pressed_keys.any_pressed()
+        || pressed_mouse_buttons.any_pressed()
+        || mouse_wheel_events.read().count() > 0;
+
+    if has_input {
+        turntable.paused_for = turntable.resume_after;
+    } else if turntable.is_paused() {
+        turntable.paused_for = (turntable.paused_for - time.delta_seconds()).max(0.0);
+    }
+}
+
+fn rotate(
+    turntable: Res<Turntable>,
+    active_metadata: ActiveMetadata,
+    mut camera_query: Query<&mut Transform, With<Camera>>,
+    time: Res<Time>,
+) {
+    if turntable.is_paused() {
+        return;
+    }
+
+    let center = active_metadata
+        .point_cloud()
+        .transform
+        .transform_aabb(&active_metadata.get().bounding_box)
+        .center();
+    let angle = turntable.degrees_per_second.to_radians() * time.delta_seconds();
+
+    for mut transform in camera_query.iter_mut() {
+        orbit(&mut transform, center, angle);
+    }
+}
+
+pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
+    let mut turntable = world.resource_mut::<Turntable>();
+
+    let mut enabled = turntable.enabled;
+    let label = if turntable.enabled && turntable.is_paused() {
+        "Turntable (paused)"
+    } else {
+        "Turntable"
+    };
+
+    if ui
+        .checkbox(&mut enabled, label)
+        .on_hover_text("Orbits around the point cloud and pauses while the camera is controlled")
+        .changed()
+    {
+        turntable.enabled = enabled;
+        turntable.paused_for = 0.0;
+    }
+
+    let mut degrees_per_second = turntable.degrees_per_second;
+    let slider =
+        egui::Slider::new(&mut degrees_per_second, -90.0..=90.0).text("Turntable speed (°/s)");
+
+    if ui.add(slider).changed() {
+        turntable.degrees_per_second = degrees_per_second;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orbit_keeps_distance_and_looks_at_the_same_point() {
+        let center = Vec3::new(10.0, 20.0, 0.0);
+        let mut transform =
+            Transform::from_translation(Vec3::new(10.0, 0.0, 5.0)).looking_at(center, Vec3::Z);
+
+        let distance = transform.translation.distance(center);
+        let height = transform.translation.z;
+
+        orbit(&mut transform, center, std::f32::consts::FRAC_PI_2);
+
+        assert!((transform.translation.distance(center) - distance).abs() < 1e-4);
+        assert!((transform.translation.z - height).abs() < 1e-4);
+        assert!(transform
+            .translation
+            .abs_diff_eq(Vec3::new(30.0, 20.0, 5.0), 1e-4));
+
+        let to_center = (center - transform.translation).normalize();
+        assert!(transform.forward().abs_diff_eq(to_center, 1e-4));
+    }
+}
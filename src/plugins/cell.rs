@@ -1,19 +1,24 @@
+use std::collections::VecDeque;
 use std::hash::{BuildHasherDefault, Hash};
 use std::io::Read;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
+use bevy_ecs::query::QueryData;
+use bevy_ecs::system::{SystemParam, SystemState};
 use bevy_state::prelude::*;
 use bytesize::ByteSize;
-use caches::{Cache, LRUCache};
+use caches::{Cache, LRUCache, ResizableCache};
 use egui::ahash::HashSetExt;
 use glam::IVec3;
 use itertools::Itertools;
 use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 use thousands::Separable;
+use web_time::{Duration, Instant};
 
 use bounding_volume::Aabb;
 use point_converter::cell::{Cell, CellId};
+use point_converter::metadata::Metadata;
 
 use crate::plugins::asset::source::{Source, SourceError};
 use crate::plugins::asset::{
@@ -22,9 +27,12 @@ use crate::plugins::asset::{
 use crate::plugins::camera::projection::PerspectiveProjection;
 use crate::plugins::camera::{Camera, UpdateFrustum, Visibility};
 use crate::plugins::cell::frustums::StreamingFrustumsScale;
-use crate::plugins::cell::shader::{CellBufferBundle, FrustumsSettings};
+use crate::plugins::cell::shader::{CellBufferBundle, FrustumsSettings, PointsPerCellLimit};
+use crate::plugins::cell::trace::{StreamingEvent, StreamingTrace};
+use crate::plugins::input::{HotkeyAction, HotkeyEvent};
 use crate::plugins::metadata::{
-    ActiveMetadata, MetadataState, UpdatedMetadataBoundingBoxEvent, UpdatedMetadataHierarchiesEvent,
+    get_working_directory, ActiveMetadata, LoadedMetadata, MetadataState, PointCloud,
+    UpdatedMetadataBoundingBoxEvent, UpdatedMetadataHierarchiesEvent,
 };
 use crate::plugins::render::point::Point;
 use crate::plugins::render::BufferSet;
@@ -32,11 +40,25 @@ use crate::plugins::wgpu::Device;
 use crate::sorted_hash::SortedHashMap;
 use crate::transform::Transform;
 
+pub mod boundaries;
+#[cfg(not(target_arch = "wasm32"))]
+mod dump;
 pub mod frustums;
+mod index;
+pub mod region;
 pub mod shader;
+pub mod trace;
+pub mod visibility;
+
+/// Cells of different point clouds share the same [CellId]s.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct CloudCellId {
+    pub point_cloud: Entity,
+    pub cell: CellId,
+}
 
 impl Asset for Cell {
-    type Id = CellId;
+    type Id = CloudCellId;
 
     fn read_from(reader: &mut dyn Read) -> Result<Self, SourceError> {
         Cell::read_from(reader).map_err(SourceError::from)
@@ -44,6 +66,7 @@ impl Asset for Cell {
 
     #[cfg(not(target_arch = "wasm32"))]
     fn save(&self, source: Source) -> Result<(), SourceError> {
+        use crate::plugins::asset::source::upload_to_url;
         use std::fs::{create_dir_all, File};
         use std::io::{BufWriter, ErrorKind, Write};
 
@@ -65,8 +88,15 @@ impl Asset for Cell {
                 self.write_to(&mut buf_writer)?;
                 buf_writer.flush().map_err(SourceError::from)
             }
-            Source::URL(_) => Err(SourceError::Other {
-                message: "URL saving is not supported".to_string(),
+            Source::URL(url) => {
+                log::debug!("Uploading cell to {}", url);
+
+                let mut bytes = Vec::new();
+                self.write_to(&mut bytes)?;
+                upload_to_url(&url, bytes)
+            }
+            Source::ArchiveEntry { .. } => Err(SourceError::Other {
+                message: "Saving into an archive is not supported".to_string(),
                 name: ErrorKind::Unsupported,
             }),
             Source::None => Err(SourceError::NoSource),
@@ -79,13 +109,20 @@ pub struct CellPlugin;
 impl Plugin for CellPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(AssetPlugin::<Cell>::default())
+            .add_plugins(AssetPlugin::<boundaries::CellBoundary>::default())
+            .add_plugins(AssetPlugin::<point_converter::index::CellIndex>::default())
             .insert_state(StreamState::Enabled)
             .insert_resource(frustums::StreamingFrustumsScale::default())
-            .insert_resource(VisibleCells::default())
-            .insert_resource(LoadedCells::default())
-            .insert_resource(MissingCells::default())
-            .insert_resource(LoadingCells::default())
+            .insert_resource(CellUploads::default())
             .insert_resource(Stats::default())
+            .init_resource::<StreamingPolicy>()
+            .init_resource::<StreamingMode>()
+            .init_resource::<trace::StreamingTrace>()
+            .init_resource::<StreamingSettled>()
+            .init_resource::<PointsPerCellLimit>()
+            .init_resource::<MissingCellsSettings>()
+            .init_resource::<MaxStreamedHierarchy>()
+            .init_resource::<visibility::HierarchyVisibility>()
             .configure_sets(
                 Update,
                 CellStreamingSet
@@ -100,53 +137,127 @@ impl Plugin for CellPlugin {
             )
             .add_systems(
                 Startup,
+                shader::create_frustums_settings_buffer.in_set(BufferSet),
+            )
+            .add_systems(
+                Update,
                 (
-                    shader::create_loaded_cells_buffer,
-                    shader::create_frustums_buffer,
-                    shader::create_frustums_settings_buffer,
+                    (add_cell_streaming, index::load_added_cell_index).chain(),
+                    remove_cells_of_removed_point_clouds,
                 )
-                    .in_set(BufferSet),
+                    .before(CellStreamingSet),
             )
-            .add_systems(PostStartup, frustums::add_streaming_frustums)
             .add_systems(
                 Update,
                 (
                     (
-                        receive_cell,
+                        (receive_cell, upload_cells.run_if(has_cell_uploads)).chain(),
                         frustums::update_streaming_frustums.after(UpdateFrustum),
                     ),
                     update_cells,
                     (
-                        enqueue_cells_to_load,
-                        count_points.run_if(resource_changed::<LoadedCells>),
+                        enqueue_cells_to_load.run_if(resource_equals(StreamingMode::Points)),
+                        count_points,
+                        (
+                            boundaries::update_boundaries,
+                            boundaries::receive_boundaries,
+                        )
+                            .chain()
+                            .run_if(resource_equals(StreamingMode::Boundaries)),
                     ),
                 )
                     .chain()
                     .in_set(CellStreamingSet),
             )
+            .add_systems(
+                Update,
+                (
+                    boundaries::clear_boundaries.run_if(
+                        resource_changed::<StreamingMode>
+                            .and_then(resource_equals(StreamingMode::Points)),
+                    ),
+                    boundaries::update_boundaries_of_moved_point_clouds,
+                    boundaries::remove_boundaries_of_removed_point_clouds,
+                )
+                    .before(CellStreamingSet),
+            )
             .add_systems(
                 OnEnter(MetadataState::Loaded),
                 (
                     set_view_distance,
                     shader::set_frustums_settings_max_hierarchy,
+                    index::load_cell_index,
                 ),
             )
-            .add_systems(OnEnter(MetadataState::Loading), cleanup_cells)
+            .add_systems(
+                OnEnter(MetadataState::Loading),
+                (cleanup_cells, boundaries::cleanup_boundaries),
+            )
+            .add_systems(
+                OnEnter(MetadataState::NotLoaded),
+                (cleanup_cells, boundaries::cleanup_boundaries),
+            )
+            .add_systems(
+                Update,
+                index::receive_cell_index
+                    .run_if(on_event::<AssetEvent<point_converter::index::CellIndex>>()),
+            )
+            .add_systems(Update, toggle_stream_state)
+            .add_systems(
+                Update,
+                set_view_distance
+                    .run_if(resource_changed::<StreamingFrustumsScale>)
+                    .run_if(in_state(MetadataState::Loaded))
+                    .before(UpdateFrustum),
+            )
+            .add_systems(Update, trace::finish_frame.after(CellStreamingSet))
+            .add_systems(Update, update_streaming_settled.after(CellStreamingSet))
+            .add_systems(
+                Update,
+                apply_missing_cells_settings
+                    .run_if(resource_changed::<MissingCellsSettings>)
+                    .before(CellStreamingSet),
+            )
+            .add_systems(
+                Update,
+                shader::apply_points_per_cell_limit
+                    .run_if(resource_changed::<PointsPerCellLimit>)
+                    .before(CellStreamingSet),
+            )
+            .add_systems(
+                Update,
+                apply_streaming_policy
+                    .run_if(resource_changed::<StreamingPolicy>)
+                    .before(CellStreamingSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    visibility::update_visibility_of_cells
+                        .run_if(resource_changed::<visibility::HierarchyVisibility>),
+                    visibility::set_visibility_of_new_cells,
+                )
+                    .after(CellStreamingSet),
+            )
             .add_systems(
                 PostUpdate,
                 (
-                    shader::update_loaded_cells_buffer.run_if(resource_changed::<LoadedCells>),
-                    shader::update_frustums_buffer,
+                    shader::add_cell_streaming_buffers,
                     (
-                        shader::set_frustums_settings_max_hierarchy
-                            .run_if(on_event::<UpdatedMetadataHierarchiesEvent>()),
-                        shader::update_frustums_settings_buffer
-                            .run_if(resource_changed::<FrustumsSettings>),
+                        shader::update_loaded_cells_buffer,
+                        shader::update_frustums_buffer,
+                        (
+                            shader::set_frustums_settings_max_hierarchy
+                                .run_if(on_event::<UpdatedMetadataHierarchiesEvent>()),
+                            shader::update_frustums_settings_buffer
+                                .run_if(resource_changed::<FrustumsSettings>),
+                        )
+                            .chain(),
                     )
-                        .chain(),
+                        .chain()
+                        .in_set(CellStreamingSet),
                 )
                     .chain()
-                    .in_set(CellStreamingSet)
                     .in_set(BufferSet),
             );
     }
@@ -154,10 +265,15 @@ impl Plugin for CellPlugin {
 
 fn set_view_distance(
     active_metadata: ActiveMetadata,
+    streaming_frustums_scale: Res<StreamingFrustumsScale>,
     mut camera_query: Query<&mut PerspectiveProjection, With<Camera>>,
 ) {
+    let far = streaming_frustums_scale.far(active_metadata.get().config.max_cell_size);
+
     for mut projection in camera_query.iter_mut() {
-        projection.far = active_metadata.get().config.max_cell_size * StreamingFrustumsScale::MAX;
+        if projection.far != far {
+            projection.far = far;
+        }
     }
 }
 
@@ -170,30 +286,273 @@ pub enum StreamState {
     Paused,
 }
 
-#[derive(Debug, Default, Resource)]
+/// Streaming state of a single [PointCloud].
+#[derive(Default, Bundle)]
+struct CellStreamingBundle {
+    visible_cells: VisibleCells,
+    loaded_cells: LoadedCells,
+    missing_cells: MissingCells,
+    loading_cells: LoadingCells,
+    existing_cells: index::ExistingCells,
+    streaming_frustums: frustums::StreamingFrustums,
+    boundaries: boundaries::CellBoundaries,
+}
+
+fn add_cell_streaming(
+    mut commands: Commands,
+    missing_cells_settings: Res<MissingCellsSettings>,
+    point_cloud_query: Query<Entity, Added<PointCloud>>,
+) {
+    for entity in point_cloud_query.iter() {
+        commands.entity(entity).insert(CellStreamingBundle {
+            missing_cells: MissingCells::new(&missing_cells_settings),
+            ..CellStreamingBundle::default()
+        });
+    }
+}
+
+fn remove_cells_of_removed_point_clouds(
+    mut commands: Commands,
+    mut removed_point_clouds: RemovedComponents<PointCloud>,
+    cell_query: Query<(Entity, &AssetHandle<Cell>)>,
+    mut cell_uploads: ResMut<CellUploads>,
+) {
+    let removed = removed_point_clouds.read().collect::<FxHashSet<_>>();
+
+    if removed.is_empty() {
+        return;
+    }
+
+    for (entity, handle) in cell_query.iter() {
+        if removed.contains(&handle.id().point_cloud) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    cell_uploads
+        .queue
+        .retain(|handle| !removed.contains(&handle.id().point_cloud));
+}
+
+#[derive(Debug, Default, Component)]
 struct VisibleCells {
     hierarchies: Vec<FxHashSet<IVec3>>,
 }
 
-#[derive(Default, Resource)]
+fn toggle_stream_state(
+    mut hotkey_events: EventReader<HotkeyEvent>,
+    stream_state: Res<State<StreamState>>,
+    mut next_stream_state: ResMut<NextState<StreamState>>,
+) {
+    let toggles = hotkey_events
+        .read()
+        .filter(|event| event.0 == HotkeyAction::ToggleStreaming)
+        .count();
+
+    if toggles % 2 == 1 {
+        next_stream_state.set(match *stream_state.get() {
+            StreamState::Enabled => StreamState::Paused,
+            StreamState::Paused => StreamState::Enabled,
+        });
+    }
+}
+
+#[derive(Default, Component)]
 struct LoadedCells(FxHashMap<CellId, Entity>);
 
-#[derive(Resource)]
-struct MissingCells(LRUCache<CellId, (), BuildHasherDefault<FxHasher>>);
+/// How long cells which don't exist are remembered, see [MissingCells].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Resource)]
+pub struct MissingCellsSettings {
+    /// The least recently seen missing cells are forgotten once there are more.
+    pub capacity: usize,
+    /// Missing cells are requested again after this time, even if they weren't forgotten.
+    pub retry_after: Option<Duration>,
+}
+
+impl Default for MissingCellsSettings {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            retry_after: None,
+        }
+    }
+}
+
+/// Cells which don't exist, so that they aren't requested every time they become visible.
+#[derive(Component)]
+struct MissingCells {
+    cells: LRUCache<CellId, Instant, BuildHasherDefault<FxHasher>>,
+    retry_after: Option<Duration>,
+}
 
 impl Default for MissingCells {
     fn default() -> Self {
-        Self(LRUCache::with_hasher(10000, BuildHasherDefault::default()).unwrap())
+        Self::new(&MissingCellsSettings::default())
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+impl MissingCells {
+    fn new(settings: &MissingCellsSettings) -> Self {
+        Self {
+            cells: LRUCache::with_hasher(settings.capacity.max(1), BuildHasherDefault::default())
+                .unwrap(),
+            retry_after: settings.retry_after,
+        }
+    }
+
+    fn apply_settings(&mut self, settings: &MissingCellsSettings) {
+        self.cells.resize(settings.capacity.max(1));
+        self.retry_after = settings.retry_after;
+    }
+
+    fn insert(&mut self, cell_id: CellId, now: Instant) {
+        self.cells.put(cell_id, now);
+    }
+
+    fn remove(&mut self, cell_id: &CellId) {
+        self.cells.remove(cell_id);
+    }
+
+    fn is_expired(&self, missing_since: Instant, now: Instant) -> bool {
+        self.retry_after
+            .is_some_and(|retry_after| now.saturating_duration_since(missing_since) >= retry_after)
+    }
+
+    /// Marks the cell as recently seen, or forgets it if it should be requested again.
+    fn is_missing(&mut self, cell_id: &CellId, now: Instant) -> bool {
+        let Some(missing_since) = self.cells.get(cell_id).copied() else {
+            return false;
+        };
+
+        if self.is_expired(missing_since, now) {
+            self.cells.remove(cell_id);
+            return false;
+        }
+
+        true
+    }
+
+    /// Like [Self::is_missing], but without changing anything.
+    fn contains(&self, cell_id: &CellId, now: Instant) -> bool {
+        self.cells
+            .peek(cell_id)
+            .is_some_and(|missing_since| !self.is_expired(*missing_since, now))
+    }
+
+    fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn clear(&mut self) {
+        self.cells.purge();
+    }
+}
+
+fn apply_missing_cells_settings(
+    settings: Res<MissingCellsSettings>,
+    mut missing_cells_query: Query<&mut MissingCells>,
+) {
+    for mut missing_cells in missing_cells_query.iter_mut() {
+        missing_cells.apply_settings(&settings);
+    }
+}
+
+/// Order in which visible cells are loaded.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Resource)]
+pub enum StreamingPolicy {
+    /// Every cell of a hierarchy is loaded before any cell of the next finer hierarchy,
+    /// so the whole view is filled coarsely before it is refined.
+    #[default]
+    CoarseFirst,
+    /// Cells closer to the camera are loaded first, whatever their hierarchy.
+    /// Details near the camera appear sooner, but the distance can stay empty for a while.
+    NearestFirst,
+}
+
+impl StreamingPolicy {
+    fn name(self) -> &'static str {
+        match self {
+            StreamingPolicy::CoarseFirst => "Coarse first",
+            StreamingPolicy::NearestFirst => "Nearest first",
+        }
+    }
+}
+
+/// What is streamed for the visible cells.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Resource)]
+pub enum StreamingMode {
+    #[default]
+    Points,
+    /// Only the headers of the cells are loaded and their bounding boxes are drawn,
+    /// which gives a quick overview of where the point cloud has cells without loading any points.
+    /// Loading points is paused in the meantime.
+    Boundaries,
+}
+
+impl StreamingMode {
+    fn name(self) -> &'static str {
+        match self {
+            StreamingMode::Points => "Points",
+            StreamingMode::Boundaries => "Boundaries only",
+        }
+    }
+}
+
+/// Cells of finer hierarchies than this are never loaded, whatever their distance to the camera.
+/// Gives a quick overview of a large point cloud with a predictable number of cells.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Resource)]
+pub struct MaxStreamedHierarchy(pub Option<u32>);
+
+impl MaxStreamedHierarchy {
+    pub fn allows(&self, hierarchy: u32) -> bool {
+        self.0.is_none_or(|max| hierarchy <= max)
+    }
+}
+
+/// Cells are ordered by hierarchy and distance to the camera, in the order of the [StreamingPolicy].
+/// The index of the cell breaks ties, so that the load order doesn't depend on hashing.
+/// Only values with the same policy can be compared.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 struct CellSortValue {
+    policy: StreamingPolicy,
     hierarchy: u32,
     distance_to_camera: u32,
+    cell_index: [i32; 3],
 }
 
-#[derive(Resource)]
+impl CellSortValue {
+    fn new(cell_id: CellId, distance_to_camera: u32, policy: StreamingPolicy) -> Self {
+        Self {
+            policy,
+            hierarchy: cell_id.hierarchy,
+            distance_to_camera,
+            cell_index: cell_id.index.to_array(),
+        }
+    }
+
+    fn priority(&self) -> (u32, u32) {
+        match self.policy {
+            StreamingPolicy::CoarseFirst => (self.hierarchy, self.distance_to_camera),
+            StreamingPolicy::NearestFirst => (self.distance_to_camera, self.hierarchy),
+        }
+    }
+}
+
+impl PartialOrd for CellSortValue {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CellSortValue {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.priority()
+            .cmp(&other.priority())
+            .then_with(|| self.cell_index.cmp(&other.cell_index))
+    }
+}
+
+#[derive(Component)]
 struct LoadingCells {
     should_load: SortedHashMap<CellId, CellSortValue, ()>,
     loading: FxHashSet<CellId>,
@@ -212,22 +571,106 @@ impl Default for LoadingCells {
     }
 }
 
+/// Whether all visible cells of the current view are loaded.
+/// True if no cell waits to be loaded or uploaded and no cell was received in this frame.
+/// Can be used to wait for the view to be complete, e.g. before taking a screenshot,
+/// with `.run_if(|settled: Res<StreamingSettled>| settled.is_settled())`.
+#[derive(Debug, Default, Resource)]
+pub struct StreamingSettled(bool);
+
+impl StreamingSettled {
+    pub fn is_settled(&self) -> bool {
+        self.0
+    }
+}
+
+fn update_streaming_settled(
+    mut streaming_settled: ResMut<StreamingSettled>,
+    mut assets_events: EventReader<AssetEvent<Cell>>,
+    loading_cells_query: Query<&LoadingCells>,
+    cell_uploads: Res<CellUploads>,
+) {
+    let received_cells = assets_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::Created { .. } | AssetEvent::Loaded(AssetLoadedEvent::Success { .. })
+        )
+    });
+
+    let is_settled = !received_cells
+        && cell_uploads.queue.is_empty()
+        && loading_cells_query.iter().all(|loading_cells| {
+            loading_cells.should_load.is_empty() && loading_cells.loading.is_empty()
+        });
+
+    if streaming_settled.0 != is_settled {
+        streaming_settled.0 = is_settled;
+    }
+}
+
+/// Received cells of all point clouds which still have to be uploaded to the gpu.
+#[derive(Resource)]
+struct CellUploads {
+    queue: VecDeque<AssetHandle<Cell>>,
+    max_uploads_per_frame: usize,
+}
+
+impl CellUploads {
+    const DEFAULT_MAX_UPLOADS_PER_FRAME: usize = 10;
+}
+
+impl Default for CellUploads {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            max_uploads_per_frame: Self::DEFAULT_MAX_UPLOADS_PER_FRAME,
+        }
+    }
+}
+
+/// Removes the cells of the active point cloud, which is about to be replaced or was unloaded.
+/// Despawning the cells frees their buffers.
 fn cleanup_cells(
     mut commands: Commands,
-    cell_query: Query<Entity, With<CellHeader>>,
-    mut visible_cells: ResMut<VisibleCells>,
-    mut loaded_cells: ResMut<LoadedCells>,
-    mut missing_cells: ResMut<MissingCells>,
-    mut loading_cells: ResMut<LoadingCells>,
+    active_metadata: ActiveMetadata,
+    cell_query: Query<(Entity, &AssetHandle<Cell>)>,
+    mut point_cloud_query: Query<(
+        &mut VisibleCells,
+        &mut LoadedCells,
+        &mut MissingCells,
+        &mut LoadingCells,
+        &mut index::ExistingCells,
+    )>,
+    mut cell_uploads: ResMut<CellUploads>,
 ) {
+    let active = active_metadata.entity();
+
+    let Ok((
+        mut visible_cells,
+        mut loaded_cells,
+        mut missing_cells,
+        mut loading_cells,
+        mut existing_cells,
+    )) = point_cloud_query.get_mut(active)
+    else {
+        return;
+    };
+
+    existing_cells.clear();
     visible_cells.hierarchies.clear();
     loading_cells.should_load.clear();
     loading_cells.loading.clear();
     loaded_cells.0.clear();
-    missing_cells.0.purge();
+    missing_cells.clear();
+
+    cell_uploads
+        .queue
+        .retain(|handle| handle.id().point_cloud != active);
 
-    for entity in cell_query.iter() {
-        commands.entity(entity).despawn();
+    for (entity, handle) in cell_query.iter() {
+        if handle.id().point_cloud == active {
+            commands.entity(entity).despawn();
+        }
     }
 }
 
@@ -243,11 +686,16 @@ struct CellBundle {
 }
 
 impl CellBundle {
-    fn new(cell_handle: AssetHandle<Cell>, cell: &Cell, device: &wgpu::Device) -> Self {
+    fn new(
+        cell_handle: AssetHandle<Cell>,
+        cell: &Cell,
+        device: &wgpu::Device,
+        max_points: Option<usize>,
+    ) -> Self {
         Self {
             cell_handle,
             header: CellHeader(cell.header().clone()),
-            buffer_bundle: CellBufferBundle::new(device, cell),
+            buffer_bundle: CellBufferBundle::new(device, cell, max_points),
             visibility: Visibility::new(true),
         }
     }
@@ -258,40 +706,60 @@ fn receive_cell(
     cell_manager: AssetManagerRes<Cell>,
     mut assets_events: EventReader<AssetEvent<Cell>>,
     device: Res<Device>,
-    visible_cells: Res<VisibleCells>,
-    mut loaded_cells: ResMut<LoadedCells>,
-    mut missing_cells: ResMut<MissingCells>,
-    mut loading_cells: ResMut<LoadingCells>,
+    points_per_cell_limit: Res<PointsPerCellLimit>,
+    mut point_cloud_query: Query<(
+        &VisibleCells,
+        &LoadedCells,
+        &mut MissingCells,
+        &mut LoadingCells,
+        &mut index::ExistingCells,
+    )>,
+    mut cell_uploads: ResMut<CellUploads>,
+    mut trace: ResMut<StreamingTrace>,
 ) {
     for event in assets_events.read() {
         match event {
             AssetEvent::Created { handle } => {
-                let id = handle.id();
-                missing_cells.0.remove(id);
+                let CloudCellId { point_cloud, cell } = handle.id();
+
+                let Ok((
+                    visible_cells,
+                    _,
+                    mut missing_cells,
+                    mut loading_cells,
+                    mut existing_cells,
+                )) = point_cloud_query.get_mut(*point_cloud)
+                else {
+                    continue;
+                };
+
+                missing_cells.remove(cell);
+                existing_cells.insert(*cell);
 
                 if visible_cells
                     .hierarchies
-                    .get(id.hierarchy as usize)
-                    .map(|cell_indices| cell_indices.contains(&id.index))
+                    .get(cell.hierarchy as usize)
+                    .map(|cell_indices| cell_indices.contains(&cell.index))
                     .unwrap_or(false)
                 {
-                    log::debug!("Received created cell {:?}", id);
-                    loading_cells.should_load.remove(id);
-
-                    // TODO delay reading of cell
-                    let cell = cell_manager.get_asset(handle);
-                    let cell_bundle = CellBundle::new(handle.clone(), cell, &device);
-                    let entity = commands.spawn(cell_bundle).id();
-
-                    loaded_cells.0.insert(*id, entity);
+                    log::debug!("Received created cell {:?}", handle.id());
+                    loading_cells.should_load.remove(cell);
+                    cell_uploads.queue.push_back(handle.clone());
                 }
             }
             AssetEvent::Changed { handle } => {
-                if let Some(entity) = loaded_cells.0.get(handle.id()) {
+                let CloudCellId { point_cloud, cell } = handle.id();
+
+                let Ok((_, loaded_cells, ..)) = point_cloud_query.get(*point_cloud) else {
+                    continue;
+                };
+
+                if let Some(entity) = loaded_cells.0.get(cell) {
                     log::debug!("Reloading points for {:?}", handle.id());
 
                     let cell = cell_manager.get_asset(handle);
-                    let cell_buffer_bundle = CellBufferBundle::new(&device, cell);
+                    let cell_buffer_bundle =
+                        CellBufferBundle::new(&device, cell, points_per_cell_limit.0);
 
                     commands.entity(*entity).insert(cell_buffer_bundle);
                 }
@@ -299,35 +767,50 @@ fn receive_cell(
             AssetEvent::Loaded(AssetLoadedEvent::Success { handle }) => {
                 let id = handle.id();
 
-                if !loading_cells.loading.remove(id) {
+                let Ok((_, _, mut missing_cells, mut loading_cells, _)) =
+                    point_cloud_query.get_mut(id.point_cloud)
+                else {
+                    continue;
+                };
+
+                if !loading_cells.loading.remove(&id.cell) {
                     log::debug!("Cell {:?} was loaded but not needed", id);
                     continue;
                 }
 
-                log::debug!("Loaded cell: {:?}", id);
-
-                // TODO delay reading of cell
-                let cell = cell_manager.get_asset(handle);
-                let cell_bundle = CellBundle::new(handle.clone(), cell, &device);
-
-                let entity = commands.spawn(cell_bundle).id();
-
-                if let Some(old) = loaded_cells.0.insert(*id, entity) {
-                    log::warn!("Loaded cell {:?} already existed", id);
-                    if let Some(mut entity_commands) = commands.get_entity(old) {
-                        entity_commands.despawn();
-                    }
+                // An empty cell has nothing to draw, so it is treated like a missing one
+                if cell_manager
+                    .get_asset(handle)
+                    .header()
+                    .total_number_of_points
+                    == 0
+                {
+                    log::debug!("Cell {:?} has no points", id);
+                    missing_cells.insert(id.cell, Instant::now());
+                    trace.record(StreamingEvent::Missing);
+                    continue;
                 }
+
+                log::debug!("Loaded cell: {:?}", id);
+                cell_uploads.queue.push_back(handle.clone());
+                trace.record(StreamingEvent::Loaded);
             }
             AssetEvent::Loaded(AssetLoadedEvent::Error { id, error }) => {
-                if !loading_cells.loading.remove(id) {
+                let Ok((_, _, mut missing_cells, mut loading_cells, _)) =
+                    point_cloud_query.get_mut(id.point_cloud)
+                else {
+                    continue;
+                };
+
+                if !loading_cells.loading.remove(&id.cell) {
                     continue;
                 }
 
                 match error {
                     SourceError::NotFound(_) => {
                         log::debug!("Cell is missing: {:?}", id);
-                        missing_cells.0.put(*id, ());
+                        missing_cells.insert(id.cell, Instant::now());
+                        trace.record(StreamingEvent::Missing);
                     }
                     SourceError::NoSource => {}
                     _ => {
@@ -340,123 +823,292 @@ fn receive_cell(
     }
 }
 
+fn has_cell_uploads(cell_uploads: Res<CellUploads>) -> bool {
+    !cell_uploads.queue.is_empty()
+}
+
+/// Turns at most [CellUploads::max_uploads_per_frame] received cells into [CellBundle]s,
+/// so that many cells finishing at once don't stall a single frame.
+fn upload_cells(
+    mut commands: Commands,
+    cell_manager: AssetManagerRes<Cell>,
+    device: Res<Device>,
+    points_per_cell_limit: Res<PointsPerCellLimit>,
+    mut point_cloud_query: Query<&mut LoadedCells>,
+    mut cell_uploads: ResMut<CellUploads>,
+) {
+    let uploads = cell_uploads
+        .max_uploads_per_frame
+        .min(cell_uploads.queue.len());
+
+    for handle in cell_uploads.queue.drain(..uploads) {
+        let id = handle.id();
+
+        let Ok(mut loaded_cells) = point_cloud_query.get_mut(id.point_cloud) else {
+            continue;
+        };
+
+        let cell = cell_manager.get_asset(&handle);
+        let cell_bundle = CellBundle::new(handle.clone(), cell, &device, points_per_cell_limit.0);
+
+        let entity = commands.spawn(cell_bundle).id();
+
+        if let Some(old) = loaded_cells.0.insert(id.cell, entity) {
+            log::warn!("Loaded cell {:?} already existed", id);
+            if let Some(mut entity_commands) = commands.get_entity(old) {
+                entity_commands.despawn();
+            }
+        }
+    }
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct CellStreamingQueryData {
+    entity: Entity,
+    point_cloud: &'static PointCloud,
+    streaming_frustums: Ref<'static, frustums::StreamingFrustums>,
+    visible_cells: &'static mut VisibleCells,
+    loaded_cells: &'static mut LoadedCells,
+    missing_cells: &'static mut MissingCells,
+    loading_cells: &'static mut LoadingCells,
+    existing_cells: &'static index::ExistingCells,
+}
+
+/// Sorts the cells which are waiting to be loaded again after the [StreamingPolicy] changed.
+fn apply_streaming_policy(
+    streaming_policy: Res<StreamingPolicy>,
+    mut loading_cells_query: Query<&mut LoadingCells>,
+) {
+    for mut loading_cells in loading_cells_query.iter_mut() {
+        // values of different policies can't be compared, so the map is built from scratch
+        let should_load = std::mem::take(&mut loading_cells.should_load)
+            .iter()
+            .map(|entry| (entry.keys.hash_key, entry.keys.sort_key))
+            .collect_vec();
+
+        for (cell_id, sort_value) in should_load {
+            let sort_value = CellSortValue {
+                policy: *streaming_policy,
+                ..sort_value
+            };
+
+            loading_cells.should_load.insert(cell_id, sort_value, ());
+        }
+    }
+}
+
+/// Buffers of [update_cells] which are kept between frames instead of being allocated for every hierarchy.
+#[derive(Default)]
+struct UpdateCellsScratch {
+    visible_cells: FxHashSet<IVec3>,
+    changed_cells: Vec<CellId>,
+}
+
+/// Settings which [update_cells] streams the cells with and the bookkeeping it records into.
+#[derive(SystemParam)]
+struct CellStreaming<'w> {
+    streaming_policy: Res<'w, StreamingPolicy>,
+    max_streamed_hierarchy: Res<'w, MaxStreamedHierarchy>,
+    trace: ResMut<'w, StreamingTrace>,
+    cell_uploads: ResMut<'w, CellUploads>,
+}
+
 fn update_cells(
     mut commands: Commands,
-    mut visible_cells: ResMut<VisibleCells>,
-    mut loaded_cells: ResMut<LoadedCells>,
-    mut missing_cells: ResMut<MissingCells>,
-    mut loading_cells: ResMut<LoadingCells>,
-    camera_query: Query<(Ref<frustums::StreamingFrustums>, &Transform), With<Camera>>,
-    active_metadata: ActiveMetadata,
+    mut point_cloud_query: Query<CellStreamingQueryData>,
+    camera_query: Query<&Transform, With<Camera>>,
+    metadata_manager: AssetManagerRes<Metadata>,
+    streaming: CellStreaming,
     mut updated_bounding_box_events: EventReader<UpdatedMetadataBoundingBoxEvent>,
+    mut scratch: Local<UpdateCellsScratch>,
 ) {
-    let metadata = active_metadata.get();
-    let updated_metadata = updated_bounding_box_events.read().count() > 0;
-
-    for (streaming_frustums, transform) in camera_query.iter() {
-        if !(streaming_frustums.is_changed() || updated_metadata) {
+    let CellStreaming {
+        streaming_policy,
+        max_streamed_hierarchy,
+        mut trace,
+        mut cell_uploads,
+    } = streaming;
+
+    let updated_metadata =
+        updated_bounding_box_events.read().count() > 0 || max_streamed_hierarchy.is_changed();
+
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+
+    let UpdateCellsScratch {
+        visible_cells,
+        changed_cells,
+    } = &mut *scratch;
+
+    let now = Instant::now();
+
+    for mut point_cloud in point_cloud_query.iter_mut() {
+        if !point_cloud.point_cloud.visible
+            || !(point_cloud.streaming_frustums.is_changed() || updated_metadata)
+        {
             continue;
         }
 
-        let mut new_visible_cells = Vec::with_capacity(metadata.hierarchies as usize);
+        let metadata = metadata_manager.get_asset(&point_cloud.point_cloud.metadata);
+        let transform = point_cloud.point_cloud.transform;
 
-        for (hierarchy, streaming_frustum) in streaming_frustums.iter().enumerate() {
-            let old_visible_cells = visible_cells.hierarchies.get(hierarchy);
+        let hierarchies = point_cloud.streaming_frustums.len();
+        point_cloud
+            .visible_cells
+            .hierarchies
+            .resize_with(hierarchies, FxHashSet::default);
 
+        for hierarchy in 0..hierarchies {
+            let streaming_frustum = &point_cloud.streaming_frustums[hierarchy];
+            let hierarchy_index = hierarchy;
             let hierarchy = hierarchy as u32;
 
             let cell_size = metadata.config.cell_size(hierarchy);
             let half_cell_size = cell_size / 2.0;
 
-            let mut frustum_aabb = streaming_frustum.aabb();
+            // The streaming frustums are in world space, the cells relative to the point cloud
+            let mut frustum_aabb = transform.inverse_transform_aabb(&streaming_frustum.aabb());
             frustum_aabb.clamp(metadata.bounding_box.min, metadata.bounding_box.max);
             let min_cell_index = metadata.config.cell_index(frustum_aabb.min, cell_size);
             let max_cell_index = metadata.config.cell_index(frustum_aabb.max, cell_size);
 
-            let visible_cells: FxHashSet<IVec3> = (min_cell_index.x..=max_cell_index.x)
-                .cartesian_product(min_cell_index.y..=max_cell_index.y)
-                .cartesian_product(min_cell_index.z..=max_cell_index.z)
-                .map(|((x, y), z)| IVec3::new(x, y, z))
-                .filter(|cell_index| {
-                    let cell_pos = metadata.config.cell_pos(*cell_index, cell_size);
-                    let cell_aabb = Aabb::new(cell_pos - half_cell_size, cell_pos + half_cell_size);
-                    !streaming_frustum.cull_aabb(cell_aabb)
-                })
-                .collect();
-
-            let not_visible_anymore_cells = old_visible_cells
-                .map_or_else::<Box<dyn Iterator<Item = &IVec3>>, _, _>(
-                    || Box::new(std::iter::empty()),
-                    |old| Box::new(old.difference(&visible_cells)),
-                )
-                .map(|cell_index| CellId {
-                    hierarchy,
-                    index: *cell_index,
-                });
+            visible_cells.clear();
+
+            // cells which are already loaded are evicted like cells which left the view
+            if max_streamed_hierarchy.allows(hierarchy) {
+                visible_cells.extend(
+                    (min_cell_index.x..=max_cell_index.x)
+                        .cartesian_product(min_cell_index.y..=max_cell_index.y)
+                        .cartesian_product(min_cell_index.z..=max_cell_index.z)
+                        .map(|((x, y), z)| IVec3::new(x, y, z))
+                        .filter(|cell_index| {
+                            let cell_pos = metadata.config.cell_pos(*cell_index, cell_size);
+                            let cell_aabb =
+                                Aabb::new(cell_pos - half_cell_size, cell_pos + half_cell_size);
+                            !streaming_frustum.cull_aabb(transform.transform_aabb(&cell_aabb))
+                        }),
+                );
+            }
 
-            for cell_id in not_visible_anymore_cells {
-                if let Some(entity) = loaded_cells.0.remove(&cell_id) {
+            let old_visible_cells = &point_cloud.visible_cells.hierarchies[hierarchy_index];
+
+            changed_cells.clear();
+            changed_cells.extend(
+                old_visible_cells
+                    .difference(visible_cells)
+                    .map(|cell_index| CellId {
+                        hierarchy,
+                        index: *cell_index,
+                    }),
+            );
+
+            for cell_id in changed_cells.iter() {
+                if let Some(entity) = point_cloud.loaded_cells.0.remove(cell_id) {
                     commands.entity(entity).despawn();
-                } else if loading_cells.should_load.remove(&cell_id).is_none() {
-                    loading_cells.loading.remove(&cell_id);
+                    trace.record(StreamingEvent::Evicted);
+                } else if point_cloud
+                    .loading_cells
+                    .should_load
+                    .remove(cell_id)
+                    .is_some()
+                    || point_cloud.loading_cells.loading.remove(cell_id)
+                {
+                    trace.record(StreamingEvent::Culled);
+                } else {
+                    // received but not uploaded yet
+                    let cloud_cell_id = CloudCellId {
+                        point_cloud: point_cloud.entity,
+                        cell: *cell_id,
+                    };
+                    cell_uploads
+                        .queue
+                        .retain(|handle| *handle.id() != cloud_cell_id);
                 }
             }
 
-            let completely_new_visible_cells = old_visible_cells
-                .map_or_else::<Box<dyn Iterator<Item = &IVec3>>, _, _>(
-                    || Box::new(visible_cells.iter()),
-                    |old| Box::new(visible_cells.difference(old)),
-                )
-                .map(|cell_index| CellId {
-                    hierarchy,
-                    index: *cell_index,
-                })
-                .filter(|cell_id| missing_cells.0.get(cell_id).is_none());
-
-            for cell_id in completely_new_visible_cells {
-                let cell_pos = metadata.config.cell_pos(cell_id.index, cell_size);
-                let distance_to_camera = (cell_pos - transform.translation).length_squared() as u32;
-
-                let sort_value = CellSortValue {
-                    hierarchy: cell_id.hierarchy,
-                    distance_to_camera,
-                };
+            let old_visible_cells = &point_cloud.visible_cells.hierarchies[hierarchy_index];
+
+            changed_cells.clear();
+            changed_cells.extend(
+                visible_cells
+                    .difference(old_visible_cells)
+                    .map(|cell_index| CellId {
+                        hierarchy,
+                        index: *cell_index,
+                    })
+                    .filter(|cell_id| point_cloud.existing_cells.may_exist(cell_id)),
+            );
+            changed_cells.retain(|cell_id| !point_cloud.missing_cells.is_missing(cell_id, now));
+
+            for cell_id in changed_cells.iter().copied() {
+                let cell_pos =
+                    transform.transform_point(metadata.config.cell_pos(cell_id.index, cell_size));
+                let distance_to_camera =
+                    (cell_pos - camera_transform.translation).length_squared() as u32;
 
-                loading_cells.should_load.insert(cell_id, sort_value, ());
+                let sort_value = CellSortValue::new(cell_id, distance_to_camera, *streaming_policy);
+
+                point_cloud
+                    .loading_cells
+                    .should_load
+                    .insert(cell_id, sort_value, ());
+                trace.record(StreamingEvent::Enqueued);
             }
 
-            new_visible_cells.push(visible_cells);
+            // the old set is cleared and reused for the next hierarchy
+            std::mem::swap(
+                &mut point_cloud.visible_cells.hierarchies[hierarchy_index],
+                visible_cells,
+            );
         }
-
-        visible_cells.hierarchies = new_visible_cells;
     }
 }
 
 fn enqueue_cells_to_load(
-    mut loading_cells: ResMut<LoadingCells>,
-    active_metadata: ActiveMetadata,
+    mut point_cloud_query: Query<(Entity, &PointCloud, &mut LoadingCells)>,
+    metadata_manager: AssetManagerRes<Metadata>,
     cell_manager: AssetManagerRes<Cell>,
+    max_streamed_hierarchy: Res<MaxStreamedHierarchy>,
 ) {
-    let free_load_slots = LoadingCells::MAX_LOADING_SIZE - loading_cells.loading.len();
-
-    for _ in 0..free_load_slots {
-        if let Some(cell_to_load) = loading_cells.should_load.pop_first() {
-            let cell_id = cell_to_load.keys.hash_key;
-            loading_cells.loading.insert(cell_id);
-
-            let working_directory = active_metadata.get_working_directory();
-            let source = working_directory.map_or(Source::None, |dir| dir.join(&cell_id.path()));
-
-            cell_manager
-                .load_sender()
-                .send(LoadAssetMsg {
-                    id: cell_id,
-                    source,
-                    reply_sender: None,
-                })
-                .unwrap();
-        } else {
-            break;
+    for (entity, point_cloud, mut loading_cells) in point_cloud_query.iter_mut() {
+        let free_load_slots = LoadingCells::MAX_LOADING_SIZE - loading_cells.loading.len();
+
+        let metadata_source = metadata_manager.get_asset_source(&point_cloud.metadata);
+        let working_directory = get_working_directory(metadata_source);
+        let cell_extension = &metadata_manager
+            .get_asset(&point_cloud.metadata)
+            .cell_extension;
+
+        for _ in 0..free_load_slots {
+            if let Some(cell_to_load) = loading_cells.should_load.pop_first() {
+                let cell_id = cell_to_load.keys.hash_key;
+
+                if !max_streamed_hierarchy.allows(cell_id.hierarchy) {
+                    continue;
+                }
+
+                loading_cells.loading.insert(cell_id);
+
+                let source = working_directory.as_ref().map_or(Source::None, |dir| {
+                    dir.join(&cell_id.logical_path(cell_extension))
+                });
+
+                cell_manager
+                    .load_sender()
+                    .send(LoadAssetMsg {
+                        id: CloudCellId {
+                            point_cloud: entity,
+                            cell: cell_id,
+                        },
+                        source,
+                        reply_sender: None,
+                    })
+                    .unwrap();
+            } else {
+                break;
+            }
         }
     }
 }
@@ -467,7 +1119,18 @@ struct Stats {
     loaded_points_byte_size: u64,
 }
 
-fn count_points(cell_header_query: Query<&CellHeader>, mut stats: ResMut<Stats>) {
+fn count_points(
+    cell_header_query: Query<&CellHeader>,
+    changed_loaded_cells_query: Query<(), Changed<LoadedCells>>,
+    mut removed_cells: RemovedComponents<CellHeader>,
+    mut stats: ResMut<Stats>,
+) {
+    let removed_cells = removed_cells.read().count() > 0;
+
+    if changed_loaded_cells_query.is_empty() && !removed_cells {
+        return;
+    }
+
     let total_points = cell_header_query
         .iter()
         .map(|header| header.0.total_number_of_points as u64)
@@ -500,21 +1163,198 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
         }
     }
 
+    {
+        let mut streaming_policy = world.resource_mut::<StreamingPolicy>();
+        let mut policy = *streaming_policy;
+
+        egui::ComboBox::from_label("Load order")
+            .selected_text(policy.name())
+            .show_ui(ui, |ui| {
+                for option in [StreamingPolicy::CoarseFirst, StreamingPolicy::NearestFirst] {
+                    ui.selectable_value(&mut policy, option, option.name());
+                }
+            });
+
+        if policy != *streaming_policy {
+            *streaming_policy = policy;
+        }
+    }
+
+    {
+        let mut streaming_mode = world.resource_mut::<StreamingMode>();
+        let mut mode = *streaming_mode;
+
+        egui::ComboBox::from_label("Stream")
+            .selected_text(mode.name())
+            .show_ui(ui, |ui| {
+                for option in [StreamingMode::Points, StreamingMode::Boundaries] {
+                    ui.selectable_value(&mut mode, option, option.name());
+                }
+            });
+
+        if mode != *streaming_mode {
+            *streaming_mode = mode;
+        }
+    }
+
+    {
+        let mut params = SystemState::<(ActiveMetadata, ResMut<MaxStreamedHierarchy>)>::new(world);
+        let (active_metadata, mut max_streamed_hierarchy) = params.get_mut(world);
+        let hierarchies = active_metadata.get().hierarchies;
+
+        let mut max_hierarchy = max_streamed_hierarchy.0;
+        let mut is_limited = max_hierarchy.is_some();
+
+        ui.checkbox(&mut is_limited, "Limit hierarchies")
+            .on_hover_text("Finer cells aren't loaded, whatever their distance");
+
+        if is_limited {
+            let mut max = max_hierarchy.unwrap_or(hierarchies.saturating_sub(1));
+            let slider = egui::Slider::new(&mut max, 0..=hierarchies.saturating_sub(1))
+                .text("Max hierarchy");
+            ui.add(slider);
+            max_hierarchy = Some(max);
+        } else {
+            max_hierarchy = None;
+        }
+
+        if max_hierarchy != max_streamed_hierarchy.0 {
+            max_streamed_hierarchy.0 = max_hierarchy;
+        }
+    }
+
     frustums::draw_ui(ui, world);
 
+    ui.collapsing("Streaming trace", |ui| {
+        trace::draw_ui(ui, world);
+    });
+
+    ui.collapsing("Visible hierarchies", |ui| {
+        visibility::draw_ui(ui, world);
+    });
+
     {
-        let loaded_cells = world.get_resource::<LoadedCells>().unwrap();
-        let missing_cells = world.get_resource::<MissingCells>().unwrap();
-        let loading_cells = world.get_resource::<LoadingCells>().unwrap();
+        let active = world.resource::<LoadedMetadata>().get_active();
+        let cell_uploads = world.resource::<CellUploads>();
+
+        if let Some(entity) = world.get_entity(active) {
+            if let (Some(loaded_cells), Some(missing_cells), Some(loading_cells)) = (
+                entity.get::<LoadedCells>(),
+                entity.get::<MissingCells>(),
+                entity.get::<LoadingCells>(),
+            ) {
+                ui.label(format!("Loaded cells: {}", loaded_cells.0.len()));
+                ui.label(format!("Missing cells: {}", missing_cells.len()));
+                ui.label(format!(
+                    "Cells to load: {}",
+                    loading_cells.should_load.len()
+                ));
+            }
+
+            if let Some(boundaries) = entity.get::<boundaries::CellBoundaries>() {
+                if *world.resource::<StreamingMode>() == StreamingMode::Boundaries {
+                    ui.label(format!("Cell boundaries: {}", boundaries.len()));
+                }
+            }
+        }
 
-        ui.label(format!("Loaded cells: {}", loaded_cells.0.len()));
-        ui.label(format!("Missing cells: {}", missing_cells.0.len()));
+        ui.label(format!("Cells to upload: {}", cell_uploads.queue.len()));
+
+        let is_settled = world.resource::<StreamingSettled>().is_settled();
         ui.label(format!(
-            "Cells to load: {}",
-            loading_cells.should_load.len()
+            "Settled: {}",
+            if is_settled { "yes" } else { "no" }
         ));
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    if ui.button("Save loaded cells as csv...").clicked() {
+        let csv = dump::loaded_cells_to_csv(world);
+
+        let window: &winit::window::Window = world
+            .get_resource::<crate::plugins::winit::Window>()
+            .unwrap();
+
+        let path = rfd::FileDialog::new()
+            .set_parent(window)
+            .set_file_name("loaded-cells.csv")
+            .save_file();
+
+        if let Some(path) = path {
+            if let Err(err) = std::fs::write(&path, csv) {
+                log::error!("Failed to save loaded cells to {:?}: {}", path, err);
+            }
+        }
+    }
+
+    {
+        let mut cell_uploads = world.resource_mut::<CellUploads>();
+        let mut max_uploads_per_frame = cell_uploads.max_uploads_per_frame;
+
+        let slider =
+            egui::Slider::new(&mut max_uploads_per_frame, 1..=50).text("Uploads per frame");
+
+        if ui.add(slider).changed() {
+            cell_uploads.max_uploads_per_frame = max_uploads_per_frame;
+        }
+    }
+
+    ui.collapsing("Missing cells", |ui| {
+        let mut missing_cells_settings = world.resource_mut::<MissingCellsSettings>();
+        let mut settings = *missing_cells_settings;
+
+        ui.add(
+            egui::Slider::new(&mut settings.capacity, 1_000..=1_000_000)
+                .logarithmic(true)
+                .text("Remembered"),
+        )
+        .on_hover_text("Cells which don't exist and aren't requested again");
+
+        let mut retry = settings.retry_after.is_some();
+        ui.checkbox(&mut retry, "Retry missing cells");
+
+        if retry {
+            let mut seconds = settings.retry_after.map_or(60, |it| it.as_secs());
+            ui.add(
+                egui::Slider::new(&mut seconds, 1..=600)
+                    .logarithmic(true)
+                    .suffix(" s")
+                    .text("After"),
+            );
+            settings.retry_after = Some(Duration::from_secs(seconds));
+        } else {
+            settings.retry_after = None;
+        }
+
+        if settings != *missing_cells_settings {
+            *missing_cells_settings = settings;
+        }
+    });
+
+    {
+        let mut points_per_cell_limit = world.resource_mut::<PointsPerCellLimit>();
+        let mut limit = points_per_cell_limit.0;
+        let mut is_limited = limit.is_some();
+
+        ui.checkbox(&mut is_limited, "Limit points per cell")
+            .on_hover_text("Uploads only every n-th point of a cell to save gpu memory");
+
+        if is_limited {
+            let mut max_points = limit.unwrap_or(PointsPerCellLimit::DEFAULT_LIMIT);
+            let slider = egui::Slider::new(&mut max_points, 100..=100_000)
+                .logarithmic(true)
+                .text("Points per cell");
+            ui.add(slider);
+            limit = Some(max_points);
+        } else {
+            limit = None;
+        }
+
+        if limit != points_per_cell_limit.0 {
+            points_per_cell_limit.0 = limit;
+        }
+    }
+
     {
         let stats = world.get_resource::<Stats>().unwrap();
         ui.label(format!(
@@ -527,3 +1367,236 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
         ));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::asset::AssetManager;
+    use crate::plugins::camera::frustum::Frustum;
+    use glam::Vec3;
+
+    #[test]
+    fn cell_sort_value_breaks_ties_by_index() {
+        let cell_ids = [
+            IVec3::new(1, 0, 0),
+            IVec3::new(-1, 2, 0),
+            IVec3::new(0, 0, 1),
+            IVec3::new(0, 0, -1),
+        ]
+        .map(|index| CellId {
+            hierarchy: 1,
+            index,
+        });
+
+        let pop_order = |cell_ids: &mut dyn Iterator<Item = &CellId>| {
+            let mut should_load = SortedHashMap::new();
+
+            for cell_id in cell_ids {
+                should_load.insert(
+                    *cell_id,
+                    CellSortValue::new(*cell_id, 42, StreamingPolicy::CoarseFirst),
+                    (),
+                );
+            }
+
+            std::iter::from_fn(|| should_load.pop_first())
+                .map(|entry| entry.keys.hash_key.index)
+                .collect_vec()
+        };
+
+        let expected = vec![
+            IVec3::new(-1, 2, 0),
+            IVec3::new(0, 0, -1),
+            IVec3::new(0, 0, 1),
+            IVec3::new(1, 0, 0),
+        ];
+
+        assert_eq!(pop_order(&mut cell_ids.iter()), expected);
+        assert_eq!(pop_order(&mut cell_ids.iter().rev()), expected);
+    }
+
+    #[test]
+    fn cell_sort_value_prefers_hierarchy_and_distance() {
+        let cell_id = |hierarchy, x| CellId {
+            hierarchy,
+            index: IVec3::new(x, 0, 0),
+        };
+
+        let coarse_first =
+            |cell_id, distance| CellSortValue::new(cell_id, distance, StreamingPolicy::CoarseFirst);
+
+        assert!(coarse_first(cell_id(0, 5), 100) < coarse_first(cell_id(1, 0), 0));
+        assert!(coarse_first(cell_id(1, 5), 1) < coarse_first(cell_id(1, 0), 2));
+    }
+
+    #[test]
+    fn nearest_first_prefers_distance() {
+        let cell_id = |hierarchy| CellId {
+            hierarchy,
+            index: IVec3::ZERO,
+        };
+
+        let nearest_first = |cell_id, distance| {
+            CellSortValue::new(cell_id, distance, StreamingPolicy::NearestFirst)
+        };
+
+        assert!(nearest_first(cell_id(3), 1) < nearest_first(cell_id(0), 100));
+        assert!(nearest_first(cell_id(0), 5) < nearest_first(cell_id(1), 5));
+    }
+
+    #[test]
+    fn streaming_settles_when_nothing_is_loading() {
+        use bevy_ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.init_resource::<StreamingSettled>();
+        world.init_resource::<CellUploads>();
+        world.init_resource::<Events<AssetEvent<Cell>>>();
+
+        let cell_id = CellId {
+            hierarchy: 0,
+            index: IVec3::ZERO,
+        };
+
+        let mut loading_cells = LoadingCells::default();
+        loading_cells.should_load.insert(
+            cell_id,
+            CellSortValue::new(cell_id, 0, StreamingPolicy::CoarseFirst),
+            (),
+        );
+        let entity = world.spawn(loading_cells).id();
+
+        world.run_system_once(update_streaming_settled);
+        assert!(!world.resource::<StreamingSettled>().is_settled());
+
+        let mut loading_cells = world.get_mut::<LoadingCells>(entity).unwrap();
+        loading_cells.should_load.remove(&cell_id);
+        loading_cells.loading.insert(cell_id);
+
+        world.run_system_once(update_streaming_settled);
+        assert!(!world.resource::<StreamingSettled>().is_settled());
+
+        world
+            .get_mut::<LoadingCells>(entity)
+            .unwrap()
+            .loading
+            .clear();
+
+        world.run_system_once(update_streaming_settled);
+        assert!(world.resource::<StreamingSettled>().is_settled());
+    }
+
+    #[test]
+    fn evicted_cells_are_not_uploaded() {
+        let mut world = World::new();
+        world.insert_resource(AssetManager::<Metadata>::default());
+        world.insert_resource(AssetManager::<Cell>::default());
+        world.init_resource::<CellUploads>();
+        world.init_resource::<StreamingPolicy>();
+        world.init_resource::<MaxStreamedHierarchy>();
+        world.init_resource::<StreamingTrace>();
+        world.init_resource::<Events<UpdatedMetadataBoundingBoxEvent>>();
+        world.spawn((Camera, Transform::IDENTITY));
+
+        let metadata = world.resource_mut::<AssetManager<Metadata>>().insert(
+            String::from("metadata"),
+            Metadata::default(),
+            Source::None,
+            false,
+        );
+
+        // the streaming frustum is far away from the cell, which was visible before
+        let far_away = Transform::from_translation(Vec3::splat(1_000_000.0));
+        let mut streaming_frustums = frustums::StreamingFrustums::default();
+        streaming_frustums.push(Frustum::new(&far_away, &PerspectiveProjection::default()));
+
+        let point_cloud = world
+            .spawn((
+                PointCloud::new(metadata),
+                CellStreamingBundle {
+                    visible_cells: VisibleCells {
+                        hierarchies: vec![FxHashSet::from_iter([IVec3::ZERO])],
+                    },
+                    streaming_frustums,
+                    ..CellStreamingBundle::default()
+                },
+            ))
+            .id();
+
+        let cell_id = cell_id(0);
+        let handle = world.resource_mut::<AssetManager<Cell>>().insert(
+            CloudCellId {
+                point_cloud,
+                cell: cell_id,
+            },
+            Cell::new(cell_id, 1.0, 10.0, Vec3::ZERO, 10),
+            Source::None,
+            false,
+        );
+        world.resource_mut::<CellUploads>().queue.push_back(handle);
+
+        // upload_cells would need a Device if there was anything left to upload
+        let mut schedule = Schedule::default();
+        schedule.add_systems((update_cells, upload_cells.run_if(has_cell_uploads)).chain());
+        schedule.run(&mut world);
+
+        assert!(world.resource::<CellUploads>().queue.is_empty());
+        assert!(world.get::<LoadedCells>(point_cloud).unwrap().0.is_empty());
+    }
+
+    fn cell_id(x: i32) -> CellId {
+        CellId {
+            hierarchy: 0,
+            index: IVec3::new(x, 0, 0),
+        }
+    }
+
+    #[test]
+    fn missing_cells_are_retried_after_some_time() {
+        let mut missing_cells = MissingCells::new(&MissingCellsSettings {
+            capacity: 10,
+            retry_after: Some(Duration::from_secs(30)),
+        });
+
+        let now = Instant::now();
+        missing_cells.insert(cell_id(0), now);
+
+        assert!(missing_cells.is_missing(&cell_id(0), now + Duration::from_secs(29)));
+        assert!(!missing_cells.contains(&cell_id(0), now + Duration::from_secs(30)));
+        assert!(!missing_cells.is_missing(&cell_id(0), now + Duration::from_secs(30)));
+        assert_eq!(missing_cells.len(), 0);
+    }
+
+    #[test]
+    fn missing_cells_forget_least_recently_seen() {
+        let mut missing_cells = MissingCells::new(&MissingCellsSettings {
+            capacity: 2,
+            retry_after: None,
+        });
+
+        let now = Instant::now();
+        missing_cells.insert(cell_id(0), now);
+        missing_cells.insert(cell_id(1), now);
+        assert!(missing_cells.is_missing(&cell_id(0), now));
+        missing_cells.insert(cell_id(2), now);
+
+        assert!(missing_cells.contains(&cell_id(0), now));
+        assert!(!missing_cells.contains(&cell_id(1), now));
+
+        missing_cells.apply_settings(&MissingCellsSettings {
+            capacity: 1,
+            retry_after: None,
+        });
+        assert_eq!(missing_cells.len(), 1);
+    }
+
+    #[test]
+    fn max_streamed_hierarchy_allows_coarser_hierarchies() {
+        assert!(MaxStreamedHierarchy(None).allows(u32::MAX));
+
+        let max_streamed_hierarchy = MaxStreamedHierarchy(Some(2));
+        assert!(max_streamed_hierarchy.allows(0));
+        assert!(max_streamed_hierarchy.allows(2));
+        assert!(!max_streamed_hierarchy.allows(3));
+    }
+}
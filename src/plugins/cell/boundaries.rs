@@ -0,0 +1,293 @@
+use std::io::Read;
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::QueryData;
+use glam::Vec3;
+use rustc_hash::{FxHashMap, FxHashSet};
+use web_time::Instant;
+
+use point_converter::cell::{CellId, Header};
+use point_converter::colormap::Colormap;
+use point_converter::metadata::Metadata;
+
+use crate::plugins::asset::source::{Source, SourceError};
+use crate::plugins::asset::{Asset, AssetEvent, AssetLoadedEvent, AssetManagerRes, LoadAssetMsg};
+use crate::plugins::cell::{index, CloudCellId, MissingCells, StreamingMode, VisibleCells};
+use crate::plugins::metadata::{get_working_directory, ActiveMetadata, PointCloud};
+use crate::plugins::render::line::utils::{line_box, transform_lines};
+use crate::plugins::render::line::Line;
+use crate::plugins::render::vertex::VertexBuffer;
+use crate::plugins::wgpu::Device;
+
+/// Only the header of a cell file, which is enough to know where a cell is and how many points it has.
+pub struct CellBoundary(pub Header);
+
+impl Asset for CellBoundary {
+    type Id = CloudCellId;
+
+    fn read_from(reader: &mut dyn Read) -> Result<Self, SourceError> {
+        Header::read_from(reader)
+            .map(CellBoundary)
+            .map_err(SourceError::from)
+    }
+}
+
+/// Boundaries of the visible cells of a point cloud for [StreamingMode::Boundaries].
+#[derive(Debug, Default, Component)]
+pub(super) struct CellBoundaries {
+    requested: FxHashSet<CellId>,
+    shown: FxHashMap<CellId, Entity>,
+}
+
+impl CellBoundaries {
+    pub(super) fn len(&self) -> usize {
+        self.shown.len()
+    }
+}
+
+#[derive(Component)]
+pub(super) struct BoundaryLine {
+    point_cloud: Entity,
+    header: Header,
+    hierarchies: u32,
+}
+
+impl BoundaryLine {
+    /// Coarse hierarchies are dark and fine ones bright.
+    fn lines(&self, point_cloud: &PointCloud) -> Vec<Line> {
+        let t = self.header.id.hierarchy as f32 / self.hierarchies.saturating_sub(1).max(1) as f32;
+        let color = Colormap::Viridis.map(t);
+
+        let mut lines = line_box(
+            color.to_array(),
+            self.header.pos,
+            Vec3::splat(self.header.size / 2.0),
+        );
+        transform_lines(&point_cloud.transform, &mut lines);
+        lines
+    }
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+pub(super) struct BoundaryStreamingQueryData {
+    entity: Entity,
+    point_cloud: &'static PointCloud,
+    visible_cells: Ref<'static, VisibleCells>,
+    missing_cells: &'static MissingCells,
+    existing_cells: &'static index::ExistingCells,
+    boundaries: &'static mut CellBoundaries,
+}
+
+/// Requests the headers of newly visible cells and removes the boundaries of cells which aren't visible anymore.
+pub(super) fn update_boundaries(
+    mut commands: Commands,
+    streaming_mode: Res<StreamingMode>,
+    metadata_manager: AssetManagerRes<Metadata>,
+    boundary_manager: AssetManagerRes<CellBoundary>,
+    mut point_cloud_query: Query<BoundaryStreamingQueryData>,
+) {
+    for BoundaryStreamingQueryDataItem {
+        entity,
+        point_cloud,
+        visible_cells,
+        missing_cells,
+        existing_cells,
+        mut boundaries,
+    } in point_cloud_query.iter_mut()
+    {
+        if !(visible_cells.is_changed() || streaming_mode.is_changed()) {
+            continue;
+        }
+
+        let visible = visible_cells
+            .hierarchies
+            .iter()
+            .enumerate()
+            .flat_map(|(hierarchy, cell_indices)| {
+                cell_indices.iter().map(move |index| CellId {
+                    hierarchy: hierarchy as u32,
+                    index: *index,
+                })
+            })
+            .collect::<FxHashSet<_>>();
+
+        boundaries
+            .requested
+            .retain(|cell_id| visible.contains(cell_id));
+        boundaries.shown.retain(|cell_id, line_entity| {
+            let is_visible = visible.contains(cell_id);
+
+            if !is_visible {
+                commands.entity(*line_entity).despawn();
+            }
+
+            is_visible
+        });
+
+        let metadata_source = metadata_manager.get_asset_source(&point_cloud.metadata);
+        let working_directory = get_working_directory(metadata_source);
+        let cell_extension = &metadata_manager
+            .get_asset(&point_cloud.metadata)
+            .cell_extension;
+        let now = Instant::now();
+
+        for cell_id in visible {
+            if boundaries.requested.contains(&cell_id)
+                || boundaries.shown.contains_key(&cell_id)
+                || !existing_cells.may_exist(&cell_id)
+                || missing_cells.contains(&cell_id, now)
+            {
+                continue;
+            }
+
+            let source = working_directory.as_ref().map_or(Source::None, |dir| {
+                dir.join(&cell_id.logical_path(cell_extension))
+            });
+
+            boundary_manager
+                .load_sender()
+                .send(LoadAssetMsg {
+                    id: CloudCellId {
+                        point_cloud: entity,
+                        cell: cell_id,
+                    },
+                    source,
+                    reply_sender: None,
+                })
+                .unwrap();
+
+            boundaries.requested.insert(cell_id);
+        }
+    }
+}
+
+pub(super) fn receive_boundaries(
+    mut commands: Commands,
+    device: Res<Device>,
+    metadata_manager: AssetManagerRes<Metadata>,
+    boundary_manager: AssetManagerRes<CellBoundary>,
+    mut boundary_events: EventReader<AssetEvent<CellBoundary>>,
+    mut point_cloud_query: Query<(&PointCloud, &mut CellBoundaries)>,
+) {
+    for event in boundary_events.read() {
+        match event {
+            AssetEvent::Loaded(AssetLoadedEvent::Success { handle }) => {
+                let id = handle.id();
+
+                let Ok((point_cloud, mut boundaries)) = point_cloud_query.get_mut(id.point_cloud)
+                else {
+                    continue;
+                };
+
+                if !boundaries.requested.remove(&id.cell) {
+                    continue;
+                }
+
+                let header = boundary_manager.get_asset(handle).0.clone();
+
+                if header.total_number_of_points == 0 {
+                    continue;
+                }
+
+                let line = BoundaryLine {
+                    point_cloud: id.point_cloud,
+                    header,
+                    hierarchies: metadata_manager
+                        .get_asset(&point_cloud.metadata)
+                        .hierarchies,
+                };
+                let buffer = VertexBuffer::new(&device, &line.lines(point_cloud));
+
+                let line_entity = commands.spawn((line, buffer)).id();
+                boundaries.shown.insert(id.cell, line_entity);
+            }
+            AssetEvent::Loaded(AssetLoadedEvent::Error { id, error }) => {
+                let Ok((_, mut boundaries)) = point_cloud_query.get_mut(id.point_cloud) else {
+                    continue;
+                };
+
+                if boundaries.requested.remove(&id.cell)
+                    && !matches!(error, SourceError::NotFound(_) | SourceError::NoSource)
+                {
+                    log::error!("Failed to load cell header {:?}: {:?}", id, error);
+                }
+            }
+            AssetEvent::Created { .. } | AssetEvent::Changed { .. } => {}
+        }
+    }
+}
+
+pub(super) fn update_boundaries_of_moved_point_clouds(
+    mut commands: Commands,
+    device: Res<Device>,
+    changed_point_cloud_query: Query<&PointCloud, Changed<PointCloud>>,
+    line_query: Query<(Entity, &BoundaryLine)>,
+) {
+    if changed_point_cloud_query.is_empty() {
+        return;
+    }
+
+    for (entity, line) in line_query.iter() {
+        if let Ok(point_cloud) = changed_point_cloud_query.get(line.point_cloud) {
+            commands
+                .entity(entity)
+                .insert(VertexBuffer::new(&device, &line.lines(point_cloud)));
+        }
+    }
+}
+
+/// Removes all boundaries after switching back to [StreamingMode::Points].
+pub(super) fn clear_boundaries(
+    mut commands: Commands,
+    mut boundaries_query: Query<&mut CellBoundaries>,
+    line_query: Query<Entity, With<BoundaryLine>>,
+) {
+    for mut boundaries in boundaries_query.iter_mut() {
+        boundaries.requested.clear();
+        boundaries.shown.clear();
+    }
+
+    for entity in line_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Removes the boundaries of the active point cloud, which is about to be replaced or was unloaded.
+pub(super) fn cleanup_boundaries(
+    mut commands: Commands,
+    active_metadata: ActiveMetadata,
+    mut boundaries_query: Query<&mut CellBoundaries>,
+    line_query: Query<(Entity, &BoundaryLine)>,
+) {
+    let active = active_metadata.entity();
+
+    if let Ok(mut boundaries) = boundaries_query.get_mut(active) {
+        boundaries.requested.clear();
+        boundaries.shown.clear();
+    }
+
+    for (entity, line) in line_query.iter() {
+        if line.point_cloud == active {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub(super) fn remove_boundaries_of_removed_point_clouds(
+    mut commands: Commands,
+    mut removed_point_clouds: RemovedComponents<PointCloud>,
+    line_query: Query<(Entity, &BoundaryLine)>,
+) {
+    let removed = removed_point_clouds.read().collect::<FxHashSet<_>>();
+
+    if removed.is_empty() {
+        return;
+    }
+
+    for (entity, line) in line_query.iter() {
+        if removed.contains(&line.point_cloud) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
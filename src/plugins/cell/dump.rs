@@ -0,0 +1,153 @@
+use bevy_ecs::prelude::*;
+use rustc_hash::FxHashSet;
+
+use crate::plugins::camera::Visibility;
+use crate::plugins::cell::{CellHeader, LoadedCells};
+
+/// A cell which is either in [LoadedCells] or has an entity, see [loaded_cells_to_csv].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct LoadedCellRow {
+    /// [None] if no [LoadedCells] knows the cell entity.
+    point_cloud: Option<Entity>,
+    hierarchy: u32,
+    index: [i32; 3],
+    in_loaded_cells: bool,
+    /// Visibility and number of points of the cell entity, [None] if it doesn't exist anymore.
+    entity: Option<(bool, u32)>,
+}
+
+impl LoadedCellRow {
+    const CSV_HEADER: &'static str = "point_cloud,hierarchy,x,y,z,in_loaded_cells,visible,points";
+
+    fn to_csv(&self) -> String {
+        let [x, y, z] = self.index;
+        let (visible, points) = self
+            .entity
+            .map_or((String::new(), String::new()), |(visible, points)| {
+                (visible.to_string(), points.to_string())
+            });
+
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.point_cloud.map_or(String::new(), |it| it.to_string()),
+            self.hierarchy,
+            x,
+            y,
+            z,
+            self.in_loaded_cells,
+            visible,
+            points
+        )
+    }
+}
+
+/// Lists every entry of [LoadedCells] and every cell entity, sorted by point cloud and cell,
+/// so that what the streaming thinks is loaded can be compared with what is drawn.
+fn loaded_cells_rows(world: &mut World) -> Vec<LoadedCellRow> {
+    let mut loaded_cells_query = world.query::<(Entity, &LoadedCells)>();
+    let mut cell_query = world.query::<(Entity, &CellHeader, &Visibility)>();
+
+    let mut rows = Vec::new();
+    let mut known_entities = FxHashSet::default();
+
+    for (point_cloud, loaded_cells) in loaded_cells_query.iter(world) {
+        for (cell_id, entity) in loaded_cells.0.iter() {
+            known_entities.insert(*entity);
+
+            rows.push(LoadedCellRow {
+                point_cloud: Some(point_cloud),
+                hierarchy: cell_id.hierarchy,
+                index: cell_id.index.to_array(),
+                in_loaded_cells: true,
+                entity: cell_query
+                    .get(world, *entity)
+                    .ok()
+                    .map(|(_, header, visibility)| {
+                        (visibility.visible, header.0.total_number_of_points)
+                    }),
+            });
+        }
+    }
+
+    for (entity, header, visibility) in cell_query.iter(world) {
+        if known_entities.contains(&entity) {
+            continue;
+        }
+
+        rows.push(LoadedCellRow {
+            point_cloud: None,
+            hierarchy: header.0.id.hierarchy,
+            index: header.0.id.index.to_array(),
+            in_loaded_cells: false,
+            entity: Some((visibility.visible, header.0.total_number_of_points)),
+        });
+    }
+
+    rows.sort();
+    rows
+}
+
+pub(super) fn loaded_cells_to_csv(world: &mut World) -> String {
+    std::iter::once(LoadedCellRow::CSV_HEADER.to_string())
+        .chain(loaded_cells_rows(world).iter().map(LoadedCellRow::to_csv))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::IVec3;
+
+    use point_converter::cell::CellId;
+
+    use super::*;
+
+    fn cell_id(x: i32) -> CellId {
+        CellId {
+            hierarchy: 0,
+            index: IVec3::new(x, 0, 0),
+        }
+    }
+
+    #[test]
+    fn loaded_cells_rows_show_mismatches() {
+        let mut world = World::new();
+
+        let header = |x: i32| {
+            CellHeader(point_converter::cell::Header::new(
+                cell_id(x),
+                1.0,
+                10.0,
+                glam::Vec3::ZERO,
+            ))
+        };
+
+        let drawn = world.spawn((header(0), Visibility::new(true))).id();
+        world.spawn((header(1), Visibility::new(false)));
+        let despawned = world.spawn_empty().id();
+        world.despawn(despawned);
+
+        let mut loaded_cells = LoadedCells::default();
+        loaded_cells.0.insert(cell_id(0), drawn);
+        loaded_cells.0.insert(cell_id(2), despawned);
+        let point_cloud = world.spawn(loaded_cells).id();
+
+        let rows = loaded_cells_rows(&mut world);
+
+        assert_eq!(
+            rows.iter()
+                .map(|row| (
+                    row.point_cloud,
+                    row.index[0],
+                    row.in_loaded_cells,
+                    row.entity
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (None, 1, false, Some((false, 0))),
+                (Some(point_cloud), 0, true, Some((true, 0))),
+                (Some(point_cloud), 2, true, None),
+            ]
+        );
+    }
+}
@@ -2,29 +2,51 @@ use std::ops::{Deref, DerefMut};
 
 use bevy_ecs::prelude::*;
 use bevy_ecs::system::SystemState;
+use point_converter::metadata::Metadata;
 
+use crate::plugins::asset::AssetManagerRes;
 use crate::plugins::camera::frustum::Frustum;
 use crate::plugins::camera::projection::PerspectiveProjection;
 use crate::plugins::camera::Camera;
 use crate::plugins::cell::shader::FrustumsSettings;
-use crate::plugins::metadata::{ActiveMetadata, UpdatedMetadataHierarchiesEvent};
+use crate::plugins::metadata::{ActiveMetadata, PointCloud, UpdatedMetadataHierarchiesEvent};
 use crate::transform::Transform;
 
+/// Scales the load distance of each hierarchy relative to its cell size.
+///
+/// The max scale also sets the far plane of the camera, see [StreamingFrustumsScale::far].
+/// With the standard depth buffer of [crate::texture::Texture::DEPTH_COMPARE]
+/// most of the depth precision is spent close to the near plane,
+/// so raising the max lets coarse cells stream from further away,
+/// but distant points fight over fewer depth values.
 #[derive(Resource)]
-pub struct StreamingFrustumsScale(f32);
+pub struct StreamingFrustumsScale {
+    scale: f32,
+    max: f32,
+}
 
 impl StreamingFrustumsScale {
     pub const MIN: f32 = 1.0;
-    pub const MAX: f32 = 5.0;
+    /// Upper limit for [StreamingFrustumsScale::max].
+    pub const MAX_LIMIT: f32 = 50.0;
+
+    /// Far plane of the camera, so that the coarsest cells can stream in up to the max scale.
+    pub fn far(&self, max_cell_size: f32) -> f32 {
+        max_cell_size * self.max
+    }
 }
 
 impl Default for StreamingFrustumsScale {
     fn default() -> Self {
-        Self(2.0)
+        Self {
+            scale: 2.0,
+            max: 5.0,
+        }
     }
 }
 
-#[derive(Debug, Component)]
+/// Streaming frustums of a [PointCloud], one per hierarchy.
+#[derive(Debug, Default, Component)]
 pub struct StreamingFrustums(Vec<Frustum>);
 
 impl Deref for StreamingFrustums {
@@ -41,36 +63,30 @@ impl DerefMut for StreamingFrustums {
     }
 }
 
-pub fn add_streaming_frustums(mut commands: Commands, camera_query: Query<Entity, With<Camera>>) {
-    for entity in camera_query.iter() {
-        commands
-            .entity(entity)
-            .insert(StreamingFrustums(Vec::new()));
-    }
-}
-
 pub fn update_streaming_frustums(
-    active_metadata: ActiveMetadata,
+    metadata_manager: AssetManagerRes<Metadata>,
     mut updated_metadata_hierarchies_events: EventReader<UpdatedMetadataHierarchiesEvent>,
-    mut camera_query: Query<
-        (
-            &Transform,
-            &PerspectiveProjection,
-            Ref<Frustum>,
-            &mut StreamingFrustums,
-        ),
-        With<Camera>,
-    >,
+    camera_query: Query<(&Transform, &PerspectiveProjection, Ref<Frustum>), With<Camera>>,
+    mut point_cloud_query: Query<(Ref<PointCloud>, &mut StreamingFrustums)>,
     streaming_frustums_scale: Res<StreamingFrustumsScale>,
 ) {
-    let metadata = active_metadata.get();
     let updated_metadata = updated_metadata_hierarchies_events.read().count() > 0;
 
-    for (transform, projection, frustum, mut streaming_frustums) in camera_query.iter_mut() {
-        if !(frustum.is_changed() || streaming_frustums_scale.is_changed() || updated_metadata) {
+    let Ok((transform, projection, frustum)) = camera_query.get_single() else {
+        return;
+    };
+
+    for (point_cloud, mut streaming_frustums) in point_cloud_query.iter_mut() {
+        if !(frustum.is_changed()
+            || streaming_frustums_scale.is_changed()
+            || point_cloud.is_changed()
+            || updated_metadata)
+        {
             continue;
         }
 
+        let metadata = metadata_manager.get_asset(&point_cloud.metadata);
+
         let mut new_projection = projection.clone();
 
         let forward = transform.forward();
@@ -80,8 +96,8 @@ pub fn update_streaming_frustums(
             .map(|hierarchy| {
                 let cell_size = metadata.config.cell_size(hierarchy);
 
-                let far_distance =
-                    projection.near + (cell_size * streaming_frustums_scale.0).min(projection.far);
+                let far_distance = projection.near
+                    + (cell_size * streaming_frustums_scale.scale).min(projection.far);
                 let center_on_far_plane = transform.translation + far_distance * forward;
 
                 new_projection.far = far_distance;
@@ -106,16 +122,29 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
 
         let mut streaming_frustums_scale =
             world.get_resource_mut::<StreamingFrustumsScale>().unwrap();
-        let mut scale = streaming_frustums_scale.0;
+        let mut scale = streaming_frustums_scale.scale;
+        let mut max = streaming_frustums_scale.max;
+
+        let slider = egui::Slider::new(&mut scale, StreamingFrustumsScale::MIN..=max).step_by(0.1);
+
+        if ui.add(slider).changed() {
+            streaming_frustums_scale.scale = scale;
+        }
 
         let slider = egui::Slider::new(
-            &mut scale,
-            StreamingFrustumsScale::MIN..=StreamingFrustumsScale::MAX,
+            &mut max,
+            StreamingFrustumsScale::MIN..=StreamingFrustumsScale::MAX_LIMIT,
         )
-        .step_by(0.1);
+        .step_by(0.5)
+        .text("Max");
 
-        if ui.add(slider).changed() {
-            streaming_frustums_scale.0 = scale;
+        if ui
+            .add(slider)
+            .on_hover_text("Also moves the far plane of the camera, which costs depth precision")
+            .changed()
+        {
+            streaming_frustums_scale.max = max;
+            streaming_frustums_scale.scale = scale.min(max);
         }
     }
 
@@ -0,0 +1,135 @@
+use std::io::Read;
+
+use bevy_ecs::prelude::*;
+
+use point_converter::cell::CellId;
+use point_converter::index::CellIndex;
+use point_converter::metadata::Metadata;
+
+use crate::plugins::asset::source::{Directory, SourceError};
+use crate::plugins::asset::{Asset, AssetEvent, AssetLoadedEvent, AssetManagerRes, LoadAssetMsg};
+use crate::plugins::metadata::{get_working_directory, ActiveMetadata, LoadedMetadata, PointCloud};
+
+impl Asset for CellIndex {
+    /// The point cloud the index belongs to and where it was loaded from.
+    type Id = (Entity, String);
+
+    fn read_from(reader: &mut dyn Read) -> Result<Self, SourceError> {
+        CellIndex::read_from(reader).map_err(SourceError::from)
+    }
+}
+
+/// Index of the cells of a point cloud.
+/// If there is none, every cell has to be requested to find out whether it exists.
+#[derive(Debug, Default, Component)]
+pub(super) struct ExistingCells(Option<CellIndex>);
+
+impl ExistingCells {
+    pub(super) fn may_exist(&self, cell_id: &CellId) -> bool {
+        match &self.0 {
+            Some(cell_index) => cell_index.contains(cell_id),
+            None => true,
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.0 = None;
+    }
+
+    pub(super) fn insert(&mut self, cell_id: CellId) {
+        if let Some(cell_index) = &mut self.0 {
+            cell_index.insert(cell_id);
+        }
+    }
+}
+
+fn send_load_cell_index(
+    cell_index_manager: &AssetManagerRes<CellIndex>,
+    point_cloud: Entity,
+    working_directory: Option<Directory>,
+) {
+    let Some(working_directory) = working_directory else {
+        return;
+    };
+
+    let source = working_directory.join(&CellIndex::logical_path());
+
+    cell_index_manager
+        .load_sender()
+        .send(LoadAssetMsg {
+            id: (point_cloud, format!("{:?}", source)),
+            source,
+            reply_sender: None,
+        })
+        .unwrap();
+}
+
+/// Reloads the index of the active point cloud after its metadata was replaced.
+pub(super) fn load_cell_index(
+    active_metadata: ActiveMetadata,
+    cell_index_manager: AssetManagerRes<CellIndex>,
+    mut existing_cells_query: Query<&mut ExistingCells>,
+) {
+    let active = active_metadata.entity();
+
+    if let Ok(mut existing_cells) = existing_cells_query.get_mut(active) {
+        existing_cells.clear();
+    }
+
+    send_load_cell_index(
+        &cell_index_manager,
+        active,
+        active_metadata.get_working_directory(),
+    );
+}
+
+/// Loads the index of point clouds which were added next to the active one.
+pub(super) fn load_added_cell_index(
+    metadata_manager: AssetManagerRes<Metadata>,
+    cell_index_manager: AssetManagerRes<CellIndex>,
+    loaded_metadata: Res<LoadedMetadata>,
+    point_cloud_query: Query<(Entity, &PointCloud), Added<PointCloud>>,
+) {
+    for (entity, point_cloud) in point_cloud_query.iter() {
+        // the active point cloud is handled once its metadata is loaded
+        if entity == loaded_metadata.get_active() {
+            continue;
+        }
+
+        let metadata_source = metadata_manager.get_asset_source(&point_cloud.metadata);
+        send_load_cell_index(
+            &cell_index_manager,
+            entity,
+            get_working_directory(metadata_source),
+        );
+    }
+}
+
+pub(super) fn receive_cell_index(
+    mut cell_index_events: EventReader<AssetEvent<CellIndex>>,
+    cell_index_manager: AssetManagerRes<CellIndex>,
+    mut existing_cells_query: Query<&mut ExistingCells>,
+) {
+    for event in cell_index_events.read() {
+        match event {
+            AssetEvent::Loaded(AssetLoadedEvent::Success { handle }) => {
+                let Ok(mut existing_cells) = existing_cells_query.get_mut(handle.id().0) else {
+                    continue;
+                };
+
+                let cell_index = cell_index_manager.get_asset(handle);
+                log::debug!("Loaded index with {} cells", cell_index.len());
+                existing_cells.0 = Some(cell_index.clone());
+            }
+            AssetEvent::Loaded(AssetLoadedEvent::Error { id, error }) => match error {
+                SourceError::NotFound(_) | SourceError::NoSource => {
+                    log::debug!("No cell index at {}", id.1);
+                }
+                _ => {
+                    log::warn!("Failed to load cell index {}: {}", id.1, error);
+                }
+            },
+            AssetEvent::Created { .. } | AssetEvent::Changed { .. } => {}
+        }
+    }
+}
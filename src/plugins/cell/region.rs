@@ -0,0 +1,54 @@
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+use glam::Vec3;
+
+use bounding_volume::Aabb;
+use point_converter::cell::Cell;
+use point_converter::point::Point;
+
+use crate::plugins::asset::{AssetHandle, AssetManagerRes};
+use crate::plugins::cell::CellHeader;
+use crate::plugins::metadata::PointCloud;
+
+/// Read access to the points of all loaded cells.
+/// Loaded cells keep their [Cell] alive through their [AssetHandle],
+/// so no gpu buffers have to be read back.
+#[derive(SystemParam)]
+pub struct LoadedPoints<'w, 's> {
+    cell_manager: AssetManagerRes<'w, Cell>,
+    cell_query: Query<'w, 's, (&'static AssetHandle<Cell>, &'static CellHeader)>,
+    point_cloud_query: Query<'w, 's, &'static PointCloud>,
+}
+
+impl<'w, 's> LoadedPoints<'w, 's> {
+    /// Loaded points of all visible point clouds inside the world space region.
+    /// The positions of the returned points are in world space.
+    /// Every point is only contained in one hierarchy, so no point is returned twice.
+    pub fn points_in_region(&self, region: Aabb) -> impl Iterator<Item = Point> + '_ {
+        self.cell_query
+            .iter()
+            .filter_map(move |(handle, header)| {
+                let point_cloud = self
+                    .point_cloud_query
+                    .get(handle.id().point_cloud)
+                    .ok()
+                    .filter(|point_cloud| point_cloud.visible)?;
+
+                let half_size = Vec3::splat(header.0.size / 2.0);
+                let cell_aabb = Aabb::new(header.0.pos - half_size, header.0.pos + half_size);
+
+                point_cloud
+                    .transform
+                    .transform_aabb(&cell_aabb)
+                    .intersects(&region)
+                    .then(|| (self.cell_manager.get_asset(handle), point_cloud.transform))
+            })
+            .flat_map(move |(cell, transform)| {
+                cell.all_points().filter_map(move |point| {
+                    let pos = transform.transform_point(point.pos);
+
+                    region.contains(pos).then_some(Point { pos, ..*point })
+                })
+            })
+    }
+}
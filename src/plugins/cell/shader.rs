@@ -5,10 +5,11 @@ use wgpu::util::DeviceExt;
 
 use point_converter::cell::CellId;
 
+use crate::plugins::asset::{AssetHandle, AssetManagerRes};
 use crate::plugins::camera::Camera;
 use crate::plugins::cell::frustums::StreamingFrustums;
-use crate::plugins::cell::CellHeader;
-use crate::plugins::metadata::ActiveMetadata;
+use crate::plugins::cell::LoadedCells;
+use crate::plugins::metadata::{ActiveMetadata, PointCloud};
 use crate::plugins::render::point::Point;
 use crate::plugins::wgpu::{Device, Queue};
 use crate::transform::Transform;
@@ -29,13 +30,18 @@ pub struct CellBufferBundle {
 }
 
 impl CellBufferBundle {
-    pub fn new(device: &wgpu::Device, cell: &point_converter::cell::Cell) -> Self {
-        let points = cell
-            .all_points()
-            .map(|it| Point {
-                position: it.pos,
-                color: it.color,
-            })
+    /// Uploads at most `max_points` of the cell, see [PointsPerCellLimit].
+    pub fn new(
+        device: &wgpu::Device,
+        cell: &point_converter::cell::Cell,
+        max_points: Option<usize>,
+    ) -> Self {
+        let number_of_points = cell.header().total_number_of_points as usize;
+        let hierarchy = cell.header().id.hierarchy;
+
+        // drawn without the compute pass if points aren't filtered
+        let points = strided(cell.all_points(), number_of_points, max_points)
+            .map(|it| Point::new(it.pos, it.color.to_array(), hierarchy))
             .collect_vec();
 
         Self {
@@ -47,6 +53,56 @@ impl CellBufferBundle {
     }
 }
 
+/// Limits the number of points which are uploaded for each cell,
+/// so that the same converted point cloud fits into the memory of weaker gpus.
+/// Unlike the conversion, this only thins out the points which are drawn.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Resource)]
+pub struct PointsPerCellLimit(pub Option<usize>);
+
+impl PointsPerCellLimit {
+    pub const DEFAULT_LIMIT: usize = 10_000;
+}
+
+/// Takes every n-th item, so that at most `max` of the `len` items are left,
+/// which are spread evenly over all items.
+fn strided<T>(
+    items: impl Iterator<Item = T>,
+    len: usize,
+    max: Option<usize>,
+) -> impl Iterator<Item = T> {
+    let max = max.unwrap_or(usize::MAX).min(len);
+    let mut next = 0;
+
+    items.enumerate().filter_map(move |(i, item)| {
+        // i * len / max is the index of the next kept item
+        if next < max && i == next * len / max {
+            next += 1;
+            Some(item)
+        } else {
+            None
+        }
+    })
+}
+
+/// Uploads the points of all loaded cells again after the [PointsPerCellLimit] changed.
+pub(super) fn apply_points_per_cell_limit(
+    mut commands: Commands,
+    device: Res<Device>,
+    cell_manager: AssetManagerRes<point_converter::cell::Cell>,
+    points_per_cell_limit: Res<PointsPerCellLimit>,
+    cell_query: Query<(Entity, &AssetHandle<point_converter::cell::Cell>)>,
+) {
+    for (entity, handle) in cell_query.iter() {
+        let cell = cell_manager.get_asset(handle);
+
+        commands.entity(entity).insert(CellBufferBundle::new(
+            &device,
+            cell,
+            points_per_cell_limit.0,
+        ));
+    }
+}
+
 #[derive(Component)]
 pub struct CellInputVertexBuffer {
     pub buffer: wgpu::Buffer,
@@ -58,7 +114,8 @@ impl CellInputVertexBuffer {
         let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("point-input-vertex-buffer"),
             contents: bytemuck::cast_slice(points),
-            usage: wgpu::BufferUsages::STORAGE,
+            // Drawn directly when the points aren't filtered, see PointFilter
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
         });
 
         Self {
@@ -92,11 +149,14 @@ impl CellOutputVertexBuffer {
 pub struct CellIndirectBuffer(pub wgpu::Buffer);
 
 impl CellIndirectBuffer {
+    /// Every point is drawn as a quad.
+    pub const VERTEX_COUNT: u32 = 4;
+
     pub fn new(device: &wgpu::Device) -> Self {
         let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("point-indirect-buffer"),
             contents: wgpu::util::DrawIndirectArgs {
-                vertex_count: 4,
+                vertex_count: Self::VERTEX_COUNT,
                 instance_count: 0,
                 first_vertex: 0,
                 first_instance: 0,
@@ -104,7 +164,8 @@ impl CellIndirectBuffer {
             .as_bytes(),
             usage: wgpu::BufferUsages::STORAGE
                 | wgpu::BufferUsages::INDIRECT
-                | wgpu::BufferUsages::COPY_DST,
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
         });
 
         Self(buffer)
@@ -131,7 +192,7 @@ impl CellIdBuffer {
     }
 }
 
-#[derive(Resource)]
+#[derive(Component)]
 pub struct LoadedCellsBuffer {
     pub buffer: wgpu::Buffer,
     pub capacity: usize,
@@ -151,7 +212,7 @@ impl LoadedCellsBuffer {
     }
 }
 
-#[derive(Resource)]
+#[derive(Component)]
 pub struct FrustumsBuffer {
     pub buffer: wgpu::Buffer,
     pub capacity: usize,
@@ -194,12 +255,17 @@ impl FrustumsSettings {
     }
 }
 
-pub(super) fn create_loaded_cells_buffer(mut commands: Commands, device: Res<Device>) {
-    commands.insert_resource(LoadedCellsBuffer::new(0, &device));
-}
-
-pub(super) fn create_frustums_buffer(mut commands: Commands, device: Res<Device>) {
-    commands.insert_resource(FrustumsBuffer::new(1, &device));
+pub(super) fn add_cell_streaming_buffers(
+    mut commands: Commands,
+    device: Res<Device>,
+    point_cloud_query: Query<Entity, Added<PointCloud>>,
+) {
+    for entity in point_cloud_query.iter() {
+        commands.entity(entity).insert((
+            LoadedCellsBuffer::new(0, &device),
+            FrustumsBuffer::new(1, &device),
+        ));
+    }
 }
 
 pub(super) fn create_frustums_settings_buffer(mut commands: Commands, device: Res<Device>) {
@@ -209,52 +275,58 @@ pub(super) fn create_frustums_settings_buffer(mut commands: Commands, device: Re
 pub(super) fn update_loaded_cells_buffer(
     queue: Res<Queue>,
     device: Res<Device>,
-    mut loaded_cells_buffer: ResMut<LoadedCellsBuffer>,
-    cell_query: Query<&CellHeader>,
+    mut point_cloud_query: Query<(&LoadedCells, &mut LoadedCellsBuffer), Changed<LoadedCells>>,
 ) {
-    let mut loaded_cells = cell_query
-        .iter()
-        .map(|cell_header| Cell {
-            hierarchy: cell_header.0.id.hierarchy,
-            index: cell_header.0.id.index,
-        })
-        .collect_vec();
-
-    loaded_cells.sort_unstable_by(|a, b| {
-        a.hierarchy
-            .cmp(&b.hierarchy)
-            .then(a.index.x.cmp(&b.index.x))
-            .then(a.index.y.cmp(&b.index.y))
-            .then(a.index.z.cmp(&b.index.z))
-    });
-
-    if loaded_cells.len() > loaded_cells_buffer.capacity {
-        *loaded_cells_buffer = LoadedCellsBuffer::new(loaded_cells.len() + 50, &device);
-    }
+    for (loaded_cells, mut loaded_cells_buffer) in point_cloud_query.iter_mut() {
+        let mut loaded_cells = loaded_cells
+            .0
+            .keys()
+            .map(|cell_id| Cell {
+                hierarchy: cell_id.hierarchy,
+                index: cell_id.index,
+            })
+            .collect_vec();
 
-    queue.write_buffer(
-        &loaded_cells_buffer.buffer,
-        0,
-        bytemuck::bytes_of(&(loaded_cells.len() as u32)),
-    );
+        loaded_cells.sort_unstable_by(|a, b| {
+            a.hierarchy
+                .cmp(&b.hierarchy)
+                .then(a.index.x.cmp(&b.index.x))
+                .then(a.index.y.cmp(&b.index.y))
+                .then(a.index.z.cmp(&b.index.z))
+        });
 
-    queue.write_buffer(
-        &loaded_cells_buffer.buffer,
-        std::mem::size_of::<u32>() as wgpu::BufferAddress,
-        bytemuck::cast_slice(&loaded_cells),
-    );
+        if loaded_cells.len() > loaded_cells_buffer.capacity {
+            *loaded_cells_buffer = LoadedCellsBuffer::new(loaded_cells.len() + 50, &device);
+        }
+
+        queue.write_buffer(
+            &loaded_cells_buffer.buffer,
+            0,
+            bytemuck::bytes_of(&(loaded_cells.len() as u32)),
+        );
+
+        queue.write_buffer(
+            &loaded_cells_buffer.buffer,
+            std::mem::size_of::<u32>() as wgpu::BufferAddress,
+            bytemuck::cast_slice(&loaded_cells),
+        );
+    }
 }
 
 pub(super) fn update_frustums_buffer(
     device: Res<Device>,
     queue: Res<Queue>,
-    mut frustums_buffer: ResMut<FrustumsBuffer>,
-    frustums_query: Query<
-        (&StreamingFrustums, &Transform),
-        (With<Camera>, Changed<StreamingFrustums>),
+    camera_query: Query<&Transform, With<Camera>>,
+    mut point_cloud_query: Query<
+        (&StreamingFrustums, &mut FrustumsBuffer),
+        Changed<StreamingFrustums>,
     >,
 ) {
-    for (frustums, camera_transform) in frustums_query.iter() {
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+
+    for (frustums, mut frustums_buffer) in point_cloud_query.iter_mut() {
         let far_distances = frustums
             .iter()
             .map(|frustum| frustum.far.iter().sum::<Vec3>() / frustum.far.iter().len() as f32)
@@ -300,3 +372,37 @@ pub(super) fn update_frustums_settings_buffer(
         bytemuck::bytes_of(&frustums_settings.max_hierarchy),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strided_spreads_kept_items() {
+        let kept = strided(0..10, 10, Some(4)).collect_vec();
+        assert_eq!(kept, vec![0, 2, 5, 7]);
+
+        let kept = strided(0..10_000, 10_000, Some(1000)).collect_vec();
+        assert_eq!(kept.len(), 1000);
+        assert_eq!(kept[1] - kept[0], 10);
+    }
+
+    #[test]
+    fn strided_keeps_everything_under_the_limit() {
+        assert_eq!(strided(0..5, 5, Some(10)).count(), 5);
+        assert_eq!(strided(0..5, 5, None).count(), 5);
+        assert_eq!(strided(0..5, 5, Some(0)).count(), 0);
+    }
+
+    #[test]
+    fn uploaded_points_carry_their_hierarchy() {
+        let point = Point::new(glam::Vec3::ZERO, [1, 2, 3, 255], 3);
+
+        assert_eq!(point.color[..3], [1, 2, 3]);
+        assert_eq!(point.color[3] & Point::HIERARCHY_MASK, 3);
+        assert_eq!(
+            point.color[3] & !Point::HIERARCHY_MASK,
+            !Point::HIERARCHY_MASK
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+
+use bevy_ecs::prelude::*;
+
+/// Something that happened to a cell while streaming.
+#[derive(Debug, Copy, Clone)]
+pub enum StreamingEvent {
+    /// Became visible and waits to be loaded.
+    Enqueued,
+    /// Was received and waits to be uploaded.
+    Loaded,
+    /// Left the view before it was loaded.
+    Culled,
+    /// Left the view after it was loaded and was removed.
+    Evicted,
+    /// Doesn't exist or has no points.
+    Missing,
+}
+
+/// Number of [StreamingEvent]s of a single frame.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct StreamingCounts {
+    pub frame: u64,
+    pub enqueued: u32,
+    pub loaded: u32,
+    pub culled: u32,
+    pub evicted: u32,
+    pub missing: u32,
+}
+
+impl StreamingCounts {
+    const CSV_HEADER: &'static str = "frame,enqueued,loaded,culled,evicted,missing";
+
+    fn is_empty(&self) -> bool {
+        self.enqueued == 0
+            && self.loaded == 0
+            && self.culled == 0
+            && self.evicted == 0
+            && self.missing == 0
+    }
+
+    fn to_csv(self) -> String {
+        format!(
+            "{},{},{},{},{},{}",
+            self.frame, self.enqueued, self.loaded, self.culled, self.evicted, self.missing
+        )
+    }
+}
+
+/// Records the [StreamingCounts] of the last frames, if enabled.
+#[derive(Debug, Resource)]
+pub struct StreamingTrace {
+    pub enabled: bool,
+    /// Only shows frames with events in the ui.
+    pub hide_empty_frames: bool,
+    frame: u64,
+    current: StreamingCounts,
+    frames: VecDeque<StreamingCounts>,
+}
+
+impl Default for StreamingTrace {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hide_empty_frames: true,
+            frame: 0,
+            current: StreamingCounts::default(),
+            frames: VecDeque::with_capacity(Self::CAPACITY),
+        }
+    }
+}
+
+impl StreamingTrace {
+    /// Number of frames which are kept.
+    pub const CAPACITY: usize = 600;
+
+    pub fn record(&mut self, event: StreamingEvent) {
+        if !self.enabled {
+            return;
+        }
+
+        let count = match event {
+            StreamingEvent::Enqueued => &mut self.current.enqueued,
+            StreamingEvent::Loaded => &mut self.current.loaded,
+            StreamingEvent::Culled => &mut self.current.culled,
+            StreamingEvent::Evicted => &mut self.current.evicted,
+            StreamingEvent::Missing => &mut self.current.missing,
+        };
+
+        *count += 1;
+    }
+
+    /// Recorded frames from oldest to newest.
+    pub fn frames(&self) -> impl DoubleEndedIterator<Item = &StreamingCounts> {
+        self.frames.iter()
+    }
+
+    pub fn to_csv(&self) -> String {
+        std::iter::once(StreamingCounts::CSV_HEADER.to_string())
+            .chain(self.frames.iter().map(|counts| counts.to_csv()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub(super) fn finish_frame(mut trace: ResMut<StreamingTrace>) {
+    trace.frame += 1;
+
+    if !trace.enabled {
+        return;
+    }
+
+    let counts = StreamingCounts {
+        frame: trace.frame,
+        ..std::mem::take(&mut trace.current)
+    };
+
+    if trace.frames.len() == StreamingTrace::CAPACITY {
+        trace.frames.pop_front();
+    }
+
+    trace.frames.push_back(counts);
+}
+
+pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
+    let mut trace = world.resource_mut::<StreamingTrace>();
+
+    let mut enabled = trace.enabled;
+    if ui.checkbox(&mut enabled, "Record").changed() {
+        trace.enabled = enabled;
+    }
+
+    let mut hide_empty_frames = trace.hide_empty_frames;
+    if ui
+        .checkbox(&mut hide_empty_frames, "Hide frames without events")
+        .changed()
+    {
+        trace.hide_empty_frames = hide_empty_frames;
+    }
+
+    egui::ScrollArea::vertical()
+        .max_height(200.0)
+        .show(ui, |ui| {
+            egui::Grid::new("streaming_trace_grid")
+                .num_columns(6)
+                .striped(true)
+                .show(ui, |ui| {
+                    for header in [
+                        "Frame", "Enqueued", "Loaded", "Culled", "Evicted", "Missing",
+                    ] {
+                        ui.label(header);
+                    }
+                    ui.end_row();
+
+                    for counts in trace
+                        .frames()
+                        .rev()
+                        .filter(|counts| !hide_empty_frames || !counts.is_empty())
+                    {
+                        ui.label(counts.frame.to_string());
+                        ui.label(counts.enqueued.to_string());
+                        ui.label(counts.loaded.to_string());
+                        ui.label(counts.culled.to_string());
+                        ui.label(counts.evicted.to_string());
+                        ui.label(counts.missing.to_string());
+                        ui.end_row();
+                    }
+                });
+        });
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if ui.button("Save as csv...").clicked() {
+        let csv = world.resource::<StreamingTrace>().to_csv();
+
+        let window: &winit::window::Window = world
+            .get_resource::<crate::plugins::winit::Window>()
+            .unwrap();
+
+        let path = rfd::FileDialog::new()
+            .set_parent(window)
+            .set_file_name("streaming-trace.csv")
+            .save_file();
+
+        if let Some(path) = path {
+            if let Err(err) = std::fs::write(&path, csv) {
+                log::error!("Failed to save streaming trace to {:?}: {}", path, err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_last_frames() {
+        let mut world = World::new();
+        world.insert_resource(StreamingTrace {
+            enabled: true,
+            ..StreamingTrace::default()
+        });
+
+        for _ in 0..StreamingTrace::CAPACITY + 5 {
+            world
+                .resource_mut::<StreamingTrace>()
+                .record(StreamingEvent::Loaded);
+            world.run_system_once(finish_frame);
+        }
+
+        let trace = world.resource::<StreamingTrace>();
+
+        assert_eq!(trace.frames().count(), StreamingTrace::CAPACITY);
+        assert_eq!(trace.frames().next().unwrap().frame, 6);
+        assert!(trace.frames().all(|counts| counts.loaded == 1));
+    }
+
+    #[test]
+    fn records_nothing_when_disabled() {
+        let mut trace = StreamingTrace::default();
+
+        trace.record(StreamingEvent::Enqueued);
+
+        assert!(trace.current.is_empty());
+    }
+}
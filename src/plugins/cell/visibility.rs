@@ -0,0 +1,82 @@
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemState;
+use rustc_hash::FxHashSet;
+
+use crate::plugins::camera::Visibility;
+use crate::plugins::cell::CellHeader;
+use crate::plugins::metadata::ActiveMetadata;
+
+/// Which hierarchies are rendered, e.g. to look at a single level of detail.
+/// Cells which are loaded again get the visibility of their hierarchy.
+#[derive(Debug, Resource)]
+pub struct HierarchyVisibility {
+    pub show_all: bool,
+    hidden: FxHashSet<u32>,
+}
+
+impl Default for HierarchyVisibility {
+    fn default() -> Self {
+        Self {
+            show_all: true,
+            hidden: FxHashSet::default(),
+        }
+    }
+}
+
+impl HierarchyVisibility {
+    pub fn is_visible(&self, hierarchy: u32) -> bool {
+        self.show_all || !self.hidden.contains(&hierarchy)
+    }
+
+    pub fn set_visible(&mut self, hierarchy: u32, visible: bool) {
+        if visible {
+            self.hidden.remove(&hierarchy);
+        } else {
+            self.hidden.insert(hierarchy);
+        }
+    }
+}
+
+pub(super) fn set_visibility_of_new_cells(
+    hierarchy_visibility: Res<HierarchyVisibility>,
+    mut cell_query: Query<(&CellHeader, &mut Visibility), Added<CellHeader>>,
+) {
+    for (cell_header, mut visibility) in cell_query.iter_mut() {
+        visibility.visible = hierarchy_visibility.is_visible(cell_header.0.id.hierarchy);
+    }
+}
+
+pub(super) fn update_visibility_of_cells(
+    hierarchy_visibility: Res<HierarchyVisibility>,
+    mut cell_query: Query<(&CellHeader, &mut Visibility)>,
+) {
+    for (cell_header, mut visibility) in cell_query.iter_mut() {
+        visibility.visible = hierarchy_visibility.is_visible(cell_header.0.id.hierarchy);
+    }
+}
+
+pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
+    let hierarchies = {
+        let mut params = SystemState::<ActiveMetadata>::new(world);
+        params.get(world).get().hierarchies
+    };
+
+    let mut hierarchy_visibility = world.resource_mut::<HierarchyVisibility>();
+
+    let mut show_all = hierarchy_visibility.show_all;
+    if ui.checkbox(&mut show_all, "Show all hierarchies").changed() {
+        hierarchy_visibility.show_all = show_all;
+    }
+
+    ui.add_enabled_ui(!show_all, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            for hierarchy in 0..hierarchies {
+                let mut visible = !hierarchy_visibility.hidden.contains(&hierarchy);
+
+                if ui.checkbox(&mut visible, hierarchy.to_string()).changed() {
+                    hierarchy_visibility.set_visible(hierarchy, visible);
+                }
+            }
+        });
+    });
+}
@@ -1,5 +1,5 @@
 use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use bevy_app::prelude::*;
@@ -14,18 +14,25 @@ use thousands::Separable;
 
 use bounding_volume::Aabb;
 use point_converter::cell::{Cell, CellId};
-use point_converter::converter::{add_points_to_cell, group_points, BatchedPointReader};
-use point_converter::metadata::Metadata;
-use point_converter::point::Point;
+use point_converter::converter::{
+    add_points_to_cell, drop_coincident_points, group_points, BatchedPointReader,
+};
+use point_converter::error::ConverterError;
+use point_converter::estimate::{estimate_output_size, format_bytes, EstimatedSize};
+use point_converter::index::CellIndex;
+use point_converter::metadata::{Metadata, MetadataConfig, MetadataConfigPreset, PointReduction};
+use point_converter::point::{ChannelOrder, Color, Point, PointAttributes};
 
 use crate::plugins::asset::source::{Source, SourceError};
 use crate::plugins::asset::{
-    AssetHandle, AssetLoadedEvent, AssetManagerRes, AssetManagerResMut, LoadAssetMsg,
+    AssetHandle, AssetLoadedEvent, AssetManagerRes, AssetManagerResMut, AssetSavedEvent,
+    LoadAssetMsg,
 };
+use crate::plugins::cell::CloudCellId;
 use crate::plugins::metadata::{
-    ActiveMetadata, LoadedMetadata, MetadataState, UpdateMetadataEvent,
+    ActiveMetadata, LoadedMetadata, MetadataState, PointCloud, UpdateMetadataEvent,
 };
-use crate::plugins::thread_pool::ThreadPool;
+use crate::plugins::thread_pool::{IoThreadPoolRes, ThreadPoolRes};
 
 pub struct ConverterPlugin;
 
@@ -39,6 +46,8 @@ impl Plugin for ConverterPlugin {
             read_batch: read_batch_system_id,
             current: 0,
             files: vec![],
+            estimate: None,
+            sampled_colors: Vec::new(),
         })
         .insert_resource(PointBatchReceiver(None))
         .insert_resource(PointReader(None))
@@ -66,7 +75,12 @@ impl Plugin for ConverterPlugin {
                 .chain()
                 .run_if(|settings: Res<Settings>| settings.auto_save),
         )
-        .add_systems(OnEnter(MetadataState::Loaded), disable_auto_save);
+        .add_systems(
+            Update,
+            log_saved_metadata.run_if(on_event::<AssetSavedEvent<Metadata>>()),
+        )
+        .add_systems(OnEnter(MetadataState::Loaded), disable_auto_save)
+        .add_systems(OnEnter(MetadataState::NotLoaded), clear_cache);
     }
 }
 
@@ -83,10 +97,12 @@ enum FileConversionStatus {
     Converting {
         total: u64,
         remaining: u64,
+        /// Fraction of the file which was read, see [BatchedPointReader::progress].
+        read: f64,
     },
     Finished,
     Failed {
-        error: std::io::Error,
+        error: ConverterError,
         total: u64,
         remaining: u64,
     },
@@ -99,8 +115,8 @@ struct FileToConvert {
 }
 
 impl FileToConvert {
-    fn create_reader(&self) -> Option<Box<dyn BatchedPointReader + Send>> {
-        point_converter::get_batched_point_reader(&self.path)
+    fn create_reader(&self) -> Result<Box<dyn BatchedPointReader + Send>, ConverterError> {
+        point_converter::open_batched_point_reader(&self.path)
     }
 }
 
@@ -110,6 +126,10 @@ struct FilesToConvert {
     read_batch: SystemId,
     current: usize,
     files: Vec<FileToConvert>,
+    /// Size which the selected files add to the active point cloud.
+    estimate: Option<EstimatedSize>,
+    /// Colors of a few points of the last batch, so that swapped channels are noticed early.
+    sampled_colors: Vec<Color>,
 }
 
 impl FilesToConvert {
@@ -136,28 +156,31 @@ fn next_file(
         if files_to_convert.next() {
             let current_file = files_to_convert.current_mut();
 
-            if let Some(reader) = current_file.create_reader() {
-                let total_points = reader.total_points();
+            match current_file.create_reader() {
+                Ok(reader) => {
+                    let total_points = reader.total_points();
 
-                point_reader.0 = Some(Arc::new(Mutex::new(reader)));
+                    point_reader.0 = Some(Arc::new(Mutex::new(reader)));
 
-                current_file.status = FileConversionStatus::Converting {
-                    total: total_points,
-                    remaining: total_points,
-                };
+                    current_file.status = FileConversionStatus::Converting {
+                        total: total_points,
+                        remaining: total_points,
+                        read: 0.0,
+                    };
 
-                commands.run_system(files_to_convert.read_batch);
+                    commands.run_system(files_to_convert.read_batch);
 
-                break;
-            } else {
-                let msg = "File type not supported";
-                let error = std::io::Error::new(std::io::ErrorKind::Unsupported, msg);
+                    break;
+                }
+                Err(error) => {
+                    log::error!("Skipping {:?}: {}", current_file.path, error);
 
-                current_file.status = FileConversionStatus::Failed {
-                    error,
-                    total: 0,
-                    remaining: 0,
-                };
+                    current_file.status = FileConversionStatus::Failed {
+                        error,
+                        total: 0,
+                        remaining: 0,
+                    };
+                }
             }
         } else {
             point_reader.0 = None;
@@ -170,7 +193,7 @@ fn next_file(
 fn read_batch(
     mut commands: Commands,
     point_reader: Res<PointReader>,
-    thread_pool: Res<ThreadPool>,
+    thread_pool: ThreadPoolRes,
     mut point_batch_receiver: ResMut<PointBatchReceiver>,
     active_metadata: ActiveMetadata,
     mut files_to_convert: ResMut<FilesToConvert>,
@@ -196,9 +219,44 @@ fn read_batch(
     point_batch_receiver.0 = Some(receiver);
 
     let batch_size = settings.batch_size;
+    let default_color = settings.default_color;
+    let channel_order = settings.channel_order;
 
     thread_pool.execute(move || {
-        let result = reader.lock().get_batch(batch_size).map(|points| {
+        let mut reader = reader.lock();
+        let has_colors = reader.has_colors();
+
+        let result = reader.get_batch(batch_size).map(|mut points| {
+            let swap_channels = has_colors && channel_order != ChannelOrder::Rgb;
+
+            if !has_colors {
+                let color = Color::from_array(default_color);
+                points.iter_mut().for_each(|point| point.color = color);
+            } else if swap_channels {
+                points
+                    .iter_mut()
+                    .for_each(|point| point.color = channel_order.to_rgb(point.color));
+            }
+
+            let sampled_colors = if has_colors {
+                let step = (points.len() / PointBatch::SAMPLED_COLORS).max(1);
+                points
+                    .iter()
+                    .step_by(step)
+                    .take(PointBatch::SAMPLED_COLORS)
+                    .map(|point| point.color)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            let mut attributes = PointAttributes::NONE;
+
+            for point in &mut points {
+                point.retain_attributes(PointAttributes::DEFAULT_CONVERTED);
+                attributes |= PointAttributes::of(point);
+            }
+
             let aabb = Aabb::from(points.iter().map(|point| point.pos)).unwrap();
             let grouped_points = group_points(points, 0, &config);
 
@@ -214,7 +272,15 @@ fn read_batch(
                 })
                 .collect();
 
-            PointBatch { aabb, tasks }
+            PointBatch {
+                aabb,
+                default_color: (!has_colors).then_some(default_color),
+                channel_order: swap_channels.then_some(channel_order),
+                sampled_colors,
+                attributes,
+                reader_progress: reader.progress(),
+                tasks,
+            }
         });
 
         sender.send(result).unwrap();
@@ -239,9 +305,23 @@ fn check_if_tasks_are_finished(
 #[derive(Debug)]
 struct PointBatch {
     aabb: Aabb,
+    /// Color given to the points if the file has no colors.
+    default_color: Option<[u8; 4]>,
+    /// Channel order which the colors were swapped from.
+    channel_order: Option<ChannelOrder>,
+    /// Evenly spaced colors of the batch, empty if the file has no colors.
+    sampled_colors: Vec<Color>,
+    /// Attributes which at least one of the points has.
+    attributes: PointAttributes,
+    /// See [BatchedPointReader::progress].
+    reader_progress: f64,
     tasks: Vec<CellTask>,
 }
 
+impl PointBatch {
+    const SAMPLED_COLORS: usize = 16;
+}
+
 #[derive(Debug)]
 struct CellTask {
     id: CellId,
@@ -249,7 +329,7 @@ struct CellTask {
 }
 
 #[derive(Resource)]
-struct PointBatchReceiver(Option<Receiver<Result<PointBatch, std::io::Error>>>);
+struct PointBatchReceiver(Option<Receiver<Result<PointBatch, ConverterError>>>);
 
 #[derive(Debug, Resource)]
 struct Tasks {
@@ -283,6 +363,30 @@ fn receive_tasks(
         Ok(result) => match result {
             Ok(point_batch) => {
                 update_metadata.send(UpdateMetadataEvent::ExtendBoundingBox(point_batch.aabb));
+
+                if let Some(color) = point_batch.default_color {
+                    update_metadata.send(UpdateMetadataEvent::DefaultColor(color));
+                }
+
+                if let Some(channel_order) = point_batch.channel_order {
+                    update_metadata.send(UpdateMetadataEvent::ChannelOrder(channel_order));
+                }
+
+                if !point_batch.sampled_colors.is_empty() {
+                    files_to_convert.sampled_colors = point_batch.sampled_colors;
+                }
+
+                if !point_batch.attributes.is_empty() {
+                    update_metadata
+                        .send(UpdateMetadataEvent::AddAttributes(point_batch.attributes));
+                }
+
+                if let FileConversionStatus::Converting { read, .. } =
+                    &mut files_to_convert.current_mut().status
+                {
+                    *read = point_batch.reader_progress;
+                }
+
                 tasks.new_tasks.extend(point_batch.tasks);
             }
             Err(error) => {
@@ -291,7 +395,9 @@ fn receive_tasks(
                 let file_to_convert = files_to_convert.current_mut();
 
                 match file_to_convert.status {
-                    FileConversionStatus::Converting { total, remaining } => {
+                    FileConversionStatus::Converting {
+                        total, remaining, ..
+                    } => {
                         file_to_convert.status = FileConversionStatus::Failed {
                             error,
                             total,
@@ -342,9 +448,117 @@ fn handle_added_points_for_converting_file(
 fn save(
     mut metadata_manager: AssetManagerResMut<Metadata>,
     mut cell_manager: AssetManagerResMut<Cell>,
+    loaded_metadata: Res<LoadedMetadata>,
+    point_cloud_query: Query<&PointCloud>,
+    io_thread_pool: IoThreadPoolRes,
 ) {
-    metadata_manager.save_all();
+    metadata_manager.save_all_in_background(&io_thread_pool);
     cell_manager.save_all();
+
+    let active_metadata = &point_cloud_query
+        .get(loaded_metadata.get_active())
+        .unwrap()
+        .metadata;
+
+    if let Source::Path(path) = metadata_manager.get_asset_source(active_metadata) {
+        let working_directory = path.parent().unwrap();
+        let hierarchies = metadata_manager.get_asset(active_metadata).hierarchies;
+
+        let result = CellIndex::from_directory(working_directory, hierarchies)
+            .and_then(|index| index.save(working_directory.join(CellIndex::path())));
+
+        if let Err(err) = result {
+            log::error!("Failed to save cell index: {}", err);
+        }
+    }
+}
+
+fn log_saved_metadata(mut events: EventReader<AssetSavedEvent<Metadata>>) {
+    for event in events.read() {
+        if event.result.is_ok() {
+            log::info!("Saved metadata {}", event.id);
+        }
+    }
+}
+
+/// Writes the active point cloud into another directory, leaving its sources and auto save as they are.
+/// Cells which are in memory are saved from there,
+/// the others are copied from the directory the point cloud is saved in.
+fn export_copy(world: &mut World, folder: &Path) {
+    let mut params = SystemState::<(
+        Res<CellCache>,
+        Res<LoadedMetadata>,
+        Query<&PointCloud>,
+        AssetManagerRes<Metadata>,
+        AssetManagerRes<Cell>,
+    )>::new(world);
+    let (cell_cache, loaded_metadata, point_cloud_query, metadata_manager, cell_manager) =
+        params.get(world);
+
+    let active_metadata = &point_cloud_query
+        .get(loaded_metadata.get_active())
+        .unwrap()
+        .metadata;
+
+    let source = Source::Path(
+        folder
+            .join(Metadata::FILE_NAME)
+            .with_extension(Metadata::EXTENSION),
+    );
+
+    if let Err(err) = metadata_manager.save_copy(active_metadata, source) {
+        log::error!("Failed to export metadata to {:?}: {}", folder, err);
+        return;
+    }
+
+    let metadata = metadata_manager.get_asset(active_metadata);
+
+    for (id, handle) in cell_cache.iter() {
+        let source = Source::Path(folder.join(id.path(&metadata.cell_extension)));
+
+        if let Err(err) = cell_manager.save_copy(handle, source) {
+            log::error!("Failed to export cell {:?}: {}", id, err);
+        }
+    }
+
+    let hierarchies = metadata.hierarchies;
+
+    // cells which were written to disk by auto save aren't in the cache anymore
+    if let Source::Path(path) = metadata_manager.get_asset_source(active_metadata) {
+        let working_directory = path.parent().unwrap();
+
+        for hierarchy in 0..hierarchies {
+            let hierarchy_string = Metadata::hierarchy_string(hierarchy);
+
+            let Ok(dir_entries) = working_directory.join(&hierarchy_string).read_dir() else {
+                continue;
+            };
+
+            for dir_entry in dir_entries.flatten() {
+                let from = dir_entry.path();
+                let to = folder.join(&hierarchy_string).join(dir_entry.file_name());
+
+                if to.exists() || !Cell::has_extension(&from) {
+                    continue;
+                }
+
+                let result = std::fs::create_dir_all(to.parent().unwrap())
+                    .and_then(|_| std::fs::copy(&from, &to));
+
+                if let Err(err) = result {
+                    log::error!("Failed to copy cell {:?} to {:?}: {}", from, to, err);
+                }
+            }
+        }
+    }
+
+    let result = CellIndex::from_directory(folder, hierarchies)
+        .and_then(|index| index.save(folder.join(CellIndex::path())));
+
+    match result {
+        Ok(_) => log::info!("Exported a copy to {:?}", folder),
+        Err(err) => log::error!("Failed to save cell index of the copy: {}", err),
+    }
 }
 
 fn clear_cache(mut cell_cache: ResMut<CellCache>) {
@@ -370,6 +584,7 @@ fn get_handles_for_new_tasks(
     cell_manager: AssetManagerRes<Cell>,
 ) {
     let working_directory = active_metadata.get_working_directory();
+    let cell_extension = &active_metadata.get().cell_extension;
     let free_loading_spots = 10usize.saturating_sub(tasks.tasks_with_loading_handle.len());
     let mut i = 0;
 
@@ -387,10 +602,13 @@ fn get_handles_for_new_tasks(
                 cell_manager
                     .load_sender()
                     .send(LoadAssetMsg {
-                        id: cell_task.id,
-                        source: working_directory
-                            .as_ref()
-                            .map_or(Source::None, |dir| dir.join(&cell_task.id.path())),
+                        id: CloudCellId {
+                            point_cloud: active_metadata.entity(),
+                            cell: cell_task.id,
+                        },
+                        source: working_directory.as_ref().map_or(Source::None, |dir| {
+                            dir.join(&cell_task.id.logical_path(cell_extension))
+                        }),
                         reply_sender: Some(sender),
                     })
                     .unwrap();
@@ -420,7 +638,7 @@ fn get_handles_for_loading_tasks(
             match receiver.try_recv() {
                 Ok(loaded_asset_event) => match loaded_asset_event {
                     AssetLoadedEvent::Success { handle } => {
-                        cell_cache.insert(*handle.id(), handle.clone());
+                        cell_cache.insert(handle.id().cell, handle.clone());
                         tasks.tasks_with_handle.push_back((cell_task, handle));
                     }
                     AssetLoadedEvent::Error { id, error } => {
@@ -437,24 +655,27 @@ fn get_handles_for_loading_tasks(
 
                         log::debug!("Creating new cell {:?}", id);
 
-                        update_metadata.send(UpdateMetadataEvent::IncreaseHierarchy(id.hierarchy));
+                        let CloudCellId { cell: cell_id, .. } = id;
+
+                        update_metadata
+                            .send(UpdateMetadataEvent::IncreaseHierarchy(cell_id.hierarchy));
 
-                        let cell_size = metadata.config.cell_size(id.hierarchy);
-                        let cell_pos = metadata.config.cell_pos(id.index, cell_size);
+                        let cell_size = metadata.config.cell_size(cell_id.hierarchy);
+                        let cell_pos = metadata.config.cell_pos(cell_id.index, cell_size);
                         let cell = Cell::new(
-                            id,
+                            cell_id,
                             metadata.config.sub_cell_size(cell_size),
                             cell_size,
                             cell_pos,
-                            10_000,
+                            metadata.config.cell_capacity(),
                         );
 
-                        let source = working_directory
-                            .as_ref()
-                            .map_or(Source::None, |dir| dir.join(&id.path()));
+                        let source = working_directory.as_ref().map_or(Source::None, |dir| {
+                            dir.join(&cell_id.logical_path(&metadata.cell_extension))
+                        });
 
                         let handle = cell_manager.insert(id, cell, source, true);
-                        cell_cache.insert(id, handle.clone());
+                        cell_cache.insert(cell_id, handle.clone());
                         tasks.tasks_with_handle.push_back((cell_task, handle));
                     }
                 },
@@ -555,8 +776,10 @@ fn add_points_to_cell_system(
     mut tasks: ResMut<Tasks>,
     active_metadata: ActiveMetadata,
     mut update_metadata: EventWriter<UpdateMetadataEvent>,
+    settings: Res<Settings>,
 ) {
     let metadata = active_metadata.get();
+    let mut total_added_points = 0;
 
     for _ in 0..tasks.tasks_with_handle.len().min(10) {
         if let Some((cell_task, handle)) = tasks.tasks_with_handle.pop_front() {
@@ -570,16 +793,26 @@ fn add_points_to_cell_system(
                 let points_after = cell.header().total_number_of_points;
 
                 let added_points = points_after as i32 - points_before as i32;
-                update_metadata.send(UpdateMetadataEvent::NumberOfPoints(added_points));
+
+                if settings.progress_per_cell {
+                    update_metadata.send(UpdateMetadataEvent::NumberOfPoints(added_points));
+                } else {
+                    total_added_points += added_points;
+                }
 
                 log::debug!("Added {} points to cell {:?}", added_points, handle.id());
 
                 remaining_points
             };
 
+            if handle.id().cell.hierarchy + 1 >= Metadata::MAX_HIERARCHIES {
+                drop_coincident_points(remaining_points);
+                continue;
+            }
+
             for (cell_index, points) in remaining_points {
                 let id = CellId {
-                    hierarchy: handle.id().hierarchy + 1,
+                    hierarchy: handle.id().cell.hierarchy + 1,
                     index: cell_index,
                 };
 
@@ -589,12 +822,27 @@ fn add_points_to_cell_system(
             }
         }
     }
+
+    if total_added_points != 0 {
+        update_metadata.send(UpdateMetadataEvent::NumberOfPoints(total_added_points));
+    }
 }
 
 #[derive(Debug, Resource)]
 struct Settings {
     auto_save: bool,
     batch_size: usize,
+    /// RGBA color of points from files without colors.
+    default_color: [u8; 4],
+    /// Order of the color channels in the files, see [ChannelOrder::to_rgb].
+    channel_order: ChannelOrder,
+    /// Config of the next new point cloud.
+    preset: MetadataConfigPreset,
+    /// Overrides the point reduction of the preset.
+    point_reduction: PointReduction,
+    /// Sends the added points of every cell as its own event instead of one per frame,
+    /// so that the remaining points of huge batches count down smoothly.
+    progress_per_cell: bool,
 }
 
 impl Default for Settings {
@@ -602,23 +850,75 @@ impl Default for Settings {
         Self {
             auto_save: false,
             batch_size: 50_000,
+            default_color: Color::default().to_array(),
+            channel_order: ChannelOrder::default(),
+            preset: MetadataConfigPreset::default(),
+            point_reduction: MetadataConfigPreset::default().config().point_reduction,
+            progress_per_cell: false,
         }
     }
 }
 
+pub fn is_converting(world: &World) -> bool {
+    match world
+        .get_resource::<State<ConversionState>>()
+        .unwrap()
+        .get()
+    {
+        ConversionState::NotStarted | ConversionState::Finished => false,
+        ConversionState::Converting => true,
+    }
+}
+
 pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
     let conversion_state = *world
         .get_resource::<State<ConversionState>>()
         .unwrap()
         .get();
 
-    let is_converting = match conversion_state {
-        ConversionState::NotStarted | ConversionState::Finished => false,
-        ConversionState::Converting => true,
-    };
+    let is_converting = is_converting(world);
 
     let new_point_cloud_button = egui::Button::new("New point cloud");
 
+    let config = {
+        let mut settings = world.resource_mut::<Settings>();
+        let mut preset = settings.preset;
+
+        egui::ComboBox::from_label("Preset")
+            .selected_text(preset.name())
+            .show_ui(ui, |ui| {
+                for option in MetadataConfigPreset::ALL {
+                    ui.selectable_value(&mut preset, option, option.name());
+                }
+            });
+
+        if preset != settings.preset {
+            settings.preset = preset;
+            settings.point_reduction = preset.config().point_reduction;
+        }
+
+        let mut point_reduction = settings.point_reduction;
+
+        egui::ComboBox::from_label("Point reduction")
+            .selected_text(format!("{:?}", point_reduction))
+            .show_ui(ui, |ui| {
+                for option in PointReduction::ALL {
+                    ui.selectable_value(&mut point_reduction, option, format!("{:?}", option));
+                }
+            })
+            .response
+            .on_hover_text("Which point a sub grid cell keeps if several points fall into it");
+
+        if point_reduction != settings.point_reduction {
+            settings.point_reduction = point_reduction;
+        }
+
+        MetadataConfig {
+            point_reduction,
+            ..preset.config()
+        }
+    };
+
     if ui
         .add_enabled(!is_converting, new_point_cloud_button)
         .clicked()
@@ -631,7 +931,10 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
         let (mut next_metadata_state, mut metadata_manager) = params.get_mut(world);
         let _ = metadata_manager.insert(
             "Unknown".to_string(),
-            Metadata::default(),
+            Metadata {
+                config,
+                ..Metadata::default()
+            },
             Source::None,
             true,
         );
@@ -643,8 +946,10 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
             ResMut<Settings>,
             ResMut<CellCache>,
             Res<LoadedMetadata>,
+            Query<&PointCloud>,
             AssetManagerResMut<Metadata>,
             AssetManagerResMut<Cell>,
+            IoThreadPoolRes,
         )>::new(world);
 
         if ui.button("Save at...").clicked() {
@@ -659,22 +964,34 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
                     mut settings,
                     mut cell_cache,
                     loaded_metadata,
+                    point_cloud_query,
                     mut metadata_manager,
                     mut cell_manager,
+                    io_thread_pool,
                 ) = params.get_mut(world);
 
+                let active_metadata = &point_cloud_query
+                    .get(loaded_metadata.get_active())
+                    .unwrap()
+                    .metadata;
+
                 let source = Source::Path(
                     folder
                         .join(Metadata::FILE_NAME)
                         .with_extension(Metadata::EXTENSION),
                 );
 
-                metadata_manager.set_source(loaded_metadata.get_active(), source);
+                metadata_manager.set_source(active_metadata, source);
                 metadata_manager.set_auto_save(true);
-                metadata_manager.save_all();
+                metadata_manager.save_all_in_background(&io_thread_pool);
+
+                let cell_extension = metadata_manager
+                    .get_asset(active_metadata)
+                    .cell_extension
+                    .clone();
 
                 for (id, handle) in cell_cache.iter() {
-                    let source = Source::Path(folder.join(id.path()));
+                    let source = Source::Path(folder.join(id.path(&cell_extension)));
                     cell_manager.set_source(handle, source);
                 }
 
@@ -686,8 +1003,38 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
             }
         }
 
-        let (mut settings, mut cell_cache, loaded_metadata, mut metadata_manager, mut cell_manager) =
-            params.get_mut(world);
+        if ui
+            .button("Export copy to...")
+            .on_hover_text("Saves a copy without changing where the point cloud is saved")
+            .clicked()
+        {
+            let window: &winit::window::Window = world
+                .get_resource::<crate::plugins::winit::Window>()
+                .unwrap();
+
+            let folder = rfd::FileDialog::new().set_parent(window).pick_folder();
+
+            if let Some(folder) = folder {
+                export_copy(world, &folder);
+            }
+        }
+
+        let (
+            mut settings,
+            mut cell_cache,
+            loaded_metadata,
+            point_cloud_query,
+            mut metadata_manager,
+            mut cell_manager,
+            _,
+        ) = params.get_mut(world);
+
+        if metadata_manager.pending_saves() > 0 {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Saving metadata...");
+            });
+        }
 
         let mut batch_size = settings.batch_size;
         let batch_size_slider = egui::Slider::new(&mut batch_size, 10_000..=500_000)
@@ -698,13 +1045,59 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
             settings.batch_size = batch_size;
         }
 
+        ui.horizontal(|ui| {
+            let mut default_color = settings.default_color;
+            ui.label("Default color")
+                .on_hover_text("Color of points from files without colors");
+
+            if ui
+                .color_edit_button_srgba_unmultiplied(&mut default_color)
+                .changed()
+            {
+                settings.default_color = default_color;
+            }
+        });
+
+        let mut channel_order = settings.channel_order;
+
+        egui::ComboBox::from_label("Channel order")
+            .selected_text(format!("{:?}", channel_order))
+            .show_ui(ui, |ui| {
+                for option in ChannelOrder::ALL {
+                    ui.selectable_value(&mut channel_order, option, format!("{:?}", option));
+                }
+            })
+            .response
+            .on_hover_text(
+                "Order of the color channels in the files, Bgr fixes blue tinted colors",
+            );
+
+        if channel_order != settings.channel_order {
+            settings.channel_order = channel_order;
+        }
+
+        let mut progress_per_cell = settings.progress_per_cell;
+        if ui
+            .checkbox(&mut progress_per_cell, "Progress per cell")
+            .on_hover_text(
+                "Updates the remaining points after every cell instead of once per frame",
+            )
+            .changed()
+        {
+            settings.progress_per_cell = progress_per_cell;
+        }
+
         let mut auto_save = settings.auto_save;
         let checkbox = egui::Checkbox::new(&mut auto_save, "Auto save");
 
-        let metadata_source = metadata_manager.get_asset_source(loaded_metadata.get_active());
+        let active_metadata = &point_cloud_query
+            .get(loaded_metadata.get_active())
+            .unwrap()
+            .metadata;
+        let metadata_source = metadata_manager.get_asset_source(active_metadata);
         let auto_save_enabled = match metadata_source {
             Source::Path(_) => true,
-            Source::URL(_) | Source::None => false,
+            Source::ArchiveEntry { .. } | Source::URL(_) | Source::None => false,
         };
 
         if ui.add_enabled(auto_save_enabled, checkbox).changed() {
@@ -756,6 +1149,37 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
         }
     }
 
+    if let Some(estimate) = world.get_resource::<FilesToConvert>().unwrap().estimate {
+        ui.label(format!(
+            "Estimated output: ~{} in ~{} cells",
+            format_bytes(estimate.bytes),
+            estimate.number_of_cells.separate_with_commas()
+        ))
+        .on_hover_text(format!(
+            "Rough estimate for {} points, the actual size depends on how the points are distributed",
+            estimate.number_of_points.separate_with_commas()
+        ));
+    }
+
+    let sampled_colors = &world.resource::<FilesToConvert>().sampled_colors;
+
+    if !sampled_colors.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Sampled colors").on_hover_text(
+                "Colors of a few converted points, which look off if the channel order is wrong",
+            );
+
+            for color in sampled_colors {
+                let [r, g, b, a] = color.to_array();
+                egui::color_picker::show_color(
+                    ui,
+                    egui::Color32::from_rgba_unmultiplied(r, g, b, a),
+                    egui::Vec2::splat(ui.spacing().interact_size.y),
+                );
+            }
+        });
+    }
+
     ui.collapsing("Files to convert", |ui| {
         list_files(ui, world);
     });
@@ -767,21 +1191,27 @@ fn select_files(world: &mut World) {
             .get_resource::<crate::plugins::winit::Window>()
             .unwrap();
 
-        // TODO supported endings from variables
         rfd::FileDialog::new()
-            .add_filter("points", &["las", "laz", "ply", Metadata::EXTENSION])
+            .add_filter("points", &point_converter::SUPPORTED_EXTENSIONS)
             .set_parent(window)
             .pick_files()
     };
 
     if let Some(files) = files {
-        let mut params =
-            SystemState::<(ResMut<FilesToConvert>, ResMut<NextState<ConversionState>>)>::new(world);
-        let (mut files_to_convert, mut next_conversion_state) = params.get_mut(world);
+        let mut params = SystemState::<(
+            ResMut<FilesToConvert>,
+            ResMut<NextState<ConversionState>>,
+            ActiveMetadata,
+        )>::new(world);
+        let (mut files_to_convert, mut next_conversion_state, active_metadata) =
+            params.get_mut(world);
 
         next_conversion_state.set(ConversionState::NotStarted);
 
         files_to_convert.current = 0;
+        files_to_convert.sampled_colors.clear();
+        files_to_convert.estimate =
+            Some(estimate_output_size(&files, &active_metadata.get().config));
 
         files_to_convert.files = files
             .into_iter()
@@ -817,11 +1247,16 @@ fn list_files(ui: &mut egui::Ui, world: &mut World) {
                     FileConversionStatus::NotStarted => {
                         ui.label(format!("⌛\u{00A0}{}", file_name));
                     }
-                    FileConversionStatus::Converting { remaining, total } => {
+                    FileConversionStatus::Converting {
+                        remaining,
+                        total,
+                        read,
+                    } => {
                         ui.label(format!(
-                            "⏳\u{00A0}{}\nRemaining: {}",
+                            "⏳\u{00A0}{}\nRemaining: {}, read: {:.0}%",
                             file_name,
-                            remaining.separate_with_commas()
+                            remaining.separate_with_commas(),
+                            read * 100.0
                         ))
                         .on_hover_text(format!("Total points: {}", total.separate_with_commas()));
                     }
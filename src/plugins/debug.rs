@@ -1,15 +1,21 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
-use bevy_ecs::system::SystemId;
+use bevy_ecs::query::QueryData;
+use bevy_ecs::system::{SystemId, SystemState};
 use bevy_state::prelude::*;
+use bounding_volume::Aabb;
 use glam::Vec3;
+use point_converter::cell::Cell;
 
+use crate::plugins::asset::{AssetHandle, AssetManagerRes};
 use crate::plugins::camera::frustum::Frustum;
-use crate::plugins::camera::{Camera, Visibility};
+use crate::plugins::camera::Camera;
 use crate::plugins::cell::frustums::StreamingFrustums;
+use crate::plugins::cell::region::LoadedPoints;
 use crate::plugins::cell::{CellHeader, CellStreamingSet};
-use crate::plugins::metadata::{ActiveMetadata, MetadataState};
-use crate::plugins::render::line::utils::{line_box, line_strip};
+use crate::plugins::input::{HotkeyAction, HotkeyEvent};
+use crate::plugins::metadata::{ActiveMetadata, MetadataState, PointCloud};
+use crate::plugins::render::line::utils::{line_box, line_strip, transform_lines};
 use crate::plugins::render::line::Line;
 use crate::plugins::render::vertex::VertexBuffer;
 use crate::plugins::wgpu::Device;
@@ -22,16 +28,16 @@ impl Plugin for DebugPlugin {
             let toggle_frustum = app.world_mut().register_system(toggle_frustum);
             let toggle_bounding_box = app.world_mut().register_system(toggle_bounding_box);
             let toggle_grid = app.world_mut().register_system(toggle_grid);
+            let toggle_point_bounds = app.world_mut().register_system(toggle_point_bounds);
             let toggle_streaming_frustums =
                 app.world_mut().register_system(toggle_streaming_frustums);
-            let toggle_hierarchy = app.world_mut().register_system(toggle_hierarchy);
 
             app.insert_resource(OneShotSystems {
                 toggle_frustum,
                 toggle_bounding_box,
                 toggle_grid,
+                toggle_point_bounds,
                 toggle_streaming_frustums,
-                toggle_hierarchy,
             });
         }
 
@@ -41,20 +47,24 @@ impl Plugin for DebugPlugin {
             grid: GridSettings {
                 show: false,
                 hierarchies: Vec::new(),
+                point_bounds: false,
             },
             streaming_frustums_visibility: StreamingFrustumsVisibility {
                 show: false,
                 hierarchies: Vec::new(),
             },
-            hierarchy_visibility: HierarchyVisibility {
-                show_all: true,
-                hierarchies: Vec::new(),
+            region: RegionSettings {
+                aabb: Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0)),
+                number_of_points: None,
             },
         });
 
         app.add_systems(
             Update,
-            (add_grid_for_new_cells, set_visibility_for_new_cells)
+            (
+                (add_point_bounds_for_new_cells, add_grid_for_new_cells).chain(),
+                update_lines_of_moved_point_clouds,
+            )
                 .after(CellStreamingSet)
                 .run_if(in_state(MetadataState::Loaded)),
         )
@@ -72,7 +82,8 @@ impl Plugin for DebugPlugin {
             (|| false)
                 .pipe(toggle_bounding_box)
                 .run_if(|state: Res<State>| state.show_bounding_box),
-        );
+        )
+        .add_systems(Update, toggle_bounding_box_by_hotkey);
     }
 }
 
@@ -81,8 +92,8 @@ struct OneShotSystems {
     toggle_frustum: SystemId<bool>,
     toggle_bounding_box: SystemId<bool>,
     toggle_grid: SystemId<(bool, u32)>,
+    toggle_point_bounds: SystemId<bool>,
     toggle_streaming_frustums: SystemId<(bool, u32)>,
-    toggle_hierarchy: SystemId<(bool, u32)>,
 }
 
 #[derive(Resource)]
@@ -91,17 +102,60 @@ struct State {
     show_bounding_box: bool,
     grid: GridSettings,
     streaming_frustums_visibility: StreamingFrustumsVisibility,
-    hierarchy_visibility: HierarchyVisibility,
+    region: RegionSettings,
 }
 
 struct GridSettings {
     show: bool,
     hierarchies: Vec<bool>,
+    /// Draws the [CellPointBounds] instead of the nominal cell boxes.
+    point_bounds: bool,
 }
 
-struct HierarchyVisibility {
-    show_all: bool,
-    hierarchies: Vec<bool>,
+/// Bounds of the points of a cell in the space of its point cloud,
+/// which can be much smaller than the nominal cell box.
+/// Only kept while [GridSettings::point_bounds] is enabled, so that the cells don't have to be
+/// read again to know them.
+#[derive(Component)]
+pub struct CellPointBounds(pub Aabb);
+
+impl CellPointBounds {
+    fn new(cell: &Cell) -> Option<Self> {
+        Aabb::from(cell.all_points().map(|point| point.pos)).map(Self)
+    }
+}
+
+/// Lines around a cell, which use its point bounds if it has them.
+fn grid_lines(
+    cell_header: &CellHeader,
+    point_bounds: Option<&CellPointBounds>,
+    point_cloud: &PointCloud,
+) -> Vec<Line> {
+    let hierarchy = cell_header.0.id.hierarchy;
+    let color = [
+        255,
+        if hierarchy.is_multiple_of(2) { 180 } else { 90 },
+        0,
+        255,
+    ];
+
+    let mut lines = match point_bounds {
+        Some(CellPointBounds(aabb)) => line_box(color, aabb.center(), aabb.extends()),
+        None => line_box(
+            color,
+            cell_header.0.pos,
+            Vec3::splat(cell_header.0.size / 2.0),
+        ),
+    };
+
+    transform_lines(&point_cloud.transform, &mut lines);
+    lines
+}
+
+struct RegionSettings {
+    aabb: Aabb,
+    /// Loaded points inside the region when they were last counted.
+    number_of_points: Option<usize>,
 }
 
 struct StreamingFrustumsVisibility {
@@ -213,11 +267,12 @@ fn toggle_bounding_box(
 ) {
     if *show {
         let aabb = active_metadata.get().bounding_box;
-        let lines = line_box(
+        let mut lines = line_box(
             [255, 0, 0, 255],
             (aabb.min + aabb.max) / 2.0,
             (aabb.max - aabb.min) / 2.0,
         );
+        transform_lines(&active_metadata.point_cloud().transform, &mut lines);
         commands.spawn((BoundingBoxLine, VertexBuffer::new(&device, &lines)));
     } else {
         for entity in bounding_box_query.iter() {
@@ -230,47 +285,77 @@ fn update_hierarchies(mut state: ResMut<State>, active_metadata: ActiveMetadata)
     let hierarchies = active_metadata.get().hierarchies as usize;
     state.grid.hierarchies = vec![true; hierarchies];
     state.streaming_frustums_visibility.hierarchies = vec![true; hierarchies];
-    state.hierarchy_visibility.hierarchies = vec![true; hierarchies];
+}
+
+fn add_point_bounds_for_new_cells(
+    mut commands: Commands,
+    cell_manager: AssetManagerRes<Cell>,
+    cell_query: Query<(Entity, &AssetHandle<Cell>), Added<CellHeader>>,
+    state: Res<State>,
+) {
+    if !state.grid.point_bounds {
+        return;
+    }
+
+    for (entity, handle) in cell_query.iter() {
+        if let Some(point_bounds) = CellPointBounds::new(cell_manager.get_asset(handle)) {
+            commands.entity(entity).insert(point_bounds);
+        }
+    }
+}
+
+fn toggle_point_bounds(
+    show: In<bool>,
+    mut commands: Commands,
+    cell_manager: AssetManagerRes<Cell>,
+    cell_query: Query<(Entity, &AssetHandle<Cell>), With<CellHeader>>,
+) {
+    for (entity, handle) in cell_query.iter() {
+        if !*show {
+            commands.entity(entity).remove::<CellPointBounds>();
+        } else if let Some(point_bounds) = CellPointBounds::new(cell_manager.get_asset(handle)) {
+            commands.entity(entity).insert(point_bounds);
+        }
+    }
+}
+
+#[derive(QueryData)]
+struct CellGridQueryData {
+    entity: Entity,
+    header: &'static CellHeader,
+    point_bounds: Option<&'static CellPointBounds>,
+    handle: &'static AssetHandle<Cell>,
 }
 
 fn add_grid_for_new_cells(
     mut commands: Commands,
     device: Res<Device>,
-    cell_query: Query<(Entity, &CellHeader), Added<CellHeader>>,
+    cell_query: Query<CellGridQueryData, Added<CellHeader>>,
+    point_cloud_query: Query<&PointCloud>,
     state: Res<State>,
 ) {
     if !state.grid.show {
         return;
     }
 
-    for (entity, cell_header) in cell_query.iter() {
+    for cell in cell_query.iter() {
+        let Ok(point_cloud) = point_cloud_query.get(cell.handle.id().point_cloud) else {
+            continue;
+        };
+
         if !state
             .grid
             .hierarchies
-            .get(cell_header.0.id.hierarchy as usize)
+            .get(cell.header.0.id.hierarchy as usize)
             .copied()
             .unwrap_or(false)
         {
             continue;
         }
 
-        let lines = line_box(
-            [
-                255,
-                if cell_header.0.id.hierarchy % 2 == 0 {
-                    180
-                } else {
-                    90
-                },
-                0,
-                255,
-            ],
-            cell_header.0.pos,
-            Vec3::splat(cell_header.0.size / 2.0),
-        );
-
+        let lines = grid_lines(cell.header, cell.point_bounds, point_cloud);
         let buffer = VertexBuffer::new(&device, &lines);
-        commands.entity(entity).insert(buffer);
+        commands.entity(cell.entity).insert(buffer);
     }
 }
 
@@ -278,20 +363,20 @@ fn toggle_grid(
     In((show, hierarchy)): In<(bool, u32)>,
     mut commands: Commands,
     device: Res<Device>,
-    add_query: Query<(Entity, &CellHeader), Without<VertexBuffer<Line>>>,
+    add_query: Query<CellGridQueryData, Without<VertexBuffer<Line>>>,
     remove_query: Query<(Entity, &CellHeader), With<VertexBuffer<Line>>>,
+    point_cloud_query: Query<&PointCloud>,
 ) {
     if show {
-        for (entity, cell_header) in add_query.iter() {
-            if cell_header.0.id.hierarchy == hierarchy {
-                let lines = line_box(
-                    [255, if hierarchy % 2 == 0 { 180 } else { 90 }, 0, 255],
-                    cell_header.0.pos,
-                    Vec3::splat(cell_header.0.size / 2.0),
-                );
+        for cell in add_query.iter() {
+            let Ok(point_cloud) = point_cloud_query.get(cell.handle.id().point_cloud) else {
+                continue;
+            };
 
+            if cell.header.0.id.hierarchy == hierarchy {
+                let lines = grid_lines(cell.header, cell.point_bounds, point_cloud);
                 let buffer = VertexBuffer::new(&device, &lines);
-                commands.entity(entity).insert(buffer);
+                commands.entity(cell.entity).insert(buffer);
             }
         }
     } else {
@@ -303,6 +388,44 @@ fn toggle_grid(
     }
 }
 
+/// Rebuilds the grid and the bounding box of point clouds whose transform was edited.
+fn update_lines_of_moved_point_clouds(
+    mut commands: Commands,
+    device: Res<Device>,
+    state: Res<State>,
+    active_metadata: ActiveMetadata,
+    changed_point_cloud_query: Query<&PointCloud, Changed<PointCloud>>,
+    grid_query: Query<CellGridQueryData, With<VertexBuffer<Line>>>,
+    bounding_box_query: Query<Entity, With<BoundingBoxLine>>,
+) {
+    if changed_point_cloud_query.is_empty() {
+        return;
+    }
+
+    for cell in grid_query.iter() {
+        let Ok(point_cloud) = changed_point_cloud_query.get(cell.handle.id().point_cloud) else {
+            continue;
+        };
+
+        let lines = grid_lines(cell.header, cell.point_bounds, point_cloud);
+
+        commands
+            .entity(cell.entity)
+            .insert(VertexBuffer::new(&device, &lines));
+    }
+
+    if state.show_bounding_box && changed_point_cloud_query.contains(active_metadata.entity()) {
+        for entity in bounding_box_query.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        let aabb = active_metadata.get().bounding_box;
+        let mut lines = line_box([255, 0, 0, 255], aabb.center(), aabb.extends());
+        transform_lines(&active_metadata.point_cloud().transform, &mut lines);
+        commands.spawn((BoundingBoxLine, VertexBuffer::new(&device, &lines)));
+    }
+}
+
 #[derive(Component)]
 struct StreamingFrustumLine(u32);
 
@@ -310,11 +433,12 @@ fn toggle_streaming_frustums(
     In((show, hierarchy)): In<(bool, u32)>,
     mut commands: Commands,
     device: Res<Device>,
-    add_query: Query<&StreamingFrustums, With<Camera>>,
+    active_metadata: ActiveMetadata,
+    streaming_frustums_query: Query<&StreamingFrustums>,
     remove_query: Query<(Entity, &StreamingFrustumLine)>,
 ) {
     if show {
-        for streaming_frustums in add_query.iter() {
+        if let Ok(streaming_frustums) = streaming_frustums_query.get(active_metadata.entity()) {
             let streaming_frustum = &streaming_frustums[hierarchy as usize];
             let far_corners = &streaming_frustum.far;
 
@@ -341,37 +465,30 @@ fn toggle_streaming_frustums(
     }
 }
 
-fn set_visibility_for_new_cells(
-    state: Res<State>,
-    mut cell_query: Query<(&CellHeader, &mut Visibility), Added<CellHeader>>,
-) {
-    if state.hierarchy_visibility.show_all {
-        return;
-    }
-
-    for (cell_header, mut visibility) in cell_query.iter_mut() {
-        visibility.visible = state
-            .hierarchy_visibility
-            .hierarchies
-            .get(cell_header.0.id.hierarchy as usize)
-            .copied()
-            .unwrap_or(true);
-    }
-}
-
-fn toggle_hierarchy(
-    In((show, hierarchy)): In<(bool, u32)>,
-    mut cell_query: Query<(&CellHeader, &mut Visibility)>,
+fn toggle_bounding_box_by_hotkey(
+    mut commands: Commands,
+    mut hotkey_events: EventReader<HotkeyEvent>,
+    mut state: ResMut<State>,
+    one_shot_systems: Res<OneShotSystems>,
 ) {
-    for (cell_header, mut visibility) in cell_query.iter_mut() {
-        if cell_header.0.id.hierarchy == hierarchy {
-            visibility.visible = show;
+    for event in hotkey_events.read() {
+        if event.0 == HotkeyAction::ToggleBoundingBox {
+            state.show_bounding_box = !state.show_bounding_box;
+            commands.run_system_with_input(
+                one_shot_systems.toggle_bounding_box,
+                state.show_bounding_box,
+            );
         }
     }
 }
 
 pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
     ui.checkbox(&mut world.get_resource_mut::<crate::plugins::render::point::pipelines::render::PointRenderPipeline>().unwrap().use_voronoi, "Voronoi").changed();
+    ui.checkbox(&mut world.get_resource_mut::<crate::plugins::render::point::pipelines::render::PointRenderPipeline>().unwrap().use_transparency, "Transparency").changed();
+    ui.checkbox(&mut world.get_resource_mut::<crate::plugins::render::point::pipelines::render::PointRenderPipeline>().unwrap().use_impostors, "Spheres").on_hover_text("Shades every point as a sphere").changed();
+    crate::plugins::render::point::soft_points::draw_ui(ui, world);
+    draw_ui_point_filter(ui, world);
+    crate::plugins::render::point::drawn_points::draw_ui(ui, world);
 
     world.resource_scope(|world, mut state: Mut<State>| {
         if ui.checkbox(&mut state.show_frustum, "Frustum").changed() {
@@ -401,7 +518,60 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
 
         draw_ui_grid(ui, world, &mut state);
         draw_ui_streaming_frustums(ui, world, &mut state);
-        draw_ui_visible_hierarchies(ui, world, &mut state);
+        draw_ui_region(ui, world, &mut state);
+    });
+
+    draw_ui_frustum_values(ui, world);
+}
+
+fn draw_ui_point_filter(ui: &mut egui::Ui, world: &mut World) {
+    use crate::plugins::render::point::PointFilter;
+
+    let mut point_filter = world.get_resource_mut::<PointFilter>().unwrap();
+
+    let mut enabled = *point_filter == PointFilter::Enabled;
+    if ui
+        .checkbox(&mut enabled, "Filter points")
+        .on_hover_text("Disable to draw all uploaded points without the compute pass")
+        .changed()
+    {
+        *point_filter = if enabled {
+            PointFilter::Enabled
+        } else {
+            PointFilter::Disabled
+        };
+    }
+}
+
+fn draw_ui_region(ui: &mut egui::Ui, world: &mut World, state: &mut State) {
+    ui.collapsing("Loaded points in region", |ui| {
+        let region = &mut state.region;
+
+        egui::Grid::new("region_grid")
+            .num_columns(4)
+            .show(ui, |ui| {
+                for (label, corner) in
+                    [("Min", &mut region.aabb.min), ("Max", &mut region.aabb.max)]
+                {
+                    ui.label(label);
+                    ui.add(egui::DragValue::new(&mut corner.x).speed(0.1));
+                    ui.add(egui::DragValue::new(&mut corner.y).speed(0.1));
+                    ui.add(egui::DragValue::new(&mut corner.z).speed(0.1));
+                    ui.end_row();
+                }
+            });
+
+        ui.horizontal(|ui| {
+            if ui.button("Count").clicked() {
+                let mut params = SystemState::<LoadedPoints>::new(world);
+                let loaded_points = params.get(world);
+                region.number_of_points = Some(loaded_points.points_in_region(region.aabb).count());
+            }
+
+            if let Some(number_of_points) = region.number_of_points {
+                ui.label(format!("{} points", number_of_points));
+            }
+        });
     });
 }
 
@@ -422,6 +592,33 @@ fn draw_ui_grid(ui: &mut egui::Ui, world: &mut World, state: &mut State) {
             }
         })
         .body(|ui| {
+            if ui
+                .checkbox(&mut state.grid.point_bounds, "Point bounds")
+                .on_hover_text("Draws the bounds of the points instead of the cell boxes")
+                .changed()
+            {
+                let one_shot_systems = world.get_resource::<OneShotSystems>().unwrap();
+                let toggle_point_bounds = one_shot_systems.toggle_point_bounds;
+                let toggle_grid = one_shot_systems.toggle_grid;
+
+                world
+                    .run_system_with_input(toggle_point_bounds, state.grid.point_bounds)
+                    .unwrap();
+
+                if state.grid.show {
+                    // rebuilds the lines of the shown hierarchies
+                    for (hierarchy, show) in state.grid.hierarchies.iter().enumerate() {
+                        if *show {
+                            for show in [false, true] {
+                                world
+                                    .run_system_with_input(toggle_grid, (show, hierarchy as u32))
+                                    .unwrap();
+                            }
+                        }
+                    }
+                }
+            }
+
             for (hierarchy, show) in state.grid.hierarchies.iter_mut().enumerate() {
                 if ui.checkbox(show, hierarchy.to_string()).changed() {
                     let toggle_grid = world.get_resource::<OneShotSystems>().unwrap().toggle_grid;
@@ -487,60 +684,78 @@ fn draw_ui_streaming_frustums(ui: &mut egui::Ui, world: &mut World, state: &mut
         });
 }
 
-fn draw_ui_visible_hierarchies(ui: &mut egui::Ui, world: &mut World, state: &mut State) {
-    let id = ui.make_persistent_id("collapsing_visible_hierarchies_header");
-    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
-        .show_header(ui, |ui| {
-            if ui
-                .checkbox(
-                    &mut state.hierarchy_visibility.show_all,
-                    "Show all hierarchies",
-                )
-                .changed()
-            {
-                let toggle_hierarchy = world
-                    .get_resource::<OneShotSystems>()
-                    .unwrap()
-                    .toggle_hierarchy;
-
-                if state.hierarchy_visibility.show_all {
-                    for hierarchy in 0..state.hierarchy_visibility.hierarchies.len() {
-                        world
-                            .run_system_with_input(toggle_hierarchy, (true, hierarchy as u32))
-                            .unwrap();
-                    }
-                } else {
-                    for (hierarchy, show) in
-                        state.hierarchy_visibility.hierarchies.iter().enumerate()
-                    {
-                        world
-                            .run_system_with_input(toggle_hierarchy, (*show, hierarchy as u32))
-                            .unwrap();
+fn draw_ui_frustum_values(ui: &mut egui::Ui, world: &mut World) {
+    let Some(frustum) = world
+        .query_filtered::<&Frustum, With<Camera>>()
+        .iter(world)
+        .next()
+    else {
+        return;
+    };
+
+    ui.collapsing("Frustum values", |ui| {
+        egui::Grid::new("frustum_corners_grid")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Corner");
+                ui.label("x");
+                ui.label("y");
+                ui.label("z");
+                ui.end_row();
+
+                for (plane, corners) in [("Near", &frustum.near), ("Far", &frustum.far)] {
+                    let named_corners = [
+                        ("top left", corners.top_left),
+                        ("top right", corners.top_right),
+                        ("bottom left", corners.bottom_left),
+                        ("bottom right", corners.bottom_right),
+                    ];
+
+                    for (name, corner) in named_corners {
+                        ui.label(format!("{} {}", plane, name));
+
+                        for value in corner.to_array() {
+                            ui.label(format!("{:.3}", value));
+                        }
+
+                        ui.end_row();
                     }
                 }
-            }
-        })
-        .body(|ui| {
-            ui.label("Visible hierarchies:");
-
-            ui.set_enabled(!state.hierarchy_visibility.show_all);
+            });
 
-            for (hierarchy, show) in state
-                .hierarchy_visibility
-                .hierarchies
-                .iter_mut()
-                .enumerate()
-            {
-                if ui.checkbox(show, hierarchy.to_string()).changed() {
-                    let toggle_hierarchy = world
-                        .get_resource::<OneShotSystems>()
-                        .unwrap()
-                        .toggle_hierarchy;
+        ui.separator();
+
+        egui::Grid::new("frustum_planes_grid")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Plane");
+                ui.label("a");
+                ui.label("b");
+                ui.label("c");
+                ui.label("d");
+                ui.end_row();
+
+                let planes = &frustum.planes;
+                let named_planes = [
+                    ("Near", planes.near),
+                    ("Far", planes.far),
+                    ("Top", planes.top),
+                    ("Bottom", planes.bottom),
+                    ("Left", planes.left),
+                    ("Right", planes.right),
+                ];
+
+                for (name, plane) in named_planes {
+                    ui.label(name);
+
+                    for value in plane.to_array() {
+                        ui.label(format!("{:.3}", value));
+                    }
 
-                    world
-                        .run_system_with_input(toggle_hierarchy, (*show, hierarchy as u32))
-                        .unwrap();
+                    ui.end_row();
                 }
-            }
-        });
+            });
+    });
 }
@@ -20,7 +20,10 @@ impl Plugin for InputPlugin {
             .add_event::<MouseButtonEvent>()
             .add_event::<MouseWheelEvent>()
             .add_event::<CursorEvent>()
-            .add_systems(First, handle_window_events);
+            .add_event::<HotkeyEvent>()
+            .init_resource::<Hotkeys>()
+            .init_resource::<UiWantsKeyboardInput>()
+            .add_systems(First, (handle_window_events, send_hotkey_events).chain());
     }
 }
 
@@ -28,6 +31,7 @@ impl Plugin for InputPlugin {
 pub struct KeyEvent {
     pub key: KeyCode,
     pub state: ElementState,
+    pub repeat: bool,
 }
 
 #[derive(Resource)]
@@ -39,6 +43,10 @@ impl<T: Hash + Eq> PressedButton<T> {
     pub fn is_pressed(&self, button: &T) -> bool {
         self.pressed.contains(button)
     }
+
+    pub fn any_pressed(&self) -> bool {
+        !self.pressed.is_empty()
+    }
 }
 
 pub type PressedKeys = PressedButton<KeyCode>;
@@ -77,6 +85,57 @@ pub struct CursorEvent {
     pub delta: PhysicalPosition<f64>,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HotkeyAction {
+    ToggleSidePanel,
+    ToggleStreaming,
+    ToggleBoundingBox,
+    CycleRenderMode,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Event)]
+pub struct HotkeyEvent(pub HotkeyAction);
+
+/// Set while a UI element like a text input has keyboard focus,
+/// so that typing doesn't trigger hotkeys.
+#[derive(Debug, Default, Resource)]
+pub struct UiWantsKeyboardInput(pub bool);
+
+#[derive(Debug, Copy, Clone, Resource)]
+pub struct Hotkeys {
+    pub toggle_side_panel: KeyCode,
+    pub toggle_streaming: KeyCode,
+    pub toggle_bounding_box: KeyCode,
+    pub cycle_render_mode: KeyCode,
+}
+
+impl Hotkeys {
+    pub fn action(&self, key: KeyCode) -> Option<HotkeyAction> {
+        if key == self.toggle_side_panel {
+            Some(HotkeyAction::ToggleSidePanel)
+        } else if key == self.toggle_streaming {
+            Some(HotkeyAction::ToggleStreaming)
+        } else if key == self.toggle_bounding_box {
+            Some(HotkeyAction::ToggleBoundingBox)
+        } else if key == self.cycle_render_mode {
+            Some(HotkeyAction::CycleRenderMode)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            toggle_side_panel: KeyCode::Tab,
+            toggle_streaming: KeyCode::KeyP,
+            toggle_bounding_box: KeyCode::KeyB,
+            cycle_render_mode: KeyCode::KeyM,
+        }
+    }
+}
+
 fn handle_window_events(
     mut window_events: EventReader<WindowEvent>,
     mut key_events: EventWriter<KeyEvent>,
@@ -99,6 +158,7 @@ fn handle_window_events(
                     WinitKeyEvent {
                         state,
                         physical_key: PhysicalKey::Code(key),
+                        repeat,
                         ..
                     },
                 ..
@@ -112,6 +172,7 @@ fn handle_window_events(
                 key_events.send(KeyEvent {
                     key: *key,
                     state: *state,
+                    repeat: *repeat,
                 });
             }
             WinitWindowEvent::MouseInput { state, button, .. } => {
@@ -152,3 +213,24 @@ fn handle_window_events(
         }
     }
 }
+
+fn send_hotkey_events(
+    mut key_events: EventReader<KeyEvent>,
+    mut hotkey_events: EventWriter<HotkeyEvent>,
+    hotkeys: Res<Hotkeys>,
+    ui_wants_keyboard_input: Res<UiWantsKeyboardInput>,
+) {
+    for event in key_events.read() {
+        if ui_wants_keyboard_input.0 {
+            continue;
+        }
+
+        if event.state != ElementState::Pressed || event.repeat {
+            continue;
+        }
+
+        if let Some(action) = hotkeys.action(event.key) {
+            hotkey_events.send(HotkeyEvent(action));
+        }
+    }
+}
@@ -3,13 +3,14 @@ use bevy_ecs::prelude::*;
 use bevy_ecs::system::{RunSystemOnce, SystemParam, SystemState};
 use bevy_state::prelude::*;
 use bounding_volume::Aabb;
-use glam::Vec3;
+use glam::{Quat, Vec3};
 use std::io::Read;
 use std::path::PathBuf;
 use thousands::Separable;
 use url::Url;
 
 use point_converter::metadata::Metadata;
+use point_converter::point::{ChannelOrder, PointAttributes};
 
 use crate::event_set::prelude::*;
 use crate::plugins::asset::source::{Directory, Source, SourceError};
@@ -17,8 +18,10 @@ use crate::plugins::asset::{
     Asset, AssetEvent, AssetHandle, AssetLoadedEvent, AssetManagerRes, AssetManagerResMut,
     AssetPlugin, LoadAssetMsg, MutAsset,
 };
-use crate::plugins::camera::Camera;
+use crate::plugins::camera::projection::PerspectiveProjection;
+use crate::plugins::camera::{draw_transform_grid, framing_distance, Camera};
 use crate::plugins::render::BufferSet;
+use crate::plugins::wgpu::SurfaceConfig;
 use crate::transform::Transform;
 
 pub mod shader;
@@ -41,6 +44,7 @@ impl Asset for Metadata {
 
     #[cfg(not(target_arch = "wasm32"))]
     fn save(&self, source: Source) -> Result<(), SourceError> {
+        use crate::plugins::asset::source::upload_to_url;
         use std::fs::File;
         use std::io::{BufWriter, ErrorKind, Write};
 
@@ -57,8 +61,19 @@ impl Asset for Metadata {
                     })?;
                 buf_writer.flush().map_err(SourceError::from)
             }
-            Source::URL(_) => Err(SourceError::Other {
-                message: "URL saving is not supported".to_string(),
+            Source::URL(url) => {
+                log::debug!("Uploading metadata to {}", url);
+
+                let mut bytes = Vec::new();
+                self.write_to(&mut bytes)
+                    .map_err(|err| SourceError::Other {
+                        message: err.to_string(),
+                        name: ErrorKind::InvalidData,
+                    })?;
+                upload_to_url(&url, bytes)
+            }
+            Source::ArchiveEntry { .. } => Err(SourceError::Other {
+                message: "Saving into an archive is not supported".to_string(),
                 name: ErrorKind::Unsupported,
             }),
             Source::None => Err(SourceError::NoSource),
@@ -77,16 +92,27 @@ impl Plugin for MetadataPlugin {
             app.add_systems(
                 Update,
                 handle_selection.run_if(in_state(MetadataState::Selecting)),
-            );
+            )
+            .add_systems(Update, handle_add_point_cloud_selection);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let url = self.url.as_ref().map(Url::to_string).unwrap_or_default();
+            app.insert_resource(MetadataUrlInput(url));
         }
 
         app.add_plugins(AssetPlugin::<Metadata>::default())
             .insert_state(MetadataState::NotLoaded)
             .insert_resource(DefaultURL(self.url.clone()))
+            .insert_resource(MetadataLoadError(None))
+            .init_resource::<LoadingMetadataSource>()
+            .init_resource::<AddingPointClouds>()
+            .init_resource::<NudgeSteps>()
+            .init_resource::<DisplayUnits>()
             .add_event::<UpdateMetadataEvent>()
             .add_event_set::<UpdatedMetadataEventSet>()
             .add_systems(PreStartup, setup)
-            .add_systems(Startup, shader::create_metadata_buffer.in_set(BufferSet))
             .add_systems(
                 Update,
                 receive_metadata.run_if(on_event::<AssetEvent<Metadata>>()),
@@ -94,26 +120,42 @@ impl Plugin for MetadataPlugin {
             .add_systems(
                 PostUpdate,
                 (
-                    update_metadata.run_if(on_event::<UpdateMetadataEvent>()),
-                    shader::update_metadata_buffer
-                        .run_if(on_event::<UpdatedMetadataHierarchiesEvent>()),
+                    (
+                        update_metadata.run_if(on_event::<UpdateMetadataEvent>()),
+                        shader::add_point_cloud_buffers,
+                    ),
+                    (
+                        shader::update_metadata_buffer,
+                        shader::update_point_cloud_buffer,
+                    ),
                 )
                     .chain()
                     .in_set(BufferSet),
             )
-            .add_systems(
-                OnEnter(MetadataState::Loaded),
-                (
-                    look_at_bounding_box,
-                    shader::update_metadata_buffer.in_set(BufferSet),
-                ),
-            );
+            .add_systems(OnEnter(MetadataState::Loaded), look_at_bounding_box);
     }
 }
 
 #[derive(Debug, Resource)]
 struct DefaultURL(Option<Url>);
 
+/// Reason why the last metadata couldn't be loaded.
+#[derive(Debug, Resource)]
+struct MetadataLoadError(Option<String>);
+
+/// Text of the URL input in the metadata UI.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default, Resource)]
+struct MetadataUrlInput(String);
+
+/// Name of the source from which metadata is currently being loaded.
+#[derive(Debug, Default, Resource)]
+struct LoadingMetadataSource(Option<String>);
+
+/// Ids of metadata which are loaded as additional point clouds instead of replacing the active one.
+#[derive(Debug, Default, Resource)]
+struct AddingPointClouds(Vec<String>);
+
 #[derive(Debug, Copy, Clone, Event)]
 pub struct UpdatedMetadataHierarchiesEvent;
 
@@ -130,6 +172,9 @@ pub enum UpdateMetadataEvent {
     NumberOfPoints(i32),
     IncreaseHierarchy(u32),
     ExtendBoundingBox(Aabb),
+    DefaultColor([u8; 4]),
+    ChannelOrder(ChannelOrder),
+    AddAttributes(PointAttributes),
 }
 
 fn update_metadata(
@@ -138,6 +183,8 @@ fn update_metadata(
     mut updated_metadata_event_set: UpdatedMetadataEventSet,
 ) {
     let mut metadata = active_metadata.get_mut();
+    let was_empty = metadata.number_of_points == 0;
+    let mut extended_boxes = Vec::new();
 
     for event in events.read() {
         match event {
@@ -153,16 +200,29 @@ fn update_metadata(
                 }
             }
             UpdateMetadataEvent::ExtendBoundingBox(aabb) => {
-                if metadata.number_of_points == 0 {
-                    metadata.bounding_box = *aabb;
-                } else {
-                    metadata.bounding_box.extend_aabb(aabb);
-                }
-
-                updated_metadata_event_set.dispatch(UpdatedMetadataBoundingBoxEvent);
+                extended_boxes.push(aabb);
+            }
+            UpdateMetadataEvent::DefaultColor(color) => {
+                metadata.default_color = Some(*color);
+            }
+            UpdateMetadataEvent::ChannelOrder(channel_order) => {
+                metadata.channel_order = Some(*channel_order);
+            }
+            UpdateMetadataEvent::AddAttributes(attributes) => {
+                metadata.attributes |= *attributes;
             }
         }
     }
+
+    if let Some(aabb) = Aabb::union_all(extended_boxes) {
+        if was_empty {
+            metadata.bounding_box = aabb;
+        } else {
+            metadata.bounding_box.extend_aabb(&aabb);
+        }
+
+        updated_metadata_event_set.dispatch(UpdatedMetadataBoundingBoxEvent);
+    }
 }
 
 fn setup(
@@ -170,6 +230,7 @@ fn setup(
     mut metadata_manager: AssetManagerResMut<Metadata>,
     mut next_metadata_state: ResMut<NextState<MetadataState>>,
     default_url: Res<DefaultURL>,
+    mut loading_metadata_source: ResMut<LoadingMetadataSource>,
 ) {
     let handle = metadata_manager.insert(
         "Unknown".to_string(),
@@ -177,7 +238,8 @@ fn setup(
         Source::None,
         false,
     );
-    commands.insert_resource(LoadedMetadata { active: handle });
+    let active = commands.spawn(PointCloud::new(handle)).id();
+    commands.insert_resource(LoadedMetadata { active });
 
     if let Some(url) = &default_url.0 {
         metadata_manager
@@ -189,6 +251,7 @@ fn setup(
             })
             .unwrap();
 
+        loading_metadata_source.0 = Some(url.to_string());
         next_metadata_state.set(MetadataState::Loading);
     }
 }
@@ -202,38 +265,62 @@ pub enum MetadataState {
     Loaded,
 }
 
+/// A point cloud which is streamed and rendered.
+/// Only the active one is edited by the converter, all others are read only.
+#[derive(Debug, Component)]
+pub struct PointCloud {
+    pub metadata: AssetHandle<Metadata>,
+    /// Placement of the whole point cloud in world space, used to align it to other point clouds.
+    pub transform: Transform,
+    pub visible: bool,
+}
+
+impl PointCloud {
+    pub fn new(metadata: AssetHandle<Metadata>) -> Self {
+        Self {
+            metadata,
+            transform: Transform::IDENTITY,
+            visible: true,
+        }
+    }
+}
+
 #[derive(Debug, Resource)]
 pub struct LoadedMetadata {
-    active: AssetHandle<Metadata>,
+    active: Entity,
 }
 
 impl LoadedMetadata {
-    pub fn get_active(&self) -> &AssetHandle<Metadata> {
-        &self.active
-    }
-    pub fn set_active(&mut self, handle: AssetHandle<Metadata>) {
-        self.active = handle;
+    /// The [PointCloud] entity whose metadata is shown in the UI and edited by the converter.
+    pub fn get_active(&self) -> Entity {
+        self.active
     }
 }
 
 #[derive(SystemParam)]
-pub struct ActiveMetadata<'w> {
+pub struct ActiveMetadata<'w, 's> {
     loaded_metadata: Res<'w, LoadedMetadata>,
+    point_clouds: Query<'w, 's, &'static PointCloud>,
     metadata_manager: AssetManagerRes<'w, Metadata>,
 }
 
 #[derive(SystemParam)]
-struct ActiveMetadataMut<'w> {
+struct ActiveMetadataMut<'w, 's> {
     loaded_metadata: Res<'w, LoadedMetadata>,
+    point_clouds: Query<'w, 's, &'static PointCloud>,
     metadata_manager: AssetManagerResMut<'w, Metadata>,
 }
 
-fn get_working_directory(source: &Source) -> Option<Directory> {
+pub fn get_working_directory(source: &Source) -> Option<Directory> {
     match source {
         #[cfg(not(target_arch = "wasm32"))]
         Source::Path(path) => Some(Directory::Path(path.parent().unwrap().to_path_buf())),
+        #[cfg(not(target_arch = "wasm32"))]
+        Source::ArchiveEntry { archive, .. } => Some(Directory::Archive(archive.clone())),
         #[cfg(target_arch = "wasm32")]
         Source::PathInDirectory { directory, .. } => Some(Directory::WebDir(directory.clone())),
+        #[cfg(target_arch = "wasm32")]
+        Source::ZipEntry { zip, .. } => Some(Directory::Zip(zip.clone())),
         Source::URL(url) => {
             let path = PathBuf::from(url.path());
             let dir = path.parent().unwrap();
@@ -245,34 +332,72 @@ fn get_working_directory(source: &Source) -> Option<Directory> {
     }
 }
 
-impl<'w> ActiveMetadata<'w> {
+impl<'w, 's> ActiveMetadata<'w, 's> {
+    pub fn entity(&self) -> Entity {
+        self.loaded_metadata.active
+    }
+
+    pub fn point_cloud(&self) -> &PointCloud {
+        self.point_clouds.get(self.loaded_metadata.active).unwrap()
+    }
+
+    pub fn handle(&self) -> &AssetHandle<Metadata> {
+        &self.point_cloud().metadata
+    }
+
     pub fn get(&self) -> &Metadata {
-        let handle = &self.loaded_metadata.active;
-        self.metadata_manager.get_asset(handle)
+        self.metadata_manager.get_asset(self.handle())
+    }
+
+    pub fn get_source(&self) -> &Source {
+        self.metadata_manager.get_asset_source(self.handle())
     }
 
     pub fn get_working_directory(&self) -> Option<Directory> {
-        let handle = &self.loaded_metadata.active;
-        let source = self.metadata_manager.get_asset_source(handle);
-        get_working_directory(source)
+        get_working_directory(self.get_source())
     }
 }
 
-impl<'w> ActiveMetadataMut<'w> {
+impl<'w, 's> ActiveMetadataMut<'w, 's> {
     pub fn get_mut(&mut self) -> MutAsset<Metadata> {
-        let handle = &self.loaded_metadata.active;
-        self.metadata_manager.get_asset_mut(handle)
+        let point_cloud = self.point_clouds.get(self.loaded_metadata.active).unwrap();
+        self.metadata_manager.get_asset_mut(&point_cloud.metadata)
     }
 }
 
+/// Progress of the metadata which is loaded or added, see [receive_metadata].
+#[derive(SystemParam)]
+struct MetadataLoadState<'w> {
+    next_metadata_state: ResMut<'w, NextState<MetadataState>>,
+    metadata_load_error: ResMut<'w, MetadataLoadError>,
+    loading_metadata_source: ResMut<'w, LoadingMetadataSource>,
+    adding_point_clouds: ResMut<'w, AddingPointClouds>,
+}
+
 fn receive_metadata(
+    mut commands: Commands,
     mut loaded_metadata_events: EventReader<AssetEvent<Metadata>>,
     metadata_manager: AssetManagerRes<Metadata>,
-    mut loaded_metadata: ResMut<LoadedMetadata>,
-    mut next_metadata_state: ResMut<NextState<MetadataState>>,
+    loaded_metadata: Res<LoadedMetadata>,
+    mut point_cloud_query: Query<&mut PointCloud>,
+    mut load_state: MetadataLoadState,
 ) {
     for event in loaded_metadata_events.read() {
         match event {
+            AssetEvent::Loaded(AssetLoadedEvent::Success { handle })
+                if load_state.adding_point_clouds.remove(handle.id()) =>
+            {
+                let metadata = metadata_manager.get_asset(handle);
+
+                log::debug!(
+                    "Added point cloud {} with {} points",
+                    metadata.name,
+                    metadata.number_of_points
+                );
+
+                commands.spawn(PointCloud::new(handle.clone()));
+                load_state.metadata_load_error.0 = None;
+            }
             AssetEvent::Created { handle }
             | AssetEvent::Loaded(AssetLoadedEvent::Success { handle }) => {
                 let metadata = metadata_manager.get_asset(handle);
@@ -283,30 +408,95 @@ fn receive_metadata(
                     metadata.number_of_points
                 );
 
-                loaded_metadata.set_active(handle.clone());
+                let mut active = point_cloud_query
+                    .get_mut(loaded_metadata.get_active())
+                    .unwrap();
+                active.metadata = handle.clone();
+
+                load_state.metadata_load_error.0 = None;
+                load_state.loading_metadata_source.0 = None;
 
-                next_metadata_state.set(MetadataState::Loaded);
+                load_state.next_metadata_state.set(MetadataState::Loaded);
             }
             AssetEvent::Changed { .. } => {}
+            AssetEvent::Loaded(AssetLoadedEvent::Error { id, error })
+                if load_state.adding_point_clouds.remove(id) =>
+            {
+                log::error!("Failed to add point cloud {}: {}", id, error);
+                load_state.metadata_load_error.0 = Some(error.to_string());
+            }
             AssetEvent::Loaded(AssetLoadedEvent::Error { id, error }) => {
-                log::error!("Failed to load metadata {}: {:?}", id, error);
-                next_metadata_state.set(MetadataState::NotLoaded);
+                log::error!("Failed to load metadata {}: {}", id, error);
+                load_state.metadata_load_error.0 = Some(error.to_string());
+                load_state.loading_metadata_source.0 = None;
+                load_state.next_metadata_state.set(MetadataState::NotLoaded);
+            }
+        }
+    }
+}
+
+impl AddingPointClouds {
+    fn remove(&mut self, id: &String) -> bool {
+        match self.0.iter().position(|it| it == id) {
+            Some(index) => {
+                self.0.swap_remove(index);
+                true
             }
+            None => false,
         }
     }
 }
 
-fn look_at_bounding_box(
-    mut query: Query<&mut Transform, With<Camera>>,
+/// Removes all point clouds except the active one, whose metadata is replaced by an empty one.
+/// Their cells are removed once [MetadataState::NotLoaded] is entered.
+fn unload(
+    mut commands: Commands,
+    loaded_metadata: Res<LoadedMetadata>,
+    mut metadata_manager: AssetManagerResMut<Metadata>,
+    mut point_cloud_query: Query<(Entity, &mut PointCloud)>,
+    mut next_metadata_state: ResMut<NextState<MetadataState>>,
+    mut metadata_load_error: ResMut<MetadataLoadError>,
+) {
+    let handle = metadata_manager.insert(
+        "Unknown".to_string(),
+        Metadata::default(),
+        Source::None,
+        false,
+    );
+
+    for (entity, mut point_cloud) in point_cloud_query.iter_mut() {
+        if entity == loaded_metadata.get_active() {
+            *point_cloud = PointCloud::new(handle.clone());
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    metadata_load_error.0 = None;
+    next_metadata_state.set(MetadataState::NotLoaded);
+}
+
+/// Looks diagonally down onto the point cloud from far enough away that all of it is visible.
+pub(crate) fn look_at_bounding_box(
+    mut query: Query<(&mut Transform, &PerspectiveProjection), With<Camera>>,
     active_metadata: ActiveMetadata,
+    config: Res<SurfaceConfig>,
 ) {
-    let aabb = active_metadata.get().bounding_box;
+    let aabb = active_metadata
+        .point_cloud()
+        .transform
+        .transform_aabb(&active_metadata.get().bounding_box);
+
     let center = (aabb.min + aabb.max) / 2.0;
 
     let center_max_z = center.with_z(aabb.max.z);
+    let direction = center - (aabb.max + (center_max_z - aabb.max) / 2.0);
+    let aspect_ratio = config.width as f32 / config.height.max(1) as f32;
+
+    for (mut transform, projection) in query.iter_mut() {
+        let distance = framing_distance(&aabb, direction, Vec3::Z, projection.fov_y, aspect_ratio);
 
-    for mut transform in query.iter_mut() {
-        *transform = Transform::from_translation(aabb.max + (center_max_z - aabb.max) / 2.0)
+        *transform = Transform::from_translation(center - direction.normalize() * distance)
             .looking_at(center, Vec3::Z);
     }
 }
@@ -320,7 +510,11 @@ enum MetadataSelection {
 #[cfg(target_arch = "wasm32")]
 fn handle_selection(
     world: &mut World,
-    params: &mut SystemState<(AssetManagerRes<Metadata>, ResMut<NextState<MetadataState>>)>,
+    params: &mut SystemState<(
+        AssetManagerRes<Metadata>,
+        ResMut<NextState<MetadataState>>,
+        ResMut<LoadingMetadataSource>,
+    )>,
 ) {
     let receiver = world
         .remove_non_send_resource::<flume::Receiver<MetadataSelection>>()
@@ -328,10 +522,21 @@ fn handle_selection(
 
     match receiver.try_recv() {
         Ok(MetadataSelection::Load(source)) => {
-            let (metadata_manager, mut metadata_state) = params.get_mut(world);
+            let (metadata_manager, mut metadata_state, mut loading_metadata_source) =
+                params.get_mut(world);
 
             metadata_state.set(MetadataState::Loading);
 
+            match &source {
+                Source::PathInDirectory { path, .. } => {
+                    loading_metadata_source.0 = Some(path.clone());
+                }
+                Source::ZipEntry { zip, .. } => {
+                    loading_metadata_source.0 = Some(zip.name().to_string());
+                }
+                Source::URL(_) | Source::None => {}
+            }
+
             let id = format!("{:?}", source);
             log::debug!("{:?}", source); // TODO does this make sense?
 
@@ -377,19 +582,133 @@ pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
     ));
     ui.label(format!("Hierarchies: {}", metadata.hierarchies));
 
+    let display_units = world.get_resource::<DisplayUnits>().unwrap();
+
     ui.collapsing("Extends", |ui| {
-        let extends = metadata.bounding_box.max - metadata.bounding_box.min;
+        let extends = metadata.bounding_box.size();
 
-        ui.label(format!("x: {}", extends.x));
-        ui.label(format!("y: {}", extends.y));
-        ui.label(format!("z: {}", extends.z));
+        ui.label(format!("x: {}", display_units.format(extends.x, metadata)));
+        ui.label(format!("y: {}", display_units.format(extends.y, metadata)));
+        ui.label(format!("z: {}", display_units.format(extends.z, metadata)));
     });
 
+    ui.collapsing("Point spacing", |ui| {
+        for hierarchy in 0..metadata.hierarchies {
+            ui.label(format!(
+                "{}: {}",
+                Metadata::hierarchy_string(hierarchy),
+                display_units.format(metadata.config.min_point_spacing(hierarchy), metadata)
+            ));
+        }
+    });
+
+    let metadata_units = metadata.units.clone();
+    draw_display_units_ui(ui, world, metadata_units);
+
     if ui.button("Look at bounding box").clicked() {
         world.run_system_once(look_at_bounding_box);
     }
 
+    let is_loaded =
+        *world.get_resource::<State<MetadataState>>().unwrap().get() == MetadataState::Loaded;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let is_converting = crate::plugins::converter::is_converting(world);
+    #[cfg(target_arch = "wasm32")]
+    let is_converting = false;
+
+    if ui
+        .add_enabled(is_loaded && !is_converting, egui::Button::new("Unload"))
+        .on_hover_text("Removes all point clouds")
+        .clicked()
+    {
+        world.run_system_once(unload);
+    }
+
     select_metadata(ui, world);
+
+    if let Some(error) = &world.get_resource::<MetadataLoadError>().unwrap().0 {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+}
+
+/// Blocks the whole window with a spinner while metadata is being selected or loaded.
+pub fn draw_loading_overlay(context: &egui::Context, world: &mut World) {
+    let message = match *world.get_resource::<State<MetadataState>>().unwrap().get() {
+        MetadataState::NotLoaded | MetadataState::Loaded => {
+            return;
+        }
+        #[cfg(target_arch = "wasm32")]
+        MetadataState::Selecting => "Waiting for selection...",
+        MetadataState::Loading => "Loading metadata...",
+    };
+
+    let source = &world.get_resource::<LoadingMetadataSource>().unwrap().0;
+    let screen_rect = context.screen_rect();
+
+    egui::Area::new(egui::Id::new("metadata_loading_overlay_background"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(screen_rect.min)
+        .show(context, |ui| {
+            ui.painter()
+                .rect_filled(screen_rect, 0.0, egui::Color32::from_black_alpha(160));
+            ui.allocate_rect(screen_rect, egui::Sense::click_and_drag());
+        });
+
+    egui::Area::new(egui::Id::new("metadata_loading_overlay"))
+        .order(egui::Order::Tooltip)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(context, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(message);
+                });
+
+                if let Some(source) = source {
+                    ui.label(source);
+                }
+            });
+        });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn pick_metadata_file(window: &winit::window::Window) -> Option<PathBuf> {
+    use point_converter::archive::Archive;
+
+    rfd::FileDialog::new()
+        .add_filter(Metadata::FILE_NAME, &[Metadata::EXTENSION])
+        .add_filter("archive", &[Archive::EXTENSION])
+        .set_parent(window)
+        .pick_file()
+}
+
+/// Archives are opened to read their index, everything else is treated as a metadata file.
+#[cfg(not(target_arch = "wasm32"))]
+fn source_from_path(path: PathBuf) -> Result<Source, String> {
+    use crate::plugins::asset::source::ArchiveFile;
+    use point_converter::archive::Archive;
+
+    if path.extension().and_then(|it| it.to_str()) == Some(Archive::EXTENSION) {
+        let archive =
+            ArchiveFile::open(path).map_err(|err| format!("Failed to open archive: {}", err))?;
+
+        Ok(Source::ArchiveEntry {
+            archive: std::sync::Arc::new(archive),
+            path: Metadata::logical_path(),
+        })
+    } else {
+        Ok(Source::Path(path))
+    }
+}
+
+/// Loads a metadata file or an archive as the active point cloud, like choosing it in the UI.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open_metadata_path(world: &mut World, path: PathBuf) -> Result<(), String> {
+    let id = path.to_str().unwrap().to_string();
+    let source = source_from_path(path)?;
+    load_metadata(world, id, source);
+    Ok(())
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -409,83 +728,521 @@ fn select_metadata(ui: &mut egui::Ui, world: &mut World) {
                 .get_resource::<crate::plugins::winit::Window>()
                 .unwrap();
 
-            rfd::FileDialog::new()
-                .add_filter(Metadata::FILE_NAME, &[Metadata::EXTENSION])
-                .set_parent(window)
-                .pick_file()
+            pick_metadata_file(window)
         };
 
         if let Some(path) = path {
-            let mut params = SystemState::<(
-                AssetManagerRes<Metadata>,
-                ResMut<NextState<MetadataState>>,
-            )>::new(world);
-            let (metadata_manager, mut next_metadata_state) = params.get_mut(world);
+            if let Err(err) = open_metadata_path(world, path) {
+                world.get_resource_mut::<MetadataLoadError>().unwrap().0 = Some(err);
+            }
+        }
+    }
 
-            next_metadata_state.set(MetadataState::Loading);
+    ui.separator();
 
-            // TODO reuse already loaded metadata
+    let mut url_input = world.get_resource_mut::<MetadataUrlInput>().unwrap();
+    let text_edit = egui::TextEdit::singleline(&mut url_input.0).hint_text("https://...");
+    ui.add_enabled(enabled, text_edit);
 
-            let id = path.to_str().unwrap().to_string();
-            let source = Source::Path(path);
+    if ui
+        .add_enabled(enabled, egui::Button::new("Open from URL..."))
+        .clicked()
+    {
+        let input = world.get_resource::<MetadataUrlInput>().unwrap().0.clone();
 
-            metadata_manager
-                .load_sender()
-                .send(LoadAssetMsg {
-                    id,
-                    source,
-                    reply_sender: None,
-                })
-                .unwrap();
+        match Url::parse(input.trim()) {
+            Ok(url) => {
+                load_metadata(world, url.to_string(), Source::URL(url));
+            }
+            Err(err) => {
+                world.get_resource_mut::<MetadataLoadError>().unwrap().0 =
+                    Some(format!("Invalid URL: {}", err));
+            }
         }
     }
 }
 
+/// Makes an already shown point cloud with the metadata the active one instead of loading it again.
+/// It replaces the previously active point cloud, like a newly loaded one would.
+/// Returns false if no point cloud has the metadata.
+#[cfg(not(target_arch = "wasm32"))]
+fn reuse_loaded_metadata(world: &mut World, id: &String) -> bool {
+    let mut params = SystemState::<(
+        Commands,
+        ResMut<LoadedMetadata>,
+        ResMut<MetadataLoadError>,
+        Query<(Entity, &PointCloud)>,
+    )>::new(world);
+    let (mut commands, mut loaded_metadata, mut metadata_load_error, point_cloud_query) =
+        params.get_mut(world);
+
+    let Some(entity) = point_cloud_query
+        .iter()
+        .find(|(_, point_cloud)| point_cloud.metadata.id() == id)
+        .map(|(entity, _)| entity)
+    else {
+        return false;
+    };
+
+    metadata_load_error.0 = None;
+
+    let active = loaded_metadata.get_active();
+
+    if entity == active {
+        log::debug!("Metadata {} is already active", id);
+        return true;
+    }
+
+    log::debug!("Reusing the loaded metadata {}", id);
+
+    commands.entity(active).despawn();
+    loaded_metadata.active = entity;
+    params.apply(world);
+
+    // Loaded -> Loaded is an identity transition, which doesn't run these schedules
+    world.try_run_schedule(OnExit(MetadataState::Loaded)).ok();
+    world.try_run_schedule(OnEnter(MetadataState::Loaded)).ok();
+
+    true
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_metadata(world: &mut World, id: String, source: Source) {
+    if reuse_loaded_metadata(world, &id) {
+        return;
+    }
+
+    let mut params = SystemState::<(
+        AssetManagerRes<Metadata>,
+        ResMut<NextState<MetadataState>>,
+        ResMut<LoadingMetadataSource>,
+    )>::new(world);
+    let (metadata_manager, mut next_metadata_state, mut loading_metadata_source) =
+        params.get_mut(world);
+
+    next_metadata_state.set(MetadataState::Loading);
+    loading_metadata_source.0 = Some(id.clone());
+
+    metadata_manager
+        .load_sender()
+        .send(LoadAssetMsg {
+            id,
+            source,
+            reply_sender: None,
+        })
+        .unwrap();
+}
+
 #[cfg(target_arch = "wasm32")]
 fn select_metadata(ui: &mut egui::Ui, world: &mut World) {
     let current_metadata_state = *world.get_resource::<State<MetadataState>>().unwrap().get();
 
-    let button = egui::Button::new("Choose dir...");
     let enabled = match current_metadata_state {
         MetadataState::Selecting | MetadataState::Loading => false,
         MetadataState::NotLoaded | MetadataState::Loaded => true,
     };
 
-    if ui.add_enabled(enabled, button).clicked() {
-        let mut next_metadata_state = world
-            .get_resource_mut::<NextState<MetadataState>>()
-            .unwrap();
+    if ui
+        .add_enabled(enabled, egui::Button::new("Choose dir..."))
+        .clicked()
+    {
+        start_selection(world, current_metadata_state, choose_web_dir());
+    }
+
+    if ui
+        .add_enabled(enabled, egui::Button::new("Choose zip..."))
+        .on_hover_text("A zip of a converted directory")
+        .clicked()
+    {
+        start_selection(world, current_metadata_state, choose_web_zip());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn start_selection(
+    world: &mut World,
+    current_metadata_state: MetadataState,
+    choose: impl std::future::Future<Output = Option<Source>> + 'static,
+) {
+    let mut next_metadata_state = world
+        .get_resource_mut::<NextState<MetadataState>>()
+        .unwrap();
+
+    next_metadata_state.set(MetadataState::Selecting);
+
+    let had_metadata = match current_metadata_state {
+        MetadataState::NotLoaded => false,
+        MetadataState::Selecting => {
+            unreachable!("Choosing metadata should be disabled while selecting one");
+        }
+        MetadataState::Loading => {
+            unreachable!("Choosing metadata should be disabled while loading one");
+        }
+        MetadataState::Loaded => true,
+    };
+
+    let (load_sender, load_receiver) = flume::bounded::<MetadataSelection>(1);
+    world.insert_non_send_resource(load_receiver);
+
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Some(source) = choose.await {
+            load_sender.send(MetadataSelection::Load(source)).unwrap();
+        } else {
+            load_sender
+                .send(MetadataSelection::Canceled { had_metadata })
+                .unwrap();
+        }
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn choose_web_dir() -> Option<Source> {
+    let directory = crate::web::WebDir::choose().await.ok()?;
+
+    Some(Source::PathInDirectory {
+        directory,
+        path: Metadata::logical_path(),
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn choose_web_zip() -> Option<Source> {
+    match crate::web::WebZip::choose().await {
+        Ok(zip) => Some(Source::ZipEntry {
+            zip: zip?,
+            path: Metadata::logical_path(),
+        }),
+        Err(err) => {
+            log::error!("Failed to open zip: {:?}", err);
+            None
+        }
+    }
+}
+
+fn add_point_cloud(world: &mut World, id: String, source: Source) {
+    let mut params =
+        SystemState::<(AssetManagerRes<Metadata>, ResMut<AddingPointClouds>)>::new(world);
+    let (metadata_manager, mut adding_point_clouds) = params.get_mut(world);
+
+    adding_point_clouds.0.push(id.clone());
+
+    metadata_manager
+        .load_sender()
+        .send(LoadAssetMsg {
+            id,
+            source,
+            reply_sender: None,
+        })
+        .unwrap();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn handle_add_point_cloud_selection(world: &mut World) {
+    let Some(receiver) = world.remove_non_send_resource::<flume::Receiver<Source>>() else {
+        return;
+    };
+
+    match receiver.try_recv() {
+        Ok(source) => {
+            let id = format!("{:?}", source);
+            add_point_cloud(world, id, source);
+        }
+        Err(flume::TryRecvError::Disconnected) => {}
+        Err(flume::TryRecvError::Empty) => {
+            world.insert_non_send_resource(receiver);
+        }
+    }
+}
+
+/// Converts the lengths which are shown in the ui into another unit,
+/// without converting the point cloud again.
+#[derive(Debug, Resource)]
+pub struct DisplayUnits {
+    pub scale: f32,
+    /// Unit after scaling, the [Metadata::units] of the point cloud are shown if it is empty.
+    pub unit: String,
+}
+
+impl Default for DisplayUnits {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            unit: String::new(),
+        }
+    }
+}
+
+impl DisplayUnits {
+    pub fn format(&self, length: f32, metadata: &Metadata) -> String {
+        let unit = if self.unit.is_empty() {
+            metadata.units.as_deref()
+        } else {
+            Some(self.unit.as_str())
+        };
+
+        match unit {
+            Some(unit) => format!("{:.3} {}", length * self.scale, unit),
+            None => format!("{:.3}", length * self.scale),
+        }
+    }
+}
+
+fn draw_display_units_ui(ui: &mut egui::Ui, world: &mut World, metadata_units: Option<String>) {
+    ui.collapsing("Units", |ui| {
+        let mut display_units = world.get_resource_mut::<DisplayUnits>().unwrap();
+
+        ui.horizontal(|ui| {
+            let mut scale = display_units.scale;
+
+            if ui
+                .add(
+                    egui::DragValue::new(&mut scale)
+                        .speed(0.01)
+                        .clamp_range(0.000_001..=f32::MAX),
+                )
+                .on_hover_text("Factor from the units of the point cloud to the shown ones")
+                .changed()
+            {
+                display_units.scale = scale;
+            }
+
+            ui.label("Scale");
+        });
 
-        next_metadata_state.set(MetadataState::Selecting);
+        ui.horizontal(|ui| {
+            let mut unit = display_units.unit.clone();
 
-        let had_metadata = match current_metadata_state {
-            MetadataState::NotLoaded => false,
-            MetadataState::Selecting => {
-                unreachable!("Choosing metadata should be disabled while selecting one");
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut unit)
+                        .desired_width(50.0)
+                        .hint_text(metadata_units.as_deref().unwrap_or_default()),
+                )
+                .changed()
+            {
+                display_units.unit = unit;
             }
-            MetadataState::Loading => {
-                unreachable!("Choosing metadata should be disabled while loading one");
+
+            ui.label("Unit");
+        });
+    });
+}
+
+/// Step sizes of the buttons which move and rotate a point cloud.
+#[derive(Debug, Resource)]
+struct NudgeSteps {
+    translation: f32,
+    /// In degrees
+    rotation: f32,
+}
+
+impl Default for NudgeSteps {
+    fn default() -> Self {
+        Self {
+            translation: 0.1,
+            rotation: 1.0,
+        }
+    }
+}
+
+/// Buttons to move a point cloud along and rotate it around the world axes.
+/// Rotations keep the center of the bounding box in place.
+fn draw_nudge_ui(
+    ui: &mut egui::Ui,
+    transform: &Transform,
+    local_center: Vec3,
+    steps: &NudgeSteps,
+) -> Option<Transform> {
+    let mut new_transform = None;
+
+    egui::Grid::new("nudge_grid").num_columns(5).show(ui, |ui| {
+        for (label, axis) in [("X", Vec3::X), ("Y", Vec3::Y), ("Z", Vec3::Z)] {
+            ui.label(label);
+
+            for direction in [-1.0, 1.0] {
+                let text = if direction < 0.0 { "-" } else { "+" };
+
+                if ui.small_button(text).on_hover_text("Move").clicked() {
+                    let mut moved = *transform;
+                    moved.translation += axis * direction * steps.translation;
+                    new_transform = Some(moved);
+                }
             }
-            MetadataState::Loaded => true,
-        };
 
-        let (load_sender, load_receiver) = flume::bounded::<MetadataSelection>(1);
-        world.insert_non_send_resource(load_receiver);
+            for direction in [-1.0, 1.0] {
+                let text = if direction < 0.0 { "⟲" } else { "⟳" };
 
-        wasm_bindgen_futures::spawn_local(async move {
-            if let Ok(dir) = crate::web::WebDir::choose().await {
-                let source = Source::PathInDirectory {
-                    directory: dir,
-                    path: std::path::PathBuf::from(Metadata::FILE_NAME)
-                        .with_extension(Metadata::EXTENSION),
-                };
+                if ui.small_button(text).on_hover_text("Rotate").clicked() {
+                    let rotation =
+                        Quat::from_axis_angle(axis, direction * steps.rotation.to_radians());
+                    let center = transform.transform_point(local_center);
 
-                load_sender.send(MetadataSelection::Load(source)).unwrap();
-            } else {
-                load_sender
-                    .send(MetadataSelection::Canceled { had_metadata })
-                    .unwrap();
+                    let mut rotated = *transform;
+                    rotated.rotation = (rotation * transform.rotation).normalize();
+                    rotated.translation =
+                        center - rotated.rotation * (rotated.scale * local_center);
+                    new_transform = Some(rotated);
+                }
             }
+
+            ui.end_row();
+        }
+    });
+
+    new_transform
+}
+
+pub fn draw_point_clouds_ui(ui: &mut egui::Ui, world: &mut World) {
+    {
+        let mut steps = world.get_resource_mut::<NudgeSteps>().unwrap();
+        let mut translation = steps.translation;
+        let mut rotation = steps.rotation;
+
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label("Steps");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut translation)
+                        .speed(0.01)
+                        .clamp_range(0.001..=100.0),
+                )
+                .changed();
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut rotation)
+                        .speed(0.1)
+                        .clamp_range(0.1..=90.0)
+                        .suffix("°"),
+                )
+                .changed();
         });
+
+        if changed {
+            steps.translation = translation;
+            steps.rotation = rotation;
+        }
     }
+
+    let mut params = SystemState::<(
+        Commands,
+        Res<LoadedMetadata>,
+        Res<NudgeSteps>,
+        Query<(Entity, &mut PointCloud)>,
+        AssetManagerRes<Metadata>,
+    )>::new(world);
+
+    {
+        let (mut commands, loaded_metadata, steps, mut point_cloud_query, metadata_manager) =
+            params.get_mut(world);
+
+        for (entity, mut point_cloud) in point_cloud_query.iter_mut() {
+            let is_active = entity == loaded_metadata.get_active();
+            let metadata = metadata_manager.get_asset(&point_cloud.metadata);
+            let name = &metadata.name;
+
+            ui.push_id(entity, |ui| {
+                ui.horizontal(|ui| {
+                    let mut visible = point_cloud.visible;
+                    if ui.checkbox(&mut visible, name).changed() {
+                        point_cloud.visible = visible;
+                    }
+
+                    if is_active {
+                        ui.weak("(active)");
+                    } else if ui.small_button("Remove").clicked() {
+                        commands.entity(entity).despawn();
+                    }
+                });
+
+                let local_center = metadata.bounding_box.center();
+
+                let edited = draw_transform_grid(
+                    ui,
+                    "point_cloud_transform_grid",
+                    &point_cloud.transform,
+                    false,
+                );
+                let nudged = draw_nudge_ui(ui, &point_cloud.transform, local_center, &steps);
+
+                if let Some(new_transform) = edited.or(nudged) {
+                    point_cloud.transform = new_transform;
+                }
+            });
+        }
+    }
+
+    params.apply(world);
+
+    let is_loaded =
+        *world.get_resource::<State<MetadataState>>().unwrap().get() == MetadataState::Loaded;
+
+    ui.add_enabled_ui(is_loaded, |ui| {
+        add_point_cloud_buttons(ui, world);
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn add_point_cloud_buttons(ui: &mut egui::Ui, world: &mut World) {
+    if ui.button("Add point cloud...").clicked() {
+        let path = {
+            let window: &winit::window::Window = world
+                .get_resource::<crate::plugins::winit::Window>()
+                .unwrap();
+
+            pick_metadata_file(window)
+        };
+
+        if let Some(path) = path {
+            let id = path.to_str().unwrap().to_string();
+
+            match source_from_path(path) {
+                Ok(source) => add_point_cloud(world, id, source),
+                Err(err) => {
+                    world.get_resource_mut::<MetadataLoadError>().unwrap().0 = Some(err);
+                }
+            }
+        }
+    }
+
+    if ui.button("Add from URL").clicked() {
+        let input = world.get_resource::<MetadataUrlInput>().unwrap().0.clone();
+
+        match Url::parse(input.trim()) {
+            Ok(url) => {
+                add_point_cloud(world, url.to_string(), Source::URL(url));
+            }
+            Err(err) => {
+                world.get_resource_mut::<MetadataLoadError>().unwrap().0 =
+                    Some(format!("Invalid URL: {}", err));
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn add_point_cloud_buttons(ui: &mut egui::Ui, world: &mut World) {
+    if ui.button("Add point cloud...").clicked() {
+        start_add_point_cloud_selection(world, choose_web_dir());
+    }
+
+    if ui.button("Add zip...").clicked() {
+        start_add_point_cloud_selection(world, choose_web_zip());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn start_add_point_cloud_selection(
+    world: &mut World,
+    choose: impl std::future::Future<Output = Option<Source>> + 'static,
+) {
+    let (sender, receiver) = flume::bounded::<Source>(1);
+    world.insert_non_send_resource(receiver);
+
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Some(source) = choose.await {
+            let _ = sender.send(source);
+        }
+    });
 }
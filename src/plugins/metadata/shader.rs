@@ -1,7 +1,9 @@
 use bevy_ecs::prelude::*;
 use itertools::Itertools;
+use point_converter::metadata::Metadata;
 
-use crate::plugins::metadata::ActiveMetadata;
+use crate::plugins::asset::AssetManagerRes;
+use crate::plugins::metadata::{PointCloud, UpdatedMetadataHierarchiesEvent};
 use crate::plugins::wgpu::{Device, Queue};
 
 #[repr(C)]
@@ -11,7 +13,7 @@ struct Hierarchy {
     spacing: f32,
 }
 
-#[derive(Resource)]
+#[derive(Component)]
 pub struct MetadataBuffer {
     pub buffer: wgpu::Buffer,
     capacity: usize,
@@ -31,43 +33,96 @@ impl MetadataBuffer {
     }
 }
 
-pub(in crate::plugins) fn create_metadata_buffer(mut commands: Commands, device: Res<Device>) {
-    let buffer = MetadataBuffer::new(&device, 0);
-    commands.insert_resource(buffer);
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct PointCloudUniform {
+    model: [[f32; 4]; 4],
+}
+
+/// Placement of a [PointCloud] in the world.
+#[derive(Component)]
+pub struct PointCloudBuffer(pub wgpu::Buffer);
+
+impl PointCloudBuffer {
+    fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("point-cloud-buffer"),
+            size: std::mem::size_of::<PointCloudUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self(buffer)
+    }
+}
+
+pub(in crate::plugins) fn add_point_cloud_buffers(
+    mut commands: Commands,
+    device: Res<Device>,
+    point_cloud_query: Query<Entity, Added<PointCloud>>,
+) {
+    for entity in point_cloud_query.iter() {
+        commands.entity(entity).insert((
+            MetadataBuffer::new(&device, 0),
+            PointCloudBuffer::new(&device),
+        ));
+    }
 }
 
 pub(in crate::plugins) fn update_metadata_buffer(
     device: Res<Device>,
     queue: Res<Queue>,
-    mut metadata_buffer: ResMut<MetadataBuffer>,
-    active_metadata: ActiveMetadata,
+    metadata_manager: AssetManagerRes<Metadata>,
+    mut point_cloud_query: Query<(Ref<PointCloud>, &mut MetadataBuffer)>,
+    mut updated_hierarchies_events: EventReader<UpdatedMetadataHierarchiesEvent>,
 ) {
-    let metadata = active_metadata.get();
+    let updated_hierarchies = updated_hierarchies_events.read().count() > 0;
 
-    let hierarchies = (0..metadata.hierarchies)
-        .map(|hierarchy| {
-            let cell_size = metadata.config.cell_size(hierarchy);
-            let spacing = metadata.config.cell_spacing(cell_size);
+    for (point_cloud, mut metadata_buffer) in point_cloud_query.iter_mut() {
+        if !(point_cloud.is_changed() || updated_hierarchies) {
+            continue;
+        }
 
-            Hierarchy { cell_size, spacing }
-        })
-        .collect_vec();
+        let metadata = metadata_manager.get_asset(&point_cloud.metadata);
 
-    if hierarchies.len() != metadata_buffer.capacity {
-        *metadata_buffer = MetadataBuffer::new(&device, hierarchies.len());
-    }
+        let hierarchies = (0..metadata.hierarchies)
+            .map(|hierarchy| {
+                let cell_size = metadata.config.cell_size(hierarchy);
+                let spacing = metadata.config.cell_spacing(cell_size);
+
+                Hierarchy { cell_size, spacing }
+            })
+            .collect_vec();
 
-    queue.write_buffer(
-        &metadata_buffer.buffer,
-        0,
-        bytemuck::bytes_of(&(hierarchies.len() as u32)),
-    );
+        if hierarchies.len() != metadata_buffer.capacity {
+            *metadata_buffer = MetadataBuffer::new(&device, hierarchies.len());
+        }
 
-    if !hierarchies.is_empty() {
         queue.write_buffer(
             &metadata_buffer.buffer,
-            std::mem::size_of::<u32>() as wgpu::BufferAddress,
-            bytemuck::cast_slice(&hierarchies),
+            0,
+            bytemuck::bytes_of(&(hierarchies.len() as u32)),
         );
+
+        if !hierarchies.is_empty() {
+            queue.write_buffer(
+                &metadata_buffer.buffer,
+                std::mem::size_of::<u32>() as wgpu::BufferAddress,
+                bytemuck::cast_slice(&hierarchies),
+            );
+        }
+    }
+}
+
+pub(in crate::plugins) fn update_point_cloud_buffer(
+    queue: Res<Queue>,
+    point_cloud_query: Query<(&PointCloud, &PointCloudBuffer), Changed<PointCloud>>,
+) {
+    for (point_cloud, point_cloud_buffer) in point_cloud_query.iter() {
+        let uniform = PointCloudUniform {
+            model: point_cloud.transform.compute_matrix().to_cols_array_2d(),
+        };
+
+        queue.write_buffer(&point_cloud_buffer.0, 0, bytemuck::bytes_of(&uniform));
     }
 }
@@ -1,13 +1,13 @@
 use crate::plugins::render::line::LineRenderPlugin;
 use crate::plugins::render::point::PointRenderPlugin;
-use crate::plugins::render::ui::UiPlugin;
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 
 mod bind_groups;
 pub mod line;
 pub mod point;
-mod ui;
+pub mod shader;
+pub mod ui;
 pub mod vertex;
 
 pub struct RenderPlugin;
@@ -19,7 +19,8 @@ impl Plugin for RenderPlugin {
             ((BufferSet, BindGroupLayoutSet), BindGroupSet, PipelineSet).chain(),
         )
         .configure_sets(PostUpdate, (BufferSet, BindGroupSet).chain())
-        .add_plugins((PointRenderPlugin, LineRenderPlugin, UiPlugin));
+        .init_resource::<shader::ShaderSources>()
+        .add_plugins((PointRenderPlugin, LineRenderPlugin));
     }
 }
 
@@ -1,4 +1,5 @@
 pub mod camera;
 pub mod cell;
+pub mod cell_density;
 pub mod resource;
 pub mod texture;
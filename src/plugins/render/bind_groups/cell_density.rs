@@ -0,0 +1,48 @@
+use crate::plugins::render::point::density::CellDensityBuffer;
+use crate::plugins::wgpu::Device;
+use bevy_ecs::prelude::*;
+
+#[derive(Resource)]
+pub struct CellDensityBindGroupLayout(pub wgpu::BindGroupLayout);
+
+pub fn create_bind_group_layout(mut commands: Commands, device: Res<Device>) {
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("cell-density-bind-group-layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0, // cell-density-buffer
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+
+    commands.insert_resource(CellDensityBindGroupLayout(layout));
+}
+
+/// Per cell resources of the render pass.
+#[derive(Component)]
+pub struct CellDensityBindGroup(pub wgpu::BindGroup);
+
+pub fn create_bind_group(
+    mut commands: Commands,
+    device: Res<Device>,
+    layout: Res<CellDensityBindGroupLayout>,
+    query: Query<(Entity, &CellDensityBuffer), Without<CellDensityBindGroup>>,
+) {
+    for (entity, buffer) in query.iter() {
+        let group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("cell-density-bind-group"),
+            layout: &layout.0,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.0.as_entire_binding(),
+            }],
+        });
+
+        commands.entity(entity).insert(CellDensityBindGroup(group));
+    }
+}
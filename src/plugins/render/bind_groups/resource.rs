@@ -1,7 +1,10 @@
 use crate::plugins::cell::shader::{FrustumsBuffer, FrustumsSettings, LoadedCellsBuffer};
-use crate::plugins::metadata::shader::MetadataBuffer;
+use crate::plugins::metadata::shader::{MetadataBuffer, PointCloudBuffer};
+use crate::plugins::render::point::highlight::HighlightSettings;
+use crate::plugins::render::point::soft_points::SoftPoints;
 use crate::plugins::wgpu::Device;
 use bevy_ecs::prelude::*;
+use bevy_ecs::query::QueryData;
 
 #[derive(Resource)]
 pub struct ResourceBindGroupLayout(pub wgpu::BindGroupLayout);
@@ -50,54 +53,121 @@ pub fn create_bind_group_layout(mut commands: Commands, device: Res<Device>) {
                 },
                 count: None,
             },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4, // highlight settings
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 5, // point cloud
+                visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 6, // soft points
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
         ],
     });
 
     commands.insert_resource(ResourceBindGroupLayout(layout));
 }
 
-#[derive(Resource)]
+/// Resources of a single [PointCloud](crate::plugins::metadata::PointCloud).
+#[derive(Component)]
 pub struct ResourceBindGroup(pub wgpu::BindGroup);
 
+#[derive(QueryData)]
+pub struct PointCloudBuffersQueryData {
+    entity: Entity,
+    metadata: Ref<'static, MetadataBuffer>,
+    point_cloud: Ref<'static, PointCloudBuffer>,
+    loaded_cells: Ref<'static, LoadedCellsBuffer>,
+    frustums: Ref<'static, FrustumsBuffer>,
+    bind_group: Option<&'static ResourceBindGroup>,
+}
+
 pub fn create_bind_group(
     mut commands: Commands,
     device: Res<Device>,
     layout: Res<ResourceBindGroupLayout>,
-    metadata: Res<MetadataBuffer>,
-    loaded_cells: Res<LoadedCellsBuffer>,
-    frustums: Res<FrustumsBuffer>,
+    point_cloud_query: Query<PointCloudBuffersQueryData>,
     frustums_settings: Res<FrustumsSettings>,
+    highlight_settings: Res<HighlightSettings>,
+    soft_points: Res<SoftPoints>,
 ) {
-    if !(metadata.is_changed()
-        || loaded_cells.is_changed()
-        || frustums.is_changed()
-        || frustums_settings.is_changed())
+    for PointCloudBuffersQueryDataItem {
+        entity,
+        metadata,
+        point_cloud,
+        loaded_cells,
+        frustums,
+        bind_group,
+    } in point_cloud_query.iter()
     {
-        return;
-    }
+        if !(bind_group.is_none()
+            || metadata.is_changed()
+            || point_cloud.is_changed()
+            || loaded_cells.is_changed()
+            || frustums.is_changed()
+            || frustums_settings.is_changed()
+            || highlight_settings.is_changed()
+            || soft_points.is_changed())
+        {
+            continue;
+        }
 
-    let group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("point-resource-bind-group"),
-        layout: &layout.0,
-        entries: &[
-            wgpu::BindGroupEntry {
-                binding: 0,
-                resource: metadata.buffer.as_entire_binding(),
-            },
-            wgpu::BindGroupEntry {
-                binding: 1,
-                resource: loaded_cells.buffer.as_entire_binding(),
-            },
-            wgpu::BindGroupEntry {
-                binding: 2,
-                resource: frustums.buffer.as_entire_binding(),
-            },
-            wgpu::BindGroupEntry {
-                binding: 3,
-                resource: frustums_settings.buffer.as_entire_binding(),
-            },
-        ],
-    });
+        let group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("point-resource-bind-group"),
+            layout: &layout.0,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: metadata.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: loaded_cells.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: frustums.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: frustums_settings.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: highlight_settings.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: point_cloud.0.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: soft_points.buffer.as_entire_binding(),
+                },
+            ],
+        });
 
-    commands.insert_resource(ResourceBindGroup(group));
+        commands.entity(entity).insert(ResourceBindGroup(group));
+    }
 }
@@ -4,6 +4,7 @@ use glam::Vec3;
 
 use crate::plugins::camera::Camera;
 use crate::plugins::render::bind_groups::camera::{CameraBindGroup, CameraBindGroupLayout};
+use crate::plugins::render::shader::{Shader, ShaderSources};
 use crate::plugins::render::vertex::VertexBuffer;
 use crate::plugins::render::PipelineSet;
 use crate::plugins::wgpu::{
@@ -38,7 +39,7 @@ pub struct LineRenderPlugin;
 
 impl Plugin for LineRenderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup.in_set(PipelineSet))
+        app.add_systems(Startup, create_pipeline.in_set(PipelineSet))
             .add_systems(Render, draw.in_set(RenderPassSet));
 
         app.world_mut()
@@ -53,13 +54,14 @@ struct RenderResources {
     pipeline: wgpu::RenderPipeline,
 }
 
-fn setup(
+pub(crate) fn create_pipeline(
     mut commands: Commands,
     device: Res<Device>,
     config: Res<SurfaceConfig>,
     camera_bind_group_layout: Res<CameraBindGroupLayout>,
+    shader_sources: Res<ShaderSources>,
 ) {
-    let shader = device.create_shader_module(wgpu::include_wgsl!("line/line.wgsl"));
+    let shader = shader_sources.create_module(&device, Shader::Line);
 
     let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("line-renderer-pipeline-layout"),
@@ -84,13 +86,7 @@ fn setup(
             unclipped_depth: false,
             conservative: false,
         },
-        depth_stencil: Some(wgpu::DepthStencilState {
-            format: Texture::DEPTH_TEXTURE_FORMAT,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
+        depth_stencil: Some(Texture::depth_stencil_state(true)),
         multisample: wgpu::MultisampleState {
             count: 1,
             mask: !0,
@@ -100,7 +96,7 @@ fn setup(
             module: &shader,
             entry_point: "fs_main",
             targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
+                format: config.color_format(),
                 blend: Some(wgpu::BlendState::REPLACE),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
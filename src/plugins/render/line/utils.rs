@@ -4,6 +4,7 @@ use glam::Vec3;
 use itertools::Itertools;
 
 use crate::plugins::render::line::Line;
+use crate::transform::Transform;
 
 pub fn line_strip(color: [u8; 4], points: &[Vec3]) -> Vec<Line> {
     points
@@ -46,6 +47,13 @@ pub fn line_box(color: [u8; 4], pos: Vec3, half_extends: Vec3) -> Vec<Line> {
     .collect()
 }
 
+pub fn transform_lines(transform: &Transform, lines: &mut [Line]) {
+    for line in lines {
+        line.start = transform.transform_point(line.start);
+        line.end = transform.transform_point(line.end);
+    }
+}
+
 pub fn line_sphere(
     color: [u8; 4],
     pos: Vec3,
@@ -5,23 +5,34 @@ use bevy_ecs::system::SystemParam;
 use bevy_state::prelude::*;
 use glam::Vec3;
 use itertools::Itertools;
+use point_converter::cell::Cell;
 
+use crate::plugins::asset::AssetHandle;
 use crate::plugins::camera::{Camera, Visibility};
 use crate::plugins::cell::shader::{
     CellIndirectBuffer, CellInputVertexBuffer, CellOutputVertexBuffer,
 };
 use crate::plugins::cell::{CellHeader, StreamState};
+use crate::plugins::input::{HotkeyAction, HotkeyEvent};
+use crate::plugins::metadata::{MetadataState, PointCloud};
 use crate::plugins::render::bind_groups::camera::CameraBindGroup;
 use crate::plugins::render::bind_groups::cell::CellBindGroup;
+use crate::plugins::render::bind_groups::cell_density::CellDensityBindGroup;
 use crate::plugins::render::bind_groups::resource::ResourceBindGroup;
 use crate::plugins::render::bind_groups::texture::TextureBindGroup;
 use crate::plugins::render::point::pipelines::compute::PointComputePipeLine;
 use crate::plugins::render::point::pipelines::render::PointRenderPipeline;
-use crate::plugins::render::{bind_groups, BindGroupLayoutSet, BindGroupSet, PipelineSet};
+use crate::plugins::render::{
+    bind_groups, BindGroupLayoutSet, BindGroupSet, BufferSet, PipelineSet,
+};
 use crate::plugins::wgpu::{CommandEncoders, GlobalRenderResources, Render, RenderPassSet};
 use crate::transform::Transform;
 
+pub mod density;
+pub mod drawn_points;
+pub mod highlight;
 pub mod pipelines;
+pub mod soft_points;
 
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
@@ -31,6 +42,21 @@ pub struct Point {
 }
 
 impl Point {
+    /// Lower bits of the alpha which hold the hierarchy of the point, see render.wgsl.
+    pub const HIERARCHY_MASK: u8 = 0x1F;
+
+    /// Keeps the upper bits of the alpha and stores the hierarchy in the lower ones,
+    /// like the compute pass does for the points which it filters.
+    pub fn new(position: Vec3, color: [u8; 4], hierarchy: u32) -> Self {
+        let [r, g, b, a] = color;
+        let a = (a & !Self::HIERARCHY_MASK) | (hierarchy as u8 & Self::HIERARCHY_MASK);
+
+        Self {
+            position,
+            color: [r, g, b, a],
+        }
+    }
+
     pub fn instance_desc() -> wgpu::VertexBufferLayout<'static> {
         const ATTRIBS: [wgpu::VertexAttribute; 2] =
             wgpu::vertex_attr_array![0 => Float32x3, 1 => Uint32];
@@ -50,15 +76,20 @@ impl Plugin for PointRenderPlugin {
         app.add_systems(
             Startup,
             (
+                (
+                    highlight::create_highlight_settings_buffer,
+                    soft_points::create_soft_points_buffer,
+                )
+                    .in_set(BufferSet),
                 (
                     bind_groups::resource::create_bind_group_layout,
                     bind_groups::camera::create_bind_group_layout,
                     bind_groups::cell::create_bind_group_layout,
+                    bind_groups::cell_density::create_bind_group_layout,
                     bind_groups::texture::create_bind_group_layout,
                 )
                     .in_set(BindGroupLayoutSet),
                 (
-                    bind_groups::resource::create_bind_group,
                     bind_groups::camera::create_bind_group,
                     bind_groups::cell::create_bind_group,
                     bind_groups::texture::create_bind_group,
@@ -71,6 +102,35 @@ impl Plugin for PointRenderPlugin {
                     .in_set(PipelineSet),
             ),
         )
+        .add_systems(
+            OnEnter(MetadataState::Loaded),
+            highlight::reset_highlight_range,
+        )
+        .init_resource::<density::DensityHeatmap>()
+        .init_resource::<drawn_points::DrawnPoints>()
+        .init_resource::<PointFilter>()
+        .add_systems(Update, (cycle_render_mode, drawn_points::read_drawn_points))
+        .add_systems(
+            PostUpdate,
+            (
+                density::add_cell_density_buffers,
+                density::update_cell_density_buffers,
+            )
+                .chain()
+                .in_set(BufferSet),
+        )
+        .add_systems(
+            PostUpdate,
+            highlight::update_highlight_settings_buffer
+                .run_if(resource_changed::<highlight::HighlightSettings>)
+                .in_set(BufferSet),
+        )
+        .add_systems(
+            PostUpdate,
+            soft_points::update_soft_points_buffer
+                .run_if(resource_changed::<soft_points::SoftPoints>)
+                .in_set(BufferSet),
+        )
         .add_systems(
             PostUpdate,
             (
@@ -84,10 +144,16 @@ impl Plugin for PointRenderPlugin {
                     bind_groups::cell::create_bind_group,
                 ),
                 bind_groups::texture::create_bind_group,
+                bind_groups::cell_density::create_bind_group,
             )
                 .in_set(BindGroupSet),
         )
-        .add_systems(Render, draw.in_set(RenderPassSet));
+        .add_systems(
+            Render,
+            (draw, drawn_points::copy_drawn_points)
+                .chain()
+                .in_set(RenderPassSet),
+        );
 
         app.world_mut()
             .get_resource_mut::<CommandEncoders>()
@@ -96,13 +162,47 @@ impl Plugin for PointRenderPlugin {
     }
 }
 
+/// Cycles through voronoi splats, plain splats and highlighted voronoi splats.
+fn cycle_render_mode(
+    mut hotkey_events: EventReader<HotkeyEvent>,
+    mut render_pipeline: ResMut<PointRenderPipeline>,
+    mut highlight_settings: ResMut<highlight::HighlightSettings>,
+) {
+    for event in hotkey_events.read() {
+        if event.0 != HotkeyAction::CycleRenderMode {
+            continue;
+        }
+
+        if highlight_settings.enabled {
+            highlight_settings.enabled = false;
+            render_pipeline.use_voronoi = true;
+        } else if render_pipeline.use_voronoi {
+            render_pipeline.use_voronoi = false;
+        } else {
+            highlight_settings.enabled = true;
+            render_pipeline.use_voronoi = true;
+        }
+    }
+}
+
+/// Whether the compute pass filters the points before they are drawn.
+/// Disabling it draws all uploaded points, which separates rendering issues from filtering bugs.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Resource)]
+pub enum PointFilter {
+    #[default]
+    Enabled,
+    Disabled,
+}
+
 #[derive(QueryData)]
 struct CellQueryData {
     bind_group: &'static CellBindGroup,
+    density_bind_group: &'static CellDensityBindGroup,
     input: &'static CellInputVertexBuffer,
     output: &'static CellOutputVertexBuffer,
     indirect: &'static CellIndirectBuffer,
     header: &'static CellHeader,
+    handle: &'static AssetHandle<Cell>,
     visibility: &'static Visibility,
 }
 
@@ -110,8 +210,8 @@ struct CellQueryData {
 struct RenderResources<'w> {
     compute_pipeline: Res<'w, PointComputePipeLine>,
     render_pipeline: Res<'w, PointRenderPipeline>,
-    resource_bind_group: Res<'w, ResourceBindGroup>,
     texture_bind_group: Res<'w, TextureBindGroup>,
+    point_filter: Res<'w, PointFilter>,
 }
 
 fn draw(
@@ -119,6 +219,7 @@ fn draw(
     render_resources: RenderResources,
     camera_query: Query<(&CameraBindGroup, &Transform), With<Camera>>,
     cell_query: Query<CellQueryData>,
+    point_cloud_query: Query<(&PointCloud, &ResourceBindGroup)>,
     stream_state: Res<State<StreamState>>,
 ) {
     global_render_resources
@@ -128,22 +229,32 @@ fn draw(
                 let cell_groups = cell_query
                     .iter()
                     .filter(|cell| cell.visibility.visible)
-                    .map(|cell| {
-                        (
-                            cell.header.0.pos.distance(camera_transform.translation) as u32,
-                            cell,
-                        )
+                    .filter_map(|cell| {
+                        let (point_cloud, resource_bind_group) =
+                            point_cloud_query.get(cell.handle.id().point_cloud).ok()?;
+
+                        if !point_cloud.visible {
+                            return None;
+                        }
+
+                        let distance = point_cloud
+                            .transform
+                            .transform_point(cell.header.0.pos)
+                            .distance(camera_transform.translation);
+
+                        Some((distance as u32, (cell, resource_bind_group)))
                     })
                     .sorted_unstable_by_key(|(distance, _)| *distance)
                     .group_by(|(distance, _)| distance.checked_ilog2().unwrap_or(0));
 
-                let filter_occluded_points = *stream_state == StreamState::Enabled;
+                let filter_points = *render_resources.point_filter == PointFilter::Enabled;
+                let filter_occluded_points = filter_points && *stream_state == StreamState::Enabled;
 
                 for (_, group) in &cell_groups {
                     let cells = group.map(|(_, cell)| cell).collect_vec();
 
                     if filter_occluded_points {
-                        for cell in &cells {
+                        for (cell, _) in &cells {
                             encoder.clear_buffer(
                                 &cell.indirect.0,
                                 std::mem::size_of::<u32>() as wgpu::BufferAddress,
@@ -159,14 +270,10 @@ fn draw(
 
                         compute_pass.set_pipeline(&render_resources.compute_pipeline.0);
                         compute_pass.set_bind_group(0, &camera_bind_group.0, &[]);
-                        compute_pass.set_bind_group(
-                            1,
-                            &render_resources.resource_bind_group.0,
-                            &[],
-                        );
                         compute_pass.set_bind_group(3, &render_resources.texture_bind_group.0, &[]);
 
-                        for cell in &cells {
+                        for (cell, resource_bind_group) in &cells {
+                            compute_pass.set_bind_group(1, &resource_bind_group.0, &[]);
                             compute_pass.set_bind_group(2, &cell.bind_group.0, &[]);
                             compute_pass.dispatch_workgroups(cell.input.len().div_ceil(128), 1, 1);
                         }
@@ -196,19 +303,21 @@ fn draw(
                         occlusion_query_set: None,
                     });
 
-                    let render_pipeline = if render_resources.render_pipeline.use_voronoi {
-                        &render_resources.render_pipeline.voronoi
-                    } else {
-                        &render_resources.render_pipeline.no_voronoi
-                    };
-
-                    render_pass.set_pipeline(render_pipeline);
+                    render_pass.set_pipeline(render_resources.render_pipeline.active());
                     render_pass.set_bind_group(0, &camera_bind_group.0, &[]);
-                    render_pass.set_bind_group(1, &render_resources.resource_bind_group.0, &[]);
 
-                    for cell in &cells {
-                        render_pass.set_vertex_buffer(0, cell.output.0.slice(..));
-                        render_pass.draw_indirect(&cell.indirect.0, 0);
+                    for (cell, resource_bind_group) in &cells {
+                        render_pass.set_bind_group(1, &resource_bind_group.0, &[]);
+                        render_pass.set_bind_group(2, &cell.density_bind_group.0, &[]);
+
+                        if filter_points {
+                            render_pass.set_vertex_buffer(0, cell.output.0.slice(..));
+                            render_pass.draw_indirect(&cell.indirect.0, 0);
+                        } else {
+                            render_pass.set_vertex_buffer(0, cell.input.buffer.slice(..));
+                            render_pass
+                                .draw(0..CellIndirectBuffer::VERTEX_COUNT, 0..cell.input.len());
+                        }
                     }
 
                     drop(render_pass);
@@ -0,0 +1,148 @@
+use bevy_ecs::prelude::*;
+
+use point_converter::colormap::Colormap;
+
+use crate::plugins::cell::CellHeader;
+use crate::plugins::wgpu::{Device, Queue};
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct CellDensityUniform {
+    color: [f32; 3],
+    enabled: u32,
+}
+
+/// Colors every cell by its number of points per volume instead of the colors of its points.
+#[derive(Debug, Resource)]
+pub struct DensityHeatmap {
+    pub enabled: bool,
+    pub colormap: Colormap,
+}
+
+impl Default for DensityHeatmap {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            colormap: Colormap::Viridis,
+        }
+    }
+}
+
+/// Heatmap color of a single cell.
+#[derive(Component)]
+pub struct CellDensityBuffer(pub wgpu::Buffer);
+
+impl CellDensityBuffer {
+    fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("cell-density-buffer"),
+            size: std::mem::size_of::<CellDensityUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self(buffer)
+    }
+}
+
+/// Points per volume on a logarithmic scale,
+/// because the densities of the hierarchies are orders of magnitude apart.
+fn log_density(header: &point_converter::cell::Header) -> f32 {
+    let volume = header.size.powi(3);
+    (header.total_number_of_points as f32 / volume)
+        .max(f32::MIN_POSITIVE)
+        .log10()
+}
+
+pub(super) fn add_cell_density_buffers(
+    mut commands: Commands,
+    device: Res<Device>,
+    cell_query: Query<(Entity, &CellHeader), Without<CellDensityBuffer>>,
+) {
+    for (entity, header) in cell_query.iter() {
+        if header.0.total_number_of_points == 0 {
+            continue;
+        }
+
+        commands
+            .entity(entity)
+            .insert(CellDensityBuffer::new(&device));
+    }
+}
+
+/// Cells which got a density buffer or whose number of points changed.
+type ChangedCellDensity = Or<(Added<CellDensityBuffer>, Changed<CellHeader>)>;
+
+/// The colormap spans the densities of all loaded cells, so all cells are updated if one changes.
+pub(super) fn update_cell_density_buffers(
+    queue: Res<Queue>,
+    density_heatmap: Res<DensityHeatmap>,
+    cell_query: Query<(&CellHeader, &CellDensityBuffer)>,
+    changed_cell_query: Query<(), ChangedCellDensity>,
+    mut removed_cells: RemovedComponents<CellHeader>,
+) {
+    let removed_cells = removed_cells.read().count() > 0;
+
+    if !density_heatmap.is_changed() && changed_cell_query.is_empty() && !removed_cells {
+        return;
+    }
+
+    let (min, max) = cell_query
+        .iter()
+        .map(|(header, _)| log_density(&header.0))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), density| {
+            (min.min(density), max.max(density))
+        });
+
+    let range = max - min;
+
+    for (header, buffer) in cell_query.iter() {
+        let uniform = if density_heatmap.enabled {
+            let value = if range > 0.0 {
+                (log_density(&header.0) - min) / range
+            } else {
+                1.0
+            };
+
+            let color = density_heatmap.colormap.map(value);
+
+            CellDensityUniform {
+                color: [color.r, color.g, color.b].map(|channel| channel as f32 / 255.0),
+                enabled: 1,
+            }
+        } else {
+            CellDensityUniform::default()
+        };
+
+        queue.write_buffer(&buffer.0, 0, bytemuck::bytes_of(&uniform));
+    }
+}
+
+pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
+    let mut density_heatmap = world.get_resource_mut::<DensityHeatmap>().unwrap();
+
+    let mut enabled = density_heatmap.enabled;
+    if ui
+        .checkbox(&mut enabled, "Density heatmap")
+        .on_hover_text("Colors the cells by their points per volume")
+        .changed()
+    {
+        density_heatmap.enabled = enabled;
+    }
+
+    ui.add_enabled_ui(enabled, |ui| {
+        let mut colormap = density_heatmap.colormap;
+
+        egui::ComboBox::from_label("Colormap")
+            .selected_text(format!("{:?}", colormap))
+            .show_ui(ui, |ui| {
+                for option in [Colormap::Viridis, Colormap::Grayscale] {
+                    ui.selectable_value(&mut colormap, option, format!("{:?}", option));
+                }
+            });
+
+        if colormap != density_heatmap.colormap {
+            density_heatmap.colormap = colormap;
+        }
+    });
+}
@@ -0,0 +1,201 @@
+use std::sync::{Arc, Mutex};
+
+use bevy_ecs::prelude::*;
+use itertools::Itertools;
+use point_converter::cell::Cell;
+use web_time::{Duration, Instant};
+
+use crate::plugins::asset::AssetHandle;
+use crate::plugins::camera::Visibility;
+use crate::plugins::cell::shader::{CellIndirectBuffer, CellInputVertexBuffer};
+use crate::plugins::metadata::PointCloud;
+use crate::plugins::render::bind_groups::cell::CellBindGroup;
+use crate::plugins::render::bind_groups::cell_density::CellDensityBindGroup;
+use crate::plugins::render::point::{PointFilter, PointRenderPlugin};
+use crate::plugins::wgpu::{CommandEncoders, Device};
+
+/// Offset of `instance_count` inside of [wgpu::util::DrawIndirectArgs].
+const INSTANCE_COUNT_OFFSET: wgpu::BufferAddress =
+    std::mem::size_of::<u32>() as wgpu::BufferAddress;
+const INSTANCE_COUNT_SIZE: wgpu::BufferAddress = std::mem::size_of::<u32>() as wgpu::BufferAddress;
+
+/// Exact number of points which are drawn, summed up from the instance counts
+/// which the compute pass writes into the [CellIndirectBuffer] of every cell.
+/// Reading them back isn't free, so it is disabled by default and only sampled every `interval`.
+#[derive(Resource)]
+pub struct DrawnPoints {
+    pub enabled: bool,
+    pub interval: Duration,
+    /// Result of the last finished sample.
+    pub count: Option<u64>,
+    last_sample: Option<Instant>,
+    readback: Option<Readback>,
+}
+
+impl Default for DrawnPoints {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(1),
+            count: None,
+            last_sample: None,
+            readback: None,
+        }
+    }
+}
+
+impl DrawnPoints {
+    fn should_sample(&self, now: Instant) -> bool {
+        self.enabled
+            && self.readback.is_none()
+            && self
+                .last_sample
+                .is_none_or(|last_sample| now.duration_since(last_sample) >= self.interval)
+    }
+}
+
+struct Readback {
+    buffer: wgpu::Buffer,
+    mapped: Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>,
+    mapping_requested: bool,
+}
+
+/// Cells which are drawn by [super::draw] once they are visible.
+type DrawnCell = (With<CellBindGroup>, With<CellDensityBindGroup>);
+
+/// Copies the instance counts after the points were drawn,
+/// they are mapped once the frame has been submitted, see [read_drawn_points].
+pub(super) fn copy_drawn_points(
+    device: Res<Device>,
+    mut encoders: ResMut<CommandEncoders>,
+    mut drawn_points: ResMut<DrawnPoints>,
+    point_filter: Res<PointFilter>,
+    cell_query: Query<
+        (
+            &CellIndirectBuffer,
+            &CellInputVertexBuffer,
+            &AssetHandle<Cell>,
+            &Visibility,
+        ),
+        DrawnCell,
+    >,
+    point_cloud_query: Query<&PointCloud>,
+) {
+    let now = Instant::now();
+
+    if !drawn_points.should_sample(now) {
+        return;
+    }
+
+    drawn_points.last_sample = Some(now);
+
+    let drawn_cells = cell_query.iter().filter(|(_, _, handle, visibility)| {
+        visibility.visible
+            && point_cloud_query
+                .get(handle.id().point_cloud)
+                .is_ok_and(|point_cloud| point_cloud.visible)
+    });
+
+    // All uploaded points are drawn, so there is nothing to read back
+    if *point_filter == PointFilter::Disabled {
+        drawn_points.count = Some(drawn_cells.map(|(_, input, _, _)| input.len() as u64).sum());
+        return;
+    }
+
+    let indirect_buffers = drawn_cells
+        .map(|(indirect, _, _, _)| indirect)
+        .collect_vec();
+
+    if indirect_buffers.is_empty() {
+        drawn_points.count = Some(0);
+        return;
+    }
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("drawn-points-readback-buffer"),
+        size: indirect_buffers.len() as wgpu::BufferAddress * INSTANCE_COUNT_SIZE,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    encoders.encode::<PointRenderPlugin>(|encoder| {
+        for (i, indirect) in indirect_buffers.iter().enumerate() {
+            encoder.copy_buffer_to_buffer(
+                &indirect.0,
+                INSTANCE_COUNT_OFFSET,
+                &buffer,
+                i as wgpu::BufferAddress * INSTANCE_COUNT_SIZE,
+                INSTANCE_COUNT_SIZE,
+            );
+        }
+    });
+
+    drawn_points.readback = Some(Readback {
+        buffer,
+        mapped: Arc::new(Mutex::new(None)),
+        mapping_requested: false,
+    });
+}
+
+/// Maps the copied instance counts without blocking and sums them up as soon as they are available.
+pub(super) fn read_drawn_points(device: Res<Device>, mut drawn_points: ResMut<DrawnPoints>) {
+    let drawn_points = &mut *drawn_points;
+
+    let Some(readback) = &mut drawn_points.readback else {
+        return;
+    };
+
+    if !readback.mapping_requested {
+        let mapped = readback.mapped.clone();
+
+        readback
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *mapped.lock().unwrap() = Some(result);
+            });
+
+        readback.mapping_requested = true;
+    }
+
+    device.poll(wgpu::Maintain::Poll);
+
+    let Some(result) = readback.mapped.lock().unwrap().take() else {
+        return;
+    };
+
+    match result {
+        Ok(()) => {
+            let count =
+                bytemuck::cast_slice::<u8, u32>(&readback.buffer.slice(..).get_mapped_range())
+                    .iter()
+                    .map(|&instance_count| instance_count as u64)
+                    .sum();
+
+            readback.buffer.unmap();
+            drawn_points.count = Some(count);
+        }
+        Err(err) => {
+            log::warn!("Failed to read back the drawn points: {}", err);
+        }
+    }
+
+    drawn_points.readback = None;
+}
+
+pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
+    let mut drawn_points = world.get_resource_mut::<DrawnPoints>().unwrap();
+
+    let mut enabled = drawn_points.enabled;
+    if ui
+        .checkbox(&mut enabled, "Count drawn points")
+        .on_hover_text("Periodically reads back how many points passed the compute pass")
+        .changed()
+    {
+        drawn_points.enabled = enabled;
+
+        if !enabled {
+            drawn_points.count = None;
+        }
+    }
+}
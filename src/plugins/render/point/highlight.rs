@@ -0,0 +1,154 @@
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemState;
+
+use point_converter::metadata::Metadata;
+
+use crate::plugins::metadata::ActiveMetadata;
+use crate::plugins::wgpu::{Device, Queue};
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum HighlightAttribute {
+    X,
+    Y,
+    #[default]
+    Z,
+    Luminance,
+}
+
+impl HighlightAttribute {
+    const ALL: [HighlightAttribute; 4] = [Self::X, Self::Y, Self::Z, Self::Luminance];
+
+    fn label(&self) -> &'static str {
+        match self {
+            HighlightAttribute::X => "X",
+            HighlightAttribute::Y => "Y",
+            HighlightAttribute::Z => "Z (Height)",
+            HighlightAttribute::Luminance => "Luminance",
+        }
+    }
+
+    /// Range of values this attribute can have in the given point cloud.
+    fn bounds(&self, metadata: &Metadata) -> (f32, f32) {
+        let aabb = &metadata.bounding_box;
+
+        match self {
+            HighlightAttribute::X => (aabb.min.x, aabb.max.x),
+            HighlightAttribute::Y => (aabb.min.y, aabb.max.y),
+            HighlightAttribute::Z => (aabb.min.z, aabb.max.z),
+            HighlightAttribute::Luminance => (0.0, 1.0),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct HighlightUniform {
+    enabled: u32,
+    attribute: u32,
+    min: f32,
+    max: f32,
+}
+
+/// Tints points whose attribute lies inside `min..=max` and dims all others.
+#[derive(Resource)]
+pub struct HighlightSettings {
+    pub enabled: bool,
+    pub attribute: HighlightAttribute,
+    pub min: f32,
+    pub max: f32,
+    pub buffer: wgpu::Buffer,
+}
+
+impl HighlightSettings {
+    fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("highlight-settings-buffer"),
+            size: std::mem::size_of::<HighlightUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            enabled: false,
+            attribute: HighlightAttribute::default(),
+            min: 0.0,
+            max: 0.0,
+            buffer,
+        }
+    }
+}
+
+pub(super) fn create_highlight_settings_buffer(mut commands: Commands, device: Res<Device>) {
+    commands.insert_resource(HighlightSettings::new(&device));
+}
+
+pub(super) fn update_highlight_settings_buffer(
+    queue: Res<Queue>,
+    highlight_settings: Res<HighlightSettings>,
+) {
+    let uniform = HighlightUniform {
+        enabled: highlight_settings.enabled as u32,
+        attribute: highlight_settings.attribute as u32,
+        min: highlight_settings.min,
+        max: highlight_settings.max,
+    };
+
+    queue.write_buffer(&highlight_settings.buffer, 0, bytemuck::bytes_of(&uniform));
+}
+
+pub(super) fn reset_highlight_range(
+    active_metadata: ActiveMetadata,
+    mut highlight_settings: ResMut<HighlightSettings>,
+) {
+    let (min, max) = highlight_settings.attribute.bounds(active_metadata.get());
+    highlight_settings.min = min;
+    highlight_settings.max = max;
+}
+
+pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
+    let mut params = SystemState::<(ActiveMetadata, ResMut<HighlightSettings>)>::new(world);
+    let (active_metadata, mut highlight_settings) = params.get_mut(world);
+
+    let mut enabled = highlight_settings.enabled;
+    if ui.checkbox(&mut enabled, "Highlight range").changed() {
+        highlight_settings.enabled = enabled;
+    }
+
+    ui.add_enabled_ui(enabled, |ui| {
+        let mut attribute = highlight_settings.attribute;
+
+        egui::ComboBox::from_label("Attribute")
+            .selected_text(attribute.label())
+            .show_ui(ui, |ui| {
+                for option in HighlightAttribute::ALL {
+                    ui.selectable_value(&mut attribute, option, option.label());
+                }
+            });
+
+        let (lower, upper) = attribute.bounds(active_metadata.get());
+
+        if attribute != highlight_settings.attribute {
+            highlight_settings.attribute = attribute;
+            highlight_settings.min = lower;
+            highlight_settings.max = upper;
+        }
+
+        let mut min = highlight_settings.min;
+        let mut max = highlight_settings.max;
+
+        let min_changed = ui
+            .add(egui::Slider::new(&mut min, lower..=upper).text("Min"))
+            .changed();
+        let max_changed = ui
+            .add(egui::Slider::new(&mut max, lower..=upper).text("Max"))
+            .changed();
+
+        if min_changed {
+            highlight_settings.min = min;
+            highlight_settings.max = max.max(min);
+        } else if max_changed {
+            highlight_settings.max = max;
+            highlight_settings.min = min.min(max);
+        }
+    });
+}
@@ -2,6 +2,7 @@ use crate::plugins::render::bind_groups::camera::CameraBindGroupLayout;
 use crate::plugins::render::bind_groups::cell::CellBindGroupLayout;
 use crate::plugins::render::bind_groups::resource::ResourceBindGroupLayout;
 use crate::plugins::render::bind_groups::texture::TextureBindGroupLayout;
+use crate::plugins::render::shader::{Shader, ShaderSources};
 use crate::plugins::wgpu::Device;
 use bevy_ecs::change_detection::Res;
 use bevy_ecs::prelude::{Commands, Resource};
@@ -16,8 +17,9 @@ pub fn create_compute_pipeline(
     resource_bind_group_layout: Res<ResourceBindGroupLayout>,
     cell_bind_group_layout: Res<CellBindGroupLayout>,
     texture_bind_group_layout: Res<TextureBindGroupLayout>,
+    shader_sources: Res<ShaderSources>,
 ) {
-    let compute_shader = device.create_shader_module(wgpu::include_wgsl!("compute.wgsl"));
+    let compute_shader = shader_sources.create_module(&device, Shader::PointCompute);
 
     let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("point-compute-pipeline-layout"),
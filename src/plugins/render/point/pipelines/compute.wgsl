@@ -43,6 +43,13 @@ struct FrustumsSettings {
 @group(1) @binding(3)
 var<uniform> frustums_settings: FrustumsSettings;
 
+struct PointCloud {
+    model: mat4x4<f32>
+}
+
+@group(1) @binding(5)
+var<uniform> point_cloud: PointCloud;
+
 struct Point {
     position: vec3<f32>,
     color: u32 // vec4<u8>
@@ -82,11 +89,12 @@ fn cell_index(position: vec3<f32>, cell_size: f32) -> vec3<i32> {
     return vec3<i32>(floor(position / cell_size));
 }
 
-fn get_hierarchy(position: vec3<f32>) -> u32 {
+// cells are searched relative to the point cloud, the camera distance is measured in the world
+fn get_hierarchy(position: vec3<f32>, world_position: vec3<f32>) -> u32 {
     let own_hierarchy = search_smallest_hierarchy(position, cell.hierarchy);
 
     if (bool(frustums_settings.size_by_distance)) {
-        let distance_to_camera = distance(vp.cam_pos, position);
+        let distance_to_camera = distance(vp.cam_pos, world_position);
 
         for (var i = frustums_settings.max_hierarchy; i > own_hierarchy; i--) {
             if (distance_to_camera < frustums_far_distances[i]) {
@@ -163,6 +171,8 @@ fn unpack4x8(packed: u32) -> vec4<u32> {
     );
 }
 
+const HIERARCHY_MASK = 0x1Fu;
+
 fn pack4x8(v: vec4<u32>) -> u32 {
     return (v.w << 24) | (v.z << 16) | (v.y << 8) | v.x;
 }
@@ -176,8 +186,9 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     }
 
     let input = in[in_index];
+    let world_position = (point_cloud.model * vec4(input.position, 1.0)).xyz;
 
-    let view = vp.view * vec4(input.position, 1.0);
+    let view = vp.view * vec4(world_position, 1.0);
     let clip = vp.projection * view;
     let ndc = clip.xyz / clip.w;
 
@@ -185,7 +196,7 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         let uv = vec2<u32>((ndc.xy * vec2(0.5, -0.5) + 0.5) * vec2<f32>(textureDimensions(depth_texture)));
         let depth = textureLoad(depth_texture, uv, 0);
        
-        let hierarchy = get_hierarchy(input.position);
+        let hierarchy = get_hierarchy(input.position, world_position);
         let radius = metadata.hierarchies[hierarchy].spacing;
         
         let moved_clip = vp.projection * vec4(view.xy, view.z + radius, view.w);
@@ -197,7 +208,10 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
             let unpacked_color = unpack4x8(input.color);
 
             var output = input;
-            output.color = pack4x8(vec4(unpacked_color.xyz, hierarchy));
+            // The lower bits of the alpha byte carry the hierarchy for the render pass,
+            // the upper bits keep a quantized alpha.
+            let alpha_and_hierarchy = (unpacked_color.w & ~HIERARCHY_MASK) | (hierarchy & HIERARCHY_MASK);
+            output.color = pack4x8(vec4(unpacked_color.xyz, alpha_and_hierarchy));
 
             let old_index = atomicAdd(&indirect_buffer.instance_count, 1u);
             out[old_index] = output;
@@ -1,30 +1,78 @@
 use crate::plugins::render::bind_groups::camera::CameraBindGroupLayout;
+use crate::plugins::render::bind_groups::cell_density::CellDensityBindGroupLayout;
 use crate::plugins::render::bind_groups::resource::ResourceBindGroupLayout;
 use crate::plugins::render::point::Point;
+use crate::plugins::render::shader::{Shader, ShaderSources};
 use crate::plugins::wgpu::{Device, SurfaceConfig};
 use crate::texture::Texture;
 use bevy_ecs::change_detection::Res;
 use bevy_ecs::prelude::{Commands, Resource};
+use bevy_ecs::system::SystemParam;
 
 #[derive(Resource)]
 pub struct PointRenderPipeline {
     pub use_voronoi: bool,
+    pub use_transparency: bool,
+    /// Shades every point as a sphere, which takes precedence over [Self::use_voronoi].
+    pub use_impostors: bool,
+    /// Fades out the edges of the points, see [SoftPoints](crate::plugins::render::point::soft_points::SoftPoints).
+    /// Takes precedence over [Self::use_voronoi], but not over [Self::use_impostors].
+    pub use_soft_points: bool,
     pub voronoi: wgpu::RenderPipeline,
     pub no_voronoi: wgpu::RenderPipeline,
+    pub transparent_voronoi: wgpu::RenderPipeline,
+    pub transparent_no_voronoi: wgpu::RenderPipeline,
+    pub impostor: wgpu::RenderPipeline,
+    pub transparent_impostor: wgpu::RenderPipeline,
+    pub soft: wgpu::RenderPipeline,
+    pub transparent_soft: wgpu::RenderPipeline,
+}
+
+impl PointRenderPipeline {
+    pub fn active(&self) -> &wgpu::RenderPipeline {
+        match (
+            self.use_transparency,
+            self.use_impostors,
+            self.use_soft_points,
+            self.use_voronoi,
+        ) {
+            (false, true, _, _) => &self.impostor,
+            (true, true, _, _) => &self.transparent_impostor,
+            (false, false, true, _) => &self.soft,
+            (true, false, true, _) => &self.transparent_soft,
+            (false, false, false, true) => &self.voronoi,
+            (false, false, false, false) => &self.no_voronoi,
+            (true, false, false, true) => &self.transparent_voronoi,
+            (true, false, false, false) => &self.transparent_no_voronoi,
+        }
+    }
+}
+
+/// Layouts of the bind groups which are set while drawing the points, in the order of their groups.
+#[derive(SystemParam)]
+pub struct PointRenderBindGroupLayouts<'w> {
+    camera: Res<'w, CameraBindGroupLayout>,
+    resource: Res<'w, ResourceBindGroupLayout>,
+    cell_density: Res<'w, CellDensityBindGroupLayout>,
 }
 
 pub fn create_render_pipeline(
     mut commands: Commands,
     device: Res<Device>,
     config: Res<SurfaceConfig>,
-    camera_bind_group_layout: Res<CameraBindGroupLayout>,
-    resource_bind_group_layout: Res<ResourceBindGroupLayout>,
+    bind_group_layouts: PointRenderBindGroupLayouts,
+    shader_sources: Res<ShaderSources>,
+    previous: Option<Res<PointRenderPipeline>>,
 ) {
-    let shader = device.create_shader_module(wgpu::include_wgsl!("render.wgsl"));
+    let shader = shader_sources.create_module(&device, Shader::PointRender);
 
     let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("point-renderer-pipeline-layout"),
-        bind_group_layouts: &[&camera_bind_group_layout.0, &resource_bind_group_layout.0],
+        bind_group_layouts: &[
+            &bind_group_layouts.camera.0,
+            &bind_group_layouts.resource.0,
+            &bind_group_layouts.cell_density.0,
+        ],
         push_constant_ranges: &[],
     });
 
@@ -45,13 +93,7 @@ pub fn create_render_pipeline(
             unclipped_depth: false,
             conservative: false,
         },
-        depth_stencil: Some(wgpu::DepthStencilState {
-            format: Texture::DEPTH_TEXTURE_FORMAT,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
+        depth_stencil: Some(Texture::depth_stencil_state(true)),
         multisample: wgpu::MultisampleState {
             count: 1,
             mask: !0,
@@ -61,35 +103,45 @@ pub fn create_render_pipeline(
         multiview: None,
     };
 
-    let voronoi_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        fragment: Some(wgpu::FragmentState {
-            module: &shader,
-            entry_point: "fs_voronoi",
-            targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
-                blend: Some(wgpu::BlendState::REPLACE),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-        }),
-        ..descriptor.clone()
-    });
+    let create_pipeline = |entry_point: &str, transparent: bool| {
+        let (blend, depth_write_enabled) = if transparent {
+            // Translucent points must not hide the points behind them
+            (wgpu::BlendState::ALPHA_BLENDING, false)
+        } else if entry_point == "fs_soft" {
+            // Only the edges are blended, so the points still occlude each other
+            (wgpu::BlendState::ALPHA_BLENDING, true)
+        } else {
+            (wgpu::BlendState::REPLACE, true)
+        };
 
-    let no_voronoi_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        fragment: Some(wgpu::FragmentState {
-            module: &shader,
-            entry_point: "fs_no_voronoi",
-            targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
-                blend: Some(wgpu::BlendState::REPLACE),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-        }),
-        ..descriptor
-    });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            depth_stencil: Some(Texture::depth_stencil_state(depth_write_enabled)),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.color_format(),
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            ..descriptor.clone()
+        })
+    };
 
+    // the settings are kept if the pipelines are created again for a changed shader
     commands.insert_resource(PointRenderPipeline {
-        use_voronoi: true,
-        voronoi: voronoi_pipeline,
-        no_voronoi: no_voronoi_pipeline,
+        use_voronoi: previous.as_ref().is_none_or(|it| it.use_voronoi),
+        use_transparency: previous.as_ref().is_some_and(|it| it.use_transparency),
+        use_impostors: previous.as_ref().is_some_and(|it| it.use_impostors),
+        use_soft_points: previous.as_ref().is_some_and(|it| it.use_soft_points),
+        voronoi: create_pipeline("fs_voronoi", false),
+        no_voronoi: create_pipeline("fs_no_voronoi", false),
+        transparent_voronoi: create_pipeline("fs_voronoi", true),
+        transparent_no_voronoi: create_pipeline("fs_no_voronoi", true),
+        impostor: create_pipeline("fs_impostor", false),
+        transparent_impostor: create_pipeline("fs_impostor", true),
+        soft: create_pipeline("fs_soft", false),
+        transparent_soft: create_pipeline("fs_soft", true),
     });
 }
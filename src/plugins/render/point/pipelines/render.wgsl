@@ -30,6 +30,49 @@ struct Hierarchy {
 @group(1) @binding(0)
 var<storage, read> metadata: Metadata;
 
+struct Highlight {
+    enabled: u32, // bool
+    attribute_kind: u32, // 0 = x, 1 = y, 2 = z, 3 = luminance
+    min: f32,
+    max: f32
+}
+
+@group(1) @binding(4)
+var<uniform> highlight: Highlight;
+
+struct PointCloud {
+    model: mat4x4<f32>
+}
+
+@group(1) @binding(5)
+var<uniform> point_cloud: PointCloud;
+
+struct SoftPoints {
+    softness: f32 // part of the radius over which the edge fades out
+}
+
+@group(1) @binding(6)
+var<uniform> soft_points: SoftPoints;
+
+struct CellDensity {
+    color: vec3<f32>,
+    enabled: u32 // bool
+}
+
+@group(2) @binding(0)
+var<uniform> cell_density: CellDensity;
+
+// Lower bits of the alpha byte hold the hierarchy, see compute.wgsl
+const HIERARCHY_MASK = 0x1Fu;
+const ALPHA_MASK = 0xE0u;
+
+const HIGHLIGHT_COLOR = vec3<f32>(1.0, 0.85, 0.0);
+const DIMMED_FACTOR = 0.2;
+
+// Impostors are lit by a light slightly above and right of the camera
+const LIGHT_DIRECTION = vec3<f32>(0.3, 0.4, 0.866);
+const AMBIENT = 0.3;
+
 struct InstanceInput {
     @location(0) position: vec3<f32>,
     @location(1) color: u32
@@ -67,6 +110,25 @@ fn unpack4x8(packed: u32) -> vec4<u32> {
     );
 }
 
+fn highlight_color(position: vec3<f32>, color: vec3<f32>) -> vec3<f32> {
+    var value: f32;
+
+    switch highlight.attribute_kind {
+        case 0u, 1u, 2u: {
+            value = position[highlight.attribute_kind];
+        }
+        default: {
+            value = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+        }
+    }
+
+    if (value >= highlight.min && value <= highlight.max) {
+        return mix(color, HIGHLIGHT_COLOR, 0.5);
+    }
+
+    return color * DIMMED_FACTOR;
+}
+
 @vertex
 fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
     var out: VertexOutput;
@@ -78,16 +140,28 @@ fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
 
     let unpacked_color = unpack4x8(instance.color);
 
-    let hierarchy = unpacked_color.w;
+    let hierarchy = unpacked_color.w & HIERARCHY_MASK;
+    let alpha = f32(unpacked_color.w & ALPHA_MASK) / f32(ALPHA_MASK);
     let radius = metadata.hierarchies[hierarchy].spacing;
 
     let local_splat_position = get_splat_position(vertex.index, radius);
     let bill_board_offset = cam_right * local_splat_position.x + cam_up * local_splat_position.y;
-    let billboard_position = vec4<f32>(instance.position + bill_board_offset, 1.0);
+    let world_position = (point_cloud.model * vec4<f32>(instance.position, 1.0)).xyz;
+    let billboard_position = vec4<f32>(world_position + bill_board_offset, 1.0);
 
     out.view_pos = vp.view * billboard_position;
     out.clip_position = vp.view_proj * billboard_position;
-    out.color = vec4<f32>(vec3<f32>(unpacked_color.xyz) / 255.0, 1.0);
+    var color = vec3<f32>(unpacked_color.xyz) / 255.0;
+
+    if (cell_density.enabled != 0u) {
+        color = cell_density.color;
+    }
+
+    if (highlight.enabled != 0u) {
+        color = highlight_color(instance.position, color);
+    }
+
+    out.color = vec4<f32>(color, alpha);
     out.splat_pos = local_splat_position;
     out.splat_radius = radius;
 
@@ -135,4 +209,45 @@ fn fs_no_voronoi(in: VertexOutput) -> FragmentOutput {
     out.color = vec4<f32>(in.color);
 
     return out;
-}
\ No newline at end of file
+}
+@fragment
+fn fs_impostor(in: VertexOutput) -> VoronoiFragmentOutput {
+    var out: VoronoiFragmentOutput;
+
+    let normalized_splat_pos = in.splat_pos / in.splat_radius;
+    let distance_squared = dot(normalized_splat_pos, normalized_splat_pos);
+
+    if (distance_squared > 1.0) {
+        discard;
+    }
+
+    // The splat faces the camera, so the normal of the sphere is already in view space
+    let normal = vec3<f32>(normalized_splat_pos, sqrt(1.0 - distance_squared));
+    let diffuse = max(dot(normal, normalize(LIGHT_DIRECTION)), 0.0);
+    let shading = AMBIENT + (1.0 - AMBIENT) * diffuse;
+
+    let depth_offset = in.splat_radius * normal.z;
+
+    let pos = vp.projection * vec4(in.view_pos.xy, in.view_pos.z + depth_offset, in.view_pos.w);
+
+    out.color = vec4<f32>(in.color.rgb * shading, in.color.a);
+    out.depth = pos.z / pos.w;
+    return out;
+}
+
+@fragment
+fn fs_soft(in: VertexOutput) -> FragmentOutput {
+    var out: FragmentOutput;
+
+    let distance = length(in.splat_pos) / in.splat_radius;
+
+    if (distance > 1.0) {
+        discard;
+    }
+
+    let coverage = 1.0 - smoothstep(1.0 - soft_points.softness, 1.0, distance);
+
+    out.color = vec4<f32>(in.color.rgb, in.color.a * coverage);
+
+    return out;
+}
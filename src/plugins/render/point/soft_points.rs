@@ -0,0 +1,78 @@
+use bevy_ecs::prelude::*;
+
+use crate::plugins::render::point::pipelines::render::PointRenderPipeline;
+use crate::plugins::wgpu::{Device, Queue};
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SoftPointsUniform {
+    softness: f32,
+    _padding: [f32; 3],
+}
+
+/// Fades the edges of round points out instead of cutting them off, which is cheaper than MSAA.
+/// Used by [PointRenderPipeline::use_soft_points].
+#[derive(Resource)]
+pub struct SoftPoints {
+    /// Part of the radius, measured from the edge, over which a point fades out.
+    pub softness: f32,
+    pub buffer: wgpu::Buffer,
+}
+
+impl SoftPoints {
+    const MIN_SOFTNESS: f32 = 0.01;
+
+    fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("soft-points-buffer"),
+            size: std::mem::size_of::<SoftPointsUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            softness: 0.3,
+            buffer,
+        }
+    }
+}
+
+pub(super) fn create_soft_points_buffer(mut commands: Commands, device: Res<Device>) {
+    commands.insert_resource(SoftPoints::new(&device));
+}
+
+pub(super) fn update_soft_points_buffer(queue: Res<Queue>, soft_points: Res<SoftPoints>) {
+    let uniform = SoftPointsUniform {
+        // The shader's smoothstep is undefined for an empty range
+        softness: soft_points.softness.clamp(SoftPoints::MIN_SOFTNESS, 1.0),
+        ..Default::default()
+    };
+
+    queue.write_buffer(&soft_points.buffer, 0, bytemuck::bytes_of(&uniform));
+}
+
+pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
+    let mut render_pipeline = world.get_resource_mut::<PointRenderPipeline>().unwrap();
+
+    let mut enabled = render_pipeline.use_soft_points;
+    if ui
+        .checkbox(&mut enabled, "Soft edges")
+        .on_hover_text("Anti-aliases the edges of the points by fading them out")
+        .changed()
+    {
+        render_pipeline.use_soft_points = enabled;
+    }
+
+    let mut soft_points = world.get_resource_mut::<SoftPoints>().unwrap();
+
+    ui.add_enabled_ui(enabled, |ui| {
+        let mut softness = soft_points.softness;
+
+        if ui
+            .add(egui::Slider::new(&mut softness, SoftPoints::MIN_SOFTNESS..=1.0).text("Softness"))
+            .changed()
+        {
+            soft_points.softness = softness;
+        }
+    });
+}
@@ -0,0 +1,64 @@
+use bevy_ecs::prelude::*;
+use rustc_hash::FxHashMap;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod hot_reload;
+
+/// The WGSL shaders of the renderer.
+/// They are compiled into the binary, but can be replaced at runtime while developing,
+/// see [ShaderSources].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Shader {
+    PointCompute,
+    PointRender,
+    Line,
+}
+
+impl Shader {
+    pub const ALL: [Self; 3] = [Self::PointCompute, Self::PointRender, Self::Line];
+
+    /// Path of the WGSL file, relative to this module.
+    pub fn path(self) -> &'static str {
+        match self {
+            Self::PointCompute => "point/pipelines/compute.wgsl",
+            Self::PointRender => "point/pipelines/render.wgsl",
+            Self::Line => "line/line.wgsl",
+        }
+    }
+
+    fn embedded_source(self) -> &'static str {
+        match self {
+            Self::PointCompute => include_str!("point/pipelines/compute.wgsl"),
+            Self::PointRender => include_str!("point/pipelines/render.wgsl"),
+            Self::Line => include_str!("line/line.wgsl"),
+        }
+    }
+}
+
+/// Sources which replace the embedded ones of the [Shader]s.
+/// The pipelines have to be created again after a source changed.
+#[derive(Debug, Default, Resource)]
+pub struct ShaderSources(FxHashMap<Shader, String>);
+
+impl ShaderSources {
+    /// Returns the source which was replaced before.
+    pub fn insert(&mut self, shader: Shader, source: String) -> Option<String> {
+        self.0.insert(shader, source)
+    }
+
+    pub fn remove(&mut self, shader: Shader) -> Option<String> {
+        self.0.remove(&shader)
+    }
+
+    pub fn create_module(&self, device: &wgpu::Device, shader: Shader) -> wgpu::ShaderModule {
+        let source = self
+            .0
+            .get(&shader)
+            .map_or(shader.embedded_source(), String::as_str);
+
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(shader.path()),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::RunSystemOnce;
+use rustc_hash::FxHashMap;
+use web_time::{Duration, Instant};
+
+use crate::plugins::render::shader::{Shader, ShaderSources};
+use crate::plugins::render::{line, point};
+use crate::plugins::wgpu::Device;
+
+/// Reloads the shaders from the source tree when their files change
+/// and creates the pipelines which use them again.
+/// Only meant for development, as it needs the sources which the binary was built from.
+pub struct ShaderHotReloadPlugin;
+
+impl Plugin for ShaderHotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WatchedShaders::new())
+            .add_systems(Update, reload_changed_shaders);
+    }
+}
+
+#[derive(Resource)]
+struct WatchedShaders {
+    modified: FxHashMap<Shader, SystemTime>,
+    last_check: Instant,
+}
+
+impl WatchedShaders {
+    const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+    fn new() -> Self {
+        let modified = Shader::ALL
+            .into_iter()
+            .filter_map(|shader| Some((shader, modified_time(shader)?)))
+            .collect();
+
+        Self {
+            modified,
+            last_check: Instant::now(),
+        }
+    }
+
+    /// Shaders whose files were modified since the last check.
+    fn changed(&mut self) -> Vec<Shader> {
+        Shader::ALL
+            .into_iter()
+            .filter(|shader| {
+                let Some(modified) = modified_time(*shader) else {
+                    return false;
+                };
+
+                self.modified.insert(*shader, modified) != Some(modified)
+            })
+            .collect()
+    }
+}
+
+fn file_path(shader: Shader) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/plugins/render")
+        .join(shader.path())
+}
+
+fn modified_time(shader: Shader) -> Option<SystemTime> {
+    std::fs::metadata(file_path(shader))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn reload_changed_shaders(world: &mut World) {
+    let changed = {
+        let mut watched_shaders = world.resource_mut::<WatchedShaders>();
+
+        if watched_shaders.last_check.elapsed() < WatchedShaders::CHECK_INTERVAL {
+            return;
+        }
+
+        watched_shaders.last_check = Instant::now();
+        watched_shaders.changed()
+    };
+
+    for shader in changed {
+        let source = match std::fs::read_to_string(file_path(shader)) {
+            Ok(source) => source,
+            Err(err) => {
+                log::error!("Failed to read shader {}: {}", shader.path(), err);
+                continue;
+            }
+        };
+
+        let previous = world.resource_mut::<ShaderSources>().insert(shader, source);
+
+        match create_pipelines(world, shader) {
+            None => log::info!("Reloaded shader {}", shader.path()),
+            Some(err) => {
+                log::error!("Failed to reload shader {}: {}", shader.path(), err);
+
+                // keep drawing with the last source which worked
+                let mut shader_sources = world.resource_mut::<ShaderSources>();
+
+                match previous {
+                    Some(previous) => shader_sources.insert(shader, previous),
+                    None => shader_sources.remove(shader),
+                };
+
+                create_pipelines(world, shader);
+            }
+        }
+    }
+}
+
+/// Returns the error if the shader or one of the pipelines is invalid.
+fn create_pipelines(world: &mut World, shader: Shader) -> Option<wgpu::Error> {
+    world
+        .resource::<Device>()
+        .push_error_scope(wgpu::ErrorFilter::Validation);
+
+    match shader {
+        Shader::PointCompute => {
+            world.run_system_once(point::pipelines::compute::create_compute_pipeline)
+        }
+        Shader::PointRender => {
+            world.run_system_once(point::pipelines::render::create_render_pipeline)
+        }
+        Shader::Line => world.run_system_once(line::create_pipeline),
+    }
+
+    pollster::block_on(world.resource::<Device>().pop_error_scope())
+}
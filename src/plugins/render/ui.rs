@@ -6,6 +6,7 @@ use egui::{Context, Visuals};
 use egui_wgpu::{Renderer, ScreenDescriptor};
 use egui_winit::State;
 
+use crate::plugins::input::{HotkeyAction, HotkeyEvent, UiWantsKeyboardInput};
 use crate::plugins::wgpu::{
     CommandEncoders, Device, GlobalRenderResources, Queue, Render, RenderPassSet, SurfaceConfig,
 };
@@ -18,6 +19,7 @@ impl Plugin for UiPlugin {
         app.insert_resource(SidePanelOpened(true))
             .add_systems(Startup, setup)
             .add_systems(PreUpdate, handle_input)
+            .add_systems(Update, toggle_side_panel)
             .add_systems(Render, (prepare, ui, draw).chain().in_set(RenderPassSet));
 
         app.world_mut()
@@ -60,7 +62,7 @@ fn setup(
         None,
     );
 
-    let egui_renderer = Renderer::new(&device, config.format, None, 1);
+    let egui_renderer = Renderer::new(&device, config.color_format(), None, 1);
 
     commands.insert_resource(Egui {
         context: egui_context,
@@ -89,6 +91,17 @@ fn prepare(mut egui: ResMut<Egui>, window: Res<Window>) {
 #[derive(Resource)]
 struct SidePanelOpened(bool);
 
+fn toggle_side_panel(
+    mut hotkey_events: EventReader<HotkeyEvent>,
+    mut side_panel_opened: ResMut<SidePanelOpened>,
+) {
+    for event in hotkey_events.read() {
+        if event.0 == HotkeyAction::ToggleSidePanel {
+            side_panel_opened.0 = !side_panel_opened.0;
+        }
+    }
+}
+
 fn ui(world: &mut World) {
     let context = world.get_resource::<Egui>().unwrap().context.clone();
 
@@ -117,6 +130,14 @@ fn ui(world: &mut World) {
                         if let Some(frame_time) = frame_time {
                             ui.label(format!("Frame: {:>2.2} ms", frame_time));
                         }
+
+                        let drawn_points = world
+                            .get_resource::<crate::plugins::render::point::drawn_points::DrawnPoints>()
+                            .and_then(|it| it.count);
+
+                        if let Some(drawn_points) = drawn_points {
+                            ui.label(format!("Drawn points: {}", drawn_points));
+                        }
                     }
 
                     egui::CollapsingHeader::new("Metadata")
@@ -125,6 +146,10 @@ fn ui(world: &mut World) {
                             crate::plugins::metadata::draw_ui(ui, world);
                         });
 
+                    ui.collapsing("Point clouds", |ui| {
+                        crate::plugins::metadata::draw_point_clouds_ui(ui, world);
+                    });
+
                     egui::CollapsingHeader::new("Cells")
                         .default_open(true)
                         .show(ui, |ui| {
@@ -136,6 +161,12 @@ fn ui(world: &mut World) {
                         crate::plugins::converter::draw_ui(ui, world);
                     });
 
+                    ui.collapsing("Highlight", |ui| {
+                        crate::plugins::render::point::highlight::draw_ui(ui, world);
+                        ui.separator();
+                        crate::plugins::render::point::density::draw_ui(ui, world);
+                    });
+
                     ui.collapsing("Camera", |ui| {
                         crate::plugins::camera::draw_ui(ui, world);
                     });
@@ -143,6 +174,23 @@ fn ui(world: &mut World) {
                     ui.collapsing("Debug", |ui| {
                         crate::plugins::debug::draw_ui(ui, world);
                     });
+
+                    ui.collapsing("Diagnostics", |ui| {
+                        crate::app::draw_build_info_ui(ui, world);
+                        ui.separator();
+                        crate::plugins::wgpu::draw_ui(ui, world);
+                        ui.separator();
+                        crate::app::draw_log_level_ui(ui);
+                        ui.separator();
+                        crate::plugins::winit::draw_ui(ui, world);
+                        ui.separator();
+                        crate::plugins::asset::draw_ui::<point_converter::metadata::Metadata>(
+                            ui, world, "Metadata",
+                        );
+                        crate::plugins::asset::draw_ui::<point_converter::cell::Cell>(
+                            ui, world, "Cells",
+                        );
+                    });
                 });
             });
 
@@ -161,6 +209,10 @@ fn ui(world: &mut World) {
                 }
             });
     });
+
+    crate::plugins::metadata::draw_loading_overlay(&context, world);
+
+    world.get_resource_mut::<UiWantsKeyboardInput>().unwrap().0 = context.wants_keyboard_input();
 }
 
 fn draw(
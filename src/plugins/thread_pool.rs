@@ -6,18 +6,36 @@ use bevy_ecs::prelude::*;
 
 use thread_pool::ThreadPool as InnerThreadPool;
 
-pub struct ThreadPoolPlugin;
+/// Creates a [ThreadPool] for cpu heavy work like converting points
+/// and a separate [IoThreadPool] for loading assets,
+/// so that a long conversion doesn't stall the streaming of cells.
+#[derive(Debug, Clone)]
+pub struct ThreadPoolPlugin {
+    pub compute_threads: usize,
+    pub io_threads: usize,
+}
+
+impl Default for ThreadPoolPlugin {
+    fn default() -> Self {
+        Self {
+            compute_threads: 2,
+            io_threads: 2,
+        }
+    }
+}
 
 impl Plugin for ThreadPoolPlugin {
     fn build(&self, app: &mut App) {
         #[cfg(not(target_arch = "wasm32"))]
         {
-            app.insert_resource(ThreadPool::new(2));
+            app.insert_resource(ThreadPool::new(self.compute_threads))
+                .insert_resource(IoThreadPool::new(self.io_threads));
         }
 
         #[cfg(target_arch = "wasm32")]
         {
-            app.insert_non_send_resource(ThreadPool::new(2));
+            app.insert_non_send_resource(ThreadPool::new(self.compute_threads))
+                .insert_non_send_resource(IoThreadPool::new(self.io_threads));
         }
     }
 }
@@ -45,3 +63,27 @@ impl Deref for ThreadPool {
         &self.0
     }
 }
+
+#[cfg_attr(not(target_arch = "wasm32"), derive(Resource))]
+#[derive(Debug, Clone)]
+pub struct IoThreadPool(ThreadPool);
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type IoThreadPoolRes<'w> = Res<'w, IoThreadPool>;
+
+#[cfg(target_arch = "wasm32")]
+pub type IoThreadPoolRes<'w> = NonSend<'w, IoThreadPool>;
+
+impl IoThreadPool {
+    pub fn new(size: usize) -> Self {
+        Self(ThreadPool::new(size))
+    }
+}
+
+impl Deref for IoThreadPool {
+    type Target = ThreadPool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
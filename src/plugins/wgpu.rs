@@ -2,7 +2,7 @@ use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
 use crate::plugins::render::BufferSet;
-use crate::plugins::winit::{Window, WindowResized};
+use crate::plugins::winit::{surface_size, Window, WindowResized};
 use crate::texture::Texture;
 use bevy_app::prelude::*;
 use bevy_app::{AppExit, MainScheduleOrder};
@@ -57,47 +57,48 @@ impl Deref for SurfaceConfig {
     }
 }
 
+impl SurfaceConfig {
+    /// Format of the surface views, which every pipeline and egui have to render to.
+    /// Colors are already sRGB encoded, so this is never an sRGB format, which would encode them a second time.
+    pub fn color_format(&self) -> TextureFormat {
+        self.0.format.remove_srgb_suffix()
+    }
+}
+
 impl DerefMut for SurfaceConfig {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
 }
 
+/// The adapter the device was created from, kept for the diagnostics ui.
+#[derive(Resource)]
+pub struct Adapter {
+    pub info: wgpu::AdapterInfo,
+    pub limits: wgpu::Limits,
+}
+
 pub struct WGPUPlugin;
 
 impl WGPUPlugin {
+    /// The backend and the power preference can be chosen with the environment variables
+    /// `WGPU_BACKEND` (e.g. `vulkan`, `dx12`, `metal` or `gl`) and `WGPU_POWER_PREF` (`low` or `high`).
     pub async fn build(window: Arc<winit::window::Window>, app: &mut App) {
-        let size = window.inner_size();
-        let instance = wgpu::Instance::default();
+        let size = surface_size(window.inner_size());
+        let instance = create_instance(wgpu::Backends::all());
         let surface = instance.create_surface(window).unwrap();
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                compatible_surface: Some(&surface),
-                ..Default::default()
-            })
+        let (adapter, device, queue) = request_device(&instance, Some(&surface))
             .await
             .expect("Failed to find an appropriate adapter");
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                },
-                None,
-            )
-            .await
-            .expect("Failed to create device");
-
         let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|format| *format == TextureFormat::Bgra8Unorm)
-            .unwrap_or(surface_caps.formats[0]);
+        let surface_format = choose_surface_format(&surface_caps.formats);
+        let view_formats = if surface_format.is_srgb() {
+            vec![surface_format.remove_srgb_suffix()]
+        } else {
+            Vec::new()
+        };
 
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -106,15 +107,63 @@ impl WGPUPlugin {
             height: size.height,
             present_mode: wgpu::PresentMode::AutoVsync,
             alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: Vec::new(),
+            view_formats,
             desired_maximum_frame_latency: 2,
         };
 
+        log::info!(
+            "Using surface format {:?}, rendering as {:?}",
+            config.format,
+            config.format.remove_srgb_suffix()
+        );
+
         surface.configure(&device, &config);
+        app.insert_resource(Surface(surface));
+
+        Self::insert_resources(app, adapter, device, queue, config);
+    }
+
+    /// Like [WGPUPlugin::build] but without a window, so no frames are rendered.
+    /// The [SurfaceConfig] only describes the size and format of an imaginary surface.
+    /// GL is only used if `WGPU_BACKEND` asks for it, because it can't load from depth textures in compute shaders,
+    /// so a software renderer like lavapipe is needed on a CI machine without a GPU.
+    /// Returns false if there is no adapter.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn build_headless(app: &mut App, width: u32, height: u32) -> bool {
+        let instance = create_instance(wgpu::Backends::PRIMARY);
+
+        let Some((adapter, device, queue)) = request_device(&instance, None).await else {
+            return false;
+        };
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: TextureFormat::Rgba8Unorm,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::AutoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: Vec::new(),
+            desired_maximum_frame_latency: 2,
+        };
+
+        Self::insert_resources(app, adapter, device, queue, config);
+        true
+    }
 
+    fn insert_resources(
+        app: &mut App,
+        adapter: wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        config: wgpu::SurfaceConfiguration,
+    ) {
+        app.insert_resource(Adapter {
+            info: adapter.get_info(),
+            limits: adapter.limits(),
+        });
         app.insert_resource(Device(device));
         app.insert_resource(Queue(queue));
-        app.insert_resource(Surface(surface));
         app.insert_resource(SurfaceConfig(config));
 
         let mut encoders = CommandEncoders::new();
@@ -124,7 +173,10 @@ impl WGPUPlugin {
         app.add_systems(Startup, setup_depth_texture.in_set(BufferSet))
             .add_systems(
                 PreUpdate,
-                (resize_window, update_depth_texture.in_set(BufferSet))
+                (
+                    resize_window.run_if(resource_exists::<Surface>),
+                    update_depth_texture.in_set(BufferSet),
+                )
                     .chain()
                     .run_if(on_event::<WindowResized>()),
             );
@@ -138,7 +190,9 @@ impl WGPUPlugin {
         app.add_systems(
             Render,
             (
-                begin_frame.before(RenderPassSet),
+                begin_frame
+                    .before(RenderPassSet)
+                    .run_if(resource_exists::<Surface>),
                 clear_pass.in_set(RenderPassSet),
                 end_frame.after(RenderPassSet),
             ),
@@ -146,6 +200,61 @@ impl WGPUPlugin {
     }
 }
 
+/// `WGPU_BACKEND` takes precedence over the given backends.
+fn create_instance(backends: wgpu::Backends) -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::util::backend_bits_from_env().unwrap_or(backends),
+        ..Default::default()
+    })
+}
+
+/// Returns [None] if there is no adapter, or none which can present to the given surface.
+async fn request_device(
+    instance: &wgpu::Instance,
+    compatible_surface: Option<&wgpu::Surface<'_>>,
+) -> Option<(wgpu::Adapter, wgpu::Device, wgpu::Queue)> {
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::util::power_preference_from_env().unwrap_or_default(),
+            compatible_surface,
+            ..Default::default()
+        })
+        .await?;
+
+    let adapter_info = adapter.get_info();
+    log::info!(
+        "Using adapter {} ({:?}) with {:?}",
+        adapter_info.name,
+        adapter_info.device_type,
+        adapter_info.backend
+    );
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::default(),
+            },
+            None,
+        )
+        .await
+        .expect("Failed to create device");
+
+    Some((adapter, device, queue))
+}
+
+/// Prefers [TextureFormat::Bgra8Unorm] and otherwise any format without sRGB encoding.
+/// If there are only sRGB formats, the surface is viewed without it, see [SurfaceConfig::color_format].
+fn choose_surface_format(formats: &[TextureFormat]) -> TextureFormat {
+    formats
+        .iter()
+        .copied()
+        .find(|format| *format == TextureFormat::Bgra8Unorm)
+        .or_else(|| formats.iter().copied().find(|format| !format.is_srgb()))
+        .unwrap_or(formats[0])
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, ScheduleLabel)]
 pub struct Render;
 
@@ -161,8 +270,8 @@ fn resize_window(
 ) {
     if let Some(resized) = window_resized_events.read().last() {
         let size = resized.physical_size;
-        config.width = size.width.max(1);
-        config.height = size.height.max(1);
+        config.width = size.width;
+        config.height = size.height;
         surface.configure(&device, &config);
         window.request_redraw();
     }
@@ -189,20 +298,14 @@ fn setup_depth_texture(mut commands: Commands, device: Res<Device>, config: Res<
     commands.insert_resource(GlobalDepthTexture::new(depth_texture));
 }
 
+/// Runs after [resize_window], so the depth texture always has the size of the surface.
 fn update_depth_texture(
-    mut window_resized: EventReader<WindowResized>,
     mut depth_texture: ResMut<GlobalDepthTexture>,
     device: Res<Device>,
+    config: Res<SurfaceConfig>,
 ) {
-    if let Some(resized) = window_resized.read().last() {
-        let texture = Texture::create_depth_texture(
-            &device,
-            resized.physical_size.width,
-            resized.physical_size.height,
-        );
-
-        *depth_texture = GlobalDepthTexture::new(texture);
-    }
+    let texture = Texture::create_depth_texture(&device, config.width, config.height);
+    *depth_texture = GlobalDepthTexture::new(texture);
 }
 
 #[derive(Debug, Resource)]
@@ -256,42 +359,56 @@ pub struct RenderView {
     frame: wgpu::SurfaceTexture,
     pub view: wgpu::TextureView,
 }
+
+/// Recovers from the errors of acquiring the next frame, see [begin_frame].
+#[derive(SystemParam)]
+struct SurfaceErrorHandler<'w> {
+    window: Res<'w, Window>,
+    window_resized: EventWriter<'w, WindowResized>,
+    app_exit: EventWriter<'w, AppExit>,
+}
+
+impl SurfaceErrorHandler<'_> {
+    fn handle(&mut self, err: SurfaceError) {
+        match err {
+            SurfaceError::Timeout => {
+                log::warn!("Timeout while trying to acquire next frame!")
+            }
+            SurfaceError::Outdated => {
+                // happens when window gets minimized
+            }
+            SurfaceError::Lost => {
+                self.window_resized
+                    .send(WindowResized::new(self.window.inner_size()));
+            }
+            SurfaceError::OutOfMemory => {
+                log::error!("Application is out of memory!");
+                self.app_exit.send(AppExit::error());
+            }
+        }
+    }
+}
+
 fn begin_frame(
     mut commands: Commands,
     surface: Res<Surface>,
     device: Res<Device>,
-    mut window_resized: EventWriter<WindowResized>,
-    mut app_exit: EventWriter<AppExit>,
-    window: Res<Window>,
+    mut surface_error_handler: SurfaceErrorHandler,
+    config: Res<SurfaceConfig>,
     mut encoders: ResMut<CommandEncoders>,
 ) {
     let frame = match surface.get_current_texture() {
         Ok(frame) => frame,
         Err(err) => {
-            match err {
-                SurfaceError::Timeout => {
-                    log::warn!("Timeout while trying to acquire next frame!")
-                }
-                SurfaceError::Outdated => {
-                    // happens when window gets minimized
-                }
-                SurfaceError::Lost => {
-                    window_resized.send(WindowResized {
-                        physical_size: window.inner_size(),
-                    });
-                }
-                SurfaceError::OutOfMemory => {
-                    log::error!("Application is out of memory!");
-                    app_exit.send(AppExit::error());
-                }
-            }
+            surface_error_handler.handle(err);
             return;
         }
     };
 
-    let view = frame
-        .texture
-        .create_view(&wgpu::TextureViewDescriptor::default());
+    let view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
+        format: Some(config.color_format()),
+        ..Default::default()
+    });
 
     commands.insert_resource(RenderView { frame, view });
 
@@ -320,7 +437,7 @@ fn clear_pass(mut global_render_resources: GlobalRenderResources) {
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                     view: &global_render_resources.depth_texture.view,
                     depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
+                        load: wgpu::LoadOp::Clear(Texture::DEPTH_CLEAR_VALUE),
                         store: wgpu::StoreOp::Store,
                     }),
                     stencil_ops: None,
@@ -352,3 +469,99 @@ pub struct GlobalRenderResources<'w> {
     pub depth_texture: Res<'w, GlobalDepthTexture>,
     pub encoders: ResMut<'w, CommandEncoders>,
 }
+
+impl Adapter {
+    /// Multi line description for bug reports.
+    fn describe(&self, config: &SurfaceConfig) -> String {
+        let wgpu::AdapterInfo {
+            name,
+            vendor,
+            device,
+            device_type,
+            driver,
+            driver_info,
+            backend,
+        } = &self.info;
+
+        [
+            format!("Adapter: {}", name),
+            format!("Vendor: {:#06x}, device: {:#06x}", vendor, device),
+            format!("Device type: {:?}", device_type),
+            format!("Backend: {:?}", backend),
+            format!("Driver: {} {}", driver, driver_info),
+            format!("Surface format: {:?}", config.format),
+            format!("Render format: {:?}", config.color_format()),
+            format!("Present mode: {:?}", config.present_mode),
+            format!("Max texture size: {}", self.limits.max_texture_dimension_2d),
+            format!("Max buffer size: {}", self.limits.max_buffer_size),
+            format!(
+                "Max storage buffer binding size: {}",
+                self.limits.max_storage_buffer_binding_size
+            ),
+            format!("Max bind groups: {}", self.limits.max_bind_groups),
+            format!("Max vertex buffers: {}", self.limits.max_vertex_buffers),
+        ]
+        .join("\n")
+    }
+}
+
+pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
+    let adapter = world.resource::<Adapter>();
+    let config = world.resource::<SurfaceConfig>();
+    let description = adapter.describe(config);
+
+    for line in description.lines() {
+        ui.label(line);
+    }
+
+    if ui.button("Copy").clicked() {
+        ui.output_mut(|output| output.copied_text = description);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choose_surface_format_prefers_formats_without_srgb() {
+        assert_eq!(
+            choose_surface_format(&[
+                TextureFormat::Bgra8UnormSrgb,
+                TextureFormat::Rgba8Unorm,
+                TextureFormat::Bgra8Unorm
+            ]),
+            TextureFormat::Bgra8Unorm
+        );
+        assert_eq!(
+            choose_surface_format(&[TextureFormat::Rgba8UnormSrgb, TextureFormat::Rgba8Unorm]),
+            TextureFormat::Rgba8Unorm
+        );
+        assert_eq!(
+            choose_surface_format(&[TextureFormat::Bgra8UnormSrgb]),
+            TextureFormat::Bgra8UnormSrgb
+        );
+    }
+
+    #[test]
+    fn color_format_is_never_srgb() {
+        for format in [
+            TextureFormat::Bgra8Unorm,
+            TextureFormat::Bgra8UnormSrgb,
+            TextureFormat::Rgba8UnormSrgb,
+        ] {
+            let config = SurfaceConfig(wgpu::SurfaceConfiguration {
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                format,
+                width: 1,
+                height: 1,
+                present_mode: wgpu::PresentMode::AutoVsync,
+                alpha_mode: wgpu::CompositeAlphaMode::Auto,
+                view_formats: Vec::new(),
+                desired_maximum_frame_latency: 2,
+            });
+
+            assert!(!config.color_format().is_srgb());
+        }
+    }
+}
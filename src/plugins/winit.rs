@@ -8,6 +8,7 @@ use bevy_app::{AppExit, PluginsState};
 use bevy_ecs::event::ManualEventReader;
 use bevy_ecs::prelude::*;
 use cfg_if::cfg_if;
+use web_time::{Duration, Instant};
 use winit::dpi::PhysicalSize;
 use winit::event::Event;
 use winit::event_loop::{ControlFlow, EventLoop, EventLoopBuilder, EventLoopWindowTarget};
@@ -26,11 +27,44 @@ impl Deref for Window {
     }
 }
 
+/// The size is never zero, so that surfaces, textures and the aspect ratio stay valid
+/// while the window is minimized.
 #[derive(Debug, Event)]
 pub struct WindowResized {
     pub physical_size: PhysicalSize<u32>,
 }
 
+impl WindowResized {
+    pub fn new(physical_size: PhysicalSize<u32>) -> Self {
+        Self {
+            physical_size: surface_size(physical_size),
+        }
+    }
+}
+
+/// Some platforms report a size of zero for minimized windows, which is invalid for surfaces.
+pub fn surface_size(physical_size: PhysicalSize<u32>) -> PhysicalSize<u32> {
+    PhysicalSize::new(physical_size.width.max(1), physical_size.height.max(1))
+}
+
+/// Caps the frame rate below the refresh rate, so that an idle app doesn't keep a core busy.
+/// The event loop waits between frames instead of sleeping, so input is still handled while waiting.
+#[derive(Debug, Default, Resource)]
+pub struct FrameLimit {
+    /// [None] renders as often as the present mode allows.
+    pub max_fps: Option<u32>,
+}
+
+impl FrameLimit {
+    pub const FPS_RANGE: std::ops::RangeInclusive<u32> = 5..=240;
+    const DEFAULT_MAX_FPS: u32 = 30;
+
+    fn frame_time(&self) -> Option<Duration> {
+        self.max_fps
+            .map(|fps| Duration::from_secs_f64(1.0 / fps.max(1) as f64))
+    }
+}
+
 #[derive(Debug, Event)]
 pub struct WindowEvent(winit::event::WindowEvent);
 
@@ -85,6 +119,7 @@ impl Plugin for WinitPlugin {
         let window = Arc::new(window_builder.build(&event_loop).unwrap());
 
         app.add_event::<WindowEvent>();
+        app.init_resource::<FrameLimit>();
         app.add_event::<WindowResized>();
         app.insert_non_send_resource(event_loop);
         app.insert_resource(Window(window.clone()));
@@ -105,6 +140,9 @@ impl Plugin for WinitPlugin {
 
             let mut app_exit_event_reader = ManualEventReader::<AppExit>::default();
 
+            // start of the next frame if the frame rate is limited
+            let mut next_frame: Option<Instant> = None;
+
             let exit = Rc::new(RefCell::new(AppExit::Success));
             let winit_exit = exit.clone();
 
@@ -123,7 +161,21 @@ impl Plugin for WinitPlugin {
 
                     match event {
                         Event::AboutToWait => {
-                            window.request_redraw();
+                            let wait = next_frame
+                                .map(|next_frame| {
+                                    next_frame.saturating_duration_since(Instant::now())
+                                })
+                                .filter(|wait| !wait.is_zero());
+
+                            match wait {
+                                Some(wait) => {
+                                    target.set_control_flow(ControlFlow::wait_duration(wait));
+                                }
+                                None => {
+                                    target.set_control_flow(ControlFlow::Poll);
+                                    window.request_redraw();
+                                }
+                            }
                         }
                         Event::WindowEvent {
                             event: window_event,
@@ -134,16 +186,18 @@ impl Plugin for WinitPlugin {
 
                             match window_event {
                                 winit::event::WindowEvent::RedrawRequested => {
+                                    let frame_start = Instant::now();
                                     app.update();
+
+                                    next_frame = app
+                                        .world()
+                                        .resource::<FrameLimit>()
+                                        .frame_time()
+                                        .map(|frame_time| frame_start + frame_time);
                                 }
                                 winit::event::WindowEvent::Resized(new_size) => {
                                     app.world_mut()
-                                        .send_event(WindowResized {
-                                            physical_size: PhysicalSize::new(
-                                                new_size.width.max(1),
-                                                new_size.height.max(1),
-                                            ),
-                                        })
+                                        .send_event(WindowResized::new(new_size))
                                         .unwrap();
                                 }
                                 winit::event::WindowEvent::CloseRequested => {
@@ -170,3 +224,48 @@ impl Plugin for WinitPlugin {
         });
     }
 }
+
+pub fn draw_ui(ui: &mut egui::Ui, world: &mut World) {
+    let mut frame_limit = world.resource_mut::<FrameLimit>();
+
+    let mut is_limited = frame_limit.max_fps.is_some();
+    let mut max_fps = frame_limit.max_fps.unwrap_or(FrameLimit::DEFAULT_MAX_FPS);
+
+    let checkbox_changed = ui.checkbox(&mut is_limited, "Limit frame rate").changed();
+    let slider = egui::Slider::new(&mut max_fps, FrameLimit::FPS_RANGE).text("FPS");
+    let slider_changed = ui.add_enabled(is_limited, slider).changed();
+
+    if checkbox_changed || slider_changed {
+        frame_limit.max_fps = is_limited.then_some(max_fps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimized_window_has_a_valid_surface_size() {
+        assert_eq!(
+            WindowResized::new(PhysicalSize::new(0, 0)).physical_size,
+            PhysicalSize::new(1, 1)
+        );
+        assert_eq!(
+            WindowResized::new(PhysicalSize::new(800, 0)).physical_size,
+            PhysicalSize::new(800, 1)
+        );
+        assert_eq!(
+            WindowResized::new(PhysicalSize::new(800, 600)).physical_size,
+            PhysicalSize::new(800, 600)
+        );
+    }
+
+    #[test]
+    fn frame_limit_frame_time() {
+        assert_eq!(FrameLimit::default().frame_time(), None);
+        assert_eq!(
+            FrameLimit { max_fps: Some(50) }.frame_time(),
+            Some(Duration::from_millis(20))
+        );
+    }
+}
@@ -6,6 +6,9 @@ use std::sync::Arc;
 
 use rustc_hash::{FxHashMap, FxHasher};
 
+/// Both keys of an entry. Entries are ordered by their `sort_key` first.
+/// Entries with equal sort keys are ordered by the hash of their `hash_key`,
+/// which is arbitrary but deterministic.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SortedHashKey<K, SK> {
     pub sort_key: SK,
@@ -47,12 +50,23 @@ pub struct SortedHashEntry<K, SK, V> {
     pub value: V,
 }
 
+/// Map which is accessed by `hash_key` like a hash map,
+/// but whose entries are iterated and popped in ascending order of their `sort_key`.
+///
+/// Each `hash_key` exists at most once. Inserting it again replaces its sort key and value.
+/// The sort key of an entry doesn't have to be unique, see [SortedHashKey] for how ties are ordered.
 #[derive(Debug)]
 pub struct SortedHashMap<K, SK, V> {
     map: FxHashMap<K, SortedHashEntry<K, SK, V>>,
     sorted_set: BTreeSet<Arc<SortedHashKey<K, SK>>>,
 }
 
+impl<K, SK, V> Default for SortedHashMap<K, SK, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<K, SK, V> SortedHashMap<K, SK, V> {
     pub fn new() -> Self {
         Self {
@@ -69,6 +83,10 @@ impl<K, SK, V> SortedHashMap<K, SK, V> {
     pub fn len(&self) -> usize {
         self.map.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
 }
 
 impl<K, SK, V> SortedHashMap<K, SK, V>
@@ -76,6 +94,7 @@ where
     K: Hash + Eq + Copy,
     SK: Ord,
 {
+    /// Inserts the value or replaces the sort key and value if the `hash_key` already exists.
     pub fn insert(&mut self, hash_key: K, sort_key: SK, value: V) {
         let keys = Arc::new(SortedHashKey { hash_key, sort_key });
 
@@ -100,6 +119,22 @@ where
         }
     }
 
+    pub fn get(&self, hash_key: &K) -> Option<&SortedHashEntry<K, SK, V>> {
+        self.map.get(hash_key)
+    }
+
+    pub fn contains_key(&self, hash_key: &K) -> bool {
+        self.map.contains_key(hash_key)
+    }
+
+    /// Entry with the lowest sort key without removing it.
+    pub fn first(&self) -> Option<&SortedHashEntry<K, SK, V>> {
+        self.sorted_set
+            .first()
+            .and_then(|keys| self.map.get(&keys.hash_key))
+    }
+
+    /// Removes and returns the entry with the lowest sort key.
     pub fn pop_first(&mut self) -> Option<SortedHashEntry<K, SK, V>> {
         if let Some(keys) = self.sorted_set.pop_first() {
             self.map.remove(&keys.hash_key)
@@ -107,6 +142,13 @@ where
             None
         }
     }
+
+    /// Iterates the entries in the same order as [SortedHashMap::pop_first] would return them.
+    pub fn iter(&self) -> impl Iterator<Item = &SortedHashEntry<K, SK, V>> {
+        self.sorted_set
+            .iter()
+            .map(|keys| self.map.get(&keys.hash_key).unwrap())
+    }
 }
 
 #[cfg(test)]
@@ -246,4 +288,88 @@ mod tests {
 
         assert_eq!(shm.len(), 0, "total length should be 0");
     }
+
+    #[test]
+    fn reinsert_changes_order() {
+        let mut shm = SortedHashMap::<u32, u32, char>::new();
+
+        shm.insert(0, 1, 'a');
+        shm.insert(1, 2, 'b');
+        shm.insert(0, 3, 'c');
+
+        let result = shm
+            .iter()
+            .map(|entry| (entry.keys.hash_key, entry.keys.sort_key, entry.value))
+            .collect::<Vec<_>>();
+
+        assert_eq!(result, vec![(1, 2, 'b'), (0, 3, 'c')]);
+    }
+
+    #[test]
+    fn iter_matches_pop_first() {
+        let mut shm = SortedHashMap::<u32, u32, ()>::new();
+
+        for (hash_key, sort_key) in [(5, 3), (1, 7), (3, 3), (9, 0), (2, 7), (4, 1)] {
+            shm.insert(hash_key, sort_key, ());
+        }
+
+        let iterated = shm
+            .iter()
+            .map(|entry| entry.keys.hash_key)
+            .collect::<Vec<_>>();
+
+        let popped = std::iter::from_fn(|| shm.pop_first())
+            .map(|entry| entry.keys.hash_key)
+            .collect::<Vec<_>>();
+
+        assert_eq!(iterated, popped);
+        assert!(shm.is_empty());
+    }
+
+    #[test]
+    fn pops_lowest_sort_key_first() {
+        let mut shm = SortedHashMap::<u32, u32, ()>::new();
+
+        for (hash_key, sort_key) in [(5, 3), (1, 7), (3, 3), (9, 0), (2, 7), (4, 1)] {
+            shm.insert(hash_key, sort_key, ());
+        }
+
+        assert_eq!(shm.first().map(|entry| entry.keys.hash_key), Some(9));
+
+        let sort_keys = std::iter::from_fn(|| shm.pop_first())
+            .map(|entry| entry.keys.sort_key)
+            .collect::<Vec<_>>();
+
+        assert_eq!(sort_keys, vec![0, 1, 3, 3, 7, 7]);
+    }
+
+    #[test]
+    fn get_and_contains_key() {
+        let mut shm = SortedHashMap::<u32, u32, char>::new();
+
+        shm.insert(0, 2, 'a');
+
+        assert!(shm.contains_key(&0));
+        assert!(!shm.contains_key(&1));
+        assert_eq!(shm.get(&0).map(|entry| entry.value), Some('a'));
+
+        shm.remove(&0);
+
+        assert!(!shm.contains_key(&0));
+        assert!(shm.get(&0).is_none());
+        assert!(shm.first().is_none());
+    }
+
+    #[test]
+    fn clear_removes_everything() {
+        let mut shm = SortedHashMap::<u32, u32, ()>::new();
+
+        shm.insert(0, 2, ());
+        shm.insert(1, 3, ());
+        shm.clear();
+
+        assert!(shm.is_empty());
+        assert_eq!(shm.sorted_set.len(), 0);
+        assert!(shm.pop_first().is_none());
+    }
 }
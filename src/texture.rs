@@ -4,14 +4,33 @@ pub struct Texture {
 }
 
 impl Texture {
+    /// Format of the global depth texture and of the depth stencil state of all pipelines.
+    /// Has to stay a depth format without stencil, as the compute shader samples it as `texture_depth_2d`.
     pub const DEPTH_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+    /// Which fragments pass the depth test. Has to fit [Texture::DEPTH_CLEAR_VALUE].
+    /// The occlusion filter of the compute shader also assumes smaller depths to be closer.
+    pub const DEPTH_COMPARE: wgpu::CompareFunction = wgpu::CompareFunction::Less;
+    /// Depth of the far plane, which the depth texture is cleared to every frame.
+    pub const DEPTH_CLEAR_VALUE: f32 = 1.0;
 
+    /// Depth test of all pipelines which draw into the global depth texture.
+    pub fn depth_stencil_state(depth_write_enabled: bool) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: Self::DEPTH_TEXTURE_FORMAT,
+            depth_write_enabled,
+            depth_compare: Self::DEPTH_COMPARE,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+
+    /// Textures can't be empty, so zero dimensions are raised to one.
     pub fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32) -> Self {
         let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("point-renderer-depth-texture"),
             size: wgpu::Extent3d {
-                width,
-                height,
+                width: width.max(1),
+                height: height.max(1),
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
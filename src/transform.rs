@@ -3,6 +3,8 @@ use std::ops::Mul;
 use bevy_ecs::prelude::Component;
 use glam::{Mat3, Mat4, Quat, Vec3};
 
+use bounding_volume::Aabb;
+
 #[derive(Debug, PartialEq, Copy, Clone, Component)]
 pub struct Transform {
     pub translation: Vec3,
@@ -131,6 +133,27 @@ impl Transform {
         point
     }
 
+    pub fn inverse_transform_point(&self, mut point: Vec3) -> Vec3 {
+        point -= self.translation;
+        point = self.rotation.inverse() * point;
+        point /= self.scale;
+        point
+    }
+
+    /// Smallest axis aligned box containing the transformed box.
+    pub fn transform_aabb(&self, aabb: &Aabb) -> Aabb {
+        Aabb::from(aabb.corners().map(|corner| self.transform_point(corner))).unwrap()
+    }
+
+    /// Smallest axis aligned box containing the box transformed back into local space.
+    pub fn inverse_transform_aabb(&self, aabb: &Aabb) -> Aabb {
+        Aabb::from(
+            aabb.corners()
+                .map(|corner| self.inverse_transform_point(corner)),
+        )
+        .unwrap()
+    }
+
     pub fn compute_matrix(&self) -> Mat4 {
         Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
     }
@@ -1,6 +1,31 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::io::{Cursor, Read};
+use std::rc::Rc;
+
+use rustc_hash::FxHashMap;
+
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 
+/// Replaces the query parameters of the page url without reloading the page and returns the new url.
+pub fn replace_query_params(params: &[(&str, String)]) -> Result<String, JsValue> {
+    let window = web_sys::window().unwrap();
+    let url = web_sys::Url::new(&window.location().href()?)?;
+    let search_params = url.search_params();
+
+    for (key, value) in params {
+        search_params.set(key, value);
+    }
+
+    let href = url.href();
+    window
+        .history()?
+        .replace_state_with_url(&JsValue::NULL, "", Some(&href))?;
+
+    Ok(href)
+}
+
 #[derive(Debug, Clone)]
 pub struct WebDir(web_sys::FileSystemDirectoryHandle);
 
@@ -51,3 +76,128 @@ impl WebFile {
             .dyn_into::<web_sys::File>()
     }
 }
+
+/// A zip of a converted directory which was picked as a single file and is kept in memory.
+/// Works in browsers without the directory picker.
+#[derive(Clone)]
+pub struct WebZip {
+    name: String,
+    /// Directory inside the zip which contains the metadata, as a logical path ending with `/`.
+    root: String,
+    /// Logical paths of all entries to their names inside the zip.
+    entries: Rc<FxHashMap<String, String>>,
+    archive: Rc<RefCell<zip::ZipArchive<Cursor<Vec<u8>>>>>,
+}
+
+impl Debug for WebZip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebZip")
+            .field("name", &self.name)
+            .field("root", &self.root)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebZip {
+    /// Resolves to [None] if no file was picked.
+    pub async fn choose() -> Result<Option<Self>, JsValue> {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let input = document
+            .create_element("input")?
+            .dyn_into::<web_sys::HtmlInputElement>()?;
+
+        input.set_type("file");
+        input.set_accept(".zip");
+
+        let (sender, receiver) = flume::bounded::<Option<web_sys::File>>(1);
+
+        let on_change = {
+            let input = input.clone();
+            let sender = sender.clone();
+
+            Closure::<dyn FnMut()>::new(move || {
+                let file = input.files().and_then(|files| files.get(0));
+                let _ = sender.try_send(file);
+            })
+        };
+
+        let on_cancel = Closure::<dyn FnMut()>::new(move || {
+            let _ = sender.try_send(None);
+        });
+
+        input.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+        input.add_event_listener_with_callback("cancel", on_cancel.as_ref().unchecked_ref())?;
+        input.click();
+
+        let Some(file) = receiver.recv_async().await.ok().flatten() else {
+            return Ok(None);
+        };
+
+        let array_buffer = JsFuture::from(file.array_buffer())
+            .await?
+            .dyn_into::<js_sys::ArrayBuffer>()?;
+        let bytes = js_sys::Uint8Array::new(&array_buffer).to_vec();
+
+        Self::from_bytes(file.name(), bytes)
+            .map(Some)
+            .map_err(|err| js_sys::Error::new(&err).into())
+    }
+
+    pub fn from_bytes(name: String, bytes: Vec<u8>) -> Result<Self, String> {
+        use point_converter::metadata::Metadata;
+
+        let archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|err| err.to_string())?;
+
+        // zips created on windows may use `\` as separator
+        let entries = archive
+            .file_names()
+            .map(|file_name| {
+                (
+                    point_converter::logical_path::normalize(file_name),
+                    file_name.to_string(),
+                )
+            })
+            .collect::<FxHashMap<_, _>>();
+
+        let metadata_path = Metadata::logical_path();
+        let root = entries
+            .keys()
+            .filter_map(|file_name| file_name.strip_suffix(&metadata_path))
+            .filter(|root| root.is_empty() || root.ends_with('/'))
+            .min_by_key(|root| root.len())
+            .map(String::from)
+            .ok_or_else(|| format!("{} contains no {}", name, metadata_path))?;
+
+        Ok(Self {
+            name,
+            root,
+            entries: Rc::new(entries),
+            archive: Rc::new(RefCell::new(archive)),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Reads a file by its [point_converter::logical_path] inside the converted directory.
+    pub fn read_bytes(&self, path: &str) -> Result<Vec<u8>, std::io::Error> {
+        let logical_path = format!(
+            "{}{}",
+            self.root,
+            point_converter::logical_path::normalize(path)
+        );
+
+        let file_name = self.entries.get(&logical_path).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, logical_path.clone())
+        })?;
+
+        let mut archive = self.archive.borrow_mut();
+        let mut file = archive.by_name(file_name)?;
+
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut bytes)?;
+
+        Ok(bytes)
+    }
+}